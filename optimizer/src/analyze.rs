@@ -0,0 +1,421 @@
+use crate::component::{INLINED_QRL, MARKER_SUFFIX, QRL, QRL_DEV, QRL_SUFFIX};
+use crate::diagnostic::Diagnostic;
+use crate::manifest::{Manifest, SegmentKind, SegmentMetadata};
+use crate::metrics::SegmentMetrics;
+use crate::prelude::*;
+use crate::routes::RouteFile;
+use crate::source::Source;
+use oxc_allocator::Allocator;
+use oxc_ast::ast::*;
+use oxc_ast::Visit;
+use oxc_parser::Parser;
+#[cfg(feature = "serde")]
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Component, Path, PathBuf};
+
+/// The extensions a segment module may have on disk, while QRLs import it without one.
+const MODULE_EXTENSIONS: [&str; 6] = [".js", ".mjs", ".jsx", ".ts", ".tsx", ".mts"];
+
+/// A QRL found in already optimized code, e.g. `qrl(() => import("./app.tsx_App_component_ckEPmXZlub0"), "App_component_ckEPmXZlub0")`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "camelCase"))]
+pub struct QrlReference {
+    pub symbol_name: String,
+    /// The name of the marker the QRL was created for, derived from the call wrapping it, e.g. `component$` for
+    /// `componentQrl(qrl(...))`.
+    pub ctx_name: String,
+    /// The module specifier of the lazy import. `None` for `inlinedQrl`, whose segment is defined in place.
+    pub import: Option<String>,
+    /// The path of the analyzed module the import resolves to, if it is one of the analyzed modules.
+    pub segment_path: Option<String>,
+    pub captures: Vec<String>,
+}
+
+/// The QRLs referenced and the symbols exported by an analyzed module.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "camelCase"))]
+pub struct AnalyzedModule {
+    pub path: String,
+    pub exports: Vec<String>,
+    pub references: Vec<QrlReference>,
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+/// A symbol name defined both by an analyzed library and by the app it is used in.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "camelCase"))]
+pub struct SymbolClash {
+    pub symbol_name: String,
+    pub library_origin: String,
+    pub app_origin: String,
+}
+
+/// A read-only analysis of already optimized modules, e.g. the published output of a third-party Qwik library.
+///
+/// The analysis does not transform anything: it only collects the QRLs of every module and the symbols exported by
+/// segment modules, and reconstructs a [Manifest] from them. The segments of the manifest are attributed to the
+/// outermost module referencing them, i.e. the host module they were originally extracted from. Their display name
+/// and hash are recovered from the symbol name, `{display_name}_{hash}`, so the display name of production symbols
+/// is just their prefix, e.g. `s`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "camelCase"))]
+pub struct Analysis {
+    /// The analyzed modules, in input order.
+    pub modules: Vec<AnalyzedModule>,
+    pub manifest: Manifest,
+}
+
+impl Analysis {
+    /// Analyzes the given optimized modules, segments and their hosts alike.
+    pub fn analyze(sources: &[Source]) -> Result<Analysis> {
+        let mut modules = sources
+            .iter()
+            .map(analyze_module)
+            .collect::<Result<Vec<_>>>()?;
+
+        // QRLs import segment modules without their extension.
+        let path_by_stem: BTreeMap<String, String> = modules
+            .iter()
+            .map(|module| {
+                let stem = strip_module_extension(&module.path).to_string();
+                (stem, module.path.clone())
+            })
+            .collect();
+
+        for module in &mut modules {
+            let dir = Path::new(&module.path)
+                .parent()
+                .map(Path::to_path_buf)
+                .unwrap_or_default();
+            for reference in &mut module.references {
+                reference.segment_path = reference.import.as_ref().and_then(|import| {
+                    let resolved = module_path(&dir.join(import));
+                    path_by_stem
+                        .get(strip_module_extension(&resolved))
+                        .or_else(|| {
+                            // Imports without a leading `./` are relative to the root of the build rather than to
+                            // the importing module.
+                            let import = module_path(Path::new(import));
+                            let import = strip_module_extension(&import);
+                            path_by_stem.iter().find_map(|(stem, path)| {
+                                let matches = stem == import
+                                    || stem
+                                        .strip_suffix(import)
+                                        .is_some_and(|dir| dir.ends_with('/'));
+                                matches.then_some(path)
+                            })
+                        })
+                        .cloned()
+                });
+            }
+        }
+
+        let manifest = Manifest::new(Self::segments(&modules));
+        Ok(Analysis { modules, manifest })
+    }
+
+    /// Reads and analyzes the optimized modules at `paths`.
+    pub fn analyze_fs(paths: Vec<PathBuf>) -> Result<Analysis> {
+        let sources = paths
+            .iter()
+            .map(Source::from_file)
+            .collect::<Result<Vec<_>>>()?;
+        Self::analyze(&sources)
+    }
+
+    /// The references whose segment is neither defined in place nor exported by one of the analyzed modules.
+    pub fn unresolved(&self) -> impl Iterator<Item = &QrlReference> {
+        let exports: HashMap<&str, &[String]> = self
+            .modules
+            .iter()
+            .map(|module| (module.path.as_str(), module.exports.as_slice()))
+            .collect();
+        self.modules
+            .iter()
+            .flat_map(|module| module.references.iter())
+            .filter(move |reference| {
+                reference.import.is_some()
+                    && !reference
+                        .segment_path
+                        .as_deref()
+                        .and_then(|path| exports.get(path))
+                        .is_some_and(|exports| exports.contains(&reference.symbol_name))
+            })
+    }
+
+    /// The symbols defined both by the analyzed modules and by `app`, which would make one of the segments
+    /// unreachable once both are bundled together.
+    pub fn clashes(&self, app: &Manifest) -> Vec<SymbolClash> {
+        self.manifest
+            .segments
+            .iter()
+            .filter_map(|segment| {
+                app.segment(&segment.symbol_name)
+                    .map(|app_segment| SymbolClash {
+                        symbol_name: segment.symbol_name.clone(),
+                        library_origin: segment.origin.clone(),
+                        app_origin: app_segment.origin.clone(),
+                    })
+            })
+            .collect()
+    }
+
+    fn segments<'m>(modules: &'m [AnalyzedModule]) -> Vec<SegmentMetadata> {
+        let referenced_by: HashMap<&'m str, &'m str> = modules
+            .iter()
+            .flat_map(|module| {
+                module.references.iter().filter_map(|reference| {
+                    Some((reference.segment_path.as_deref()?, module.path.as_str()))
+                })
+            })
+            .collect();
+        let host_of = |module: &'m AnalyzedModule| {
+            let mut path = module.path.as_str();
+            // Bounded, in case a malformed build contains a cycle.
+            for _ in 0..modules.len() {
+                match referenced_by.get(path) {
+                    Some(parent) => path = parent,
+                    None => break,
+                }
+            }
+            path.to_string()
+        };
+
+        let mut segments: BTreeMap<&str, SegmentMetadata> = BTreeMap::new();
+        for module in modules {
+            for reference in &module.references {
+                let (display_name, hash) = reference
+                    .symbol_name
+                    .rsplit_once('_')
+                    .unwrap_or((&reference.symbol_name, ""));
+                let origin = host_of(module);
+                segments
+                    .entry(&reference.symbol_name)
+                    .or_insert_with(|| SegmentMetadata {
+                        route: RouteFile::from_path(&origin).map(|file| file.route),
+                        origin,
+                        symbol_name: reference.symbol_name.clone(),
+                        display_name: display_name.to_string(),
+                        hash: hash.to_string(),
+                        ctx_kind: SegmentKind::from_ctx_name(&reference.ctx_name),
+                        ctx_name: reference.ctx_name.clone(),
+                        root: None,
+                        parent: None,
+                        exported: None,
+                        route_export: None,
+                        entry: None,
+                        captures: !reference.captures.is_empty(),
+                        inlined_captures: BTreeMap::new(),
+                        excerpt: None,
+                        listener: None,
+                        async_only: false,
+                        metrics: SegmentMetrics {
+                            captures: reference.captures.len(),
+                            ..SegmentMetrics::default()
+                        },
+                    });
+            }
+        }
+        segments.into_values().collect()
+    }
+}
+
+fn analyze_module(source: &Source) -> Result<AnalyzedModule> {
+    let allocator = Allocator::default();
+    let source_type = source.source_info().try_into()?;
+    let ret = Parser::new(&allocator, source.source_code(), source_type).parse();
+
+    let mut collector = QrlCollector::default();
+    collector.visit_program(&ret.program);
+
+    let exports = ret
+        .program
+        .body
+        .iter()
+        .filter_map(|statement| match statement {
+            Statement::ExportNamedDeclaration(export) => export.declaration.as_ref(),
+            _ => None,
+        })
+        .filter_map(|declaration| match declaration {
+            Declaration::VariableDeclaration(decl) => Some(decl),
+            _ => None,
+        })
+        .flat_map(|decl| decl.declarations.iter())
+        .filter_map(|declarator| declarator.id.get_identifier_name())
+        .map(|name| name.to_string())
+        .collect();
+
+    Ok(AnalyzedModule {
+        path: module_path(&source.source_info().rel_path),
+        exports,
+        references: collector.references,
+        diagnostics: ret.errors.iter().map(Diagnostic::from).collect(),
+    })
+}
+
+/// Normalizes `path` lexically, e.g. `./a/../b.js` to `b.js`.
+fn module_path(path: &Path) -> String {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir if normalized.file_name().is_some() => {
+                normalized.pop();
+            }
+            component => normalized.push(component),
+        }
+    }
+    normalized.to_string_lossy().replace('\\', "/")
+}
+
+fn strip_module_extension(path: &str) -> &str {
+    MODULE_EXTENSIONS
+        .iter()
+        .find_map(|extension| path.strip_suffix(extension))
+        .unwrap_or(path)
+}
+
+#[derive(Default)]
+struct QrlCollector {
+    references: Vec<QrlReference>,
+    /// The names of the calls enclosing the current node, innermost last.
+    callees: Vec<String>,
+}
+
+impl QrlCollector {
+    fn reference(&self, call: &CallExpression, callee: &str) -> Option<QrlReference> {
+        let symbol_name = match call.arguments.get(1)? {
+            Argument::StringLiteral(name) => name.value.to_string(),
+            _ => return None,
+        };
+        let import = if callee == INLINED_QRL {
+            None
+        } else {
+            Some(lazy_import(call.arguments.first()?)?)
+        };
+        let captures = match call.arguments.get(2) {
+            Some(Argument::ArrayExpression(array)) => array
+                .elements
+                .iter()
+                .filter_map(|element| match element {
+                    ArrayExpressionElement::Identifier(ident) => Some(ident.name.to_string()),
+                    _ => None,
+                })
+                .collect(),
+            _ => Vec::new(),
+        };
+        let ctx_name = self
+            .callees
+            .last()
+            .and_then(|wrapper| wrapper.strip_suffix(QRL_SUFFIX))
+            .filter(|prefix| !prefix.is_empty())
+            .map(|prefix| format!("{prefix}{MARKER_SUFFIX}"))
+            .unwrap_or_else(|| MARKER_SUFFIX.to_string());
+
+        Some(QrlReference {
+            symbol_name,
+            ctx_name,
+            import,
+            segment_path: None,
+            captures,
+        })
+    }
+}
+
+/// The specifier of `() => import("./segment")`.
+fn lazy_import(argument: &Argument) -> Option<String> {
+    let Argument::ArrowFunctionExpression(arrow) = argument else {
+        return None;
+    };
+    let expression = match arrow.body.statements.first()? {
+        Statement::ExpressionStatement(stmt) => &stmt.expression,
+        Statement::ReturnStatement(stmt) => stmt.argument.as_ref()?,
+        _ => return None,
+    };
+    match expression.without_parentheses() {
+        Expression::ImportExpression(import) => match &import.source {
+            Expression::StringLiteral(specifier) => Some(specifier.value.to_string()),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+impl<'a> Visit<'a> for QrlCollector {
+    fn visit_call_expression(&mut self, call: &CallExpression<'a>) {
+        let callee = call.callee_name().unwrap_or_default().to_string();
+        if [QRL, QRL_DEV, INLINED_QRL].contains(&callee.as_str()) {
+            if let Some(reference) = self.reference(call, &callee) {
+                self.references.push(reference);
+            }
+        }
+
+        self.callees.push(callee);
+        oxc_ast::visit::walk::walk_call_expression(self, call);
+        self.callees.pop();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transform::{transform_fs, TransformOptions};
+
+    #[test]
+    fn reconstructs_the_manifest_of_optimized_output() {
+        let input = PathBuf::from("src/test_input/test_example_1.tsx");
+        let result = transform_fs(vec![input], TransformOptions::default()).unwrap();
+
+        let mut sources =
+            vec![Source::from_module("lib/test.tsx", &result.modules[0].code).unwrap()];
+        for segment in &result.segments {
+            let path = format!("lib/{}.js", segment.id.local_file_name);
+            sources.push(Source::from_module(path, &segment.code).unwrap());
+        }
+
+        let analysis = Analysis::analyze(&sources).unwrap();
+
+        assert_eq!(analysis.unresolved().count(), 0);
+        let symbols = |manifest: &Manifest| {
+            manifest
+                .segments
+                .iter()
+                .map(|s| (s.symbol_name.clone(), s.ctx_name.clone(), s.captures))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(symbols(&analysis.manifest), symbols(&result.manifest));
+        assert!(analysis
+            .manifest
+            .segments
+            .iter()
+            .all(|segment| segment.origin == "lib/test.tsx"));
+
+        let clashes = analysis.clashes(&result.manifest);
+        assert_eq!(clashes.len(), result.manifest.segments.len());
+    }
+
+    #[test]
+    fn reports_unresolved_references() {
+        let host = Source::from_module(
+            "lib/index.js",
+            r#"export const App = componentQrl(qrl(() => import("./missing"), "s_abc", [a]));
+               export const Inline = inlinedQrl(() => 1, "s_def");"#,
+        )
+        .unwrap();
+
+        let analysis = Analysis::analyze(&[host]).unwrap();
+
+        let unresolved: Vec<_> = analysis.unresolved().map(|r| &r.symbol_name).collect();
+        assert_eq!(unresolved, vec!["s_abc"]);
+        let segment = analysis.manifest.segment("s_abc").unwrap();
+        assert_eq!(segment.ctx_name, "component$");
+        assert!(segment.captures);
+        assert_eq!(segment.hash, "abc");
+        assert!(analysis.manifest.segment("s_def").is_some());
+    }
+}
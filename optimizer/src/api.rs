@@ -0,0 +1,78 @@
+//! The stable interface of the optimizer, for bundler plugins and other downstream crates.
+//!
+//! Everything re-exported here follows semantic versioning: it is only removed or changed incompatibly in a new major
+//! version. The other modules of the crate expose the internals of the optimizer, e.g. its AST helpers, which change
+//! with every release and should not be depended upon.
+//!
+//! # Examples
+//! ```
+//! use qwik_optimizer::api::{transform, EntryStrategy, Target, TransformOptions};
+//!
+//! let options = TransformOptions::builder()
+//!     .mode(Target::Prod)
+//!     .entry_strategy(EntryStrategy::Segment)
+//!     .build()?;
+//! let result = transform("src/app.tsx", "export const App = component$(() => <div />);", options)?;
+//! assert_eq!(result.segments.len(), 1);
+//! assert!(result.diagnostics().next().is_none());
+//! # Ok::<(), qwik_optimizer::api::Error>(())
+//! ```
+
+// -- Entry points
+pub use crate::transform::{
+    transform as transform_source, transform_dir, transform_fs, transform_module as transform,
+    transform_modules, transform_with_registry, warm_up,
+};
+pub use crate::watch::{Optimizer, WatchUpdate};
+
+// -- Options
+pub use crate::cache::TransformCache;
+pub use crate::capture::MAX_INLINED_CAPTURE_LENGTH;
+pub use crate::chunk::{ChunkRequest, ChunkResolver};
+pub use crate::cjs::ModuleFormat;
+pub use crate::component::{Language, Markers, SanitizeMode, SymbolFormat, Target};
+pub use crate::entry_strategy::EntryStrategy;
+pub use crate::external::{ExternalMarkers, ExternalPaths};
+pub use crate::glob::FileSet;
+pub use crate::limits::ResourceLimits;
+pub use crate::listener::{ExtractedSymbol, SymbolListener};
+pub use crate::name_registry::NameRegistry;
+pub use crate::preprocess::{PreprocessRequest, Preprocessor};
+pub use crate::source::Source;
+pub use crate::transform::{
+    EmitStyle, MinifyMode, PathPrivacy, SegmentNaming, TransformFsOptions, TransformModuleInput,
+    TransformModulesOptions, TransformOptions, TransformOptionsBuilder,
+};
+
+// -- Results
+pub use crate::classify::ModuleKind;
+pub use crate::compare::{compare_outputs, ModuleChange, OutputDiff, OutputSize, SegmentChange};
+pub use crate::component::{Id, Qrl, QrlComponent, QrlDev, QrlType};
+pub use crate::entry_strategy::{Entry, EntryPlan};
+pub use crate::events::{
+    EventListener, EventModifier, EventModifierKind, EventScope, ModifierProblem,
+};
+pub use crate::intern::Name;
+pub use crate::metrics::SegmentMetrics;
+pub use crate::processing_failure::ProcessingFailure;
+pub use crate::routes::RouteExportKind;
+pub use crate::search::{find_symbols, SymbolQuery};
+pub use crate::stats::TransformStats;
+pub use crate::styles::ScopedStyle;
+pub use crate::trace::{Decision, DecisionKind, TraceEntry};
+pub use crate::transform::{OptimizationResult, OptimizedApp, TransformResult, TransformedModule};
+
+// -- Diagnostics
+pub use crate::diagnostic::{Diagnostic, DiagnosticCode, Label, Severity};
+pub use crate::error::Error;
+pub use crate::invariants::{check_id, check_identifier, check_ids, NamingViolation};
+pub use crate::prelude::Result;
+
+// -- Manifest
+pub use crate::emit::MANIFEST_FILE_NAME;
+#[cfg(feature = "manifest")]
+pub use crate::entry_strategy::simulate_strategy;
+#[cfg(feature = "manifest")]
+pub use crate::manifest::{Manifest, NameMapping};
+pub use crate::manifest::{SegmentEnvironment, SegmentKind, SegmentMetadata};
+pub use crate::symbol_registry::SymbolRegistry;
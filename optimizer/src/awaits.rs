@@ -0,0 +1,92 @@
+use oxc_ast::ast::*;
+use oxc_ast::visit::walk;
+use oxc_ast::Visit;
+use oxc_span::Span;
+use oxc_syntax::scope::ScopeFlags;
+
+/// Returns the spans of the `await` expressions of `expr` which are not nested in a function, i.e. which await at
+/// the top level of the module the segment of `expr` is extracted into, e.g. `$(await loadConfig())`. Such segments
+/// can only be loaded asynchronously, see [QrlComponent::async_only](crate::component::QrlComponent::async_only).
+pub(crate) fn top_level_awaits(expr: &Expression) -> Vec<Span> {
+    let mut awaits = TopLevelAwaits::default();
+    awaits.visit_expression(expr);
+    awaits.spans
+}
+
+/// Whether `expr` is an `async` function, e.g. `async () => {}`.
+pub(crate) fn is_async_function(expr: &Expression) -> bool {
+    match expr.without_parentheses() {
+        Expression::ArrowFunctionExpression(arrow) => arrow.r#async,
+        Expression::FunctionExpression(function) => function.r#async,
+        _ => false,
+    }
+}
+
+#[derive(Default)]
+struct TopLevelAwaits {
+    spans: Vec<Span>,
+}
+
+impl<'a> Visit<'a> for TopLevelAwaits {
+    fn visit_await_expression(&mut self, expr: &AwaitExpression<'a>) {
+        self.spans.push(expr.span);
+        walk::walk_await_expression(self, expr);
+    }
+
+    fn visit_function(&mut self, _: &Function<'a>, _: ScopeFlags) {}
+
+    fn visit_arrow_function_expression(&mut self, _: &ArrowFunctionExpression<'a>) {}
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use oxc_allocator::Allocator;
+    use oxc_parser::Parser;
+    use oxc_span::SourceType;
+
+    fn parse<'a>(allocator: &'a Allocator, source: &'a str) -> Expression<'a> {
+        Parser::new(allocator, source, SourceType::mjs())
+            .parse_expression()
+            .unwrap()
+    }
+
+    #[test]
+    fn finds_top_level_awaits() {
+        let allocator = Allocator::default();
+        let source =
+            "[await config, async () => await load(), { value: await (await fetch()).json() }]";
+        let awaits: Vec<&str> = top_level_awaits(&parse(&allocator, source))
+            .into_iter()
+            .map(|span| &source[span.start as usize..span.end as usize])
+            .collect();
+        assert_eq!(
+            awaits,
+            vec![
+                "await config",
+                "await (await fetch()).json()",
+                "await fetch()"
+            ]
+        );
+        assert!(
+            top_level_awaits(&parse(&allocator, "async function () { await load(); }")).is_empty()
+        );
+    }
+
+    #[test]
+    fn detects_async_functions() {
+        let allocator = Allocator::default();
+        assert!(is_async_function(&parse(
+            &allocator,
+            "(async (event) => {})"
+        )));
+        assert!(is_async_function(&parse(
+            &allocator,
+            "async function () {}"
+        )));
+        assert!(!is_async_function(&parse(
+            &allocator,
+            "(event) => event.preventDefault()"
+        )));
+    }
+}
@@ -0,0 +1,334 @@
+use qwik_optimizer::api::{
+    transform_dir, transform_fs, EntryStrategy, Manifest, SegmentMetadata, Target,
+    TransformOptions, TransformResult,
+};
+use std::io;
+use std::path::PathBuf;
+use std::process::ExitCode;
+use std::time::Instant;
+
+const USAGE: &str = "Usage: qwik-optimize --serve
+       qwik-optimize build <src-dir> [--target <target>] [--entry-strategy <strategy>] [--out-dir <dir>]
+       qwik-optimize symbols grep <query> <manifest.json | file...>
+       qwik-optimize verify [--threads <n>,<m>] <file...>
+
+Options:
+  --serve    Serve JSON-RPC transform requests over stdin/stdout, one message per line
+
+Commands:
+  build           Transform the modules below <src-dir> in parallel, write the modules, segments and manifest to
+                  <dir> (`dist` by default), and print a summary. <target> is one of prod, lib, dev or test
+                  (resolved from NODE_ENV by default), <strategy> one of inline, hoist, single, hook, segment,
+                  component or smart (segment by default). Fails if any module has errors
+  symbols grep    Print the metadata of the segments matching <query>, read from a manifest or extracted from the
+                  given files. <query> matches display names, origins or hash prefixes, or only one of them with a
+                  `name:`, `origin:` or `hash:` prefix
+  verify          Transform the given files twice, on <n> and then on <m> threads (1 and the number of CPUs by
+                  default), and fail unless both runs produce byte-identical modules, segments and manifests";
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    match args
+        .iter()
+        .map(String::as_str)
+        .collect::<Vec<_>>()
+        .as_slice()
+    {
+        ["--serve"] => {
+            match qwik_optimizer::serve::serve(io::stdin().lock(), io::stdout().lock()) {
+                Ok(()) => ExitCode::SUCCESS,
+                Err(e) => {
+                    eprintln!("qwik-optimize: {e}");
+                    ExitCode::FAILURE
+                }
+            }
+        }
+        ["build", args @ ..] => match BuildArgs::parse(args) {
+            Ok(args) => build(&args),
+            Err(e) => {
+                eprintln!("qwik-optimize: {e}\n\n{USAGE}");
+                ExitCode::from(2)
+            }
+        },
+        ["symbols", "grep", query, paths @ ..] if !paths.is_empty() => {
+            match grep_symbols(query, paths) {
+                Ok(segments) if segments.is_empty() => ExitCode::FAILURE,
+                Ok(segments) => {
+                    let json = serde_json::to_string_pretty(&segments).unwrap_or_default();
+                    println!("{json}");
+                    ExitCode::SUCCESS
+                }
+                Err(e) => {
+                    eprintln!("qwik-optimize: {e}");
+                    ExitCode::FAILURE
+                }
+            }
+        }
+        ["verify", "--threads", threads, paths @ ..] if !paths.is_empty() => {
+            match parse_threads(threads) {
+                Some(threads) => verify(threads, paths),
+                None => {
+                    eprintln!("qwik-optimize: invalid thread counts '{threads}'");
+                    ExitCode::from(2)
+                }
+            }
+        }
+        ["verify", paths @ ..] if !paths.is_empty() => {
+            let cpus = std::thread::available_parallelism().map_or(1, usize::from);
+            verify([1, cpus], paths)
+        }
+        ["--help"] | ["-h"] => {
+            println!("{USAGE}");
+            ExitCode::SUCCESS
+        }
+        _ => {
+            eprintln!("{USAGE}");
+            ExitCode::from(2)
+        }
+    }
+}
+
+/// The arguments of the `build` command.
+#[derive(Debug)]
+struct BuildArgs {
+    src_dir: PathBuf,
+    out_dir: PathBuf,
+    target: Target,
+    entry_strategy: EntryStrategy,
+}
+
+impl BuildArgs {
+    fn parse(args: &[&str]) -> Result<BuildArgs, String> {
+        let mut src_dir = None;
+        let mut out_dir = PathBuf::from("dist");
+        let mut target = None;
+        let mut entry_strategy = EntryStrategy::default();
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            let mut value = || {
+                args.next()
+                    .ok_or_else(|| format!("missing value for {arg}"))
+            };
+            match *arg {
+                "--out-dir" => out_dir = PathBuf::from(value()?),
+                "--target" => target = Some(*value()?),
+                "--entry-strategy" => {
+                    entry_strategy = value()?.parse().map_err(|e| format!("{e}"))?
+                }
+                arg if arg.starts_with("--") => return Err(format!("unknown option {arg}")),
+                arg if src_dir.is_none() => src_dir = Some(PathBuf::from(arg)),
+                arg => return Err(format!("unexpected argument {arg}")),
+            }
+        }
+        Ok(BuildArgs {
+            src_dir: src_dir.ok_or("missing <src-dir>")?,
+            out_dir,
+            target: Target::resolve(target).map_err(|e| format!("{e}"))?,
+            entry_strategy,
+        })
+    }
+}
+
+/// Transforms the modules below the source directory, writes them to the output directory and prints a summary.
+fn build(args: &BuildArgs) -> ExitCode {
+    let start = Instant::now();
+    let options = TransformOptions::builder()
+        .mode(args.target)
+        .entry_strategy(args.entry_strategy)
+        .build();
+    let result = options
+        .and_then(|options| transform_dir(&args.src_dir, &[] as &[&str], options))
+        .and_then(|result| {
+            let written = result.emit_to_disk(&args.out_dir)?;
+            Ok((result, written))
+        });
+    let (result, written) = match result {
+        Ok(result) => result,
+        Err(e) => {
+            eprintln!("qwik-optimize: {e}");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    print!("{}", summary(&result));
+    println!(
+        "Wrote {} files to {} in {} ms ({}, {} entries)",
+        written.len(),
+        args.out_dir.display(),
+        start.elapsed().as_millis(),
+        args.target,
+        format!("{:?}", args.entry_strategy).to_lowercase()
+    );
+    for diagnostic in result.diagnostics() {
+        eprintln!("{diagnostic}");
+    }
+    let failed = result.diagnostics().any(|diagnostic| diagnostic.is_error());
+    match failed {
+        true => ExitCode::FAILURE,
+        false => ExitCode::SUCCESS,
+    }
+}
+
+/// A table of the modules of `result`, with the number of their segments, the size of their code and the number of
+/// their errors and warnings, followed by the totals.
+fn summary(result: &TransformResult) -> String {
+    let mut rows: Vec<[String; 5]> = result
+        .modules
+        .iter()
+        .map(|module| {
+            let origin = module.path.to_string_lossy().to_string();
+            let segments = result
+                .segments
+                .iter()
+                .filter(|segment| module.path.ends_with(&segment.origin))
+                .count();
+            let errors = module.diagnostics.iter().filter(|d| d.is_error()).count();
+            [
+                origin,
+                segments.to_string(),
+                module.code.len().to_string(),
+                errors.to_string(),
+                (module.diagnostics.len() - errors).to_string(),
+            ]
+        })
+        .collect();
+    let total = |column: usize| -> String {
+        rows.iter()
+            .map(|row| row[column].parse::<usize>().unwrap_or(0))
+            .sum::<usize>()
+            .to_string()
+    };
+    let totals = [
+        "Total".to_string(),
+        result.segments.len().to_string(),
+        total(2),
+        total(3),
+        total(4),
+    ];
+    rows.insert(
+        0,
+        ["Module", "Segments", "Bytes", "Errors", "Warnings"].map(str::to_string),
+    );
+    rows.push(totals);
+
+    let widths: Vec<usize> = (0..5)
+        .map(|column| rows.iter().map(|row| row[column].len()).max().unwrap_or(0))
+        .collect();
+    let mut table = String::new();
+    for row in &rows {
+        table.push_str(&format!("{:<width$}", row[0], width = widths[0]));
+        for (cell, width) in row.iter().zip(&widths).skip(1) {
+            table.push_str(&format!("  {cell:>width$}"));
+        }
+        table.push('\n');
+    }
+    table
+}
+
+/// Searches the manifest at `paths`, or the segments extracted from the files at `paths`.
+fn grep_symbols(query: &str, paths: &[&str]) -> qwik_optimizer::api::Result<Vec<SegmentMetadata>> {
+    if let [path] = paths {
+        if path.ends_with(".json") {
+            let manifest = Manifest::from_json(std::fs::read_to_string(path)?)?;
+            return Ok(manifest.find_symbol(query).into_iter().cloned().collect());
+        }
+    }
+    let paths = paths.iter().map(PathBuf::from).collect();
+    let result = transform_fs(paths, TransformOptions::default())?;
+    Ok(result.find_symbol(query))
+}
+
+/// Parses thread counts written as `<n>,<m>`, e.g. `1,8`.
+fn parse_threads(threads: &str) -> Option<[usize; 2]> {
+    let (first, second) = threads.split_once(',')?;
+    let parse = |count: &str| count.trim().parse().ok().filter(|count| *count > 0);
+    Some([parse(first)?, parse(second)?])
+}
+
+/// Transforms the files at `paths` once on each of the thread pools sized by `threads`, and reports whether the
+/// results are identical.
+fn verify(threads: [usize; 2], paths: &[&str]) -> ExitCode {
+    let paths: Vec<PathBuf> = paths.iter().map(PathBuf::from).collect();
+    let run = |threads: usize| -> Result<TransformResult, String> {
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(threads)
+            .build()
+            .map_err(|e| e.to_string())?;
+        pool.install(|| transform_fs(paths.clone(), TransformOptions::default()))
+            .map_err(|e| e.to_string())
+    };
+    let (first, second) = match (run(threads[0]), run(threads[1])) {
+        (Ok(first), Ok(second)) => (first, second),
+        (Err(e), _) | (_, Err(e)) => {
+            eprintln!("qwik-optimize: {e}");
+            return ExitCode::FAILURE;
+        }
+    };
+    let differences = differences(&first, &second);
+    if differences.is_empty() {
+        println!(
+            "Deterministic: {} modules and {} segments are identical on {} and {} threads",
+            first.modules.len(),
+            first.segments.len(),
+            threads[0],
+            threads[1]
+        );
+        return ExitCode::SUCCESS;
+    }
+    eprintln!(
+        "Not deterministic: the runs on {} and {} threads differ",
+        threads[0], threads[1]
+    );
+    for difference in differences {
+        eprintln!("  {difference}");
+    }
+    ExitCode::FAILURE
+}
+
+/// Describes how `second` differs from `first`: which modules, segments or manifests are not byte-identical.
+fn differences(first: &TransformResult, second: &TransformResult) -> Vec<String> {
+    let mut differences = Vec::new();
+    let modules = |result: &TransformResult| -> Vec<(String, String)> {
+        result
+            .modules
+            .iter()
+            .map(|module| (module.path.to_string_lossy().to_string(), json(module)))
+            .collect()
+    };
+    let segments = |result: &TransformResult| -> Vec<(String, String)> {
+        result
+            .segments
+            .iter()
+            .map(|segment| (segment.id.symbol_name.to_string(), json(segment)))
+            .collect()
+    };
+    for (kind, first, second) in [
+        ("module", modules(first), modules(second)),
+        ("segment", segments(first), segments(second)),
+    ] {
+        let names = |entries: &[(String, String)]| -> Vec<String> {
+            entries.iter().map(|(name, _)| name.clone()).collect()
+        };
+        if names(&first) != names(&second) {
+            differences.push(format!("the {kind}s are produced in a different order"));
+        }
+        for (name, json) in &first {
+            match second.iter().find(|(other, _)| other == name) {
+                Some((_, other)) if other == json => {}
+                Some(_) => differences.push(format!("{kind} '{name}' differs")),
+                None => differences.push(format!("{kind} '{name}' is missing")),
+            }
+        }
+    }
+    if json(&first.manifest) != json(&second.manifest) {
+        differences.push("the manifests differ".to_string());
+    }
+    if json(first) != json(second) && differences.is_empty() {
+        differences.push("the results differ".to_string());
+    }
+    differences
+}
+
+fn json<T: serde::Serialize>(value: &T) -> String {
+    serde_json::to_string(value).unwrap_or_default()
+}
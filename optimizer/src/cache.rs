@@ -0,0 +1,301 @@
+use crate::hash::StableHasher;
+use crate::prelude::*;
+use crate::source::Source;
+use crate::transform::{transform, OptimizationResult, TransformOptions};
+#[cfg(feature = "serde")]
+use crate::{
+    diagnostic::Diagnostic, entry_strategy::EntryPlan, styles::ScopedStyle, trace::TraceEntry,
+    transform::OptimizedApp,
+};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+
+/// The directory, relative to the project root, used by [TransformCache::on_disk].
+pub const CACHE_DIR: &str = "node_modules/.cache/qwik-optimizer";
+
+/// Remembers the result of transforming a file, so that transforming it again with the same contents and options
+/// returns instantly, e.g. when a dev server re-requests an unchanged module.
+///
+/// Results are keyed by the path of the source, a hash of its contents, a hash of the [TransformOptions] and the
+/// version of the optimizer. Transforms with a [chunk_resolver](TransformOptions::chunk_resolver) or a
+/// [preprocessor](TransformOptions::preprocessor) are never cached, since their output depends on callbacks which can
+/// not be hashed. The in-memory cache keeps the latest result of every path. The optional on-disk cache,
+/// available with the `serde` feature, additionally persists results across processes, one JSON file per key.
+///
+/// The cache is best-effort: a cache file which can not be read or written is treated as a miss. Results with
+/// processing failures are never written to disk, since their failures reference ids which are only meaningful
+/// within a single transform.
+///
+/// A cache can be shared between threads, e.g. by the rayon pool of [transform_fs](crate::transform::transform_fs).
+#[derive(Debug, Default)]
+pub struct TransformCache {
+    entries: Mutex<HashMap<PathBuf, (u64, OptimizationResult)>>,
+    dir: Option<PathBuf>,
+    hits: AtomicUsize,
+    misses: AtomicUsize,
+}
+
+/// The serialized form of an [OptimizationResult] without processing failures.
+#[cfg(feature = "serde")]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "camelCase"))]
+struct CachedResult {
+    app: OptimizedApp,
+    diagnostics: Vec<Diagnostic>,
+    trace: Vec<TraceEntry>,
+    entry_plan: EntryPlan,
+    styles: Vec<ScopedStyle>,
+}
+
+impl TransformCache {
+    pub fn in_memory() -> Self {
+        Self::default()
+    }
+
+    /// Creates a cache which also persists results under [CACHE_DIR] of the project located at `root_dir`.
+    #[cfg(feature = "serde")]
+    pub fn on_disk<P: AsRef<Path>>(root_dir: P) -> Self {
+        Self::in_dir(root_dir.as_ref().join(CACHE_DIR))
+    }
+
+    /// Creates a cache which also persists results in `dir`.
+    #[cfg(feature = "serde")]
+    pub fn in_dir<P: Into<PathBuf>>(dir: P) -> Self {
+        Self {
+            dir: Some(dir.into()),
+            ..Self::default()
+        }
+    }
+
+    /// Transforms `source`, or returns the cached result of a previous transform of the same contents with the
+    /// same options.
+    pub fn transform(
+        &self,
+        source: Source,
+        options: &TransformOptions,
+    ) -> Result<OptimizationResult> {
+        let Some(key) = Self::key(&source, options) else {
+            self.misses.fetch_add(1, Ordering::Relaxed);
+            return transform(source, options.clone());
+        };
+        let path = source.source_info().rel_path.clone();
+
+        if let Some(result) = self.lookup(&path, key) {
+            self.hits.fetch_add(1, Ordering::Relaxed);
+            return Ok(result);
+        }
+        self.misses.fetch_add(1, Ordering::Relaxed);
+
+        let result = transform(source, options.clone())?;
+        self.store(path, key, &result);
+        Ok(result)
+    }
+
+    /// The number of transforms answered from the cache.
+    pub fn hits(&self) -> usize {
+        self.hits.load(Ordering::Relaxed)
+    }
+
+    /// The number of transforms which had to be performed.
+    pub fn misses(&self) -> usize {
+        self.misses.load(Ordering::Relaxed)
+    }
+
+    /// Forgets the result cached in memory for the file at `path`. Results persisted to disk are kept, but only
+    /// returned for the contents they were computed from.
+    pub fn invalidate(&self, path: &Path) {
+        self.entries().remove(path);
+    }
+
+    /// Forgets every cached result, including those persisted to disk.
+    pub fn clear(&self) -> Result<()> {
+        self.entries().clear();
+        if let Some(dir) = self.dir.as_ref().filter(|dir| dir.exists()) {
+            fs::remove_dir_all(dir)?;
+        }
+        Ok(())
+    }
+
+    /// The key of the result of transforming `source` with `options`, which is stable across processes, or `None` if
+    /// the result can not be cached. The [symbol_listener](TransformOptions::symbol_listener) only observes results,
+    /// so it is left out.
+    fn key(source: &Source, options: &TransformOptions) -> Option<u64> {
+        if options.chunk_resolver.is_some() || options.preprocessor.is_some() {
+            return None;
+        }
+        let options = TransformOptions {
+            symbol_listener: None,
+            ..options.clone()
+        };
+        let mut hasher = StableHasher::new();
+        env!("CARGO_PKG_VERSION").hash(&mut hasher);
+        source.source_info().rel_path.hash(&mut hasher);
+        source.source_code().hash(&mut hasher);
+        options.hash(&mut hasher);
+        Some(hasher.finish())
+    }
+
+    fn lookup(&self, path: &Path, key: u64) -> Option<OptimizationResult> {
+        if let Some((cached_key, result)) = self.entries().get(path) {
+            if *cached_key == key {
+                return Some(result.clone());
+            }
+        }
+
+        let result = self.read(key)?;
+        self.entries()
+            .insert(path.to_path_buf(), (key, result.clone()));
+        Some(result)
+    }
+
+    fn store(&self, path: PathBuf, key: u64, result: &OptimizationResult) {
+        self.entries().insert(path, (key, result.clone()));
+        self.write(key, result);
+    }
+
+    #[cfg(feature = "serde")]
+    fn read(&self, key: u64) -> Option<OptimizationResult> {
+        let json = fs::read_to_string(self.file(key)?).ok()?;
+        let cached: CachedResult = serde_json::from_str(&json).ok()?;
+        Some(OptimizationResult::new(
+            cached.app,
+            Vec::new(),
+            cached.diagnostics,
+            cached.trace,
+            cached.entry_plan,
+            cached.styles,
+        ))
+    }
+
+    #[cfg(feature = "serde")]
+    fn write(&self, key: u64, result: &OptimizationResult) {
+        let Some(file) = self.file(key).filter(|_| result.errors().is_empty()) else {
+            return;
+        };
+        let cached = CachedResult {
+            app: result.optimized_app().clone(),
+            diagnostics: result.diagnostics().to_vec(),
+            trace: result.trace().to_vec(),
+            entry_plan: result.entry_plan().clone(),
+            styles: result.styles().to_vec(),
+        };
+        if let (Some(dir), Ok(json)) = (file.parent(), serde_json::to_string(&cached)) {
+            // Written to a temporary file first, so that concurrent readers never see a partial entry.
+            let tmp = file.with_extension(format!("{}.tmp", std::process::id()));
+            let _ = fs::create_dir_all(dir)
+                .and_then(|_| fs::write(&tmp, json))
+                .and_then(|_| fs::rename(&tmp, &file));
+        }
+    }
+
+    /// Caches can only be created in memory without the `serde` feature.
+    #[cfg(not(feature = "serde"))]
+    fn read(&self, _key: u64) -> Option<OptimizationResult> {
+        None
+    }
+
+    #[cfg(not(feature = "serde"))]
+    fn write(&self, _key: u64, _result: &OptimizationResult) {}
+
+    #[cfg(feature = "serde")]
+    fn file(&self, key: u64) -> Option<PathBuf> {
+        self.dir
+            .as_ref()
+            .map(|dir| dir.join(format!("{key:016x}.json")))
+    }
+
+    fn entries(&self) -> std::sync::MutexGuard<'_, HashMap<PathBuf, (u64, OptimizationResult)>> {
+        self.entries
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chunk::ChunkResolver;
+    use crate::listener::SymbolListener;
+
+    const TEST_FILE: &str = "./src/test_input/test_example_1.tsx";
+
+    #[test]
+    fn returns_cached_results_for_unchanged_sources() {
+        let cache = TransformCache::in_memory();
+        let options = TransformOptions::default();
+
+        let first = cache
+            .transform(Source::from_file(TEST_FILE).unwrap(), &options)
+            .unwrap();
+        let second = cache
+            .transform(Source::from_file(TEST_FILE).unwrap(), &options)
+            .unwrap();
+        assert_eq!((cache.hits(), cache.misses()), (1, 1));
+        assert_eq!(first.optimized_app(), second.optimized_app());
+
+        let changed = Source::from_module(TEST_FILE, "export const a = 1;").unwrap();
+        cache.transform(changed, &options).unwrap();
+        let minified = TransformOptions {
+            minify: crate::transform::MinifyMode::Simplify,
+            ..TransformOptions::default()
+        };
+        cache
+            .transform(Source::from_file(TEST_FILE).unwrap(), &minified)
+            .unwrap();
+        assert_eq!((cache.hits(), cache.misses()), (1, 3));
+    }
+
+    #[test]
+    fn skips_options_with_callbacks() {
+        let cache = TransformCache::in_memory();
+        let transform = |options: &TransformOptions| {
+            cache
+                .transform(Source::from_file(TEST_FILE).unwrap(), options)
+                .unwrap();
+        };
+
+        let resolved = TransformOptions {
+            chunk_resolver: Some(ChunkResolver::new(|_| None)),
+            ..TransformOptions::default()
+        };
+        transform(&resolved);
+        transform(&resolved);
+        assert_eq!((cache.hits(), cache.misses()), (0, 2));
+
+        // Listeners do not change results, and are not notified of cached ones.
+        transform(&TransformOptions::default());
+        transform(&TransformOptions {
+            symbol_listener: Some(SymbolListener::new(|_| {})),
+            ..TransformOptions::default()
+        });
+        assert_eq!((cache.hits(), cache.misses()), (1, 3));
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn persists_results_on_disk() {
+        let dir = std::env::temp_dir().join(format!("qwik-optimizer-cache-{}", std::process::id()));
+        let options = TransformOptions::default();
+
+        let first = TransformCache::in_dir(&dir)
+            .transform(Source::from_file(TEST_FILE).unwrap(), &options)
+            .unwrap();
+
+        let cache = TransformCache::in_dir(&dir);
+        let second = cache
+            .transform(Source::from_file(TEST_FILE).unwrap(), &options)
+            .unwrap();
+        assert_eq!(cache.hits(), 1);
+        assert_eq!(first.optimized_app(), second.optimized_app());
+        assert_eq!(first.entry_plan(), second.entry_plan());
+
+        cache.clear().unwrap();
+        assert!(!dir.exists());
+    }
+}
@@ -0,0 +1,563 @@
+use crate::error::Error;
+use crate::prelude::*;
+use crate::processing_failure::ProcessingFailure;
+use oxc_allocator::Box as OxcBox;
+use oxc_ast::ast::*;
+use oxc_ast::{AstBuilder, Visit};
+use oxc_codegen::Codegen;
+use oxc_parser::Parser;
+use oxc_semantic::{ScopeTree, SymbolId, SymbolTable};
+use oxc_span::{GetSpan, SourceType, Span, SPAN};
+use std::collections::{BTreeMap, HashMap};
+
+/// The function a segment calls to receive the values it captured from its enclosing scope.
+pub const USE_LEXICAL_SCOPE: &str = "useLexicalScope";
+
+/// The length of the longest printed value inlined into a segment rather than captured, see
+/// [TransformOptions::inline_const_captures](crate::transform::TransformOptions::inline_const_captures).
+pub const MAX_INLINED_CAPTURE_LENGTH: usize = 128;
+
+/// A value that Qwik can not serialize, and which therefore can not be captured by a segment.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Unserializable {
+    Function,
+    ClassInstance,
+}
+
+impl Unserializable {
+    /// Classifies the value a variable is initialized with, e.g. `() => {}` or `new Date()`.
+    pub(crate) fn of(init: &Expression) -> Option<Unserializable> {
+        match init.without_parentheses() {
+            Expression::ArrowFunctionExpression(_) | Expression::FunctionExpression(_) => {
+                Some(Unserializable::Function)
+            }
+            Expression::NewExpression(_) | Expression::ClassExpression(_) => {
+                Some(Unserializable::ClassInstance)
+            }
+            _ => None,
+        }
+    }
+
+    pub(crate) fn description(&self) -> &'static str {
+        match self {
+            Unserializable::Function => "function",
+            Unserializable::ClassInstance => "class instance",
+        }
+    }
+}
+
+/// A capture which can not be serialized, located at the declaration of the captured variable.
+pub(crate) type CaptureFailure = (ProcessingFailure, Span);
+
+/// The captured identifiers, inlined constants and capture failures of a segment, see [Captures::collect].
+pub(crate) type CollectedCaptures = (Vec<String>, BTreeMap<String, String>, Vec<CaptureFailure>);
+
+/// Determines which identifiers an extracted expression closes over.
+///
+/// An identifier is captured when it resolves to a binding declared outside the expression in any scope other than
+/// the module scope. Module-level bindings are imports or top-level declarations, which do not need to be passed to
+/// the segment at runtime. References in types, e.g. `(item: Item) => ...` or `typeof item`, are erased at runtime,
+/// so they never capture anything.
+///
+/// [V 1.0 REF] see `compute_scoped_idents` in `code_move.rs`.
+pub(crate) struct Captures<'s> {
+    span: Span,
+    symbols: &'s SymbolTable,
+    root_scope: oxc_semantic::ScopeId,
+    captured: BTreeMap<String, SymbolId>,
+    inlined: BTreeMap<String, String>,
+    is_excluded: &'s dyn Fn(SymbolId) -> bool,
+    unserializable: &'s HashMap<SymbolId, Unserializable>,
+    constants: &'s HashMap<SymbolId, String>,
+}
+
+impl<'s> Captures<'s> {
+    /// Returns the captured identifiers of `arg`, sorted by name, the values of the captured `constants` by name,
+    /// which are inlined rather than captured, and a failure for every capture that can not be serialized, located at
+    /// the declaration of the captured variable. Symbols for which `is_excluded` returns `true` are never captured,
+    /// and captures of the `unserializable` symbols are reported as failures.
+    pub fn collect(
+        arg: &Argument,
+        symbols: &'s SymbolTable,
+        scopes: &ScopeTree,
+        is_excluded: &'s dyn Fn(SymbolId) -> bool,
+        unserializable: &'s HashMap<SymbolId, Unserializable>,
+        constants: &'s HashMap<SymbolId, String>,
+    ) -> CollectedCaptures {
+        let mut captures = Captures {
+            span: arg.span(),
+            symbols,
+            root_scope: scopes.root_scope_id(),
+            captured: BTreeMap::new(),
+            inlined: BTreeMap::new(),
+            is_excluded,
+            unserializable,
+            constants,
+        };
+        captures.visit_argument(arg);
+
+        let failures = captures
+            .captured
+            .iter()
+            .filter_map(|(name, symbol_id)| captures.validate(name, *symbol_id))
+            .collect();
+
+        (
+            captures.captured.into_keys().collect(),
+            captures.inlined,
+            failures,
+        )
+    }
+
+    /// Captured values are serialized, so they must be `const`: a reassigned variable would diverge from the value
+    /// the segment received. Captured function and class declarations are reported as
+    /// [IllegalCode](crate::illegal_code) by the transform and excluded via `is_excluded`.
+    fn validate(&self, name: &str, symbol_id: SymbolId) -> Option<CaptureFailure> {
+        let is_mutable = self
+            .symbols
+            .get_resolved_references(symbol_id)
+            .any(|reference| reference.is_write());
+
+        let failure = if is_mutable {
+            ProcessingFailure::MutableCapture(name.to_string())
+        } else {
+            let kind = self.unserializable.get(&symbol_id)?;
+            ProcessingFailure::UnserializableCapture(name.to_string(), *kind)
+        };
+        Some((failure, self.symbols.get_span(symbol_id)))
+    }
+}
+
+impl<'a> Visit<'a> for Captures<'_> {
+    fn visit_identifier_reference(&mut self, id_ref: &IdentifierReference<'a>) {
+        let Some(symbol_id) = id_ref
+            .reference_id
+            .get()
+            .map(|ref_id| self.symbols.get_reference(ref_id))
+            .filter(|reference| reference.is_value())
+            .and_then(|reference| reference.symbol_id())
+        else {
+            return;
+        };
+
+        let declared_outside = !self
+            .span
+            .contains_inclusive(self.symbols.get_span(symbol_id));
+        if declared_outside
+            && self.symbols.get_scope_id(symbol_id) != self.root_scope
+            && !(self.is_excluded)(symbol_id)
+        {
+            let name = self.symbols.get_name(symbol_id).to_string();
+            if let Some(value) = self.constants.get(&symbol_id) {
+                self.inlined.insert(name, value.clone());
+            } else {
+                self.captured.insert(name, symbol_id);
+            }
+        }
+    }
+}
+
+/// Prepends `const [a, b] = useLexicalScope();` to the body of a segment function, so that it receives the values
+/// passed via the capture array of its `qrl(...)` call.
+///
+/// Arrow functions with an expression body are converted to a block body. Expressions other than functions are
+/// left untouched.
+pub(crate) fn inject_lexical_scope<'a>(
+    expr: &mut Expression<'a>,
+    captures: &[String],
+    ast: AstBuilder<'a>,
+) {
+    if captures.is_empty() {
+        return;
+    }
+    if let Some(body) = function_body(expr, ast) {
+        body.statements
+            .insert(0, lexical_scope_declaration(captures, ast));
+    }
+}
+
+/// Prepends `const name = value;` to the body of a segment function for each of the captured `constants`, printed
+/// by [inlinable_value], so that the segment does not need to capture them. Returns `false`, leaving `expr`
+/// untouched, if it is not a function, and fails with [Error::Parse] if a value is not an expression.
+pub(crate) fn inline_constants<'a>(
+    expr: &mut Expression<'a>,
+    constants: &BTreeMap<String, String>,
+    ast: AstBuilder<'a>,
+) -> Result<bool> {
+    let Some(body) = function_body(expr, ast) else {
+        return Ok(false);
+    };
+    for (index, (name, value)) in constants.iter().enumerate() {
+        let value = Parser::new(
+            ast.allocator,
+            ast.allocator.alloc_str(value),
+            SourceType::mjs(),
+        )
+        .parse_expression()
+        .map_err(|errors| {
+            let message = errors.first().map(ToString::to_string).unwrap_or_default();
+            Error::Parse(value.clone(), message)
+        })?;
+        let declarator = ast.variable_declarator(
+            SPAN,
+            VariableDeclarationKind::Const,
+            ast.binding_pattern(
+                ast.binding_pattern_kind_binding_identifier(SPAN, name.as_str()),
+                None::<OxcBox<TSTypeAnnotation>>,
+                false,
+            ),
+            Some(value),
+            false,
+        );
+        let declaration = ast.alloc_variable_declaration(
+            SPAN,
+            VariableDeclarationKind::Const,
+            ast.vec1(declarator),
+            false,
+        );
+        body.statements
+            .insert(index, Statement::VariableDeclaration(declaration));
+    }
+    Ok(true)
+}
+
+/// The printed value of a `const` variable initialized with `init`, if it can be inlined into the segments
+/// capturing it: a primitive literal, or an `Object.freeze` of an object or array literal whose values can be
+/// inlined, printed in at most [MAX_INLINED_CAPTURE_LENGTH] bytes.
+pub(crate) fn inlinable_value(init: &Expression) -> Option<String> {
+    if !is_constant(init) {
+        return None;
+    }
+    let mut codegen = Codegen::new();
+    codegen.print_expression(init);
+    Some(codegen.into_source_text()).filter(|value| value.len() <= MAX_INLINED_CAPTURE_LENGTH)
+}
+
+fn is_constant(expr: &Expression) -> bool {
+    match expr.without_parentheses() {
+        Expression::StringLiteral(_)
+        | Expression::NumericLiteral(_)
+        | Expression::BigIntLiteral(_)
+        | Expression::BooleanLiteral(_)
+        | Expression::NullLiteral(_) => true,
+        Expression::TemplateLiteral(template) => template.expressions.is_empty(),
+        Expression::UnaryExpression(unary) => {
+            unary.operator == UnaryOperator::UnaryNegation
+                && matches!(unary.argument, Expression::NumericLiteral(_))
+        }
+        Expression::CallExpression(call) => {
+            let is_freeze = matches!(
+                &call.callee,
+                Expression::StaticMemberExpression(member)
+                    if member.property.name == "freeze"
+                        && matches!(&member.object, Expression::Identifier(object) if object.name == "Object")
+            );
+            let frozen = match call.arguments.as_slice() {
+                [Argument::ObjectExpression(object)] => object.properties.iter().all(|property| {
+                    matches!(
+                        property,
+                        ObjectPropertyKind::ObjectProperty(property)
+                            if property.kind == PropertyKind::Init
+                                && !property.computed
+                                && !property.method
+                                && !property.shorthand
+                                && is_constant(&property.value)
+                    )
+                }),
+                [Argument::ArrayExpression(array)] => array
+                    .elements
+                    .iter()
+                    .all(|element| element.as_expression().is_some_and(is_constant)),
+                _ => false,
+            };
+            is_freeze && call.type_parameters.is_none() && !call.optional && frozen
+        }
+        _ => false,
+    }
+}
+
+/// The body of a segment function, whose expression body is converted to a block body. `None` for expressions
+/// other than functions.
+fn function_body<'b, 'a>(
+    expr: &'b mut Expression<'a>,
+    ast: AstBuilder<'a>,
+) -> Option<&'b mut FunctionBody<'a>> {
+    let body = match expr {
+        Expression::ArrowFunctionExpression(arrow) => {
+            if arrow.expression {
+                arrow.expression = false;
+                if let Some(Statement::ExpressionStatement(stmt)) = arrow.body.statements.pop() {
+                    let returned = stmt.unbox().expression;
+                    arrow
+                        .body
+                        .statements
+                        .push(ast.statement_return(SPAN, Some(returned)));
+                }
+            }
+            &mut arrow.body
+        }
+        Expression::FunctionExpression(func) => func.body.as_mut()?,
+        _ => return None,
+    };
+    Some(body)
+}
+
+fn lexical_scope_declaration<'a>(captures: &[String], ast: AstBuilder<'a>) -> Statement<'a> {
+    let elements = ast.vec_from_iter(captures.iter().map(|name| {
+        Some(ast.binding_pattern(
+            ast.binding_pattern_kind_binding_identifier(SPAN, name.as_str()),
+            None::<OxcBox<TSTypeAnnotation>>,
+            false,
+        ))
+    }));
+    let pattern = ast.binding_pattern(
+        ast.binding_pattern_kind_array_pattern(SPAN, elements, None::<OxcBox<BindingRestElement>>),
+        None::<OxcBox<TSTypeAnnotation>>,
+        false,
+    );
+    let init = ast.expression_call(
+        SPAN,
+        ast.expression_identifier(SPAN, USE_LEXICAL_SCOPE),
+        None::<OxcBox<TSTypeParameterInstantiation>>,
+        ast.vec(),
+        false,
+    );
+    let declarator = ast.variable_declarator(
+        SPAN,
+        VariableDeclarationKind::Const,
+        pattern,
+        Some(init),
+        false,
+    );
+
+    Statement::VariableDeclaration(ast.alloc_variable_declaration(
+        SPAN,
+        VariableDeclarationKind::Const,
+        ast.vec1(declarator),
+        false,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use oxc_allocator::Allocator;
+    use oxc_codegen::Codegen;
+    use oxc_parser::Parser;
+    use oxc_semantic::{SemanticBuilder, SemanticBuilderReturn};
+    use oxc_span::SourceType;
+
+    /// Collects the captures of the first argument of the call to `$`.
+    fn captures_of(source: &str) -> (Vec<String>, Vec<String>) {
+        let allocator = Allocator::default();
+        let program = Parser::new(&allocator, source, SourceType::tsx())
+            .parse()
+            .program;
+        let SemanticBuilderReturn { semantic, .. } = SemanticBuilder::new().build(&program);
+
+        struct FindMarker<'s> {
+            semantic: &'s oxc_semantic::Semantic<'s>,
+            unserializable: &'s HashMap<SymbolId, Unserializable>,
+            found: Option<CollectedCaptures>,
+        }
+        impl<'a> Visit<'a> for FindMarker<'_> {
+            fn visit_call_expression(&mut self, call: &CallExpression<'a>) {
+                if call.callee_name() == Some("$") {
+                    self.found = Some(Captures::collect(
+                        &call.arguments[0],
+                        self.semantic.symbols(),
+                        self.semantic.scopes(),
+                        &|_| false,
+                        self.unserializable,
+                        &HashMap::new(),
+                    ));
+                }
+                oxc_ast::visit::walk::walk_call_expression(self, call);
+            }
+        }
+        let unserializable: HashMap<SymbolId, Unserializable> = semantic
+            .nodes()
+            .iter()
+            .filter_map(|node| match node.kind() {
+                oxc_ast::AstKind::VariableDeclarator(decl) => Some((
+                    decl.id.get_binding_identifier()?.symbol_id(),
+                    Unserializable::of(decl.init.as_ref()?)?,
+                )),
+                _ => None,
+            })
+            .collect();
+        let mut finder = FindMarker {
+            semantic: &semantic,
+            unserializable: &unserializable,
+            found: None,
+        };
+        finder.visit_program(&program);
+
+        let (captures, _, failures) = finder.found.unwrap();
+        (
+            captures,
+            failures.iter().map(|(f, _)| f.to_string()).collect(),
+        )
+    }
+
+    #[test]
+    fn captures_enclosing_bindings_only() {
+        let (captures, failures) = captures_of(
+            r#"
+            import { foo } from "foo";
+            const top = 1;
+            export const App = (props) => {
+                const b = 2, a = 3;
+                return $(() => { const local = 4; return [a, b, props, top, foo, local]; });
+            };
+            "#,
+        );
+
+        assert_eq!(captures, vec!["a", "b", "props"]);
+        assert!(failures.is_empty());
+    }
+
+    #[test]
+    fn ignores_type_only_references() {
+        let (captures, failures) = captures_of(
+            r#"
+            export const App = () => {
+                type Item = { id: number };
+                interface Props { item: Item }
+                const item: Item = { id: 1 }, other = 2;
+                return $((props: Props): typeof item => ({ ...props.item, id: other } as Item));
+            };
+            "#,
+        );
+
+        assert_eq!(captures, vec!["other"]);
+        assert!(failures.is_empty());
+    }
+
+    #[test]
+    fn rejects_reassigned_captures() {
+        let (captures, failures) = captures_of(
+            r#"
+            export const App = () => {
+                let count = 0, other = 0;
+                count = 1;
+                return $(() => [count, other]);
+            };
+            "#,
+        );
+
+        assert_eq!(captures, vec!["count", "other"]);
+        assert_eq!(
+            failures,
+            vec![
+                "Captured variable 'count' is reassigned, only const values can be captured by a Qrl($) scope"
+            ]
+        );
+    }
+
+    #[test]
+    fn rejects_unserializable_captures() {
+        let (captures, failures) = captures_of(
+            r#"
+            export const App = () => {
+                const format = (v) => `${v}`, date = new Date(), data = { a: 1 };
+                return $(() => [format, date, data]);
+            };
+            "#,
+        );
+
+        assert_eq!(captures, vec!["data", "date", "format"]);
+        assert_eq!(
+            failures,
+            vec![
+                "Reference to identifier 'date' can not be used inside a Qrl($) scope because it's a class instance",
+                "Reference to identifier 'format' can not be used inside a Qrl($) scope because it's a function",
+            ]
+        );
+    }
+
+    #[test]
+    fn injects_lexical_scope_into_expression_body() {
+        let allocator = Allocator::default();
+        let ast = AstBuilder::new(&allocator);
+        let mut expr = oxc_parser::Parser::new(&allocator, "(e) => a + b", SourceType::tsx())
+            .parse_expression()
+            .unwrap();
+
+        inject_lexical_scope(&mut expr, &["a".to_string(), "b".to_string()], ast);
+
+        let program = ast.program(
+            SPAN,
+            SourceType::tsx(),
+            "",
+            ast.vec(),
+            None,
+            ast.vec(),
+            ast.vec1(ast.statement_expression(SPAN, expr)),
+        );
+        let code = Codegen::new().build(&program).code;
+        assert_eq!(
+            code,
+            "(e) => {\n\tconst [a, b] = useLexicalScope();\n\treturn a + b;\n};\n"
+        );
+    }
+
+    #[test]
+    fn inlines_constant_values() {
+        let allocator = Allocator::default();
+        let value = |source: &str| {
+            let expr = Parser::new(&allocator, allocator.alloc_str(source), SourceType::tsx())
+                .parse_expression()
+                .unwrap();
+            inlinable_value(&expr)
+        };
+        assert_eq!(value(r#""Save""#).as_deref(), Some(r#""Save""#));
+        assert_eq!(value("-1.5").as_deref(), Some("-1.5"));
+        assert_eq!(value("`plain`").as_deref(), Some("`plain`"));
+        assert_eq!(
+            value("Object.freeze({ size: 2, tags: Object.freeze(['a', null]) })").as_deref(),
+            Some(
+                r#"Object.freeze({
+	size: 2,
+	tags: Object.freeze(["a", null])
+})"#
+            )
+        );
+        assert_eq!(value("`${name}`"), None);
+        assert_eq!(value("{ size: 2 }"), None);
+        assert_eq!(value("Object.freeze({ size, tags: ['a'] })"), None);
+        assert_eq!(
+            value(&format!("\"{}\"", "a".repeat(MAX_INLINED_CAPTURE_LENGTH))),
+            None
+        );
+
+        let ast = AstBuilder::new(&allocator);
+        let mut expr = Parser::new(&allocator, "() => label + size", SourceType::tsx())
+            .parse_expression()
+            .unwrap();
+        let constants = BTreeMap::from([
+            ("label".to_string(), r#""Save""#.to_string()),
+            ("size".to_string(), "2".to_string()),
+        ]);
+        assert!(inline_constants(&mut expr, &constants, ast).unwrap());
+        let program = ast.program(
+            SPAN,
+            SourceType::tsx(),
+            "",
+            ast.vec(),
+            None,
+            ast.vec(),
+            ast.vec1(ast.statement_expression(SPAN, expr)),
+        );
+        let code = Codegen::new().build(&program).code;
+        assert_eq!(
+            code,
+            "() => {\n\tconst label = \"Save\";\n\tconst size = 2;\n\treturn label + size;\n};\n"
+        );
+
+        let mut expr = Parser::new(&allocator, "label", SourceType::tsx())
+            .parse_expression()
+            .unwrap();
+        assert!(!inline_constants(&mut expr, &constants, ast).unwrap());
+    }
+}
@@ -0,0 +1,68 @@
+use std::fmt::Debug;
+use std::hash::{Hash, Hasher};
+use std::sync::Arc;
+
+/// The segment a [ChunkResolver] is asked to resolve.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ChunkRequest<'r> {
+    /// The symbol name of the segment, e.g. `App_component_ckEPmXZlub0`.
+    pub symbol_name: &'r str,
+    /// The file name the segment is emitted to, which its `qrl` imports by default, e.g.
+    /// `./app.tsx_App_component_ckEPmXZlub0`.
+    pub file_name: &'r str,
+    /// The relative path of the module the segment was extracted from.
+    pub origin: &'r str,
+}
+
+/// Maps segments to the specifier their `qrl` imports them from, e.g. content hashed file names or URLs on a CDN,
+/// so that no post-processing of the emitted code is needed once the final chunks are known.
+///
+/// # Examples
+/// ```
+/// use qwik_optimizer::chunk::ChunkResolver;
+/// use qwik_optimizer::transform::TransformOptions;
+///
+/// let options = TransformOptions {
+///     chunk_resolver: Some(ChunkResolver::new(|request| {
+///         Some(format!("https://cdn.example.com/build/{}.js", request.symbol_name))
+///     })),
+///     ..TransformOptions::default()
+/// };
+/// ```
+/// emits
+/// ```javascript
+/// qrl(() => import("https://cdn.example.com/build/App_component_ckEPmXZlub0.js"), "App_component_ckEPmXZlub0");
+/// ```
+#[derive(Clone)]
+pub struct ChunkResolver(Arc<ResolveFn>);
+
+type ResolveFn = dyn Fn(&ChunkRequest) -> Option<String> + Send + Sync;
+
+impl ChunkResolver {
+    /// Creates a resolver from `resolve`, which returns the specifier of a segment, or `None` to keep importing it
+    /// from its [file name](ChunkRequest::file_name).
+    pub fn new<F>(resolve: F) -> Self
+    where
+        F: Fn(&ChunkRequest) -> Option<String> + Send + Sync + 'static,
+    {
+        Self(Arc::new(resolve))
+    }
+
+    pub(crate) fn resolve(&self, request: &ChunkRequest) -> Option<String> {
+        (self.0)(request)
+    }
+}
+
+impl Debug for ChunkResolver {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("ChunkResolver(..)")
+    }
+}
+
+/// Resolvers can not be compared, so a resolver is only equal to its clones. As a result, transforms cached with
+/// one resolver are never reused with another.
+impl Hash for ChunkResolver {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        (Arc::as_ptr(&self.0) as *const () as usize).hash(state);
+    }
+}
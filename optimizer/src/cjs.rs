@@ -0,0 +1,529 @@
+use oxc_allocator::Allocator;
+use oxc_ast::ast::*;
+use oxc_ast::visit::walk_mut;
+use oxc_ast::{AstBuilder, VisitMut, NONE};
+use oxc_parser::Parser;
+use oxc_span::{SourceType, SPAN};
+use oxc_syntax::identifier::is_identifier_name;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// The module system of the emitted host modules and segments, see
+/// [TransformOptions::module_format](crate::transform::TransformOptions::module_format).
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "camelCase"))]
+pub enum ModuleFormat {
+    /// `import` and `export` declarations, as written.
+    #[default]
+    Esm,
+    /// `require` calls and getters on `exports`, for SSR hosts which can not load ES modules.
+    Cjs,
+}
+
+/// Marks the emitted module as compiled from an ES module, so that bundlers importing it read its `default` export
+/// rather than the whole `exports` object.
+const ES_MODULE_FLAG: &str = r#"Object.defineProperty(exports, "__esModule", { value: true });"#;
+
+/// Re-exports the exports of a required module for `export * from`, except for `default` and those the module
+/// already exports, which take precedence as they do in ES modules.
+const EXPORT_STAR_HELPER: &str = r#"function __exportStar(module) {
+    for (const key of Object.keys(module)) {
+        if (key !== "default" && key !== "__esModule" && !Object.prototype.hasOwnProperty.call(exports, key)) {
+            Object.defineProperty(exports, key, { enumerable: true, get: () => module[key] });
+        }
+    }
+}"#;
+
+/// Converts the `import` and `export` declarations of `program` into `require` calls and getters on `exports`,
+/// and, with `require_dynamic_imports`, its `import()` calls into `Promise.resolve().then(() => require(...))`.
+/// Otherwise `import()` is kept, which CommonJS modules support, so that QRLs still load their segments lazily.
+///
+/// Exports are defined as getters at the top of the module, so that they are live bindings, as in ES modules, and
+/// take precedence over those of `export * from`, which skips `default`. Imports are destructured from the required
+/// module, so they do not observe later reassignments of the exports of the modules they import.
+///
+/// # Examples
+/// ```javascript
+/// import { componentQrl, qrl } from "@qwik.dev/core";
+/// export const App = componentQrl(qrl(() => import("./app.tsx_App_component"), "App_component"));
+/// ```
+/// becomes
+/// ```javascript
+/// Object.defineProperty(exports, "__esModule", { value: true });
+/// Object.defineProperty(exports, "App", { enumerable: true, get: () => App });
+/// const { componentQrl, qrl } = require("@qwik.dev/core");
+/// const App = componentQrl(qrl(() => import("./app.tsx_App_component"), "App_component"));
+/// ```
+pub(crate) fn to_commonjs<'a>(
+    program: &mut Program<'a>,
+    require_dynamic_imports: bool,
+    allocator: &'a Allocator,
+) {
+    let ast = AstBuilder::new(allocator);
+    if require_dynamic_imports {
+        DynamicImports { ast }.visit_program(program);
+    }
+
+    let mut body = ast.vec_with_capacity(program.body.len());
+    let mut getters = ast.vec();
+    let mut has_exports = false;
+    let mut has_export_star = false;
+    for statement in program.body.drain(..) {
+        match statement {
+            Statement::ImportDeclaration(import) if !import.import_kind.is_type() => {
+                require_imports(import.unbox(), &mut body, ast);
+            }
+            Statement::ExportNamedDeclaration(export)
+                if !export.export_kind.is_type()
+                    && !export
+                        .declaration
+                        .as_ref()
+                        .is_some_and(Declaration::is_typescript_syntax) =>
+            {
+                has_exports = true;
+                export_named(export.unbox(), &mut body, &mut getters, ast);
+            }
+            Statement::ExportDefaultDeclaration(export)
+                if !export.declaration.is_typescript_syntax() =>
+            {
+                has_exports = true;
+                export_default(export.unbox(), &mut body, &mut getters, ast);
+            }
+            Statement::ExportAllDeclaration(export) if !export.export_kind.is_type() => {
+                has_exports = true;
+                let source = &export.source.value;
+                match &export.exported {
+                    Some(exported) => {
+                        body.push(ast.statement_expression(SPAN, require(ast, source)));
+                        getters.push(export_getter(ast, &exported.name(), require(ast, source)));
+                    }
+                    None => {
+                        has_export_star = true;
+                        let callee = ast.expression_identifier(SPAN, "__exportStar");
+                        let arguments = ast.vec1(require(ast, source).into());
+                        let call = ast.expression_call(SPAN, callee, NONE, arguments, false);
+                        body.push(ast.statement_expression(SPAN, call));
+                    }
+                }
+            }
+            statement => body.push(statement),
+        }
+    }
+
+    if has_exports {
+        let prelude = match has_export_star {
+            true => allocator.alloc_str(&format!("{ES_MODULE_FLAG}\n{EXPORT_STAR_HELPER}")),
+            false => ES_MODULE_FLAG,
+        };
+        let prelude = Parser::new(allocator, prelude, SourceType::cjs())
+            .parse()
+            .program
+            .body;
+        let mut statements = ast.vec_with_capacity(prelude.len() + getters.len() + body.len());
+        statements.extend(prelude);
+        statements.extend(getters);
+        statements.extend(body);
+        body = statements;
+    }
+    program.body = body;
+    program.source_type = program.source_type.with_script(true);
+}
+
+/// `const { imported: local, ... } = require("source")`, or `require("source")` for side effect imports.
+fn require_imports<'a>(
+    import: ImportDeclaration<'a>,
+    body: &mut oxc_allocator::Vec<'a, Statement<'a>>,
+    ast: AstBuilder<'a>,
+) {
+    let mut module = require(ast, &import.source.value);
+    let specifiers = import.specifiers.unwrap_or_else(|| ast.vec());
+    if specifiers.is_empty() {
+        body.push(ast.statement_expression(SPAN, module));
+        return;
+    }
+
+    let mut properties = ast.vec();
+    for specifier in specifiers {
+        match specifier {
+            ImportDeclarationSpecifier::ImportNamespaceSpecifier(namespace) => {
+                body.push(const_declaration(
+                    ast,
+                    ast.binding_pattern_kind_binding_identifier(SPAN, namespace.local.name),
+                    module,
+                ));
+                module = ast.expression_identifier(SPAN, namespace.local.name);
+            }
+            ImportDeclarationSpecifier::ImportDefaultSpecifier(default) => {
+                properties.push(binding_property(ast, "default", &default.local.name));
+            }
+            ImportDeclarationSpecifier::ImportSpecifier(specifier) => {
+                if !specifier.import_kind.is_type() {
+                    let imported = specifier.imported.name();
+                    properties.push(binding_property(ast, &imported, &specifier.local.name));
+                }
+            }
+        }
+    }
+    if !properties.is_empty() {
+        body.push(const_declaration(
+            ast,
+            ast.binding_pattern_kind_object_pattern(SPAN, properties, NONE),
+            module,
+        ));
+    }
+}
+
+/// The declaration of `export const a = ...` and the getter of `a` on `exports`, or of `b` for `export { a as b }`.
+/// The modules of re-exports are required in place, and read by the getters.
+fn export_named<'a>(
+    export: ExportNamedDeclaration<'a>,
+    body: &mut oxc_allocator::Vec<'a, Statement<'a>>,
+    getters: &mut oxc_allocator::Vec<'a, Statement<'a>>,
+    ast: AstBuilder<'a>,
+) {
+    if let Some(declaration) = export.declaration {
+        let names: Vec<Atom<'a>> = match &declaration {
+            Declaration::VariableDeclaration(variables) => variables
+                .declarations
+                .iter()
+                .flat_map(|declarator| declarator.id.get_binding_identifiers())
+                .map(|id| id.name)
+                .collect(),
+            Declaration::FunctionDeclaration(function) => {
+                function.id.iter().map(|id| id.name).collect()
+            }
+            Declaration::ClassDeclaration(class) => class.id.iter().map(|id| id.name).collect(),
+            _ => Vec::new(),
+        };
+        body.push(Statement::from(declaration));
+        for name in names {
+            let value = ast.expression_identifier(SPAN, name);
+            getters.push(export_getter(ast, &name, value));
+        }
+    }
+
+    if let Some(source) = &export.source {
+        if export.specifiers.iter().any(|s| !s.export_kind.is_type()) {
+            body.push(ast.statement_expression(SPAN, require(ast, &source.value)));
+        }
+    }
+    for specifier in export.specifiers {
+        if specifier.export_kind.is_type() {
+            continue;
+        }
+        let local = specifier.local.name();
+        let value = match &export.source {
+            Some(source) => {
+                let module = require(ast, &source.value);
+                member(ast, module, &local).into()
+            }
+            None => ast.expression_identifier(SPAN, local),
+        };
+        getters.push(export_getter(ast, &specifier.exported.name(), value));
+    }
+}
+
+/// `exports.default = ...`, or the declaration of a named default function or class and the getter of `default` on
+/// `exports`.
+fn export_default<'a>(
+    export: ExportDefaultDeclaration<'a>,
+    body: &mut oxc_allocator::Vec<'a, Statement<'a>>,
+    getters: &mut oxc_allocator::Vec<'a, Statement<'a>>,
+    ast: AstBuilder<'a>,
+) {
+    let value = match export.declaration {
+        ExportDefaultDeclarationKind::FunctionDeclaration(mut function) => {
+            match function.id.as_ref().map(|id| id.name) {
+                Some(name) => {
+                    body.push(Statement::FunctionDeclaration(function));
+                    let value = ast.expression_identifier(SPAN, name);
+                    getters.push(export_getter(ast, "default", value));
+                    return;
+                }
+                None => {
+                    function.r#type = FunctionType::FunctionExpression;
+                    Expression::FunctionExpression(function)
+                }
+            }
+        }
+        ExportDefaultDeclarationKind::ClassDeclaration(mut class) => {
+            match class.id.as_ref().map(|id| id.name) {
+                Some(name) => {
+                    body.push(Statement::ClassDeclaration(class));
+                    let value = ast.expression_identifier(SPAN, name);
+                    getters.push(export_getter(ast, "default", value));
+                    return;
+                }
+                None => {
+                    class.r#type = ClassType::ClassExpression;
+                    Expression::ClassExpression(class)
+                }
+            }
+        }
+        declaration => declaration.into_expression(),
+    };
+    body.push(export_assignment(ast, "default", value));
+}
+
+/// `require("source")`.
+fn require<'a>(ast: AstBuilder<'a>, source: &str) -> Expression<'a> {
+    let callee = ast.expression_identifier(SPAN, "require");
+    let source = ast.expression_string_literal(SPAN, source, None);
+    ast.expression_call(SPAN, callee, NONE, ast.vec1(source.into()), false)
+}
+
+/// `object.name`, or `object["name"]` if `name` is not an identifier, e.g. `exports["a-b"]`.
+fn member<'a>(ast: AstBuilder<'a>, object: Expression<'a>, name: &str) -> MemberExpression<'a> {
+    if is_identifier_name(name) {
+        ast.member_expression_static(SPAN, object, ast.identifier_name(SPAN, name), false)
+    } else {
+        let name = ast.expression_string_literal(SPAN, name, None);
+        ast.member_expression_computed(SPAN, object, name, false)
+    }
+}
+
+/// `exports.name = value;`.
+fn export_assignment<'a>(ast: AstBuilder<'a>, name: &str, value: Expression<'a>) -> Statement<'a> {
+    let exports = ast.expression_identifier(SPAN, "exports");
+    let target = SimpleAssignmentTarget::from(member(ast, exports, name));
+    let assignment =
+        ast.expression_assignment(SPAN, AssignmentOperator::Assign, target.into(), value);
+    ast.statement_expression(SPAN, assignment)
+}
+
+/// `Object.defineProperty(exports, "name", { enumerable: true, get: () => value });`.
+fn export_getter<'a>(ast: AstBuilder<'a>, name: &str, value: Expression<'a>) -> Statement<'a> {
+    let define_property = ast.member_expression_static(
+        SPAN,
+        ast.expression_identifier(SPAN, "Object"),
+        ast.identifier_name(SPAN, "defineProperty"),
+        false,
+    );
+    let get = ast.expression_arrow_function(
+        SPAN,
+        true,
+        false,
+        NONE,
+        ast.formal_parameters(
+            SPAN,
+            FormalParameterKind::ArrowFormalParameters,
+            ast.vec(),
+            NONE,
+        ),
+        NONE,
+        ast.function_body(
+            SPAN,
+            ast.vec(),
+            ast.vec1(ast.statement_expression(SPAN, value)),
+        ),
+    );
+    let property = |key: &str, value: Expression<'a>| {
+        ast.object_property_kind_object_property(
+            SPAN,
+            PropertyKind::Init,
+            ast.property_key_static_identifier(SPAN, ast.atom(key)),
+            value,
+            false,
+            false,
+            false,
+        )
+    };
+    let descriptor = ast.expression_object(
+        SPAN,
+        ast.vec_from_array([
+            property("enumerable", ast.expression_boolean_literal(SPAN, true)),
+            property("get", get),
+        ]),
+        None,
+    );
+    let arguments = ast.vec_from_array([
+        ast.expression_identifier(SPAN, "exports").into(),
+        ast.expression_string_literal(SPAN, ast.atom(name), None)
+            .into(),
+        descriptor.into(),
+    ]);
+    ast.statement_expression(
+        SPAN,
+        ast.expression_call(SPAN, define_property.into(), NONE, arguments, false),
+    )
+}
+
+/// `const pattern = init;`.
+fn const_declaration<'a>(
+    ast: AstBuilder<'a>,
+    pattern: BindingPatternKind<'a>,
+    init: Expression<'a>,
+) -> Statement<'a> {
+    let declarator = ast.variable_declarator(
+        SPAN,
+        VariableDeclarationKind::Const,
+        ast.binding_pattern(pattern, NONE, false),
+        Some(init),
+        false,
+    );
+    Statement::VariableDeclaration(ast.alloc_variable_declaration(
+        SPAN,
+        VariableDeclarationKind::Const,
+        ast.vec1(declarator),
+        false,
+    ))
+}
+
+/// `imported: local` in an object pattern, or `local` if both are the same.
+fn binding_property<'a>(ast: AstBuilder<'a>, imported: &str, local: &str) -> BindingProperty<'a> {
+    let key = if is_identifier_name(imported) {
+        ast.property_key_static_identifier(SPAN, imported)
+    } else {
+        PropertyKey::from(ast.expression_string_literal(SPAN, imported, None))
+    };
+    let value = ast.binding_pattern(
+        ast.binding_pattern_kind_binding_identifier(SPAN, local),
+        NONE,
+        false,
+    );
+    ast.binding_property(SPAN, key, value, imported == local, false)
+}
+
+/// Replaces `import(source)` with `Promise.resolve().then(() => require(source))`.
+struct DynamicImports<'a> {
+    ast: AstBuilder<'a>,
+}
+
+impl<'a> VisitMut<'a> for DynamicImports<'a> {
+    fn visit_expression(&mut self, expr: &mut Expression<'a>) {
+        walk_mut::walk_expression(self, expr);
+        let Expression::ImportExpression(import) = expr else {
+            return;
+        };
+        let ast = self.ast;
+        let source = ast.move_expression(&mut import.source);
+        let callee = ast.expression_identifier(SPAN, "require");
+        let required = ast.expression_call(SPAN, callee, NONE, ast.vec1(source.into()), false);
+        let load = ast.expression_arrow_function(
+            SPAN,
+            true,
+            false,
+            NONE,
+            ast.formal_parameters(
+                SPAN,
+                FormalParameterKind::ArrowFormalParameters,
+                ast.vec(),
+                NONE,
+            ),
+            NONE,
+            ast.function_body(
+                SPAN,
+                ast.vec(),
+                ast.vec1(ast.statement_expression(SPAN, required)),
+            ),
+        );
+        let promise = ast.expression_identifier(SPAN, "Promise");
+        let resolve = ast.member_expression_static(
+            SPAN,
+            promise,
+            ast.identifier_name(SPAN, "resolve"),
+            false,
+        );
+        let resolved = ast.expression_call(SPAN, resolve.into(), NONE, ast.vec(), false);
+        let then =
+            ast.member_expression_static(SPAN, resolved, ast.identifier_name(SPAN, "then"), false);
+        *expr = ast.expression_call(SPAN, then.into(), NONE, ast.vec1(load.into()), false);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use oxc_codegen::Codegen;
+
+    fn convert(source: &str, require_dynamic_imports: bool) -> String {
+        let allocator = Allocator::default();
+        let mut program = Parser::new(&allocator, source, SourceType::mjs())
+            .parse()
+            .program;
+        to_commonjs(&mut program, require_dynamic_imports, &allocator);
+        Codegen::new().build(&program).code
+    }
+
+    #[test]
+    fn converts_imports() {
+        assert_eq!(
+            convert(
+                r#"import "./polyfill"; import App, { qrl, "a-b" as ab } from "@qwik.dev/core"; import * as ns from "./ns"; import d, * as all from "./all";"#,
+                false
+            ),
+            concat!(
+                "require(\"./polyfill\");\n",
+                "const { default: App, qrl, \"a-b\": ab } = require(\"@qwik.dev/core\");\n",
+                "const ns = require(\"./ns\");\n",
+                "const all = require(\"./all\");\n",
+                "const { default: d } = all;\n",
+            )
+        );
+    }
+
+    #[test]
+    fn converts_exports() {
+        assert_eq!(
+            convert(
+                r#"export const a = 1, { b } = c; export function f() {} const g = 2; export { g as h, g as "i-j" }; export { k } from "./k"; export * as l from "./l"; export * from "./m"; export default class {}"#,
+                false
+            ),
+            concat!(
+                "Object.defineProperty(exports, \"__esModule\", { value: true });\n",
+                "function __exportStar(module) {\n",
+                "\tfor (const key of Object.keys(module)) {\n",
+                "\t\tif (key !== \"default\" && key !== \"__esModule\" && !Object.prototype.hasOwnProperty.call(exports, key)) {\n",
+                "\t\t\tObject.defineProperty(exports, key, {\n",
+                "\t\t\t\tenumerable: true,\n",
+                "\t\t\t\tget: () => module[key]\n",
+                "\t\t\t});\n",
+                "\t\t}\n",
+                "\t}\n",
+                "}\n",
+                "Object.defineProperty(exports, \"a\", {\n\tenumerable: true,\n\tget: () => a\n});\n",
+                "Object.defineProperty(exports, \"b\", {\n\tenumerable: true,\n\tget: () => b\n});\n",
+                "Object.defineProperty(exports, \"f\", {\n\tenumerable: true,\n\tget: () => f\n});\n",
+                "Object.defineProperty(exports, \"h\", {\n\tenumerable: true,\n\tget: () => g\n});\n",
+                "Object.defineProperty(exports, \"i-j\", {\n\tenumerable: true,\n\tget: () => g\n});\n",
+                "Object.defineProperty(exports, \"k\", {\n\tenumerable: true,\n\tget: () => require(\"./k\").k\n});\n",
+                "Object.defineProperty(exports, \"l\", {\n\tenumerable: true,\n\tget: () => require(\"./l\")\n});\n",
+                "const a = 1, { b } = c;\n",
+                "function f() {}\n",
+                "const g = 2;\n",
+                "require(\"./k\");\n",
+                "require(\"./l\");\n",
+                "__exportStar(require(\"./m\"));\n",
+                "exports.default = class {};\n",
+            )
+        );
+    }
+
+    #[test]
+    fn keeps_exports_live() {
+        let code = convert(
+            "export let count = 0; export default function inc() { count++; }",
+            false,
+        );
+        assert_eq!(
+            code,
+            concat!(
+                "Object.defineProperty(exports, \"__esModule\", { value: true });\n",
+                "Object.defineProperty(exports, \"count\", {\n\tenumerable: true,\n\tget: () => count\n});\n",
+                "Object.defineProperty(exports, \"default\", {\n\tenumerable: true,\n\tget: () => inc\n});\n",
+                "let count = 0;\n",
+                "function inc() {\n\tcount++;\n}\n",
+            )
+        );
+        assert!(!convert("export * from \"./m\";", false).contains("Object.assign"));
+    }
+
+    #[test]
+    fn converts_dynamic_imports() {
+        let source = r#"export const s = qrl(() => import("./s"), "s");"#;
+        assert!(convert(source, false).contains(r#"qrl(() => import("./s"), "s")"#));
+        assert!(convert(source, true)
+            .contains(r#"qrl(() => Promise.resolve().then(() => require("./s")), "s")"#));
+    }
+}
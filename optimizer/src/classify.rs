@@ -0,0 +1,140 @@
+use crate::component::Markers;
+use oxc_ast::ast::*;
+use oxc_ast::Visit;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// The import sources of Qwik packages, including the legacy `@builder.io` ones.
+const QWIK_SOURCE_PREFIXES: [&str; 2] = ["@qwik.dev/", "@builder.io/qwik"];
+
+/// How a module was handled by the transform. Modules other than [ModuleKind::Transformed] are emitted exactly as
+/// they were provided, without segments, so hosts can cache them by their source alone and skip them in later
+/// builds. Only [ModuleKind::External] modules may have diagnostics.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "camelCase"))]
+pub enum ModuleKind {
+    /// The module was parsed and transformed.
+    #[default]
+    Transformed,
+    /// The module is empty or only contains whitespace.
+    Empty,
+    /// The module only contains comments.
+    CommentsOnly,
+    /// The module neither imports a Qwik package nor references a `$` marker or one of the other
+    /// [markers](crate::transform::TransformOptions::markers), and no option requires rewriting it, e.g.
+    /// [transpile_ts](crate::transform::TransformOptions::transpile_ts).
+    MarkerFree,
+    /// The module is outside of the app, e.g. below `node_modules`, and its markers are not optimized, see
+    /// [forbid_external_markers](crate::transform::TransformOptions::forbid_external_markers). Its marker calls are
+    /// reported as diagnostics with [ExternalMarkers::Error](crate::external::ExternalMarkers::Error).
+    External,
+}
+
+impl ModuleKind {
+    /// Whether the module was emitted as it was provided.
+    pub fn is_unchanged(&self) -> bool {
+        *self != ModuleKind::Transformed
+    }
+}
+
+/// Classifies a module whose source has no content, before it is parsed.
+pub(crate) fn classify_source(source_text: &str) -> Option<ModuleKind> {
+    source_text.trim().is_empty().then_some(ModuleKind::Empty)
+}
+
+/// Classifies a parsed module, returning `None` if it has to be transformed.
+pub(crate) fn classify_program(program: &Program, markers: &Markers) -> Option<ModuleKind> {
+    if program.body.is_empty() && program.directives.is_empty() && program.hashbang.is_none() {
+        return Some(ModuleKind::CommentsOnly);
+    }
+    let mut finder = QwikUsage {
+        markers,
+        found: false,
+    };
+    finder.visit_program(program);
+    (!finder.found).then_some(ModuleKind::MarkerFree)
+}
+
+/// Finds imports of Qwik packages and references to markers, i.e. names ending with `$` or matching the other
+/// [Markers], including method markers such as `obj.method$`.
+struct QwikUsage<'m> {
+    markers: &'m Markers,
+    found: bool,
+}
+
+impl<'a> Visit<'a> for QwikUsage<'_> {
+    fn visit_import_declaration(&mut self, decl: &ImportDeclaration<'a>) {
+        self.found |= is_qwik_source(&decl.source.value);
+    }
+
+    fn visit_export_named_declaration(&mut self, decl: &ExportNamedDeclaration<'a>) {
+        self.found |= decl
+            .source
+            .as_ref()
+            .is_some_and(|source| is_qwik_source(&source.value));
+        oxc_ast::visit::walk::walk_export_named_declaration(self, decl);
+    }
+
+    fn visit_export_all_declaration(&mut self, decl: &ExportAllDeclaration<'a>) {
+        self.found |= is_qwik_source(&decl.source.value);
+    }
+
+    fn visit_identifier_reference(&mut self, id_ref: &IdentifierReference<'a>) {
+        self.found |= self.markers.is_marker(&id_ref.name);
+    }
+
+    fn visit_identifier_name(&mut self, name: &IdentifierName<'a>) {
+        self.found |= self.markers.is_marker(&name.name);
+    }
+}
+
+fn is_qwik_source(source: &str) -> bool {
+    QWIK_SOURCE_PREFIXES
+        .iter()
+        .any(|prefix| source.starts_with(prefix))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use oxc_allocator::Allocator;
+    use oxc_parser::Parser;
+    use oxc_span::SourceType;
+
+    fn classify(source_text: &str) -> Option<ModuleKind> {
+        let allocator = Allocator::default();
+        classify_source(source_text).or_else(|| {
+            let program = Parser::new(&allocator, source_text, SourceType::tsx())
+                .parse()
+                .program;
+            classify_program(&program, &Markers::default().with_suffix("_"))
+        })
+    }
+
+    #[test]
+    fn classifies_modules() {
+        assert_eq!(classify(""), Some(ModuleKind::Empty));
+        assert_eq!(classify(" \n\t"), Some(ModuleKind::Empty));
+        assert_eq!(
+            classify("// @license MIT\n/* nothing */"),
+            Some(ModuleKind::CommentsOnly)
+        );
+        assert_eq!(
+            classify("import { format } from 'date-fns';\nexport const f = (d: Date) => `${format(d, 'P')}`;"),
+            Some(ModuleKind::MarkerFree)
+        );
+        assert_eq!(classify("'use client';"), Some(ModuleKind::MarkerFree));
+        assert_eq!(
+            classify("import { useSignal } from '@qwik.dev/core';"),
+            None
+        );
+        assert_eq!(classify("export * from '@builder.io/qwik';"), None);
+        assert_eq!(classify("export const App = component$(() => null);"), None);
+        assert_eq!(
+            classify("export const handler = store.on$(() => null);"),
+            None
+        );
+        assert_eq!(classify("export const Card = lazy_(() => null);"), None);
+    }
+}
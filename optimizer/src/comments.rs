@@ -0,0 +1,161 @@
+use oxc_allocator::Allocator;
+use oxc_ast::ast::{Comment, Program};
+use oxc_ast::AstBuilder;
+use oxc_codegen::{Codegen, CodegenOptions};
+use oxc_span::{GetSpan, SPAN};
+use std::path::PathBuf;
+
+/// The directives and comments of a module which are copied into the segments extracted from it.
+///
+/// Every directive of the module, e.g. `"use client"`, applies to its segments as well. Comments are copied if their
+/// text contains one of the [configured patterns](crate::transform::TransformOptions::preserve_comments), e.g.
+/// `eslint-disable` or `@license`: those at the top of the module (its header) are copied into the host module and
+/// every segment, the others stay in front of the statement they were written in front of, wherever it is emitted.
+///
+/// oxc only prints JSDoc, legal and annotation comments, so the preserved comments are inserted into the generated
+/// code instead: the statement following a comment is located through the source map of the generated code. Without
+/// the `source-maps` feature, only the header is preserved.
+pub(crate) struct PreservedComments<'s> {
+    source_text: &'s str,
+    directives: Vec<String>,
+    header: Vec<Comment>,
+    body: Vec<Comment>,
+}
+
+impl<'s> PreservedComments<'s> {
+    pub(crate) fn new(program: &Program<'s>, patterns: &[String]) -> Self {
+        let source_text = program.source_text;
+        let code_start = program
+            .directives
+            .first()
+            .map(|directive| directive.span.start)
+            .into_iter()
+            .chain(program.body.first().map(|statement| statement.span().start))
+            .min()
+            .unwrap_or(source_text.len() as u32);
+
+        let (header, body): (Vec<Comment>, Vec<Comment>) = program
+            .comments
+            .iter()
+            .filter(|comment| {
+                let text = comment.span.source_text(source_text);
+                patterns
+                    .iter()
+                    .any(|pattern| text.contains(pattern.as_str()))
+            })
+            .partition(|comment| comment.span.end <= code_start);
+
+        Self {
+            source_text,
+            directives: program
+                .directives
+                .iter()
+                .map(|directive| directive.directive.to_string())
+                .collect(),
+            header,
+            // Comments in the body are located through source maps, see [PreservedComments].
+            body: if cfg!(feature = "source-maps") {
+                body
+            } else {
+                Vec::new()
+            },
+        }
+    }
+
+    /// Copies the directives of the module into `segment`, the module of a segment extracted from it. If any
+    /// comment needs to be placed, the source text of the module is attached for [PreservedComments::print].
+    pub(crate) fn prepare_segment<'a>(&self, segment: &mut Program<'a>, allocator: &'a Allocator) {
+        let ast = AstBuilder::new(allocator);
+        segment.directives = ast.vec_from_iter(self.directives.iter().map(|directive| {
+            let value = ast.atom(directive);
+            ast.directive(SPAN, ast.string_literal(SPAN, value, None), value)
+        }));
+        if !self.body.is_empty() {
+            segment.source_text = allocator.alloc_str(self.source_text);
+        }
+    }
+
+    /// Generates the code of `program`, the host module or a segment, with the preserved comments in place.
+    pub(crate) fn print(
+        &self,
+        codegen: Codegen,
+        mut options: CodegenOptions,
+        program: &mut Program,
+    ) -> String {
+        // The preserved comments are inserted below, so oxc must not print the ones it would print otherwise.
+        program
+            .comments
+            .retain(|comment| !self.header.contains(comment) && !self.body.contains(comment));
+
+        let place_body = !self.body.is_empty() && !options.minify;
+        if place_body {
+            options.source_map_path = Some(PathBuf::from("preserved_comments.js"));
+        }
+        let generated = codegen.with_options(options).build(program);
+
+        let mut code = String::new();
+        for comment in &self.header {
+            code.push_str(comment.span.source_text(self.source_text));
+            code.push('\n');
+        }
+        #[cfg(feature = "source-maps")]
+        if place_body {
+            self.insert_body_comments(&mut code, &generated);
+            return code;
+        }
+        code.push_str(&generated.code);
+        code
+    }
+
+    /// Appends `generated` to `code`, inserting every body comment before the generated line of the statement it
+    /// was written in front of, with the same indentation.
+    #[cfg(feature = "source-maps")]
+    fn insert_body_comments(&self, code: &mut String, generated: &oxc_codegen::CodegenReturn) {
+        let positions: Vec<_> = generated
+            .map
+            .iter()
+            .flat_map(|map| map.get_tokens())
+            .map(|token| {
+                (
+                    (token.get_src_line(), token.get_src_col()),
+                    token.get_dst_line(),
+                )
+            })
+            .collect();
+        let mut comments_by_line: Vec<(u32, &Comment)> = self
+            .body
+            .iter()
+            .filter_map(|comment| {
+                let position = self.position(comment.attached_to);
+                positions
+                    .iter()
+                    .filter(|(src, _)| *src == position)
+                    .map(|(_, dst_line)| *dst_line)
+                    .min()
+                    .map(|line| (line, comment))
+            })
+            .collect();
+        comments_by_line.sort_by_key(|(line, comment)| (*line, comment.span.start));
+
+        let mut comments = comments_by_line.into_iter().peekable();
+        for (index, line) in generated.code.split_inclusive('\n').enumerate() {
+            let indent = &line[..line.len() - line.trim_start().len()];
+            while let Some((_, comment)) = comments.next_if(|(line, _)| *line as usize == index) {
+                code.push_str(indent);
+                code.push_str(comment.span.source_text(self.source_text));
+                code.push('\n');
+            }
+            code.push_str(line);
+        }
+    }
+
+    #[cfg(feature = "source-maps")]
+    /// The line and UTF-16 column of `offset` in the source text, as recorded by source maps.
+    fn position(&self, offset: u32) -> (u32, u32) {
+        let before = &self.source_text[..offset as usize];
+        let line_start = before.rfind('\n').map_or(0, |index| index + 1);
+        let line = before.matches('\n').count() as u32;
+        let column = before[line_start..].encode_utf16().count() as u32;
+        (line, column)
+    }
+}
@@ -0,0 +1,242 @@
+use crate::component::QrlComponent;
+use crate::transform::TransformResult;
+#[cfg(feature = "serde")]
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fmt::Display;
+use std::path::PathBuf;
+
+/// The size of the output of a transform, in bytes of emitted code.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "camelCase"))]
+pub struct OutputSize {
+    /// The code of all host modules.
+    pub module_bytes: usize,
+    /// The code of all extracted segments.
+    pub segment_bytes: usize,
+    pub segments: usize,
+    /// The chunks the segments are emitted in: the entries of the [EntryPlan](crate::entry_strategy::EntryPlan),
+    /// plus every segment which is its own chunk.
+    pub chunks: usize,
+}
+
+impl OutputSize {
+    fn of(result: &TransformResult) -> Self {
+        Self {
+            module_bytes: result.modules.iter().map(|module| module.code.len()).sum(),
+            segment_bytes: result
+                .segments
+                .iter()
+                .map(|segment| segment.code.len())
+                .sum(),
+            segments: result.segments.len(),
+            chunks: result.entry_plan.entries.len() + result.entry_plan.standalone.len(),
+        }
+    }
+
+    pub fn total_bytes(&self) -> usize {
+        self.module_bytes + self.segment_bytes
+    }
+}
+
+/// A segment emitted by both transforms, whose symbol name, entry or size differ.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "camelCase"))]
+pub struct SegmentChange {
+    /// The display name of the segment, which does not depend on the [Target](crate::component::Target).
+    pub display_name: String,
+    pub symbol_names: (String, String),
+    pub entries: (Option<String>, Option<String>),
+    pub bytes: (usize, usize),
+}
+
+/// A host module whose code differs in size between both transforms.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "camelCase"))]
+pub struct ModuleChange {
+    pub path: PathBuf,
+    pub bytes: (usize, usize),
+}
+
+/// The differences between two [TransformResult]s of the same sources, transformed with different options, see
+/// [compare_outputs]. Pairs are given as `(a, b)`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "camelCase"))]
+pub struct OutputDiff {
+    pub size: (OutputSize, OutputSize),
+    /// The display names of the segments only emitted by `b`.
+    pub added: Vec<String>,
+    /// The display names of the segments only emitted by `a`.
+    pub removed: Vec<String>,
+    pub changed: Vec<SegmentChange>,
+    pub modules: Vec<ModuleChange>,
+}
+
+impl OutputDiff {
+    /// Whether both transforms emitted the same segments, in the same entries, with code of the same sizes.
+    pub fn is_empty(&self) -> bool {
+        self.size.0 == self.size.1
+            && self.added.is_empty()
+            && self.removed.is_empty()
+            && self.changed.is_empty()
+            && self.modules.is_empty()
+    }
+
+    /// The change of the total size of the emitted code from `a` to `b`, in bytes.
+    pub fn bytes_delta(&self) -> isize {
+        self.size.1.total_bytes() as isize - self.size.0.total_bytes() as isize
+    }
+}
+
+impl Display for OutputDiff {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let (a, b) = &self.size;
+        writeln!(
+            f,
+            "bytes: {} -> {} ({:+})",
+            a.total_bytes(),
+            b.total_bytes(),
+            self.bytes_delta()
+        )?;
+        writeln!(f, "segments: {} -> {}", a.segments, b.segments)?;
+        writeln!(f, "chunks: {} -> {}", a.chunks, b.chunks)?;
+        for name in &self.added {
+            writeln!(f, "+ {name}")?;
+        }
+        for name in &self.removed {
+            writeln!(f, "- {name}")?;
+        }
+        for change in &self.changed {
+            writeln!(
+                f,
+                "~ {}: {} -> {}, {} -> {} bytes, entry {} -> {}",
+                change.display_name,
+                change.symbol_names.0,
+                change.symbol_names.1,
+                change.bytes.0,
+                change.bytes.1,
+                change.entries.0.as_deref().unwrap_or("<own chunk>"),
+                change.entries.1.as_deref().unwrap_or("<own chunk>"),
+            )?;
+        }
+        for module in &self.modules {
+            writeln!(
+                f,
+                "~ {}: {} -> {} bytes",
+                module.path.display(),
+                module.bytes.0,
+                module.bytes.1
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Summarizes the differences between `a` and `b`, the results of transforming the same sources with two sets of
+/// options, e.g. to quantify the impact of an entry strategy or of minification.
+///
+/// Segments are matched by their origin and display name, so that they are matched across
+/// [targets](crate::component::Target) as well, and host modules by their path.
+pub fn compare_outputs(a: &TransformResult, b: &TransformResult) -> OutputDiff {
+    let segments_a = by_name(&a.segments);
+    let mut segments_b = by_name(&b.segments);
+
+    let mut diff = OutputDiff {
+        size: (OutputSize::of(a), OutputSize::of(b)),
+        ..OutputDiff::default()
+    };
+    for (key, segment_a) in segments_a {
+        let Some(segment_b) = segments_b.remove(&key) else {
+            diff.removed.push(segment_a.id.display_name.to_string());
+            continue;
+        };
+        let change = SegmentChange {
+            display_name: segment_a.id.display_name.to_string(),
+            symbol_names: (
+                segment_a.id.symbol_name.to_string(),
+                segment_b.id.symbol_name.to_string(),
+            ),
+            entries: (segment_a.entry.clone(), segment_b.entry.clone()),
+            bytes: (segment_a.code.len(), segment_b.code.len()),
+        };
+        if change.symbol_names.0 != change.symbol_names.1
+            || change.entries.0 != change.entries.1
+            || change.bytes.0 != change.bytes.1
+        {
+            diff.changed.push(change);
+        }
+    }
+    diff.added = segments_b
+        .into_values()
+        .map(|segment| segment.id.display_name.to_string())
+        .collect();
+
+    for module_a in &a.modules {
+        let module_b = b.modules.iter().find(|module| module.path == module_a.path);
+        if let Some(module_b) = module_b.filter(|b| b.code.len() != module_a.code.len()) {
+            diff.modules.push(ModuleChange {
+                path: module_a.path.clone(),
+                bytes: (module_a.code.len(), module_b.code.len()),
+            });
+        }
+    }
+    diff
+}
+
+fn by_name(segments: &[QrlComponent]) -> BTreeMap<(&str, &str), &QrlComponent> {
+    segments
+        .iter()
+        .map(|segment| {
+            (
+                (segment.origin.as_str(), segment.id.display_name.as_str()),
+                segment,
+            )
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::component::Target;
+    use crate::entry_strategy::EntryStrategy;
+    use crate::transform::{transform_module, MinifyMode, TransformOptions};
+
+    #[test]
+    fn compares_outputs() {
+        let code = std::fs::read_to_string("src/test_input/test_example_1.tsx").unwrap();
+        let run = |options: TransformOptions| transform_module("app.tsx", &code, options).unwrap();
+
+        let dev = run(TransformOptions::default());
+        assert!(compare_outputs(&dev, &dev).is_empty());
+
+        let prod = run(TransformOptions {
+            target: Target::Prod,
+            minify: MinifyMode::Simplify,
+            entry_strategy: EntryStrategy::Single,
+            ..TransformOptions::default()
+        });
+        let diff = compare_outputs(&dev, &prod);
+        assert!(diff.added.is_empty() && diff.removed.is_empty());
+        assert_eq!(diff.changed.len(), dev.segments.len());
+        assert!(diff
+            .changed
+            .iter()
+            .all(|change| change.symbol_names.1.starts_with("s_")));
+        assert_eq!(diff.size.1.chunks, 1);
+        assert!(diff.bytes_delta() < 0);
+        assert!(diff.to_string().starts_with("bytes: "));
+
+        let inline = run(TransformOptions {
+            entry_strategy: EntryStrategy::Inline,
+            ..TransformOptions::default()
+        });
+        let diff = compare_outputs(&dev, &inline);
+        assert_eq!(diff.removed.len(), dev.segments.len());
+        assert_eq!(diff.modules.len(), 1);
+    }
+}
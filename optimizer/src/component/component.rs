@@ -1,65 +1,240 @@
+use crate::capture::{inject_lexical_scope, USE_LEXICAL_SCOPE};
+use crate::chunk::ChunkRequest;
+use crate::cjs::{to_commonjs, ModuleFormat};
+use crate::comments::PreservedComments;
 use crate::component::Language;
 use crate::component::*;
+use crate::error::Error;
+use crate::events::EventListener;
+use crate::import_clean_up::ImportCleanUp;
+use crate::manifest::SegmentKind;
+use crate::metrics::SegmentMetrics;
+use crate::prelude::*;
+use crate::pretty::{write_indented, Truncated};
+use crate::routes::RouteExportKind;
 use crate::segment::Segment;
+use crate::shadowing::avoid_shadowing;
+use crate::transform::TransformOptions;
+use crate::transpile::Transpile;
 use oxc_allocator::{Allocator, Box as OxcBox, CloneIn, IntoIn, Vec as OxcVec};
 use oxc_ast::ast::*;
 use oxc_ast::*;
 use oxc_codegen::{Codegen, CodegenOptions};
+#[cfg(feature = "minifier")]
 use oxc_minifier::*;
 use oxc_span::{SourceType, SPAN};
-use serde::Serialize;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::fmt::{Debug, Display};
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::path::Path;
 
-#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct QrlComponent {
     pub id: Id,
     pub language: Language,
     pub code: String,
     pub qrl: Qrl,
+    /// The relative path of the file the component was extracted from.
+    pub origin: String,
+    pub ctx_kind: SegmentKind,
+    /// The name of the marker that produced this component, e.g. `component$`.
+    pub ctx_name: String,
+    /// The outermost named segment enclosing this component.
+    pub root: Option<String>,
+    /// The symbol name of the segment this one is nested in, whose hash is [Id::parent_hash].
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub parent: Option<String>,
+    /// The name the host module exports the declaration the segment was extracted from as, e.g. `App` for
+    /// `export const App = component$(...)`, or `default` for a default export. Only set for segments which are not
+    /// nested in another one, see [TransformOptions::reexport_segments].
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub exported: Option<String>,
+    /// Whether the segment is the server function of a qwik-city loader or action, exported by its host module as
+    /// [QrlComponent::exported], so that the router registers it with its server entry.
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub route_export: Option<RouteExportKind>,
+    /// The entry assigned by the configured [EntryStrategy](crate::entry_strategy::EntryStrategy).
+    pub entry: Option<String>,
+    /// The beginning of the original source of the segment, e.g. `() => store.count++`, so that devtools overlays
+    /// and error messages can show it without loading the chunk. Only recorded under [Target::Dev] with
+    /// [TransformOptions::source_excerpt_length].
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub excerpt: Option<String>,
+    /// The listener declared by the JSX attribute whose value the segment was extracted from, e.g. `on:click` for
+    /// `<button onClick$={$(() => ...)} />`.
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub listener: Option<EventListener>,
+    /// Whether the segment awaits at the top level of its module, e.g. `$(await loadConfig())`, so that it can only
+    /// be loaded asynchronously: the modules importing it wait for the awaited values before they run.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub async_only: bool,
+    /// The size and imports of the segment, see [SegmentMetrics].
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub metrics: SegmentMetrics,
+    /// The extension of the file the segment is written to, without a leading `.`, e.g. `tsx` for a segment of a
+    /// TypeScript module whose types and JSX are kept, see [SourceInfo::segment_extension].
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub extension: String,
 }
 
 impl QrlComponent {
+    #[allow(clippy::too_many_arguments)]
     pub(crate) fn new(
         source_info: &SourceInfo,
-        id: Id,
+        mut id: Id,
         exported_expression: Expression<'_>,
         imports: Vec<Import>,
-        minify: bool,
+        captures: Vec<String>,
+        options: &TransformOptions,
         qrl_type: QrlType,
+        root: Option<String>,
+        preserved_comments: &PreservedComments,
     ) -> QrlComponent {
         let language = source_info.language.clone();
-        let qrl = Qrl::new(&id.local_file_name, &id.symbol_name, qrl_type);
+        let ctx_name: String = (&qrl_type).into();
+        let rel_path = source_info.rel_path.to_string_lossy();
+        let origin = rel_path.strip_prefix("./").unwrap_or(&rel_path).to_string();
 
-        let source_type: SourceType = language.into();
+        let source_type: SourceType = language.clone().into();
 
-        let code = Self::gen(
+        let (code, metrics) = Self::gen(
             &id,
             exported_expression,
             imports,
-            minify,
+            &captures,
+            options,
             &source_type,
+            preserved_comments,
             &Allocator::default(),
         );
+
+        id.local_file_name = options.segment_naming.file_name(&id, &code);
+        if let Some(build_id) = options.file_name_build_id() {
+            id.local_file_name = format!("{}_{}", id.local_file_name, build_id);
+        }
+        if let Some(scope_dir) = options.scope_dir() {
+            let file_name = id.local_file_name.trim_start_matches("./");
+            id.local_file_name = format!("{scope_dir}/{file_name}");
+        }
+        let mut qrl = Qrl::new(&id.local_file_name, &id.symbol_name, qrl_type, captures);
+        let extension = match options.import_extensions {
+            true => source_info.segment_extension(options),
+            false => "",
+        };
+        qrl.import_specifier = id.import_specifier(&source_info.rel_dir, extension);
+        qrl.chunk = options.chunk_resolver.as_ref().and_then(|resolver| {
+            resolver.resolve(&ChunkRequest {
+                symbol_name: &id.symbol_name,
+                file_name: &id.local_file_name,
+                origin: &origin,
+            })
+        });
+
+        // Once TypeScript has been erased, the segment is emitted as plain JavaScript.
+        let language = if options.transpile_ts {
+            Language::Javascript
+        } else {
+            language
+        };
+
         QrlComponent {
             id,
-            language: source_info.language.clone(),
+            language,
             code,
             qrl,
+            origin,
+            ctx_kind: SegmentKind::from_ctx_name(&ctx_name),
+            ctx_name,
+            root,
+            parent: None,
+            exported: None,
+            route_export: None,
+            entry: None,
+            excerpt: None,
+            listener: None,
+            async_only: false,
+            metrics,
+            extension: source_info.segment_extension(options).to_string(),
         }
     }
 
-    fn gen(
+    /// The file the segment is written to, relative to the output directory, e.g.
+    /// `src/app.tsx_App_component_ckEPmXZlub0.tsx`.
+    pub fn file_name(&self) -> String {
+        match self.extension.as_str() {
+            "" => self.id.chunk_file_name(&self.language.extension()),
+            extension => self.id.chunk_file_name(extension),
+        }
+    }
+
+    /// The specifier importing the segment from a module in the directory `relative_to`, e.g.
+    /// `./app.tsx_App_component_ckEPmXZlub0` from `src` for the segment written to
+    /// `src/app.tsx_App_component_ckEPmXZlub0.tsx`, or `./app.tsx_App_component_ckEPmXZlub0.tsx` `with_extension`,
+    /// see [Id::import_specifier] and [TransformOptions::import_extensions].
+    pub fn import_specifier(&self, relative_to: &Path, with_extension: bool) -> String {
+        let extension = match (with_extension, self.extension.as_str()) {
+            (false, _) => String::new(),
+            (true, "") => self.language.extension(),
+            (true, extension) => extension.to_string(),
+        };
+        self.id.import_specifier(relative_to, &extension)
+    }
+
+    /// Identifies the behaviour of the segment: its code with its own symbol name left out, the variables it
+    /// captures and the marker it was extracted from. Segments with the same content key are interchangeable.
+    pub(crate) fn content_key(&self) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        self.code
+            .replace(self.id.symbol_name.as_str(), "")
+            .hash(&mut hasher);
+        self.qrl.captures.hash(&mut hasher);
+        self.ctx_name.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn gen<'a>(
         id: &Id,
-        exported_expression: Expression<'_>,
-        imports: Vec<Import>,
-        minify: bool,
+        mut exported_expression: Expression<'a>,
+        mut imports: Vec<Import>,
+        captures: &[String],
+        options: &TransformOptions,
         source_type: &SourceType,
-        allocator: &Allocator,
-    ) -> String {
+        preserved_comments: &PreservedComments,
+        allocator: &'a Allocator,
+    ) -> (String, SegmentMetrics) {
         let name = &id.symbol_name;
+        let source_path = Path::new(&id.local_file_name);
+        let minify = options.is_minify();
 
         let ast_builder = AstBuilder::new(allocator);
 
-        let id = OxcBox::new_in(ast_builder.binding_identifier(SPAN, name), allocator);
+        if !captures.is_empty() {
+            inject_lexical_scope(&mut exported_expression, captures, ast_builder);
+            imports.push(Import::new(
+                vec![USE_LEXICAL_SCOPE.into()],
+                QWIK_CORE_SOURCE,
+            ));
+        }
+
+        let id = OxcBox::new_in(
+            ast_builder.binding_identifier(SPAN, name.as_str()),
+            allocator,
+        );
         let bind_pat = ast_builder.binding_pattern(
             BindingPatternKind::BindingIdentifier(id),
             None::<OxcBox<'_, TSTypeAnnotation<'_>>>,
@@ -93,7 +268,14 @@ impl QrlComponent {
         );
         let export = Statement::ExportNamedDeclaration(OxcBox::new_in(export, allocator));
 
-        let imports = imports.iter().map(|import| {
+        // A type import is redundant once the segment imports the same names as values.
+        let imports = imports.iter().filter(|import| {
+            !import.is_type_only()
+                || !imports
+                    .iter()
+                    .any(|other| !other.is_type_only() && other.is_same_as(import))
+        });
+        let imports = imports.map(|import| {
             let statement: Statement = import.clone().into_in(allocator);
             statement
         });
@@ -113,14 +295,25 @@ impl QrlComponent {
             OxcVec::new_in(allocator),
             body,
         );
+        preserved_comments.prepare_segment(&mut new_pgm, allocator);
+
+        // Aliased before unused imports are pruned, as a shadowed import looks unused.
+        avoid_shadowing(&mut new_pgm, ast_builder);
+        ImportCleanUp::prune(&mut new_pgm);
+        Transpile::transpile(&mut new_pgm, source_path, options, allocator);
+        let metrics = SegmentMetrics::measure(&new_pgm, captures.len());
+        if options.module_format == ModuleFormat::Cjs {
+            to_commonjs(&mut new_pgm, options.require_dynamic_imports, allocator);
+        }
 
         let codegen = Codegen::new();
         let codegen_options = CodegenOptions {
             annotation_comments: true,
-            minify: minify,
+            minify: minify || options.is_compact(),
             ..Default::default()
         };
 
+        #[cfg(feature = "minifier")]
         if minify {
             let ops = MinifierOptions {
                 compress: Some(CompressOptions::default()),
@@ -131,49 +324,114 @@ impl QrlComponent {
             let ret = minifier.build(allocator, &mut new_pgm);
             let sym_tab = ret.symbol_table;
 
-            codegen
-                .with_options(codegen_options)
-                .with_symbol_table(sym_tab)
-                .build(&new_pgm)
-                .code
-        } else {
-            codegen.with_options(codegen_options).build(&new_pgm).code
+            let code = preserved_comments.print(
+                codegen.with_symbol_table(sym_tab),
+                codegen_options,
+                &mut new_pgm,
+            );
+            return (code, metrics);
         }
+
+        let code = preserved_comments.print(codegen, codegen_options, &mut new_pgm);
+        (code, metrics)
     }
 
-    /// Create a QrlComponent from an `Expression`.
+    /// Create a QrlComponent from an `Expression`. Fails with [Error::UnsupportedSyntax] if the innermost segment
+    /// is not a QRL.
     pub(crate) fn from_expression(
         expr: Expression<'_>,
         imports: Vec<Import>,
-        segments: &Vec<Segment>,
-        target: &Target,
-        scope: &Option<String>,
+        captures: Vec<String>,
+        segments: &[Segment],
+        options: &TransformOptions,
         source_info: &SourceInfo,
-        minify: bool,
-    ) -> QrlComponent {
-        let qrl_type: QrlType = segments
-            .last()
-            .iter()
-            .flat_map(|segment| segment.qrl_type())
+        preserved_comments: &PreservedComments,
+    ) -> Result<QrlComponent> {
+        let qrl_type = segments
             .last()
-            .unwrap(); // TODO Clean this up.
+            .and_then(|segment| segment.qrl_type())
+            .ok_or_else(|| {
+                Error::UnsupportedSyntax(
+                    source_info.rel_path.display().to_string(),
+                    "a segment is extracted from outside of a `$` marker call".to_string(),
+                )
+            })?;
 
-        let id = Id::new(source_info, segments, target, scope);
+        let id = Id::from_options(source_info, segments, options);
+
+        let root = segments.iter().find_map(|segment| match segment {
+            Segment::Named(name) | Segment::NamedQrl(name, _) => Some(name.clone()),
+            Segment::IndexQrl(_) => None,
+        });
 
-        QrlComponent::new(source_info, id, expr, imports, minify, qrl_type)
+        Ok(QrlComponent::new(
+            source_info,
+            id,
+            expr,
+            imports,
+            captures,
+            options,
+            qrl_type,
+            root,
+            preserved_comments,
+        ))
     }
 
+    #[allow(clippy::too_many_arguments)]
     pub(crate) fn from_call_expression_argument(
         arg: &Argument,
         imports: Vec<Import>,
-        segments: &Vec<Segment>,
-        target: &Target,
-        scope: &Option<String>,
+        captures: Vec<String>,
+        segments: &[Segment],
+        options: &TransformOptions,
         source_info: &SourceInfo,
-        minify: bool,
+        preserved_comments: &PreservedComments,
         allocator: &Allocator,
-    ) -> QrlComponent {
+    ) -> Result<QrlComponent> {
         let init = arg.clone_in(allocator).into_expression();
-        Self::from_expression(init, imports, segments, target, scope, source_info, minify)
+        Self::from_expression(
+            init,
+            imports,
+            captures,
+            segments,
+            options,
+            source_info,
+            preserved_comments,
+        )
+    }
+}
+
+impl Debug for QrlComponent {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("QrlComponent")
+            .field("id", &self.id)
+            .field("language", &self.language)
+            .field("code", &Truncated(&self.code))
+            .field("qrl", &self.qrl)
+            .field("origin", &self.origin)
+            .field("ctx_kind", &self.ctx_kind)
+            .field("ctx_name", &self.ctx_name)
+            .field("root", &self.root)
+            .field("entry", &self.entry)
+            .field("excerpt", &self.excerpt)
+            .field("listener", &self.listener)
+            .field("metrics", &self.metrics)
+            .finish()
+    }
+}
+
+impl Display for QrlComponent {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "QrlComponent [{:?}]", self.language)?;
+        writeln!(f, "├── id:")?;
+        write_indented(f, "│   ", &self.id.to_string())?;
+        writeln!(
+            f,
+            "├── qrl: {} ({:?})",
+            self.qrl.rel_path.to_string_lossy(),
+            self.qrl.qrl_type
+        )?;
+        writeln!(f, "└── code:")?;
+        write_indented(f, "    ", &Truncated(&self.code).to_string())
     }
 }
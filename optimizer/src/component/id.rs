@@ -1,51 +1,182 @@
 use crate::component::{SourceInfo, Target};
+use crate::intern::Name;
 use crate::segment::Segment;
+use crate::transform::TransformOptions;
 use base64::{engine, Engine};
-use serde::Serialize;
+use oxc_syntax::identifier::is_identifier_part;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::fmt::Display;
 use std::hash::{DefaultHasher, Hasher};
+use std::path::{Component, Path};
+
+/// How the names a display name is made of are turned into an identifier, in particular their non-ASCII letters
+/// and digits, e.g. of a component named `计数器`. Any other character, e.g. `-` or `.`, is replaced with `_`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "camelCase"))]
+pub enum SanitizeMode {
+    /// Strict ASCII: every other character is replaced with `_`, so `计数器` and `按钮` both become `_`, and
+    /// their segments are only told apart by the index appended to colliding names.
+    Ascii,
+    /// Every run of non-ASCII letters and digits is replaced with a short hash of it, e.g. `uRHv8f0` for
+    /// `计数器`, so that names stay ASCII, and thus safe in file names, while distinct names stay distinct.
+    #[default]
+    Hashed,
+    /// Non-ASCII letters and digits are kept as they are, as JavaScript identifiers allow them, e.g.
+    /// `app.tsx_计数器_component`.
+    Unicode,
+}
+
+/// Controls how the symbol name and hash of an [Id] are formatted.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct SymbolFormat {
+    /// The prefix of symbol names under [Target::Lib] and [Target::Prod], e.g. `s` in `s_0RVAWYCCxyk`.
+    pub prefix: String,
+    /// The number of base64 characters of the hash, between [SymbolFormat::MIN_HASH_LENGTH] and
+    /// [SymbolFormat::MAX_HASH_LENGTH].
+    pub hash_length: usize,
+    /// How display names are sanitized into identifiers.
+    pub sanitize: SanitizeMode,
+}
+
+impl SymbolFormat {
+    pub const DEFAULT_PREFIX: &'static str = "s";
+    pub const DEFAULT_HASH_LENGTH: usize = 11;
+    pub const MIN_HASH_LENGTH: usize = 4;
+    pub const MAX_HASH_LENGTH: usize = 22;
+
+    /// Creates a format, sanitizing `prefix` into an ASCII identifier and clamping `hash_length` to the supported
+    /// range. Like display names, a prefix starting with a digit is prefixed with an underscore.
+    pub fn new(prefix: Option<&str>, hash_length: Option<usize>) -> Self {
+        let prefix = prefix
+            .map(|prefix| Id::sanitize(prefix, SanitizeMode::Ascii))
+            .filter(|prefix| !prefix.is_empty() && prefix != "_")
+            .map(|prefix| {
+                if prefix.starts_with(|c: char| c.is_ascii_digit()) {
+                    format!("_{prefix}")
+                } else {
+                    prefix
+                }
+            })
+            .unwrap_or_else(|| Self::DEFAULT_PREFIX.to_string());
+        let hash_length = hash_length
+            .unwrap_or(Self::DEFAULT_HASH_LENGTH)
+            .clamp(Self::MIN_HASH_LENGTH, Self::MAX_HASH_LENGTH);
+        Self {
+            prefix,
+            hash_length,
+            sanitize: SanitizeMode::default(),
+        }
+    }
+
+    /// The same format, sanitizing display names with `mode`.
+    pub fn with_sanitize(mut self, mode: SanitizeMode) -> Self {
+        self.sanitize = mode;
+        self
+    }
+}
+
+impl Default for SymbolFormat {
+    fn default() -> Self {
+        Self::new(None, None)
+    }
+}
 
 /// Represents a component identifier, including its display name, symbol name, local file name, hash, and optional scope.
 ///
-/// This information is used to uniquely identify a component in the Qwik framework.
-#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+/// This information is used to uniquely identify a component in the Qwik framework. Names are [interned](Name), as
+/// they are copied into the QRLs, manifests and entry plans of a build.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Id {
-    pub display_name: String,
-    pub symbol_name: String,
+    pub display_name: Name,
+    pub symbol_name: Name,
     pub local_file_name: String,
-    pub hash: String,
+    pub hash: Name,
     pub scope: Option<String>,
+    /// The [hash](Id::hash) of the segment this one is nested in, e.g. of `App_component` for
+    /// `App_component_button_onClick`, so that the runtime can prefetch the parents of a segment along with it.
+    /// `None` for segments which are not nested in another segment.
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub parent_hash: Option<Name>,
 }
 
 impl Id {
-    fn sanitize(input: &str) -> String {
-        input
-            .chars()
-            .fold((String::new(), false), |(mut acc, uscore), c| {
-                if c.is_ascii_alphanumeric() {
-                    acc.push(c);
-                    (acc, false)
-                } else if uscore {
-                    // Never push consecutive underscores.
-                    (acc, true)
-                } else {
-                    acc.push('_');
-                    (acc, true)
+    fn sanitize(input: &str, mode: SanitizeMode) -> String {
+        let is_unicode_part = |c: char| !c.is_ascii() && is_identifier_part(c);
+        let mut sanitized = String::with_capacity(input.len());
+        let mut uscore = false;
+        let mut chars = input.chars().peekable();
+        while let Some(c) = chars.next() {
+            if c.is_ascii_alphanumeric() || (is_unicode_part(c) && mode == SanitizeMode::Unicode) {
+                sanitized.push(c);
+            } else if is_unicode_part(c) && mode == SanitizeMode::Hashed {
+                let mut run = String::from(c);
+                while let Some(c) = chars.next_if(|c| is_unicode_part(*c)) {
+                    run.push(c);
                 }
-            })
-            .0
+                let mut hasher = DefaultHasher::new();
+                hasher.write(run.as_bytes());
+                sanitized.push('u');
+                sanitized.push_str(&Self::encode_hash(hasher.finish())[..6]);
+            } else {
+                // Never push consecutive underscores.
+                if !uscore {
+                    sanitized.push('_');
+                }
+                uscore = true;
+                continue;
+            }
+            uscore = false;
+        }
+        sanitized
     }
 
+    /// `hash` in base64, with the characters which are not valid in identifiers replaced with `0`.
+    fn encode_hash(hash: u64) -> String {
+        engine::general_purpose::URL_SAFE_NO_PAD
+            .encode(hash.to_le_bytes())
+            .replace(['-', '_'], "0")
+    }
+
+    #[cfg(test)]
     fn calculate_hash(local_file_name: &str, display_name: &str, scope: &Option<String>) -> String {
+        Self::calculate_hash_of_length(
+            local_file_name,
+            display_name,
+            scope,
+            SymbolFormat::DEFAULT_HASH_LENGTH,
+        )
+    }
+
+    /// Hashes of up to [SymbolFormat::DEFAULT_HASH_LENGTH] characters are a prefix of the default hash. Longer hashes
+    /// append characters of a second hash, derived by continuing to write to the same hasher.
+    fn calculate_hash_of_length(
+        local_file_name: &str,
+        display_name: &str,
+        scope: &Option<String>,
+        length: usize,
+    ) -> String {
+        let encode = Self::encode_hash;
+
         let mut hasher = DefaultHasher::new();
         if let Some(scope) = scope {
             hasher.write(scope.as_bytes());
         }
         hasher.write(local_file_name.as_bytes());
         hasher.write(display_name.as_bytes());
-        let hash = hasher.finish();
-        engine::general_purpose::URL_SAFE_NO_PAD
-            .encode(hash.to_le_bytes())
-            .replace(['-', '_'], "0")
+        let mut hash = encode(hasher.finish());
+
+        if length > hash.len() {
+            hasher.write_u8(0);
+            hash.push_str(&encode(hasher.finish()));
+        }
+        hash.truncate(length);
+        hash
     }
 
     fn update_display_name(display_name: &mut String, name_segment: String) {
@@ -66,6 +197,21 @@ impl Id {
         }
     }
 
+    /// Creates an `Id` like [Id::with_format], using the [TransformOptions::symbol_format].
+    pub(crate) fn from_options(
+        source_info: &SourceInfo,
+        segments: &[Segment],
+        options: &TransformOptions,
+    ) -> Id {
+        Self::with_format(
+            source_info,
+            segments,
+            &options.target,
+            &options.scope,
+            &options.symbol_format(),
+        )
+    }
+
     /// Creates a component [Id] from a given [SourceInfo], a `Vec[String]` of segment identifiers that relate back the
     /// components location in the source code, a target (prod, lib, dev, test), and an optional scope.
     ///
@@ -104,7 +250,8 @@ impl Id {
     ///
     /// If display_name is `a_b_c` and the hash is `0RVAWYCCxyk`, the symbol name will be `a_b_c_0RVAWYCCxyk`.
     ///
-    /// When [Target::Lib] or [Target::Prod] is provided, the symbol name will be generated as `s_{hash}`.
+    /// When [Target::Lib] or [Target::Prod] is provided, the symbol name will be generated as `{prefix}_{hash}`, where
+    /// the prefix is [SymbolFormat::prefix]. The hash is [SymbolFormat::hash_length] characters long.
     ///
     /// ## Examples
     ///
@@ -119,11 +266,12 @@ impl Id {
     /// - The `scope` (if provided).
     ///
     /// [V 1.0 REF] see `QwikTransform.register_context_name` in `transform.rs.
-    pub fn new(
+    pub(crate) fn with_format(
         source_info: &SourceInfo,
-        segments: &Vec<Segment>,
+        segments: &[Segment],
         target: &Target,
         scope: &Option<String>,
+        format: &SymbolFormat,
     ) -> Id {
         let local_file_name = source_info.rel_path.to_string_lossy();
 
@@ -159,30 +307,119 @@ impl Id {
                 }
             }
 
-            display_name = Self::sanitize(&display_name);
+            display_name = Self::sanitize(&display_name, format.sanitize);
         }
 
         let normalized_local_file_name = local_file_name
             .strip_prefix("./")
             .unwrap_or(&local_file_name);
-        let hash64 = Self::calculate_hash(normalized_local_file_name, &display_name, scope);
+        let hash64 = Self::calculate_hash_of_length(
+            normalized_local_file_name,
+            &display_name,
+            scope,
+            format.hash_length,
+        );
 
         let symbol_name = match target {
             Target::Dev | Target::Test => format!("{}_{}", display_name, hash64),
-            Target::Lib | Target::Prod => format!("s_{}", hash64),
+            Target::Lib | Target::Prod => format!("{}_{}", format.prefix, hash64),
         };
 
         let display_name = format!("{}_{}", &source_info.file_name, display_name);
 
         let local_file_name = format!("{}_{}", local_file_name, symbol_name);
         Id {
-            display_name,
-            symbol_name,
+            display_name: display_name.into(),
+            symbol_name: symbol_name.into(),
             local_file_name,
-            hash: hash64,
+            hash: hash64.into(),
             scope: scope.clone(),
+            parent_hash: None,
+        }
+    }
+
+    /// The name of the chunk the segment is emitted to, i.e. its [local_file_name](Id::local_file_name) without a
+    /// leading `./`, e.g. `src/app.tsx_App_component_ckEPmXZlub0`.
+    pub fn chunk_name(&self) -> &str {
+        self.local_file_name
+            .strip_prefix("./")
+            .unwrap_or(&self.local_file_name)
+    }
+
+    /// The file the segment is written to, i.e. its [chunk name](Id::chunk_name) with the extension `ext`, given
+    /// with or without a leading `.`, e.g. `src/app.tsx_App_component_ckEPmXZlub0.js` for `js`.
+    pub fn chunk_file_name(&self, ext: &str) -> String {
+        match ext.trim_start_matches('.') {
+            "" => self.chunk_name().to_string(),
+            ext => format!("{}.{ext}", self.chunk_name()),
         }
     }
+
+    /// The specifier importing the [chunk file](Id::chunk_file_name) of the segment with the extension `ext` from a
+    /// module in the directory `relative_to`, given relative to the same directory as the chunk name, e.g.
+    /// `../app.tsx_App_component_ckEPmXZlub0.js` from `src/routes` for the segment
+    /// `src/app.tsx_App_component_ckEPmXZlub0`. Always starts with `./` or `../`, so that bundlers and browsers
+    /// resolve it relative to the importing module.
+    pub fn import_specifier(&self, relative_to: &Path, ext: &str) -> String {
+        let normal = |path: &'_ Path| -> Vec<String> {
+            path.components()
+                .filter_map(|component| match component {
+                    Component::Normal(name) => Some(name.to_string_lossy().to_string()),
+                    _ => None,
+                })
+                .collect()
+        };
+        let base = normal(relative_to);
+        let target = normal(Path::new(&self.chunk_file_name(ext)));
+        let common = base
+            .iter()
+            .zip(&target)
+            .take_while(|(base, target)| base == target)
+            .count();
+        let parents = vec![".."; base.len() - common];
+        let specifier = parents
+            .into_iter()
+            .map(str::to_string)
+            .chain(target[common..].iter().cloned())
+            .collect::<Vec<_>>()
+            .join("/");
+        match specifier.starts_with("../") {
+            true => specifier,
+            false => format!("./{specifier}"),
+        }
+    }
+
+    /// The [display name](Id::display_name) sanitized into an ASCII identifier, e.g. `app_tsx_App_component` for
+    /// `app.tsx_App_component`.
+    pub fn sanitized_display(&self) -> String {
+        Self::sanitize(&self.display_name, SanitizeMode::Ascii)
+    }
+
+    /// Creates an `Id` with the default [SymbolFormat].
+    #[allow(private_interfaces)]
+    pub fn new(
+        source_info: &SourceInfo,
+        segments: &[Segment],
+        target: &Target,
+        scope: &Option<String>,
+    ) -> Id {
+        Self::with_format(
+            source_info,
+            segments,
+            target,
+            scope,
+            &SymbolFormat::default(),
+        )
+    }
+}
+
+impl Display for Id {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "{} ({})", self.symbol_name, self.display_name)?;
+        writeln!(f, "├── local_file_name: {}", self.local_file_name)?;
+        writeln!(f, "├── hash: {}", self.hash)?;
+        write!(f, "└── scope: {}", self.scope.as_deref().unwrap_or("-"))
+    }
 }
 
 #[cfg(test)]
@@ -191,10 +428,65 @@ mod tests {
 
     #[test]
     fn escapes_a_name() {
-        let name0 = Id::sanitize("a'b-c");
-        let name1 = Id::sanitize("A123b_c-~45");
+        let name0 = Id::sanitize("a'b-c", SanitizeMode::Ascii);
+        let name1 = Id::sanitize("A123b_c-~45", SanitizeMode::Ascii);
         assert_eq!(name0, "a_b_c");
         assert_eq!(name1, "A123b_c_45");
+        for mode in [SanitizeMode::Hashed, SanitizeMode::Unicode] {
+            assert_eq!(Id::sanitize("A123b_c-~45", mode), "A123b_c_45");
+        }
+    }
+
+    #[test]
+    fn sanitizes_unicode_names() {
+        let sanitize = Id::sanitize;
+        assert_eq!(
+            sanitize("计数器_component", SanitizeMode::Ascii),
+            "_component"
+        );
+        assert_eq!(
+            sanitize("按钮_component", SanitizeMode::Ascii),
+            "_component"
+        );
+        assert_eq!(
+            sanitize("计数器_component", SanitizeMode::Unicode),
+            "计数器_component"
+        );
+        assert_eq!(sanitize("café-bar", SanitizeMode::Unicode), "café_bar");
+
+        let counter = sanitize("计数器_component", SanitizeMode::Hashed);
+        let button = sanitize("按钮_component", SanitizeMode::Hashed);
+        assert_ne!(counter, button);
+        assert_eq!(counter, sanitize("计数器_component", SanitizeMode::Hashed));
+        assert_eq!(counter, "uRHv8f0_component");
+        // Emoji are neither letters nor digits, so they are still replaced with `_`.
+        assert_eq!(sanitize("🚀-launch", SanitizeMode::Hashed), "_launch");
+    }
+
+    #[test]
+    fn keeps_unicode_components_apart() {
+        let source_info = SourceInfo::new("app.tsx").unwrap();
+        let ids = |mode: SanitizeMode| {
+            let format = SymbolFormat::default().with_sanitize(mode);
+            ["计数器", "按钮"].map(|name| {
+                let segments = [
+                    Segment::Named(name.to_string()),
+                    Segment::Named("component".to_string()),
+                ];
+                Id::with_format(&source_info, &segments, &Target::Dev, &None, &format)
+            })
+        };
+
+        let [counter, button] = ids(SanitizeMode::Ascii);
+        assert_eq!(counter.display_name, button.display_name);
+
+        let [counter, button] = ids(SanitizeMode::default());
+        assert_ne!(counter.display_name, button.display_name);
+        assert_ne!(counter.symbol_name, button.symbol_name);
+        assert!(counter.symbol_name.is_ascii());
+
+        let [counter, _] = ids(SanitizeMode::Unicode);
+        assert_eq!(counter.display_name, "app.tsx_计数器_component");
     }
 
     #[test]
@@ -210,7 +502,7 @@ mod tests {
         let source_info0 = SourceInfo::new("app.js").unwrap();
         let id0 = Id::new(
             &source_info0,
-            &vec![
+            &[
                 Segment::Named("a".to_string()),
                 Segment::Named("b".to_string()),
                 Segment::Named("c".to_string()),
@@ -221,17 +513,18 @@ mod tests {
         let hash0 = Id::calculate_hash("app.js", "a_b_c", &None);
 
         let expected0 = Id {
-            display_name: "app.js_a_b_c".to_string(),
-            symbol_name: format!("a_b_c_{}", hash0),
+            display_name: "app.js_a_b_c".into(),
+            symbol_name: format!("a_b_c_{}", hash0).into(),
             local_file_name: "app.js_a_b_c_tZuivXMgs2w".to_string(),
-            hash: hash0,
+            hash: hash0.into(),
             scope: None,
+            parent_hash: None,
         };
 
         let scope1 = Some("scope".to_string());
         let id1 = Id::new(
             &source_info0,
-            &vec![
+            &[
                 Segment::Named("1".to_string()),
                 Segment::Named("b".to_string()),
                 Segment::Named("c".to_string()),
@@ -242,24 +535,57 @@ mod tests {
         // Leading  segments that are digits are prefixed with an additional underscore.
         let hash1 = Id::calculate_hash("app.js", "_1_b_c", &scope1);
         let expected1 = Id {
-            display_name: "app.js__1_b_c".to_string(),
+            display_name: "app.js__1_b_c".into(),
             // When Target is neither "Dev" nor "Test", the symbol name is set to "s_{hash}".
-            symbol_name: format!("s_{}", hash1),
+            symbol_name: format!("s_{}", hash1).into(),
             local_file_name: "app.js_s_bQ4D62Vr0Zg".to_string(),
-            hash: hash1,
+            hash: hash1.into(),
             scope: Some("scope".to_string()),
+            parent_hash: None,
         };
 
         assert_eq!(id0, expected0);
         assert_eq!(id1, expected1);
     }
 
+    #[test]
+    fn formats_symbol_names() {
+        let source_info = SourceInfo::new("app.js").unwrap();
+        let segments = [Segment::Named("a".to_string())];
+        let default = Id::new(&source_info, &segments, &Target::Prod, &None);
+
+        let short = Id::with_format(
+            &source_info,
+            &segments,
+            &Target::Prod,
+            &None,
+            &SymbolFormat::new(Some("ui-kit"), Some(6)),
+        );
+        assert_eq!(short.symbol_name, format!("ui_kit_{}", &default.hash[..6]));
+
+        let long = Id::with_format(
+            &source_info,
+            &segments,
+            &Target::Prod,
+            &None,
+            &SymbolFormat::new(None, Some(100)),
+        );
+        assert_eq!(long.hash.len(), SymbolFormat::MAX_HASH_LENGTH);
+        assert!(long.hash.starts_with(default.hash.as_str()));
+        assert_eq!(SymbolFormat::new(Some("-"), Some(0)).prefix, "s");
+        assert_eq!(SymbolFormat::new(Some("1x"), None).prefix, "_1x");
+        assert_eq!(
+            SymbolFormat::new(Some("-"), Some(0)).hash_length,
+            SymbolFormat::MIN_HASH_LENGTH
+        );
+    }
+
     #[test]
     fn creates_a_id_with_indexes() {
         let source_info0 = SourceInfo::new("app.js").unwrap();
         let id1 = Id::new(
             &source_info0,
-            &vec![
+            &[
                 Segment::Named("a".to_string()),
                 Segment::Named("b".to_string()),
                 Segment::IndexQrl(1),
@@ -272,7 +598,7 @@ mod tests {
 
         let id2 = Id::new(
             &source_info0,
-            &vec![
+            &[
                 Segment::Named("a".to_string()),
                 Segment::Named("b".to_string()),
                 Segment::IndexQrl(1),
@@ -283,7 +609,7 @@ mod tests {
 
         let id3 = Id::new(
             &source_info0,
-            &vec![
+            &[
                 Segment::Named("a".to_string()),
                 Segment::Named("b".to_string()),
                 Segment::NamedQrl("c".to_string(), 0),
@@ -294,7 +620,7 @@ mod tests {
 
         let id4 = Id::new(
             &source_info0,
-            &vec![
+            &[
                 Segment::Named("a".to_string()),
                 Segment::Named("b".to_string()),
                 Segment::NamedQrl("c".to_string(), 1),
@@ -305,7 +631,7 @@ mod tests {
 
         let id5 = Id::new(
             &source_info0,
-            &vec![
+            &[
                 Segment::Named("a".to_string()),
                 Segment::NamedQrl("b".to_string(), 0),
                 Segment::IndexQrl(1),
@@ -316,7 +642,7 @@ mod tests {
 
         let id6 = Id::new(
             &source_info0,
-            &vec![
+            &[
                 Segment::Named("a".to_string()),
                 Segment::NamedQrl("b".to_string(), 0),
                 Segment::IndexQrl(0),
@@ -332,4 +658,53 @@ mod tests {
         assert_eq!(id5.display_name, "app.js_a_b_1");
         assert_eq!(id6.display_name, "app.js_a_b");
     }
+
+    #[test]
+    fn displays_as_tree() {
+        let source_info = SourceInfo::new("app.js").unwrap();
+        let id = Id::new(
+            &source_info,
+            &[Segment::Named("a".to_string())],
+            &Target::Dev,
+            &None,
+        );
+        let rendered = id.to_string();
+        let lines: Vec<&str> = rendered.lines().collect();
+
+        assert_eq!(lines.len(), 4);
+        assert_eq!(lines[0], format!("{} (app.js_a)", id.symbol_name));
+        assert_eq!(lines[3], "└── scope: -");
+    }
+
+    #[test]
+    fn derives_chunk_paths() {
+        let id = Id {
+            display_name: "app.tsx_App_component".into(),
+            symbol_name: "App_component_ckEPmXZlub0".into(),
+            local_file_name: "./src/app.tsx_App_component_ckEPmXZlub0".to_string(),
+            hash: "ckEPmXZlub0".into(),
+            scope: None,
+            parent_hash: None,
+        };
+
+        assert_eq!(id.chunk_name(), "src/app.tsx_App_component_ckEPmXZlub0");
+        assert_eq!(
+            id.chunk_file_name(".js"),
+            "src/app.tsx_App_component_ckEPmXZlub0.js"
+        );
+        assert_eq!(id.chunk_file_name(""), id.chunk_name());
+        assert_eq!(
+            id.import_specifier(Path::new("src"), "js"),
+            "./app.tsx_App_component_ckEPmXZlub0.js"
+        );
+        assert_eq!(
+            id.import_specifier(Path::new("./src/routes/blog"), "tsx"),
+            "../../app.tsx_App_component_ckEPmXZlub0.tsx"
+        );
+        assert_eq!(
+            id.import_specifier(Path::new(""), "js"),
+            "./src/app.tsx_App_component_ckEPmXZlub0.js"
+        );
+        assert_eq!(id.sanitized_display(), "app_tsx_App_component");
+    }
 }
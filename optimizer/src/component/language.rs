@@ -1,10 +1,12 @@
 use crate::error::Error;
 use crate::prelude::*;
 use oxc_span::SourceType;
-use serde::Serialize;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
 use std::path::Path;
 
-#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum Language {
     Javascript,
     Typescript,
@@ -20,12 +22,30 @@ impl Language {
     }
 }
 
+/// The extensions of Markdown modules, e.g. the routes of Qwik City. They are transformed as JSX, once compiled to
+/// it by a [Preprocessor](crate::preprocess::Preprocessor) or an upstream plugin.
+pub const MARKDOWN_EXTENSIONS: [&str; 2] = ["md", "mdx"];
+
+/// Whether `path` is a Markdown module, see [MARKDOWN_EXTENSIONS].
+pub(crate) fn is_markdown(path: &Path) -> bool {
+    path.extension()
+        .and_then(|extension| extension.to_str())
+        .is_some_and(|extension| MARKDOWN_EXTENSIONS.contains(&extension))
+}
+
+/// The source type of the module at `path`. Markdown modules are parsed as JSX.
+pub(crate) fn source_type(path: &Path) -> Result<SourceType> {
+    match is_markdown(path) {
+        true => Ok(SourceType::jsx()),
+        false => Ok(SourceType::from_path(path)?),
+    }
+}
+
 impl<'a> TryFrom<&'a Path> for Language {
     type Error = Error;
 
     fn try_from(path: &'a Path) -> Result<Language> {
-        let source_type = SourceType::from_path(path)?;
-        source_type.try_into()
+        source_type(path)?.try_into()
     }
 }
 
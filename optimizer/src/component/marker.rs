@@ -0,0 +1,69 @@
+use crate::component::MARKER_SUFFIX;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// The functions whose first argument is extracted into a segment, in addition to the ones named with the
+/// [MARKER_SUFFIX] (e.g. `component$`), see [TransformOptions::markers](crate::transform::TransformOptions::markers).
+///
+/// Like `component$` is replaced with `componentQrl`, a marker is replaced with the function named after its prefix
+/// followed by `Qrl`, which the module it is imported from has to export. The prefix of a marker named with one of
+/// the [suffixes](Markers::suffixes) is its name without the suffix, e.g. `lazy` for `lazy_` with the suffix `_`. The
+/// prefix of a marker listed in [names](Markers::names) is its whole name. Segments are named after the prefix, and
+/// their [ctx_name](crate::component::QrlComponent::ctx_name) is the prefix followed by `$`, e.g. `lazy$`, so that
+/// they are classified like the `$` markers of the same prefix.
+///
+/// ```
+/// use qwik_optimizer::api::Markers;
+///
+/// let markers = Markers::default().with_suffix("_").with_name("defer");
+/// assert_eq!(markers.prefix("component$"), Some("component"));
+/// assert_eq!(markers.prefix("lazy_"), Some("lazy"));
+/// assert_eq!(markers.prefix("defer"), Some("defer"));
+/// assert_eq!(markers.prefix("deferred"), None);
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "camelCase", default))]
+pub struct Markers {
+    /// Suffixes marking the functions named with them, e.g. `_` for `lazy_`.
+    pub suffixes: Vec<String>,
+    /// The exact names of marker functions, e.g. a `defer` wrapper of a design system.
+    pub names: Vec<String>,
+}
+
+impl Markers {
+    /// Adds `suffix` to the [suffixes](Markers::suffixes). Empty suffixes are ignored.
+    pub fn with_suffix<S: Into<String>>(mut self, suffix: S) -> Self {
+        let suffix = suffix.into();
+        if !suffix.is_empty() && !self.suffixes.contains(&suffix) {
+            self.suffixes.push(suffix);
+        }
+        self
+    }
+
+    /// Adds `name` to the [names](Markers::names).
+    pub fn with_name<S: Into<String>>(mut self, name: S) -> Self {
+        let name = name.into();
+        if !self.names.contains(&name) {
+            self.names.push(name);
+        }
+        self
+    }
+
+    /// The prefix of the marker `name`, or `None` if `name` is not a marker. The longest matching suffix is stripped,
+    /// and `$` is an unanchored marker with an empty prefix.
+    pub fn prefix<'n>(&self, name: &'n str) -> Option<&'n str> {
+        if self.names.iter().any(|marker| marker == name) {
+            return Some(name);
+        }
+        std::iter::once(MARKER_SUFFIX)
+            .chain(self.suffixes.iter().map(String::as_str))
+            .filter(|suffix| !suffix.is_empty())
+            .filter_map(|suffix| name.strip_suffix(suffix))
+            .min_by_key(|prefix| prefix.len())
+    }
+
+    pub fn is_marker(&self, name: &str) -> bool {
+        self.prefix(name).is_some()
+    }
+}
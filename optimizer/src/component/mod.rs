@@ -1,14 +1,19 @@
+#[allow(clippy::module_inception)]
 mod component;
 mod id;
 mod language;
+mod marker;
 mod qrl;
 mod shared;
 mod source_info;
 
 // -- Flatten
-pub(crate) use component::*;
-pub(crate) use id::*;
-pub(crate) use language::*;
-pub(crate) use qrl::*;
+pub use component::QrlComponent;
+pub use id::{Id, SanitizeMode, SymbolFormat};
+pub(crate) use language::{is_markdown, source_type};
+pub use language::{Language, MARKDOWN_EXTENSIONS};
+pub use marker::Markers;
+pub use qrl::{Qrl, QrlDev, QrlType};
+pub use shared::Target;
 pub(crate) use shared::*;
 pub(crate) use source_info::*;
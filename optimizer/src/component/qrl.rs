@@ -1,16 +1,19 @@
-use crate::component::{Import, QRL, QRL_SUFFIX, QWIK_CORE_SOURCE};
+use crate::component::{Import, INLINED_QRL, NOOP_QRL, QRL, QRL_DEV, QRL_SUFFIX, QWIK_CORE_SOURCE};
 use crate::ext::AstBuilderExt;
 use oxc_allocator::{Allocator, Box as OxcBox, CloneIn, FromIn, IntoIn, Vec as OxcVec};
 use oxc_ast::ast::*;
 use oxc_ast::AstBuilder;
 use oxc_semantic::{NodeId, ReferenceFlags, ReferenceId, ScopeId, SymbolFlags, SymbolId};
 use oxc_span::{Atom, SPAN};
+use oxc_syntax::number::NumberBase;
 use oxc_traverse::TraverseCtx;
-use serde::Serialize;
-use std::collections::HashMap;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
 use std::path::PathBuf;
 
-#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum QrlType {
     Qrl,
     PrefixedQrl(String),
@@ -33,20 +36,148 @@ impl From<QrlType> for Import {
     }
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Qrl {
     pub rel_path: PathBuf,
     pub display_name: String,
     pub qrl_type: QrlType,
+    /// The identifiers captured from the enclosing scope, passed to `qrl` as its third argument.
+    pub captures: Vec<String>,
+    /// The values of the `const` variables declared in the segment rather than captured, by name, see
+    /// [TransformOptions::inline_const_captures](crate::transform::TransformOptions::inline_const_captures).
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "BTreeMap::is_empty")
+    )]
+    pub inlined_captures: BTreeMap<String, String>,
+    /// The specifier the module containing the QRL imports the segment from, `rel_path` relative to that module, see
+    /// [Id::import_specifier](crate::component::Id::import_specifier).
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub import_specifier: String,
+    /// The specifier the segment is imported from when resolved by a
+    /// [ChunkResolver](crate::chunk::ChunkResolver), instead of `import_specifier`.
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub chunk: Option<String>,
+    /// The origin of the segment, with which the QRL is created by `qrlDEV` rather than `qrl`, see
+    /// [TransformOptions::embed_origin](crate::transform::TransformOptions::embed_origin).
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub dev: Option<QrlDev>,
+}
+
+/// The origin of a segment passed to `qrlDEV`, so that devtools can attribute the segment to its source.
+///
+/// ```javascript
+/// qrlDEV(() => import("./app.tsx_App_component_ckEPmXZlub0"), "s_ckEPmXZlub0", { file: "src/app.tsx", lo: 52, hi: 96, displayName: "app.tsx_App_component" })
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "camelCase"))]
+pub struct QrlDev {
+    /// The path of the file the segment was extracted from, relative to the package.
+    pub file: String,
+    /// The offset of the start of the segment in the file.
+    pub lo: u32,
+    /// The offset of the end of the segment in the file.
+    pub hi: u32,
+    pub display_name: String,
 }
 
 impl Qrl {
-    pub fn new<T: Into<PathBuf>>(rel_path: T, display_name: &str, qrl_type: QrlType) -> Self {
+    pub fn new<T: Into<PathBuf>>(
+        rel_path: T,
+        display_name: &str,
+        qrl_type: QrlType,
+        captures: Vec<String>,
+    ) -> Self {
+        let rel_path = rel_path.into();
         Self {
-            rel_path: rel_path.into(),
+            import_specifier: rel_path.to_string_lossy().to_string(),
+            rel_path,
             display_name: display_name.into(),
             qrl_type,
+            captures,
+            inlined_captures: BTreeMap::new(),
+            chunk: None,
+            dev: None,
+        }
+    }
+
+    /// The import of the functions creating the QRL, e.g. `qrl` and `componentQrl`.
+    pub(crate) fn import(&self) -> Import {
+        match (&self.qrl_type, &self.dev) {
+            (_, None) => self.qrl_type.clone().into(),
+            (QrlType::Qrl | QrlType::IndexedQrl(_), Some(_)) => {
+                Import::new(vec![QRL_DEV.into()], QWIK_CORE_SOURCE)
+            }
+            (QrlType::PrefixedQrl(prefix), Some(_)) => Import::new(
+                vec![
+                    format!("{}{}", prefix, QRL_SUFFIX).as_str().into(),
+                    QRL_DEV.into(),
+                ],
+                QWIK_CORE_SOURCE,
+            ),
+        }
+    }
+
+    /// Creates the dev argument of `qrlDEV`, e.g. `{ file: "src/app.tsx", lo: 52, hi: 96, displayName: "..." }`.
+    fn into_dev_argument<'a>(dev: &QrlDev, ast_builder: &AstBuilder<'a>) -> Argument<'a> {
+        let string = |value: &str| {
+            let value = ast_builder.atom(value);
+            ast_builder.expression_string_literal(SPAN, value, None)
+        };
+        let number = |value: u32| {
+            ast_builder.expression_numeric_literal(SPAN, value as f64, None, NumberBase::Decimal)
+        };
+        let properties = [
+            ("file", string(&dev.file)),
+            ("lo", number(dev.lo)),
+            ("hi", number(dev.hi)),
+            ("displayName", string(&dev.display_name)),
+        ]
+        .into_iter()
+        .map(|(key, value)| {
+            ast_builder.object_property_kind_object_property(
+                SPAN,
+                PropertyKind::Init,
+                ast_builder.property_key_static_identifier(SPAN, key),
+                value,
+                false,
+                false,
+                false,
+            )
+        });
+        let properties = ast_builder.vec_from_iter(properties);
+        Argument::from(ast_builder.expression_object(SPAN, properties, None))
+    }
+
+    /// Creates the capture array, e.g. `[a, b]`, binding each identifier in the scope the `qrl` call is placed in.
+    #[allow(clippy::wrong_self_convention)]
+    fn into_captures_argument<'a>(&self, ctx: &mut TraverseCtx<'a>) -> Argument<'a> {
+        let ast_builder = ctx.ast;
+        let mut elements = ast_builder.vec_with_capacity(self.captures.len());
+        for name in &self.captures {
+            let reference = match ctx.scopes().find_binding(ctx.current_scope_id(), name) {
+                Some(symbol_id) => ctx.create_bound_reference(symbol_id, ReferenceFlags::Read),
+                None => ctx.create_unbound_reference(name, ReferenceFlags::Read),
+            };
+            let ident =
+                ast_builder.identifier_reference_with_reference_id(SPAN, name.as_str(), reference);
+            elements.push(ArrayExpressionElement::Identifier(OxcBox::new_in(
+                ident,
+                ast_builder.allocator,
+            )));
         }
+        Argument::ArrayExpression(OxcBox::new_in(
+            ast_builder.array_expression(SPAN, elements, None),
+            ast_builder.allocator,
+        ))
     }
 
     /// Creates a reference id, attempting to bind it
@@ -62,20 +193,21 @@ impl Qrl {
             QrlType::Qrl | QrlType::IndexedQrl(_) => {
                 // `qrl` is ALWAYS part of newly created expression, even if `$` was not used in the initial script.
                 // If `qrl` was not explicitly imported in the original script, we need to synthesize both a SymbolId and an Import.
-                let qrl_symbol_id = if !symbols_by_name.contains_key(QRL) {
-                    let symbol_id = ctx.symbols_mut().create_symbol(
-                        SPAN,
-                        QRL,
-                        SymbolFlags::Import,
-                        ScopeId::new(0),
-                        NodeId::DUMMY,
-                    );
-                    let import = Import::new(vec!["qrl".into()], QWIK_CORE_SOURCE);
-                    symbols_by_name.insert(QRL.to_string(), symbol_id);
-                    import_by_symbol.insert(symbol_id, import);
-                    symbol_id
-                } else {
-                    *symbols_by_name.get(QRL).unwrap() // This should never fail based on the call above.
+                let qrl_symbol_id = match symbols_by_name.get(QRL) {
+                    Some(symbol_id) => *symbol_id,
+                    None => {
+                        let symbol_id = ctx.symbols_mut().create_symbol(
+                            SPAN,
+                            QRL,
+                            SymbolFlags::Import,
+                            ScopeId::new(0),
+                            NodeId::DUMMY,
+                        );
+                        let import = Import::new(vec!["qrl".into()], QWIK_CORE_SOURCE);
+                        symbols_by_name.insert(QRL.to_string(), symbol_id);
+                        import_by_symbol.insert(symbol_id, import);
+                        symbol_id
+                    }
                 };
 
                 ctx.create_bound_reference(qrl_symbol_id, ReferenceFlags::None)
@@ -139,12 +271,11 @@ impl Qrl {
     /// qrl(() => import("./test.tsx_renderHeader_zBbHWn4e8Cg"), "renderHeader_zBbHWn4e8Cg");
     /// ```
     ///
+    #[allow(clippy::wrong_self_convention)]
     fn into_arrow_function<'a>(&self, ast_builder: &AstBuilder<'a>) -> ArrowFunctionExpression<'a> {
-        let rel_path = self.rel_path.to_string_lossy();
-
         // Function Body /////////
         let mut statements = ast_builder.vec_with_capacity(1);
-        statements.push(ast_builder.create_simple_import(rel_path.as_ref()));
+        statements.push(ast_builder.create_simple_import(self.specifier()));
         let function_body = ast_builder.function_body(SPAN, ast_builder.vec(), statements);
         let func_params = ast_builder.formal_parameters(
             SPAN,
@@ -165,6 +296,13 @@ impl Qrl {
         )
     }
 
+    /// The specifier the segment is imported from: its [chunk](Qrl::chunk) if resolved, or its
+    /// [import specifier](Qrl::import_specifier).
+    pub(crate) fn specifier(&self) -> &str {
+        self.chunk.as_deref().unwrap_or(&self.import_specifier)
+    }
+
+    #[allow(clippy::wrong_self_convention)]
     fn into_arguments<'a>(&self, ast_builder: &AstBuilder<'a>) -> OxcVec<'a, Argument<'a>> {
         let allocator = ast_builder.allocator;
         let display_name = self.display_name.clone();
@@ -195,18 +333,30 @@ impl Qrl {
     ) -> CallExpression<'a> {
         let ast_builder = ctx.ast;
 
-        let qrl_ref_id = Self::make_ref_id(&QrlType::Qrl, ctx, symbols_by_name, import_by_symbol);
-        let qrl = ast_builder.identifier_reference_with_reference_id(SPAN, QRL, qrl_ref_id);
+        let qrl = match &self.dev {
+            Some(_) => Self::make_callee(QRL_DEV, ctx, symbols_by_name),
+            None => {
+                let qrl_ref_id =
+                    Self::make_ref_id(&QrlType::Qrl, ctx, symbols_by_name, import_by_symbol);
+                let qrl = ast_builder.identifier_reference_with_reference_id(SPAN, QRL, qrl_ref_id);
+                Expression::Identifier(OxcBox::new_in(qrl, ast_builder.allocator))
+            }
+        };
         let qrl_type = self.qrl_type.clone();
 
-        let args = self
+        let mut args = self
             .into_arguments(&ast_builder)
             .clone_in(ast_builder.allocator);
-        let qrl = OxcBox::new_in(qrl, ast_builder.allocator);
+        if let Some(dev) = &self.dev {
+            args.push(Self::into_dev_argument(dev, &ast_builder));
+        }
+        if !self.captures.is_empty() {
+            args.push(self.into_captures_argument(ctx));
+        }
 
         let qrl_call_expr = ast_builder.call_expression(
             SPAN,
-            Expression::Identifier(qrl),
+            qrl,
             None::<OxcBox<TSTypeParameterInstantiation>>,
             args,
             false,
@@ -241,6 +391,103 @@ impl Qrl {
         }
     }
 
+    /// Creates an `inlinedQrl` call which keeps `expr` in place rather than lazily importing it from its segment
+    /// file, wrapped in the prefixed call if the QRL was created by a marker other than `$`.
+    ///
+    /// # Examples
+    /// ```javascript
+    /// componentQrl(inlinedQrl(() => { const [a] = useLexicalScope(); ... }, "App_component_ckEPmXZlub0", [a]))
+    /// ```
+    pub fn into_inlined_call_expression<'a>(
+        &self,
+        expr: Expression<'a>,
+        ctx: &mut TraverseCtx<'a>,
+        symbols_by_name: &mut HashMap<String, SymbolId>,
+    ) -> CallExpression<'a> {
+        self.into_local_call_expression(INLINED_QRL, Some(expr), ctx, symbols_by_name)
+    }
+
+    /// Creates a `_noopQrl` call, a QRL which only carries the symbol name and captures of a segment whose code was
+    /// stripped from the current build, wrapped in the prefixed call if the QRL was created by a marker other than
+    /// `$`.
+    ///
+    /// # Examples
+    /// ```javascript
+    /// serverQrl(_noopQrl("App_component_server_Ud1D2kXNrjU", [a]))
+    /// ```
+    pub fn into_noop_call_expression<'a>(
+        &self,
+        ctx: &mut TraverseCtx<'a>,
+        symbols_by_name: &mut HashMap<String, SymbolId>,
+    ) -> CallExpression<'a> {
+        self.into_local_call_expression(NOOP_QRL, None, ctx, symbols_by_name)
+    }
+
+    /// Creates a call to `callee` which does not import the segment: `callee(expr?, "symbol", [captures]?)`.
+    #[allow(clippy::wrong_self_convention)]
+    fn into_local_call_expression<'a>(
+        &self,
+        callee: &str,
+        expr: Option<Expression<'a>>,
+        ctx: &mut TraverseCtx<'a>,
+        symbols_by_name: &mut HashMap<String, SymbolId>,
+    ) -> CallExpression<'a> {
+        let ast_builder = ctx.ast;
+        let allocator = ast_builder.allocator;
+
+        let raw: Atom = format!(r#""{}""#, self.display_name).into_in(allocator);
+        let mut args = ast_builder.vec_with_capacity(3);
+        if let Some(expr) = expr {
+            args.push(Argument::from(expr));
+        }
+        args.push(Argument::StringLiteral(OxcBox::new_in(
+            ast_builder.string_literal(SPAN, self.display_name.as_str(), Some(raw)),
+            allocator,
+        )));
+        if !self.captures.is_empty() {
+            args.push(self.into_captures_argument(ctx));
+        }
+
+        let call_expr = ast_builder.call_expression(
+            SPAN,
+            Self::make_callee(callee, ctx, symbols_by_name),
+            None::<OxcBox<TSTypeParameterInstantiation>>,
+            args,
+            false,
+        );
+
+        match &self.qrl_type {
+            QrlType::Qrl | QrlType::IndexedQrl(_) => call_expr,
+            QrlType::PrefixedQrl(prefix) => {
+                let name = format!("{}{}", prefix, QRL_SUFFIX);
+                let arg = Argument::CallExpression(OxcBox::new_in(call_expr, allocator));
+                ast_builder.call_expression(
+                    SPAN,
+                    Self::make_callee(&name, ctx, symbols_by_name),
+                    None::<OxcBox<TSTypeParameterInstantiation>>,
+                    ast_builder.vec1(arg),
+                    false,
+                )
+            }
+        }
+    }
+
+    /// Creates an identifier referencing `name`, bound to its import if it is already imported.
+    fn make_callee<'a>(
+        name: &str,
+        ctx: &mut TraverseCtx<'a>,
+        symbols_by_name: &HashMap<String, SymbolId>,
+    ) -> Expression<'a> {
+        let ref_id = match symbols_by_name.get(name) {
+            Some(symbol_id) => ctx.create_bound_reference(*symbol_id, ReferenceFlags::None),
+            None => ctx.create_unbound_reference(name, ReferenceFlags::None),
+        };
+        let ident =
+            ctx.ast
+                .identifier_reference_with_reference_id(SPAN, ctx.ast.atom(name), ref_id);
+        Expression::Identifier(OxcBox::new_in(ident, ctx.ast.allocator))
+    }
+
     /// To access this logic call `IntoIn` to convert `Qrl` to  full call `Expression`.
     /// # Examples
     /// ```ignore
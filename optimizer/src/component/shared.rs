@@ -1,38 +1,46 @@
+use crate::error::Error;
 use crate::ext::AstBuilderExt;
 use crate::import_clean_up::ImportCleanUp;
+use crate::intern::Name;
+use crate::prelude::*;
 use oxc_allocator::{Allocator, FromIn};
 use oxc_ast::ast::{ImportDeclarationSpecifier, ImportOrExportKind, Statement};
 use oxc_ast::AstBuilder;
 use oxc_span::{Atom, SPAN};
+#[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
 use std::convert::Into;
-use std::path::PathBuf;
+use std::fmt::Display;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
 
 pub const QWIK_CORE_SOURCE: &str = "@qwik.dev/core";
 pub const MARKER_SUFFIX: &str = "$";
 pub const QRL: &str = "qrl";
+/// The `qrl` variant carrying the origin of its segment, see [QrlDev](crate::component::QrlDev).
+pub const QRL_DEV: &str = "qrlDEV";
 pub const QRL_SUFFIX: &str = "Qrl";
+pub const INLINED_QRL: &str = "inlinedQrl";
+pub const NOOP_QRL: &str = "_noopQrl";
 
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub(crate) enum ImportId {
-    Named(String),
-    NamedWithAlias(String, String),
-    Default(String),
-    Namespace(String),
+    Named(Name),
+    NamedWithAlias(Name, Name),
+    Default(Name),
+    Namespace(Name),
 }
 
 impl From<&str> for ImportId {
     fn from(value: &str) -> Self {
-        ImportId::Named(value.to_string())
+        ImportId::Named(value.into())
     }
 }
 
-fn replace_marker_with_qrl(name: Atom<'_>) -> String {
-    let name = name.to_string();
-    if let Some(qrl_call) = name.strip_suffix(MARKER_SUFFIX) {
-        format!("{}{}", qrl_call, QRL_SUFFIX)
-    } else {
-        name
+fn replace_marker_with_qrl(name: Atom<'_>) -> Name {
+    match name.strip_suffix(MARKER_SUFFIX) {
+        Some(qrl_call) => format!("{}{}", qrl_call, QRL_SUFFIX).into(),
+        None => name.as_str().into(),
     }
 }
 
@@ -50,24 +58,33 @@ impl From<&ImportDeclarationSpecifier<'_>> for ImportId {
                 }
             }
             ImportDeclarationSpecifier::ImportDefaultSpecifier(specifier) => {
-                let local_name = specifier.local.name.to_string();
-                ImportId::Default(local_name)
+                ImportId::Default(specifier.local.name.as_str().into())
             }
             ImportDeclarationSpecifier::ImportNamespaceSpecifier(specifier) => {
-                let local_name = specifier.local.name.to_string();
-                ImportId::Namespace(local_name)
+                ImportId::Namespace(specifier.local.name.as_str().into())
             }
         }
     }
 }
 
+impl Display for ImportId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ImportId::Named(name) => write!(f, "{name}"),
+            ImportId::NamedWithAlias(name, local_name) => write!(f, "{name} as {local_name}"),
+            ImportId::Default(local_name) => write!(f, "default as {local_name}"),
+            ImportId::Namespace(local_name) => write!(f, "* as {local_name}"),
+        }
+    }
+}
+
 impl<'a> FromIn<'a, ImportId> for ImportDeclarationSpecifier<'a> {
     fn from_in(value: ImportId, allocator: &'a Allocator) -> Self {
         let ast = AstBuilder::new(allocator);
         match value {
             ImportId::Named(name) => {
-                let imported = ast.module_export_name_identifier_name(SPAN, &name);
-                let local_name = ast.binding_identifier(SPAN, &name);
+                let imported = ast.module_export_name_identifier_name(SPAN, name.as_str());
+                let local_name = ast.binding_identifier(SPAN, name.as_str());
                 ast.import_declaration_specifier_import_specifier(
                     SPAN,
                     imported,
@@ -77,8 +94,8 @@ impl<'a> FromIn<'a, ImportId> for ImportDeclarationSpecifier<'a> {
             }
 
             ImportId::NamedWithAlias(name, local_name) => {
-                let imported = ast.module_export_name_identifier_name(SPAN, &name);
-                let local_name = ast.binding_identifier(SPAN, &local_name);
+                let imported = ast.module_export_name_identifier_name(SPAN, name.as_str());
+                let local_name = ast.binding_identifier(SPAN, local_name.as_str());
                 ast.import_declaration_specifier_import_specifier(
                     SPAN,
                     imported,
@@ -87,11 +104,11 @@ impl<'a> FromIn<'a, ImportId> for ImportDeclarationSpecifier<'a> {
                 )
             }
             ImportId::Namespace(local_name) => {
-                let local_name = ast.binding_identifier(SPAN, &local_name);
+                let local_name = ast.binding_identifier(SPAN, local_name.as_str());
                 ast.import_declaration_specifier_import_namespace_specifier(SPAN, local_name)
             }
             ImportId::Default(name) => {
-                let local_name = ast.binding_identifier(SPAN, &name);
+                let local_name = ast.binding_identifier(SPAN, name.as_str());
                 ast.import_declaration_specifier_import_default_specifier(SPAN, local_name)
             }
         }
@@ -102,20 +119,49 @@ impl<'a> FromIn<'a, ImportId> for ImportDeclarationSpecifier<'a> {
 pub struct Import {
     names: Vec<ImportId>,
     source: PathBuf,
+    /// Whether the import is emitted as `import type`, because the segment only references it in types.
+    type_only: bool,
 }
 
 impl Import {
+    #[allow(private_interfaces)]
     pub fn new<T: AsRef<str>>(names: Vec<ImportId>, source: T) -> Self {
         let source = ImportCleanUp::rename_qwik_imports(source);
         Self {
             names,
             source: source.into(),
+            type_only: false,
+        }
+    }
+
+    /// The same import, emitted as `import type`.
+    pub(crate) fn into_type_only(self) -> Self {
+        Self {
+            type_only: true,
+            ..self
         }
     }
 
+    pub(crate) fn is_type_only(&self) -> bool {
+        self.type_only
+    }
+
+    /// Whether `other` imports the same names from the same module, either as a value or as a type.
+    pub(crate) fn is_same_as(&self, other: &Import) -> bool {
+        self.names == other.names && self.source == other.source
+    }
+
+    #[allow(clippy::wrong_self_convention)]
     pub fn into_statement<'a>(&self, allocator: &'a Allocator) -> Statement<'a> {
         let ast_builder = AstBuilder::new(allocator);
-        ast_builder.create_import_statement(self.names.clone(), self.source.to_string_lossy())
+        let mut statement =
+            ast_builder.create_import_statement(self.names.clone(), self.source.to_string_lossy());
+        if let Statement::ImportDeclaration(import) = &mut statement {
+            if self.type_only {
+                import.import_kind = ImportOrExportKind::Type;
+            }
+        }
+        statement
     }
 
     pub fn from_import_declaration_specifier<T: AsRef<str>>(
@@ -126,12 +172,35 @@ impl Import {
         Self::new(names, source)
     }
 
+    /// Whether the import is the one of `qrl` or `qrlDEV`, which every module containing the QRL of an extracted
+    /// segment has.
+    pub(crate) fn creates_qrl(&self) -> bool {
+        self.source == Path::new(QWIK_CORE_SOURCE)
+            && self
+                .names
+                .iter()
+                .any(|name| matches!(name, ImportId::Named(name) if name == QRL || name == QRL_DEV))
+    }
+
     pub fn qrl() -> Self {
         let names = vec![QRL.into()];
         Self::new(names, QWIK_CORE_SOURCE)
     }
 }
 
+impl Display for Import {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let names: Vec<String> = self.names.iter().map(|n| n.to_string()).collect();
+        let kind = if self.type_only { "type " } else { "" };
+        write!(
+            f,
+            "{kind}{{ {} }} from \"{}\"",
+            names.join(", "),
+            self.source.to_string_lossy()
+        )
+    }
+}
+
 impl<'a> FromIn<'a, &Import> for Statement<'a> {
     fn from_in(value: &Import, allocator: &'a Allocator) -> Self {
         value.into_statement(allocator)
@@ -146,10 +215,95 @@ impl<'a> FromIn<'a, Import> for Statement<'a> {
 
 /// Renamed from `EmitMode` in V 1.0.
 #[allow(dead_code)]
-#[derive(Debug, Serialize, Deserialize, Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
 pub enum Target {
+    #[cfg_attr(feature = "serde", serde(alias = "Prod"))]
     Prod,
+    #[cfg_attr(feature = "serde", serde(alias = "Lib"))]
     Lib,
+    #[cfg_attr(feature = "serde", serde(alias = "Dev"))]
     Dev,
+    #[cfg_attr(feature = "serde", serde(alias = "Test"))]
     Test,
 }
+
+impl Target {
+    /// The names of the targets, as accepted by [Target::from_str] and printed by [Display].
+    pub const NAMES: [&'static str; 4] = ["prod", "lib", "dev", "test"];
+
+    /// Resolves the target from the `NODE_ENV` environment variable, the way Node tooling does: `production`
+    /// builds for [Target::Prod], `test` for [Target::Test], and anything else, including an unset variable, for
+    /// [Target::Dev].
+    pub fn from_node_env() -> Target {
+        match std::env::var("NODE_ENV").as_deref() {
+            Ok("production") => Target::Prod,
+            Ok("test") => Target::Test,
+            _ => Target::Dev,
+        }
+    }
+
+    /// Resolves the target from a configuration value, e.g. a CLI flag, or from `NODE_ENV` when no value is given,
+    /// see [Target::from_node_env].
+    pub fn resolve(value: Option<&str>) -> Result<Target> {
+        value.map_or(Ok(Target::from_node_env()), str::parse)
+    }
+}
+
+impl FromStr for Target {
+    type Err = Error;
+
+    /// Parses the name of a target or a common alias, ignoring case: `production` and `ssr` (server bundles are only
+    /// built for production) for [Target::Prod], `development` for [Target::Dev], and `library` for [Target::Lib].
+    ///
+    /// ```
+    /// use qwik_optimizer::component::Target;
+    ///
+    /// assert_eq!("production".parse::<Target>()?, Target::Prod);
+    /// assert_eq!("Dev".parse::<Target>()?, Target::Dev);
+    /// assert!("staging".parse::<Target>().is_err());
+    /// # Ok::<(), qwik_optimizer::api::Error>(())
+    /// ```
+    fn from_str(value: &str) -> Result<Target> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "prod" | "production" | "ssr" => Ok(Target::Prod),
+            "lib" | "library" => Ok(Target::Lib),
+            "dev" | "development" => Ok(Target::Dev),
+            "test" => Ok(Target::Test),
+            _ => Err(Error::UnknownTarget(value.to_string())),
+        }
+    }
+}
+
+impl Display for Target {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Target::Prod => "prod",
+            Target::Lib => "lib",
+            Target::Dev => "dev",
+            Target::Test => "test",
+        };
+        write!(f, "{name}")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_targets() {
+        for name in Target::NAMES {
+            let target: Target = name.parse().unwrap();
+            assert_eq!(target.to_string(), name);
+        }
+        assert_eq!(" SSR ".parse::<Target>().unwrap(), Target::Prod);
+        assert_eq!("library".parse::<Target>().unwrap(), Target::Lib);
+        assert!(matches!(
+            "staging".parse::<Target>(),
+            Err(Error::UnknownTarget(value)) if value == "staging"
+        ));
+        assert_eq!(Target::resolve(Some("test")).unwrap(), Target::Test);
+    }
+}
@@ -1,9 +1,13 @@
-use crate::component::Language;
+use crate::component::{is_markdown, source_type, Language};
 use crate::error::*;
+use crate::hash::StableHasher;
 use crate::prelude::*;
+use crate::transform::{PathPrivacy, TransformOptions};
+use base64::{engine, Engine};
 use oxc_span::SourceType;
 use std::ffi::OsStr;
-use std::path::{Path, PathBuf};
+use std::hash::Hasher;
+use std::path::{Component, Path, PathBuf};
 
 /// Contains information about the source file, including its absolute and relative paths, directory paths.
 /// Renamed from `PathData` in V 1.0.
@@ -13,8 +17,13 @@ pub struct SourceInfo {
     pub rel_dir: PathBuf,
     pub file_name: String,
     pub language: Language,
+    /// Whether the source is a virtual module, see [SourceInfo::virtual_module]. Its `rel_path` is synthetic.
+    pub is_virtual: bool,
 }
 
+/// The directory virtual modules are placed in, see [SourceInfo::virtual_module].
+pub const VIRTUAL_DIR: &str = "_virtual";
+
 impl SourceInfo {
     /// Creates a new `SourceInfo` instance from a source file path and a base directory.
     ///
@@ -47,9 +56,74 @@ impl SourceInfo {
             rel_dir,
             file_name: file_name.into(),
             language,
+            is_virtual: false,
         })
     }
 
+    /// Creates a `SourceInfo` for a module which does not exist on disk, e.g. a bundler virtual module such as
+    /// `\0qwik-city-plan` or a source generated in memory.
+    ///
+    /// The module is given the synthetic path `_virtual/{id}.{ext}`, with the `\0` prefix and any `virtual:`
+    /// scheme of `id` removed and characters other than ASCII alphanumerics, `-`, `_`, `.` and `@` replaced with
+    /// `_`. The path only depends on `id` and `language`, so the hashes and file names of the segments of a virtual
+    /// module are as deterministic as those of a file, and [path privacy](crate::transform::PathPrivacy) leaves it
+    /// untouched.
+    pub fn virtual_module<S: AsRef<str>>(id: S, language: Language) -> SourceInfo {
+        let id = id.as_ref().trim_start_matches('\0');
+        let id = id.strip_prefix("virtual:").unwrap_or(id);
+        let name: String = id
+            .chars()
+            .map(|c| match c {
+                c if c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.' | '@') => c,
+                _ => '_',
+            })
+            .collect();
+        let name = if name.is_empty() { "module" } else { &name };
+        let file_name = format!("{}.{}", name, language.extension());
+
+        SourceInfo {
+            rel_path: Path::new(VIRTUAL_DIR).join(&file_name),
+            rel_dir: PathBuf::from(VIRTUAL_DIR),
+            file_name,
+            language,
+            is_virtual: true,
+        }
+    }
+
+    /// Returns a copy whose path is rewritten according to `privacy` and relative to `root`, see [private_path].
+    pub(crate) fn with_path_privacy(
+        &self,
+        privacy: PathPrivacy,
+        root: Option<&Path>,
+    ) -> Result<SourceInfo> {
+        match privacy {
+            _ if self.is_virtual => Ok(self.clone()),
+            PathPrivacy::None => Ok(self.clone()),
+            _ => SourceInfo::new(private_path(&self.rel_path, privacy, root)),
+        }
+    }
+
+    /// Whether the source is a Markdown module, see [MARKDOWN_EXTENSIONS](crate::component::MARKDOWN_EXTENSIONS).
+    pub fn is_markdown(&self) -> bool {
+        is_markdown(&self.rel_path)
+    }
+
+    /// The extension of the files the segments of the source are written to, without a leading `.`: TypeScript
+    /// sources keep `ts` or `tsx` unless [TypeScript is erased](TransformOptions::transpile_ts), and sources with
+    /// JSX keep `jsx` or `tsx` unless [JSX is compiled](TransformOptions::transpile_jsx).
+    pub fn segment_extension(&self, options: &TransformOptions) -> &'static str {
+        let extension = self.rel_path.extension().and_then(OsStr::to_str);
+        let is_typescript = self.language == Language::Typescript && !options.transpile_ts;
+        let is_jsx = !options.transpile_jsx
+            && (self.is_markdown() || matches!(extension, Some("tsx" | "jsx")));
+        match (is_typescript, is_jsx) {
+            (true, true) => "tsx",
+            (true, false) => "ts",
+            (false, true) => "jsx",
+            (false, false) => "js",
+        }
+    }
+
     pub fn rel_import_path(&self) -> PathBuf {
         match self.language {
             Language::Javascript => self.rel_path.clone(),
@@ -58,11 +132,61 @@ impl SourceInfo {
     }
 }
 
+/// Rewrites `path` so that it no longer reveals where the file lives on the machine running the build.
+///
+/// - [PathPrivacy::Relative] applies [reproducible_path] with `root` and drops any remaining `..` component.
+/// - [PathPrivacy::Hashed] additionally replaces the directory with a hash of it, keeping only the file name, e.g.
+///   `src/routes/blog/index.tsx` becomes `0ICQ6hetKbc/index.tsx`. Files of the same directory share a hash.
+pub(crate) fn private_path(path: &Path, privacy: PathPrivacy, root: Option<&Path>) -> PathBuf {
+    if privacy == PathPrivacy::None {
+        return path.to_path_buf();
+    }
+
+    let relative: PathBuf = reproducible_path(path, root)
+        .components()
+        .filter(|c| !matches!(c, Component::ParentDir))
+        .collect();
+
+    match (privacy, relative.parent(), relative.file_name()) {
+        (PathPrivacy::Hashed, Some(dir), Some(file_name)) => hashed_dir(dir).join(file_name),
+        _ => relative,
+    }
+}
+
+/// Strips machine specific prefixes from `path`: absolute paths below `root` are made relative to it, while the
+/// directory of absolute paths outside of it, and of relative paths leaving it through `..`, is replaced with a hash
+/// of it, e.g. `/home/jane/app.tsx` becomes `neiFDvgAGr0/app.tsx`. Without a `root`, every absolute path is
+/// outside of it. The result never depends on the working directory.
+pub(crate) fn reproducible_path(path: &Path, root: Option<&Path>) -> PathBuf {
+    if let Some(relative) = root.and_then(|root| path.strip_prefix(root).ok()) {
+        return relative.to_path_buf();
+    }
+    let outside = path.has_root() || path.components().any(|c| c == Component::ParentDir);
+    match (outside, path.parent(), path.file_name()) {
+        (false, _, _) => path.to_path_buf(),
+        (true, Some(dir), Some(file_name)) => hashed_dir(dir).join(file_name),
+        (true, _, _) => path
+            .components()
+            .filter(|c| matches!(c, Component::Normal(_)))
+            .collect(),
+    }
+}
+
+/// The directory standing for `dir` in [private paths](private_path): a hash of it, which reveals nothing about it.
+fn hashed_dir(dir: &Path) -> PathBuf {
+    let mut hasher = StableHasher::new();
+    hasher.write(dir.to_string_lossy().as_bytes());
+    let hash = engine::general_purpose::URL_SAFE_NO_PAD
+        .encode(hasher.finish().to_le_bytes())
+        .replace(['-', '_'], "0");
+    PathBuf::from(hash)
+}
+
 impl TryInto<SourceType> for &SourceInfo {
     type Error = Error;
 
     fn try_into(self) -> std::result::Result<SourceType, Self::Error> {
-        SourceType::from_path(&self.rel_path).map_err(|e| e.into())
+        source_type(&self.rel_path)
     }
 }
 
@@ -70,7 +194,7 @@ impl TryInto<SourceType> for SourceInfo {
     type Error = Error;
 
     fn try_into(self) -> std::result::Result<SourceType, Self::Error> {
-        SourceType::from_path(&self.rel_path).map_err(|e| e.into())
+        source_type(&self.rel_path)
     }
 }
 
@@ -109,6 +233,99 @@ mod tests {
         assert_eq!(source_info.file_name, "app.js");
     }
 
+    #[test]
+    fn reproducible_paths_are_relative() {
+        let root = Path::new("/home/jane/project");
+
+        assert_eq!(
+            reproducible_path(&root.join("src/app.tsx"), Some(root)),
+            Path::new("src/app.tsx")
+        );
+        assert_eq!(
+            reproducible_path(Path::new("./app.tsx"), Some(root)),
+            Path::new("./app.tsx")
+        );
+
+        // Outside of the root, only the file name is revealed.
+        let outside = reproducible_path(Path::new("/home/jane/app.tsx"), Some(root));
+        assert_eq!(outside.file_name().unwrap(), "app.tsx");
+        assert!(!outside.to_string_lossy().contains("jane"));
+        assert_eq!(
+            outside,
+            reproducible_path(Path::new("/home/jane/app.tsx"), None)
+        );
+        let escaping = reproducible_path(Path::new("../jane/app.tsx"), Some(root));
+        assert!(!escaping.to_string_lossy().contains("jane"));
+
+        // The working directory makes no difference.
+        let cwd = std::env::current_dir().unwrap();
+        assert_eq!(
+            reproducible_path(&cwd.join("src/app.tsx"), None).parent(),
+            reproducible_path(&cwd.join("src/other.tsx"), None).parent()
+        );
+        assert_ne!(
+            reproducible_path(&cwd.join("src/app.tsx"), None),
+            Path::new("src/app.tsx")
+        );
+    }
+
+    #[test]
+    fn virtual_modules_have_stable_paths() {
+        let plan = SourceInfo::virtual_module("\0qwik-city-plan", Language::Typescript);
+        assert_eq!(plan.rel_path, Path::new("_virtual/qwik-city-plan.tsx"));
+        assert_eq!(plan.file_name, "qwik-city-plan.tsx");
+        assert!(plan.is_virtual);
+
+        let nested = SourceInfo::virtual_module("virtual:@app/routes?v=1", Language::Javascript);
+        assert_eq!(nested.rel_path, Path::new("_virtual/@app_routes_v_1.jsx"));
+        assert_eq!(
+            plan.with_path_privacy(PathPrivacy::Hashed, None).unwrap(),
+            plan
+        );
+    }
+
+    #[test]
+    fn private_paths_hide_directories() {
+        let root = Path::new("/home/jane/project");
+
+        assert_eq!(
+            private_path(
+                Path::new("/home/jane/app.tsx"),
+                PathPrivacy::None,
+                Some(root)
+            ),
+            Path::new("/home/jane/app.tsx")
+        );
+        assert_eq!(
+            private_path(
+                Path::new("src/../app.tsx"),
+                PathPrivacy::Relative,
+                Some(root)
+            ),
+            reproducible_path(Path::new("src/../app.tsx"), Some(root))
+        );
+        let relative = private_path(&root.join("src/app.tsx"), PathPrivacy::Relative, Some(root));
+        assert_eq!(relative, Path::new("src/app.tsx"));
+        let escaping = private_path(
+            Path::new("../../jane/src/app.tsx"),
+            PathPrivacy::Relative,
+            None,
+        );
+        assert!(!escaping.to_string_lossy().contains("jane"));
+
+        let hashed = private_path(
+            &root.join("src/routes/app.tsx"),
+            PathPrivacy::Hashed,
+            Some(root),
+        );
+        assert_eq!(hashed.file_name().unwrap(), "app.tsx");
+        assert!(!hashed.to_string_lossy().contains("routes"));
+        assert_eq!(
+            hashed.parent(),
+            private_path(Path::new("src/routes/other.tsx"), PathPrivacy::Hashed, None).parent()
+        );
+    }
+
     #[test]
     fn properly_normalize_path() {
         let path0 = Path::new("/a/b/c");
@@ -0,0 +1,192 @@
+use crate::error::Error;
+use crate::prelude::*;
+use oxc_allocator::{Allocator, CloneIn};
+use oxc_ast::ast::*;
+use oxc_ast::visit::walk_mut;
+use oxc_ast::VisitMut;
+use oxc_parser::Parser;
+use oxc_span::{GetSpan, GetSpanMut, SourceType};
+use std::collections::BTreeMap;
+
+/// Replaces the member expressions of `program` listed in `defines`, e.g. `import.meta.env.DEV` or
+/// `process.env.NODE_ENV`, with their value, see [TransformOptions::defines](crate::transform::TransformOptions::defines).
+///
+/// Runs before the segments of `program` are extracted, so that every segment sees the replaced values. The longest
+/// listed expression wins, e.g. with both `import.meta.env` and `import.meta.env.DEV` defined, `import.meta.env.DEV`
+/// is replaced with the value of the latter. Assignments to a defined expression are left as they are.
+///
+/// # Examples
+/// With `import.meta.env.DEV` defined as `false`,
+/// ```javascript
+/// if (import.meta.env.DEV) { console.log(import.meta.env.MODE); }
+/// ```
+/// becomes
+/// ```javascript
+/// if (false) { console.log(import.meta.env.MODE); }
+/// ```
+pub(crate) fn replace_defines<'a>(
+    program: &mut Program<'a>,
+    defines: &BTreeMap<String, String>,
+    allocator: &'a Allocator,
+) -> Result<()> {
+    if defines.is_empty() {
+        return Ok(());
+    }
+    let values = defines
+        .iter()
+        .map(|(key, value)| Ok((key.as_str(), parse_value(key, value, allocator)?)))
+        .collect::<Result<_>>()?;
+    Defines { values, allocator }.visit_program(program);
+    Ok(())
+}
+
+/// Checks that every value of `defines` is a valid expression, failing with [Error::InvalidDefine] otherwise.
+pub(crate) fn validate_defines(defines: &BTreeMap<String, String>) -> Result<()> {
+    let allocator = Allocator::default();
+    for (key, value) in defines {
+        parse_value(key, value, &allocator)?;
+    }
+    Ok(())
+}
+
+fn parse_value<'a>(key: &str, value: &str, allocator: &'a Allocator) -> Result<Expression<'a>> {
+    let source = allocator.alloc_str(value);
+    Parser::new(allocator, source, SourceType::mjs())
+        .parse_expression()
+        .map_err(|_| Error::InvalidDefine(key.to_string(), value.to_string()))
+}
+
+struct Defines<'d, 'a> {
+    values: BTreeMap<&'d str, Expression<'a>>,
+    allocator: &'a Allocator,
+}
+
+impl<'a> VisitMut<'a> for Defines<'_, 'a> {
+    fn visit_expression(&mut self, expr: &mut Expression<'a>) {
+        if expr.is_member_expression() {
+            if let Some(value) = dotted_path(expr).and_then(|path| self.values.get(path.as_str())) {
+                let span = expr.span();
+                *expr = value.clone_in(self.allocator);
+                *expr.span_mut() = span;
+                return;
+            }
+        }
+        walk_mut::walk_expression(self, expr);
+    }
+
+    fn visit_simple_assignment_target(&mut self, target: &mut SimpleAssignmentTarget<'a>) {
+        if target
+            .as_member_expression()
+            .and_then(member_path)
+            .is_some()
+        {
+            return;
+        }
+        walk_mut::walk_simple_assignment_target(self, target);
+    }
+}
+
+/// The dotted path of a chain of static property accesses, e.g. `import.meta.env.DEV` or `process.env["NODE_ENV"]`.
+fn dotted_path(expr: &Expression) -> Option<String> {
+    match expr {
+        Expression::Identifier(id) => Some(id.name.to_string()),
+        Expression::MetaProperty(meta) => {
+            Some(format!("{}.{}", meta.meta.name, meta.property.name))
+        }
+        expr => member_path(expr.as_member_expression()?),
+    }
+}
+
+fn member_path(member: &MemberExpression) -> Option<String> {
+    let property = match member {
+        MemberExpression::StaticMemberExpression(member) => member.property.name.as_str(),
+        MemberExpression::ComputedMemberExpression(member) => match &member.expression {
+            Expression::StringLiteral(property) => property.value.as_str(),
+            _ => return None,
+        },
+        MemberExpression::PrivateFieldExpression(_) => return None,
+    };
+    Some(format!("{}.{}", dotted_path(member.object())?, property))
+}
+
+/// (De)serializes the values of [TransformOptions::defines](crate::transform::TransformOptions::defines) as JSON
+/// values rather than as the text of JSON values, e.g. `{ "import.meta.env.DEV": false }`.
+#[cfg(feature = "serde")]
+pub(crate) mod json_values {
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+    use serde_json::Value;
+    use std::collections::BTreeMap;
+
+    pub fn serialize<S: Serializer>(
+        defines: &BTreeMap<String, String>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        defines
+            .iter()
+            .map(|(key, value)| {
+                let value =
+                    serde_json::from_str(value).unwrap_or_else(|_| Value::String(value.clone()));
+                (key, value)
+            })
+            .collect::<BTreeMap<_, _>>()
+            .serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<BTreeMap<String, String>, D::Error> {
+        let defines = BTreeMap::<String, Value>::deserialize(deserializer)?;
+        Ok(defines
+            .into_iter()
+            .map(|(key, value)| (key, value.to_string()))
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use oxc_codegen::Codegen;
+
+    #[test]
+    fn replaces_defines() {
+        let allocator = Allocator::default();
+        let source = r#"
+            if (import.meta.env.DEV) console.log(import.meta.env.MODE, import.meta.env.SSR);
+            const env = process.env["NODE_ENV"] + process.env.OTHER;
+            import.meta.env.DEV = true;
+            "#;
+        let mut program = Parser::new(&allocator, source, SourceType::mjs())
+            .parse()
+            .program;
+        let defines = BTreeMap::from([
+            ("import.meta.env.DEV".to_string(), "false".to_string()),
+            (
+                "import.meta.env".to_string(),
+                r#"{"MODE":"production"}"#.to_string(),
+            ),
+            (
+                "process.env.NODE_ENV".to_string(),
+                r#""production""#.to_string(),
+            ),
+        ]);
+
+        replace_defines(&mut program, &defines, &allocator).unwrap();
+        let code = Codegen::new().build(&program).code;
+        assert!(
+            code.contains("if (false) console.log({ \"MODE\": \"production\" }.MODE"),
+            "{code}"
+        );
+        assert!(
+            code.contains("const env = \"production\" + process.env.OTHER;"),
+            "{code}"
+        );
+        assert!(code.contains("import.meta.env.DEV = true;"), "{code}");
+
+        let invalid = BTreeMap::from([("DEBUG".to_string(), "{".to_string())]);
+        assert!(matches!(
+            validate_defines(&invalid),
+            Err(Error::InvalidDefine(key, _)) if key == "DEBUG"
+        ));
+    }
+}
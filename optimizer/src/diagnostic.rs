@@ -0,0 +1,243 @@
+use crate::capture::Unserializable;
+use crate::processing_failure::ProcessingFailure;
+use oxc_span::Span;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::fmt::Display;
+
+/// Identifies the kind of problem a [Diagnostic] reports, so that tooling can react to it without parsing messages.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum DiagnosticCode {
+    /// The source could not be parsed, or contains a syntax error.
+    SyntaxError,
+    /// A function or class declared in an enclosing scope is referenced by a segment.
+    IllegalCode,
+    /// A variable captured by a segment is reassigned.
+    MutableCapture,
+    /// A variable captured by a segment holds a function or class instance.
+    UnserializableCapture,
+    /// A `$` marker was called without an expression as its first argument.
+    InvalidQrlArgument,
+    /// A CommonJS global such as `require` is referenced, see [TransformOptions::strict_esm](crate::transform::TransformOptions::strict_esm).
+    NonEsm,
+    /// A module exceeds a [resource limit](crate::limits::ResourceLimits) of a hardened transform.
+    LimitExceeded,
+    /// The optimizer failed unexpectedly on a module in a hardened transform, instead of panicking.
+    InternalError,
+    /// A module or the whole build has more segments than allowed by
+    /// [TransformOptions::max_segments_per_file](crate::transform::TransformOptions::max_segments_per_file) or
+    /// [TransformOptions::max_segments_per_project](crate::transform::TransformOptions::max_segments_per_project).
+    TooManySegments,
+    /// Two segments of a module produced the same symbol name, e.g. from the keys `a-b$` and `a_b$`, which only
+    /// differ in characters that are not valid in identifiers. The later one is renamed with a numeric suffix.
+    SymbolCollision,
+    /// A `sync$` function references a variable declared outside of it, e.g. an import, which is not defined once
+    /// its serialized text is evaluated in the browser.
+    SyncCapture,
+    /// A module has more diagnostics than
+    /// [TransformOptions::max_diagnostics](crate::transform::TransformOptions::max_diagnostics). Reported once, after
+    /// the ones kept, with the number of the others.
+    TooManyDiagnostics,
+    /// A `$` marker is called in a module outside of the app, e.g. below `node_modules`, with
+    /// [forbid_external_markers](crate::transform::TransformOptions::forbid_external_markers) set to
+    /// [ExternalMarkers::Error](crate::external::ExternalMarkers::Error).
+    ExternalMarker,
+    /// A `worker$` closure captures a variable of its enclosing scope, which is not available in the web worker it
+    /// runs in.
+    WorkerCapture,
+    /// A `routeLoader$`, `routeAction$` or `globalAction$` is not the value of a top-level named export, which the
+    /// router finds it by, or its name does not start with `use`.
+    InvalidRouteExport,
+    /// An `await` in code which must run synchronously: an `async` function passed to `sync$`, or an `await` at the
+    /// top level of the segment of an event handler, which delays the loading of the handler.
+    AwaitInSyncContext,
+    /// A `preventdefault:` or `stoppropagation:` attribute is misspelled, names its event like a listener, e.g.
+    /// `preventdefault:onClick$`, or names an event resembling a well-known DOM event, see
+    /// [EventModifier](crate::events::EventModifier).
+    InvalidEventModifier,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "camelCase"))]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+/// A range of the source, given as byte offsets, together with an explanation of its role in a [Diagnostic].
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "camelCase"))]
+pub struct Label {
+    pub start: u32,
+    pub end: u32,
+    pub message: Option<String>,
+}
+
+/// A problem found while transforming a module, located in its source so that editors can underline it.
+///
+/// Diagnostics are accumulated over the whole transform rather than aborting it: the emitted code is still
+/// produced, but should not be shipped while any diagnostic has [Severity::Error].
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "camelCase"))]
+pub struct Diagnostic {
+    pub code: DiagnosticCode,
+    pub message: String,
+    pub severity: Severity,
+    pub label: Option<Label>,
+    /// A human readable fix for the problem, if one is known.
+    pub suggestion: Option<String>,
+}
+
+impl Diagnostic {
+    pub fn error<S: Into<String>>(code: DiagnosticCode, message: S) -> Self {
+        Self {
+            code,
+            message: message.into(),
+            severity: Severity::Error,
+            label: None,
+            suggestion: None,
+        }
+    }
+
+    pub fn warning<S: Into<String>>(code: DiagnosticCode, message: S) -> Self {
+        Self {
+            severity: Severity::Warning,
+            ..Self::error(code, message)
+        }
+    }
+
+    pub fn with_label(mut self, span: Span, message: Option<String>) -> Self {
+        self.label = Some(Label {
+            start: span.start,
+            end: span.end,
+            message,
+        });
+        self
+    }
+
+    pub fn with_suggestion<S: Into<String>>(mut self, suggestion: S) -> Self {
+        self.suggestion = Some(suggestion.into());
+        self
+    }
+
+    pub fn is_error(&self) -> bool {
+        self.severity == Severity::Error
+    }
+
+    /// Whether the diagnostic reports a capture which can not be serialized.
+    pub(crate) fn is_invalid_capture(&self) -> bool {
+        matches!(
+            self.code,
+            DiagnosticCode::IllegalCode
+                | DiagnosticCode::MutableCapture
+                | DiagnosticCode::UnserializableCapture
+        )
+    }
+
+    /// Reports `failure`, located at `span`.
+    pub(crate) fn from_failure(failure: &ProcessingFailure, span: Span) -> Self {
+        match failure {
+            ProcessingFailure::IllegalCode(illegal_code_type) => {
+                Diagnostic::error(DiagnosticCode::IllegalCode, failure.to_string())
+                    .with_label(span, Some("referenced here".to_string()))
+                    .with_suggestion(format!(
+                        "Move the {} `{}` to the top level of the module, or turn it into an arrow function assigned to a `const`",
+                        illegal_code_type.expression_type(),
+                        illegal_code_type.identifier()
+                    ))
+            }
+            ProcessingFailure::MutableCapture(name) => {
+                Diagnostic::error(DiagnosticCode::MutableCapture, failure.to_string())
+                    .with_label(span, Some("declared here".to_string()))
+                    .with_suggestion(format!(
+                        "Declare `{name}` with `const`, or use a signal if it needs to change"
+                    ))
+            }
+            ProcessingFailure::UnserializableCapture(name, kind) => {
+                let suggestion = match kind {
+                    Unserializable::Function => format!(
+                        "Wrap `{name}` with `$()` to turn it into a QRL, or move it to the top level of the module"
+                    ),
+                    Unserializable::ClassInstance => format!(
+                        "Create `{name}` inside the Qrl($) scope, or capture the plain data it is built from"
+                    ),
+                };
+                Diagnostic::error(DiagnosticCode::UnserializableCapture, failure.to_string())
+                    .with_label(span, Some("declared here".to_string()))
+                    .with_suggestion(suggestion)
+            }
+        }
+    }
+}
+
+/// Parse and semantic errors reported by oxc, labeled with their first span.
+impl From<&oxc_diagnostics::OxcDiagnostic> for Diagnostic {
+    fn from(error: &oxc_diagnostics::OxcDiagnostic) -> Self {
+        let mut diagnostic = Diagnostic::error(DiagnosticCode::SyntaxError, error.message.clone());
+        if let Some(label) = error.labels.as_ref().and_then(|labels| labels.first()) {
+            let start = label.offset() as u32;
+            diagnostic = diagnostic.with_label(
+                Span::new(start, start + label.len() as u32),
+                label.label().map(String::from),
+            );
+        }
+        if let Some(help) = &error.help {
+            diagnostic = diagnostic.with_suggestion(help.clone());
+        }
+        diagnostic
+    }
+}
+
+impl Display for Diagnostic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let severity = match self.severity {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+        };
+        write!(f, "{severity}[{:?}]: {}", self.code, self.message)?;
+        if let Some(label) = &self.label {
+            write!(f, " ({}..{})", label.start, label.end)?;
+        }
+        if let Some(suggestion) = &self.suggestion {
+            write!(f, "\n  help: {suggestion}")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use oxc_allocator::Allocator;
+    use oxc_parser::Parser;
+    use oxc_span::SourceType;
+
+    #[test]
+    fn converts_parse_errors() {
+        let allocator = Allocator::default();
+        let ret = Parser::new(&allocator, "const a = ;", SourceType::tsx()).parse();
+
+        let diagnostic = Diagnostic::from(&ret.errors[0]);
+
+        assert_eq!(diagnostic.code, DiagnosticCode::SyntaxError);
+        assert!(diagnostic.is_error());
+        assert_eq!(diagnostic.label.map(|l| (l.start, l.end)), Some((10, 11)));
+    }
+
+    #[test]
+    fn renders_code_span_and_suggestion() {
+        let diagnostic = Diagnostic::from_failure(
+            &ProcessingFailure::MutableCapture("count".to_string()),
+            Span::new(4, 9),
+        );
+
+        assert_eq!(
+            diagnostic.to_string(),
+            "error[MutableCapture]: Captured variable 'count' is reassigned, only const values can be captured by a Qrl($) scope (4..9)\n  help: Declare `count` with `const`, or use a signal if it needs to change"
+        );
+    }
+}
@@ -0,0 +1,157 @@
+use crate::component::{private_path, QrlComponent};
+use crate::error::Error;
+use crate::prelude::*;
+use crate::transform::{PathPrivacy, TransformResult};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// The file the [Manifest](crate::manifest::Manifest) is written to by [TransformResult::emit_to_disk].
+pub const MANIFEST_FILE_NAME: &str = "q-manifest.json";
+
+impl TransformResult {
+    /// Writes the host modules and segments to `out_dir`, so that standalone tools such as the CLI do not have to
+    /// persist them themselves. Returns the paths of the written files, sorted.
+    ///
+    /// The tree below `out_dir` mirrors the relative paths of the modules: a host module is written to its own path,
+    /// e.g. `out/src/app.tsx`, and a segment next to the module it was extracted from, to its
+    /// [file name](QrlComponent::file_name), e.g. `out/src/app.tsx_App_component_ckEPmXZlub0.tsx`. Absolute paths,
+    /// which transforms without [path privacy](crate::transform::PathPrivacy) leave as they are, and paths leading out
+    /// of the project through `..` have their directory replaced with a hash of it, so that nothing is written
+    /// outside of `out_dir`. The
+    /// [Manifest](crate::manifest::Manifest) is written to [MANIFEST_FILE_NAME], when the `manifest` and `serde`
+    /// features are enabled.
+    ///
+    /// Directories are created as needed, and every file is written to a temporary file which then replaces it, so
+    /// that a dev server reading `out_dir` concurrently never sees a partially written file. Fails with
+    /// [Error::EmitCollision] before anything is written if two files would be written to the same path, and with
+    /// [Error::Emit] if a file can not be written.
+    ///
+    /// ```no_run
+    /// use qwik_optimizer::api::{transform_dir, TransformOptions};
+    ///
+    /// let result = transform_dir("src", &["**/*.tsx"], TransformOptions::default())?;
+    /// for path in result.emit_to_disk("dist")? {
+    ///     println!("wrote {}", path.display());
+    /// }
+    /// # Ok::<(), qwik_optimizer::api::Error>(())
+    /// ```
+    pub fn emit_to_disk<P: AsRef<Path>>(&self, out_dir: P) -> Result<Vec<PathBuf>> {
+        let out_dir = out_dir.as_ref();
+        #[cfg(all(feature = "manifest", feature = "serde"))]
+        let manifest = serde_json::to_string_pretty(&self.manifest)?;
+        // Every file, along with what it is written for.
+        let mut outputs: Vec<(PathBuf, String, &str)> = Vec::new();
+        for module in &self.modules {
+            outputs.push((
+                out_dir.join(private_path(&module.path, PathPrivacy::Relative, None)),
+                module.path.to_string_lossy().to_string(),
+                &module.code,
+            ));
+        }
+        for segment in &self.segments {
+            outputs.push((
+                out_dir.join(segment_path(segment)),
+                segment.id.symbol_name.to_string(),
+                &segment.code,
+            ));
+        }
+        #[cfg(all(feature = "manifest", feature = "serde"))]
+        outputs.push((
+            out_dir.join(MANIFEST_FILE_NAME),
+            "the manifest".to_string(),
+            &manifest,
+        ));
+
+        let mut files: BTreeMap<PathBuf, (String, &str)> = BTreeMap::new();
+        for (path, source, code) in outputs {
+            if let Some((other, _)) = files.get(&path) {
+                return Err(Error::EmitCollision(
+                    path.to_string_lossy().to_string(),
+                    other.clone(),
+                    source,
+                ));
+            }
+            files.insert(path, (source, code));
+        }
+        for (path, (_, code)) in &files {
+            let emit_error = |e| Error::Emit(path.to_string_lossy().to_string(), e);
+            if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+                fs::create_dir_all(dir).map_err(emit_error)?;
+            }
+            let tmp = path.with_extension(format!("{}.tmp", std::process::id()));
+            fs::write(&tmp, code).map_err(emit_error)?;
+            fs::rename(&tmp, path).map_err(emit_error)?;
+        }
+        Ok(files.into_keys().collect())
+    }
+}
+
+/// The path of `segment` relative to the output directory.
+fn segment_path(segment: &QrlComponent) -> PathBuf {
+    private_path(Path::new(&segment.file_name()), PathPrivacy::Relative, None)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::source::Source;
+    use crate::transform::{transform, TransformOptions};
+
+    #[test]
+    fn emits_to_disk() {
+        let out_dir =
+            std::env::temp_dir().join(format!("qwik-optimizer-emit-{}", std::process::id()));
+        let code = "import { component$ } from \"@qwik.dev/core\";\n\
+                    export const App = component$(() => <p>Hello</p>);\n";
+        let options = TransformOptions::default();
+        let results = ["src/app.tsx", "../shared/button.tsx"]
+            .into_iter()
+            .map(|path| {
+                let source = Source::from_module(path, code).unwrap();
+                (path.into(), transform(source, options.clone()).unwrap())
+            })
+            .collect();
+        let result = TransformResult::from_results(results, &options).unwrap();
+
+        let written = result.emit_to_disk(&out_dir).unwrap();
+        let relative: Vec<_> = written
+            .iter()
+            .map(|path| path.strip_prefix(&out_dir).unwrap().to_string_lossy())
+            .collect();
+        let manifests = usize::from(cfg!(all(feature = "manifest", feature = "serde")));
+        assert_eq!(relative.len(), 4 + manifests, "{relative:?}");
+        assert!(relative.contains(&"src/app.tsx".into()));
+        // Outside of the project, only the file name is kept.
+        assert!(relative
+            .iter()
+            .any(|path| path.ends_with("/button.tsx") && !path.contains("shared")));
+        assert!(relative
+            .iter()
+            .any(|path| path.starts_with("src/app.tsx_App_component_") && path.ends_with(".tsx")));
+        assert_eq!(
+            relative.contains(&MANIFEST_FILE_NAME.into()),
+            manifests == 1
+        );
+        assert_eq!(
+            fs::read_to_string(out_dir.join("src/app.tsx")).unwrap(),
+            result.modules[0].code
+        );
+
+        let mut colliding = result.clone();
+        colliding.modules[1].path = "./src/app.tsx".into();
+        assert!(matches!(
+            colliding.emit_to_disk(&out_dir),
+            Err(Error::EmitCollision(..))
+        ));
+
+        let blocked = out_dir.join("blocked");
+        fs::write(&blocked, "").unwrap();
+        match result.emit_to_disk(&blocked) {
+            Err(Error::Emit(path, _)) => assert!(path.starts_with(&*blocked.to_string_lossy())),
+            other => panic!("expected an emit error, got {other:?}"),
+        }
+
+        fs::remove_dir_all(&out_dir).unwrap();
+    }
+}
@@ -0,0 +1,353 @@
+use crate::error::Error;
+use crate::hash::StableHasher;
+#[cfg(feature = "manifest")]
+use crate::manifest::Manifest;
+use crate::manifest::{SegmentKind, SegmentMetadata};
+use crate::prelude::*;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::hash::Hasher;
+use std::str::FromStr;
+
+/// The entry name used when every segment is grouped into a single entry.
+pub const ENTRY_SEGMENTS: &str = "entry_segments";
+
+/// The entry of the segments of `worker$`, under every strategy but [EntryStrategy::Inline] and
+/// [EntryStrategy::Hoist]. They run in a web worker rather than in the page, so they are never grouped with the
+/// other segments.
+pub const WORKER_ENTRY: &str = "worker";
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "camelCase"))]
+pub enum EntryStrategy {
+    Inline,
+    Hoist,
+    Single,
+    Hook,
+    #[default]
+    Segment,
+    Component,
+    Smart,
+}
+
+impl EntryStrategy {
+    /// The names of the strategies, as accepted by [EntryStrategy::from_str].
+    pub const NAMES: [&'static str; 7] = [
+        "inline",
+        "hoist",
+        "single",
+        "hook",
+        "segment",
+        "component",
+        "smart",
+    ];
+
+    /// Determines the entry (chunk) a segment belongs to. `None` means the segment is emitted as its own chunk.
+    ///
+    /// [V 1.0 REF] see `EntryPolicy::get_entry_for_sym` in `entry_strategy.rs`.
+    pub fn entry_for(&self, segment: &SegmentMetadata) -> Option<String> {
+        if segment.ctx_kind == SegmentKind::Worker
+            && !matches!(self, EntryStrategy::Inline | EntryStrategy::Hoist)
+        {
+            return Some(WORKER_ENTRY.to_string());
+        }
+        match self {
+            EntryStrategy::Inline
+            | EntryStrategy::Hoist
+            | EntryStrategy::Hook
+            | EntryStrategy::Segment => None,
+            EntryStrategy::Single => Some(ENTRY_SEGMENTS.to_string()),
+            EntryStrategy::Component => {
+                Some(Self::component_entry(segment).unwrap_or_else(|| ENTRY_SEGMENTS.to_string()))
+            }
+            EntryStrategy::Smart => {
+                // Event handlers which capture nothing are put into their own chunk, everything else is grouped per
+                // component so that all QRLs of a component are loaded together when one of them is used.
+                if segment.ctx_kind == SegmentKind::EventHandler && !segment.captures {
+                    None
+                } else {
+                    Self::component_entry(segment)
+                }
+            }
+        }
+    }
+
+    /// Explains why [EntryStrategy::entry_for] returned the entry it did.
+    fn reason(&self, segment: &SegmentMetadata) -> &'static str {
+        if segment.ctx_kind == SegmentKind::Worker
+            && !matches!(self, EntryStrategy::Inline | EntryStrategy::Hoist)
+        {
+            return "worker segments share the worker entry";
+        }
+        match self {
+            EntryStrategy::Inline | EntryStrategy::Hoist => "segments are inlined into the host",
+            EntryStrategy::Hook | EntryStrategy::Segment => "every segment is its own chunk",
+            EntryStrategy::Single => "every segment shares a single entry",
+            EntryStrategy::Component if segment.root.is_some() => "grouped by root component",
+            EntryStrategy::Component => "no root component, using the shared entry",
+            EntryStrategy::Smart
+                if segment.ctx_kind == SegmentKind::EventHandler && !segment.captures =>
+            {
+                "event handlers without captures are their own chunk"
+            }
+            EntryStrategy::Smart if segment.root.is_some() => "grouped by root component",
+            EntryStrategy::Smart => "no root component, emitted as its own chunk",
+        }
+    }
+
+    fn component_entry(segment: &SegmentMetadata) -> Option<String> {
+        segment
+            .root
+            .as_ref()
+            .map(|root| format!("{}_entry_{}", segment.origin, root))
+    }
+}
+
+/// The symbols grouped into one entry.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "camelCase"))]
+pub struct Entry {
+    pub name: String,
+    /// Symbol names, sorted.
+    pub symbols: Vec<String>,
+}
+
+/// The assignment of every segment to an entry under a given [EntryStrategy].
+///
+/// # Determinism
+///
+/// Plans never depend on traversal or `HashMap` iteration order:
+/// - segments are visited in ascending `symbol_name` order, so whenever two segments compete for the same position
+///   the one with the lexicographically smaller symbol name wins;
+/// - entries are ordered by name and the symbols within each entry are sorted by symbol name;
+/// - segments that are their own chunk are listed in `standalone`, sorted by symbol name.
+///
+/// The [EntryPlan::fingerprint] summarizes the full assignment so that two machines can cheaply verify they produced
+/// identical plans, and [EntryPlan::audit] lists every assignment in the order it was made.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "camelCase"))]
+pub struct EntryPlan {
+    pub strategy: EntryStrategy,
+    pub entries: Vec<Entry>,
+    pub standalone: Vec<String>,
+    /// One line per segment, `{symbol} -> {entry} ({reason})`, in assignment order.
+    pub audit: Vec<String>,
+}
+
+impl EntryPlan {
+    pub fn new(strategy: EntryStrategy, segments: &[SegmentMetadata]) -> EntryPlan {
+        let mut ordered: Vec<&SegmentMetadata> = segments.iter().collect();
+        ordered.sort_by(|a, b| a.symbol_name.cmp(&b.symbol_name));
+
+        let mut entries: BTreeMap<String, Vec<String>> = BTreeMap::new();
+        let mut standalone = Vec::new();
+        let mut audit = Vec::with_capacity(ordered.len());
+
+        for segment in ordered {
+            let entry = strategy.entry_for(segment);
+            audit.push(format!(
+                "{} -> {} ({})",
+                segment.symbol_name,
+                entry.as_deref().unwrap_or("<own chunk>"),
+                strategy.reason(segment)
+            ));
+            match entry {
+                Some(entry) => entries
+                    .entry(entry)
+                    .or_default()
+                    .push(segment.symbol_name.clone()),
+                None => standalone.push(segment.symbol_name.clone()),
+            }
+        }
+
+        EntryPlan {
+            strategy,
+            entries: entries
+                .into_iter()
+                .map(|(name, symbols)| Entry { name, symbols })
+                .collect(),
+            standalone,
+            audit,
+        }
+    }
+
+    /// Returns the entry a symbol was assigned to, if any.
+    pub fn entry_of(&self, symbol_name: &str) -> Option<&str> {
+        self.entries
+            .iter()
+            .find(|e| e.symbols.iter().any(|s| s == symbol_name))
+            .map(|e| e.name.as_str())
+    }
+
+    /// A stable hash of the complete assignment, encoded as hex.
+    pub fn fingerprint(&self) -> String {
+        let mut hasher = StableHasher::new();
+        for entry in &self.entries {
+            hasher.write(entry.name.as_bytes());
+            hasher.write_u8(0);
+            for symbol in &entry.symbols {
+                hasher.write(symbol.as_bytes());
+                hasher.write_u8(0);
+            }
+        }
+        hasher.write_u8(1);
+        for symbol in &self.standalone {
+            hasher.write(symbol.as_bytes());
+            hasher.write_u8(0);
+        }
+        format!("{:016x}", hasher.finish())
+    }
+}
+
+/// Re-groups the segments of an existing build under a different [EntryStrategy], without re-transforming any
+/// code. The entries recorded in the manifest are ignored.
+#[cfg(feature = "manifest")]
+pub fn simulate_strategy(manifest: &Manifest, strategy: EntryStrategy) -> EntryPlan {
+    EntryPlan::new(strategy, &manifest.segments)
+}
+
+impl FromStr for EntryStrategy {
+    type Err = Error;
+
+    /// Parses the name of a strategy, ignoring case, e.g. `segment` or `Smart`.
+    fn from_str(value: &str) -> Result<EntryStrategy> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "inline" => Ok(EntryStrategy::Inline),
+            "hoist" => Ok(EntryStrategy::Hoist),
+            "single" => Ok(EntryStrategy::Single),
+            "hook" => Ok(EntryStrategy::Hook),
+            "segment" => Ok(EntryStrategy::Segment),
+            "component" => Ok(EntryStrategy::Component),
+            "smart" => Ok(EntryStrategy::Smart),
+            _ => Err(Error::UnknownEntryStrategy(value.to_string())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn segment(symbol_name: &str, ctx_name: &str, root: Option<&str>) -> SegmentMetadata {
+        segment_metadata!(
+            symbol_name,
+            ctx_kind: SegmentKind::from_ctx_name(ctx_name),
+            ctx_name: ctx_name.to_string(),
+            root: root.map(String::from),
+        )
+    }
+
+    fn segments() -> Vec<SegmentMetadata> {
+        vec![
+            segment("s_c", "component$", Some("Header")),
+            segment("s_b", "onClick$", Some("App")),
+            segment("s_a", "component$", Some("App")),
+            segment("s_d", "$", None),
+        ]
+    }
+
+    #[test]
+    fn smart_groups_by_component_and_splits_event_handlers() {
+        let plan = EntryPlan::new(EntryStrategy::Smart, &segments());
+
+        assert_eq!(
+            plan.entries,
+            vec![
+                Entry {
+                    name: "app.tsx_entry_App".to_string(),
+                    symbols: vec!["s_a".to_string()],
+                },
+                Entry {
+                    name: "app.tsx_entry_Header".to_string(),
+                    symbols: vec!["s_c".to_string()],
+                },
+            ]
+        );
+        assert_eq!(plan.standalone, vec!["s_b", "s_d"]);
+        assert_eq!(plan.entry_of("s_a"), Some("app.tsx_entry_App"));
+        assert_eq!(plan.entry_of("s_b"), None);
+    }
+
+    #[test]
+    fn smart_groups_event_handlers_with_captures() {
+        let mut segments = segments();
+        segments[1].captures = true;
+
+        let plan = EntryPlan::new(EntryStrategy::Smart, &segments);
+
+        assert_eq!(plan.entry_of("s_b"), Some("app.tsx_entry_App"));
+        assert_eq!(plan.standalone, vec!["s_d"]);
+    }
+
+    #[test]
+    fn component_falls_back_to_shared_entry() {
+        let plan = EntryPlan::new(EntryStrategy::Component, &segments());
+
+        assert_eq!(plan.entry_of("s_b"), Some("app.tsx_entry_App"));
+        assert_eq!(plan.entry_of("s_d"), Some(ENTRY_SEGMENTS));
+        assert!(plan.standalone.is_empty());
+    }
+
+    #[test]
+    fn plan_is_independent_of_input_order() {
+        let mut reversed = segments();
+        reversed.reverse();
+
+        let plan0 = EntryPlan::new(EntryStrategy::Smart, &segments());
+        let plan1 = EntryPlan::new(EntryStrategy::Smart, &reversed);
+
+        assert_eq!(plan0, plan1);
+        assert_eq!(plan0.fingerprint(), plan1.fingerprint());
+        assert_eq!(
+            plan0.audit,
+            vec![
+                "s_a -> app.tsx_entry_App (grouped by root component)",
+                "s_b -> <own chunk> (event handlers without captures are their own chunk)",
+                "s_c -> app.tsx_entry_Header (grouped by root component)",
+                "s_d -> <own chunk> (no root component, emitted as its own chunk)",
+            ]
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "manifest")]
+    fn simulates_strategy_from_manifest() {
+        let mut segments = segments();
+        for segment in segments.iter_mut() {
+            segment.entry = Some(ENTRY_SEGMENTS.to_string());
+        }
+        let manifest = Manifest::new(segments);
+
+        let plan = simulate_strategy(&manifest, EntryStrategy::Segment);
+
+        assert!(plan.entries.is_empty());
+        assert_eq!(plan.standalone, vec!["s_a", "s_b", "s_c", "s_d"]);
+    }
+
+    #[test]
+    fn fingerprint_differs_between_strategies() {
+        let smart = EntryPlan::new(EntryStrategy::Smart, &segments());
+        let single = EntryPlan::new(EntryStrategy::Single, &segments());
+
+        assert_ne!(smart.fingerprint(), single.fingerprint());
+    }
+
+    #[test]
+    fn parses_entry_strategies() {
+        for name in EntryStrategy::NAMES {
+            assert!(name.parse::<EntryStrategy>().is_ok(), "{name}");
+        }
+        assert_eq!(
+            " Smart".parse::<EntryStrategy>().unwrap(),
+            EntryStrategy::Smart
+        );
+        assert!(matches!(
+            "chunks".parse::<EntryStrategy>(),
+            Err(Error::UnknownEntryStrategy(name)) if name == "chunks"
+        ));
+    }
+}
@@ -1,3 +1,4 @@
+use crate::capture::Unserializable;
 use crate::illegal_code::IllegalCodeType;
 
 #[derive(thiserror::Error, Debug)]
@@ -14,9 +15,58 @@ pub enum Error {
     #[error(transparent)]
     IO(#[from] std::io::Error),
 
+    #[cfg(feature = "serde")]
+    #[error(transparent)]
+    Json(#[from] serde_json::Error),
+
     #[error(transparent)]
     OxcUnknownExtension(#[from] oxc_span::UnknownExtension),
 
     #[error( "Reference to identifier '{id}' can not be used inside a Qrl($) scope because it's a {expr_type}", id =.0.identifier(), expr_type = .0.expression_type())]
     IllegalCode(IllegalCodeType),
+
+    #[error("Captured variable '{0}' is reassigned, only const values can be captured by a Qrl($) scope")]
+    MutableCapture(String),
+
+    #[error("Reference to identifier '{0}' can not be used inside a Qrl($) scope because it's a {desc}", desc = .1.description())]
+    UnserializableCapture(String, Unserializable),
+
+    #[error("Can not merge transform results: {0}")]
+    MergeConflict(String),
+
+    #[error("Symbol '{0}' is produced by both '{1}' and '{2}'")]
+    SymbolConflict(String, String, String),
+
+    #[error("Unsupported ECMAScript target '{0}': {1}")]
+    UnsupportedTarget(String, String),
+
+    #[error("Invalid glob pattern '{0}': {1}")]
+    InvalidGlob(String, String),
+
+    #[error("Unknown build target '{0}', expected one of prod, lib, dev or test")]
+    UnknownTarget(String),
+
+    #[error("Failed to preprocess '{0}': {1}")]
+    Preprocess(String, String),
+
+    #[error("Can not emit '{0}': it is written by both {1} and {2}")]
+    EmitCollision(String, String, String),
+
+    #[error("Unknown entry strategy '{0}', expected one of inline, hoist, single, hook, segment, component or smart")]
+    UnknownEntryStrategy(String),
+
+    #[error("Invalid value of define '{0}', expected a JSON value or an expression: {1}")]
+    InvalidDefine(String, String),
+
+    #[error("Invalid captures:\n{}", .0.join("\n"))]
+    InvalidCaptures(Vec<String>),
+
+    #[error("Failed to parse '{0}': {1}")]
+    Parse(String, String),
+
+    #[error("Unsupported syntax in '{0}': {1}")]
+    UnsupportedSyntax(String, String),
+
+    #[error("Failed to write '{0}': {1}")]
+    Emit(String, #[source] std::io::Error),
 }
@@ -0,0 +1,91 @@
+use crate::diagnostic::{Diagnostic, DiagnosticCode};
+use oxc_semantic::Semantic;
+use oxc_span::GetSpan;
+
+/// The CommonJS globals which are not defined in ES modules, see
+/// [differences between ES modules and CommonJS](https://nodejs.org/api/esm.html#differences-between-es-modules-and-commonjs).
+pub const COMMONJS_GLOBALS: [&str; 5] = ["require", "module", "exports", "__dirname", "__filename"];
+
+/// Reports every reference to one of the [COMMONJS_GLOBALS] which is not bound to a declaration of the module.
+///
+/// Such references throw a `ReferenceError` once the module is loaded by Node as ESM, and a segment capturing
+/// them can not be extracted. Diagnostics are sorted by their position in the source.
+pub(crate) fn validate_esm(semantic: &Semantic) -> Vec<Diagnostic> {
+    let mut diagnostics: Vec<Diagnostic> = semantic
+        .scopes()
+        .root_unresolved_references()
+        .iter()
+        .filter(|(name, _)| COMMONJS_GLOBALS.contains(name))
+        .flat_map(|(name, reference_ids)| {
+            reference_ids.iter().map(move |reference_id| {
+                let node_id = semantic.symbols().get_reference(*reference_id).node_id();
+                let span = semantic.nodes().get_node(node_id).kind().span();
+                Diagnostic::error(
+                    DiagnosticCode::NonEsm,
+                    format!("'{name}' is not defined in ES modules"),
+                )
+                .with_label(span, None)
+                .with_suggestion(suggestion(name))
+            })
+        })
+        .collect();
+    diagnostics.sort_by_key(|diagnostic| diagnostic.label.as_ref().map(|label| label.start));
+    diagnostics
+}
+
+fn suggestion(name: &str) -> &'static str {
+    match name {
+        "require" => "Use an `import` declaration, or `import()` to load a module lazily",
+        "module" | "exports" => "Use `export` declarations",
+        _ => {
+            "Use `import.meta.dirname` / `import.meta.filename`, or `new URL('.', import.meta.url)`"
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use oxc_allocator::Allocator;
+    use oxc_parser::Parser;
+    use oxc_semantic::SemanticBuilder;
+    use oxc_span::SourceType;
+
+    #[test]
+    fn reports_unbound_commonjs_globals() {
+        let allocator = Allocator::default();
+        let source = r#"
+            const fs = require('fs');
+            export const dir = () => __dirname;
+            module.exports = {};
+            export const local = (require) => require('x');
+        "#;
+        let program = Parser::new(&allocator, source, SourceType::mjs())
+            .parse()
+            .program;
+        let semantic = SemanticBuilder::new().build(&program).semantic;
+
+        let names: Vec<_> = validate_esm(&semantic)
+            .into_iter()
+            .map(|d| (d.code, d.message))
+            .collect();
+
+        assert_eq!(
+            names,
+            vec![
+                (
+                    DiagnosticCode::NonEsm,
+                    "'require' is not defined in ES modules".to_string()
+                ),
+                (
+                    DiagnosticCode::NonEsm,
+                    "'__dirname' is not defined in ES modules".to_string()
+                ),
+                (
+                    DiagnosticCode::NonEsm,
+                    "'module' is not defined in ES modules".to_string()
+                ),
+            ]
+        );
+    }
+}
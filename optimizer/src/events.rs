@@ -0,0 +1,477 @@
+use crate::component::MARKER_SUFFIX;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::fmt::Display;
+
+/// The target a listener is attached to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "camelCase"))]
+pub enum EventScope {
+    /// The element the attribute is set on, e.g. `onClick$`.
+    Element,
+    /// The document containing the element, e.g. `onDocumentScroll$` or `document:onScroll$`.
+    Document,
+    /// The window containing the element, e.g. `onWindowResize$` or `window:onResize$`.
+    Window,
+}
+
+impl EventScope {
+    /// The namespace of the serialized attribute, e.g. `on-document` for `on-document:scroll`.
+    pub fn namespace(&self) -> &'static str {
+        match self {
+            EventScope::Element => "on",
+            EventScope::Document => "on-document",
+            EventScope::Window => "on-window",
+        }
+    }
+}
+
+/// The hooks registering a listener of the element of a component, of its document or of its window, e.g.
+/// `useOnDocument("scroll", $(() => ...))`, with the target they attach it to.
+pub const USE_ON_HOOKS: [(&str, EventScope); 3] = [
+    ("useOn", EventScope::Element),
+    ("useOnDocument", EventScope::Document),
+    ("useOnWindow", EventScope::Window),
+];
+
+/// An event listener declared by a `$`-suffixed JSX attribute of an element, or registered with one of the
+/// [USE_ON_HOOKS].
+///
+/// ```
+/// use qwik_optimizer::events::{EventListener, EventScope};
+///
+/// let listener = EventListener::from_attribute("onDocumentScroll$").unwrap();
+/// assert_eq!(listener.scope, EventScope::Document);
+/// assert_eq!(listener.to_string(), "on-document:scroll");
+/// assert_eq!(EventListener::from_attribute("on-my-Event$").unwrap().to_string(), "on:my-Event");
+/// assert_eq!(EventListener::from_attribute("onion$"), None);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "camelCase"))]
+pub struct EventListener {
+    /// The name of the DOM event, e.g. `click` or `dblclick`.
+    pub name: String,
+    pub scope: EventScope,
+}
+
+impl EventListener {
+    /// Parses the name of a JSX attribute, returning `None` if it does not declare a listener.
+    ///
+    /// Listeners are named `on{Event}$`, optionally scoped with `onDocument{Event}$`, `onWindow{Event}$` or the
+    /// `document:` and `window:` namespaces. Event names are lowercased, except for custom events named
+    /// `on-{event}$`, which are kept as written.
+    pub fn from_attribute(attribute: &str) -> Option<EventListener> {
+        let attribute = attribute.strip_suffix(MARKER_SUFFIX)?;
+        let (scope, event) = if let Some(event) = attribute.strip_prefix("document:on") {
+            (EventScope::Document, event)
+        } else if let Some(event) = attribute.strip_prefix("window:on") {
+            (EventScope::Window, event)
+        } else {
+            let event = attribute.strip_prefix("on")?;
+            [
+                ("Document", EventScope::Document),
+                ("Window", EventScope::Window),
+            ]
+            .into_iter()
+            .find_map(|(prefix, scope)| {
+                event
+                    .strip_prefix(prefix)
+                    .filter(|event| is_event_name(event))
+                    .map(|event| (scope, event))
+            })
+            .unwrap_or((EventScope::Element, event))
+        };
+        if !is_event_name(event) {
+            return None;
+        }
+        let name = match event.strip_prefix('-') {
+            Some(custom) => custom.to_string(),
+            None => event.to_ascii_lowercase(),
+        };
+        Some(EventListener { name, scope })
+    }
+
+    /// The listener registered by calling `hook` with the name of the DOM event `event`, e.g. `click` for
+    /// `useOn("click", $(...))`. Returns `None` if `hook` is not one of the [USE_ON_HOOKS].
+    pub fn from_use_on(hook: &str, event: &str) -> Option<EventListener> {
+        let (_, scope) = USE_ON_HOOKS.iter().find(|(name, _)| *name == hook)?;
+        Some(EventListener {
+            name: event.to_string(),
+            scope: *scope,
+        })
+    }
+}
+
+impl Display for EventListener {
+    /// The serialized attribute, e.g. `on:click`, `on-document:scroll` or `on-window:resize`.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}", self.scope.namespace(), self.name)
+    }
+}
+
+/// Whether `event` follows the `on` prefix of a listener, i.e. starts with an uppercase letter or, for custom events,
+/// with a `-` followed by a name.
+fn is_event_name(event: &str) -> bool {
+    let mut chars = event.chars();
+    match chars.next() {
+        Some('-') => chars.next().is_some(),
+        Some(c) => c.is_ascii_uppercase(),
+        None => false,
+    }
+}
+
+/// Whether the JSX attribute `name` is a listener, either as written or after normalization.
+pub(crate) fn is_listener_attribute(name: &str) -> bool {
+    name.ends_with(MARKER_SUFFIX)
+        || [
+            EventScope::Element,
+            EventScope::Document,
+            EventScope::Window,
+        ]
+        .iter()
+        .any(|scope| {
+            name.strip_prefix(scope.namespace())
+                .is_some_and(|rest| rest.starts_with(':'))
+        })
+}
+
+/// The JSX attributes making the runtime call `preventDefault()` or `stopPropagation()` on an event of the element
+/// before dispatching it to its listeners, e.g. `<a preventdefault:click onClick$={...} />`. The browser has already
+/// acted on the event by the time a `$` handler is loaded, so this can not be done by the handler itself.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "camelCase"))]
+pub enum EventModifierKind {
+    PreventDefault,
+    StopPropagation,
+}
+
+impl EventModifierKind {
+    pub const ALL: [EventModifierKind; 2] = [
+        EventModifierKind::PreventDefault,
+        EventModifierKind::StopPropagation,
+    ];
+
+    /// The namespace of the attribute, e.g. `preventdefault` for `preventdefault:click`.
+    pub fn namespace(&self) -> &'static str {
+        match self {
+            EventModifierKind::PreventDefault => "preventdefault",
+            EventModifierKind::StopPropagation => "stoppropagation",
+        }
+    }
+
+    /// The modifier whose namespace is `namespace`, and whether it is misspelled, e.g. `preventDefault` or
+    /// `stoppropogation`. Returns `None` if `namespace` does not resemble any modifier.
+    fn from_namespace(namespace: &str) -> Option<(EventModifierKind, bool)> {
+        let normalized: String = namespace
+            .chars()
+            .filter(|c| *c != '-' && *c != '_')
+            .map(|c| c.to_ascii_lowercase())
+            .collect();
+        EventModifierKind::ALL.into_iter().find_map(|kind| {
+            let expected = kind.namespace();
+            if namespace == expected {
+                Some((kind, false))
+            } else if edit_distance(&normalized, expected) <= 2 {
+                Some((kind, true))
+            } else {
+                None
+            }
+        })
+    }
+}
+
+/// What is wrong with an [EventModifier] attribute as written.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ModifierProblem {
+    /// The namespace is misspelled, e.g. `preventDefault:click`.
+    Namespace,
+    /// The event is named like a listener, e.g. `preventdefault:onClick$`, rather than like the DOM event.
+    ListenerName,
+    /// The event resembles a well-known DOM event without being one, e.g. `clik`. Custom events are accepted, so the
+    /// attribute is kept as written.
+    UnknownEvent(&'static str),
+    /// The event name is empty or contains characters which can not be part of an event name.
+    InvalidEvent,
+}
+
+/// A `preventdefault:` or `stoppropagation:` attribute of an element, see [EventModifierKind].
+///
+/// ```
+/// use qwik_optimizer::events::{EventModifier, EventModifierKind, ModifierProblem};
+///
+/// let (modifier, problem) = EventModifier::from_attribute("preventdefault", "click").unwrap();
+/// assert_eq!(modifier.kind, EventModifierKind::PreventDefault);
+/// assert_eq!(problem, None);
+/// let (modifier, problem) = EventModifier::from_attribute("stopPropagation", "onClick$").unwrap();
+/// assert_eq!(modifier.to_string(), "stoppropagation:click");
+/// assert_eq!(problem, Some(ModifierProblem::Namespace));
+/// assert_eq!(EventModifier::from_attribute("xlink", "href"), None);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "camelCase"))]
+pub struct EventModifier {
+    pub kind: EventModifierKind,
+    /// The name of the DOM event, e.g. `click`.
+    pub event: String,
+}
+
+impl EventModifier {
+    /// Parses the JSX attribute `namespace:event`, returning `None` if it is not a modifier, and the first problem
+    /// found in it. Misspelled namespaces and events named like listeners are corrected in the returned modifier.
+    pub fn from_attribute(
+        namespace: &str,
+        event: &str,
+    ) -> Option<(EventModifier, Option<ModifierProblem>)> {
+        let (kind, misspelled) = EventModifierKind::from_namespace(namespace)?;
+        let mut problem = misspelled.then_some(ModifierProblem::Namespace);
+
+        let listener = match event.ends_with(MARKER_SUFFIX) {
+            true => EventListener::from_attribute(event),
+            false => EventListener::from_attribute(&format!("{event}{MARKER_SUFFIX}")),
+        };
+        let event = match listener.filter(|listener| listener.scope == EventScope::Element) {
+            Some(listener) => {
+                problem = problem.or(Some(ModifierProblem::ListenerName));
+                listener.name
+            }
+            None => event.to_string(),
+        };
+
+        let is_valid = !event.is_empty()
+            && event
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_' || c == ':');
+        if !is_valid {
+            problem = problem.or(Some(ModifierProblem::InvalidEvent));
+        } else if let Some(known) = resembling_event(&event) {
+            problem = problem.or(Some(ModifierProblem::UnknownEvent(known)));
+        }
+        Some((EventModifier { kind, event }, problem))
+    }
+}
+
+impl Display for EventModifier {
+    /// The serialized attribute, e.g. `preventdefault:click`.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}", self.kind.namespace(), self.event)
+    }
+}
+
+/// The DOM events most commonly modified, which misspelled event names are compared against.
+const KNOWN_EVENTS: [&str; 56] = [
+    "animationend",
+    "animationstart",
+    "auxclick",
+    "beforeinput",
+    "blur",
+    "change",
+    "click",
+    "contextmenu",
+    "copy",
+    "cut",
+    "dblclick",
+    "drag",
+    "dragend",
+    "dragenter",
+    "dragleave",
+    "dragover",
+    "dragstart",
+    "drop",
+    "error",
+    "focus",
+    "focusin",
+    "focusout",
+    "input",
+    "invalid",
+    "keydown",
+    "keypress",
+    "keyup",
+    "load",
+    "mousedown",
+    "mouseenter",
+    "mouseleave",
+    "mousemove",
+    "mouseout",
+    "mouseover",
+    "mouseup",
+    "paste",
+    "pointercancel",
+    "pointerdown",
+    "pointerenter",
+    "pointerleave",
+    "pointermove",
+    "pointerout",
+    "pointerover",
+    "pointerup",
+    "reset",
+    "resize",
+    "scroll",
+    "select",
+    "submit",
+    "toggle",
+    "touchcancel",
+    "touchend",
+    "touchmove",
+    "touchstart",
+    "transitionend",
+    "wheel",
+];
+
+/// The [known event](KNOWN_EVENTS) `event` is likely a misspelling of, e.g. `click` for `clik` or `Click`. Short names
+/// only tolerate a single edit, so that distinct events such as `cut` and `copy` are not confused.
+fn resembling_event(event: &str) -> Option<&'static str> {
+    if KNOWN_EVENTS.contains(&event) {
+        return None;
+    }
+    let lowercase = event.to_ascii_lowercase();
+    let tolerance = if event.len() <= 5 { 1 } else { 2 };
+    KNOWN_EVENTS
+        .into_iter()
+        .map(|known| (edit_distance(&lowercase, known), known))
+        .filter(|(distance, _)| *distance <= tolerance)
+        .min()
+        .map(|(_, known)| known)
+}
+
+/// The Levenshtein distance between `a` and `b`, counted in characters.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, a) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, b) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(a != *b);
+            current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn normalize(attribute: &str) -> Option<String> {
+        EventListener::from_attribute(attribute).map(|listener| listener.to_string())
+    }
+
+    #[test]
+    fn resolves_use_on_hooks() {
+        assert_eq!(
+            EventListener::from_use_on("useOnWindow", "resize")
+                .map(|listener| listener.to_string()),
+            Some("on-window:resize".to_string())
+        );
+        assert_eq!(
+            EventListener::from_use_on("useOn", "qvisible").map(|listener| listener.scope),
+            Some(EventScope::Element)
+        );
+        assert_eq!(EventListener::from_use_on("useSignal", "click"), None);
+    }
+
+    #[test]
+    fn normalizes_event_names() {
+        assert_eq!(normalize("onClick$").as_deref(), Some("on:click"));
+        assert_eq!(normalize("onDblClick$").as_deref(), Some("on:dblclick"));
+        assert_eq!(
+            normalize("onDocumentScroll$").as_deref(),
+            Some("on-document:scroll")
+        );
+        assert_eq!(
+            normalize("document:onVisibilityChange$").as_deref(),
+            Some("on-document:visibilitychange")
+        );
+        assert_eq!(
+            normalize("onWindowResize$").as_deref(),
+            Some("on-window:resize")
+        );
+        assert_eq!(
+            normalize("window:onResize$").as_deref(),
+            Some("on-window:resize")
+        );
+        assert_eq!(normalize("on-qvisible$").as_deref(), Some("on:qvisible"));
+        assert_eq!(
+            normalize("onWindow-myEvent$").as_deref(),
+            Some("on-window:myEvent")
+        );
+        // An event named after the scope itself is attached to the element.
+        assert_eq!(normalize("onDocument$").as_deref(), Some("on:document"));
+
+        assert_eq!(normalize("onClick"), None);
+        assert_eq!(normalize("on$"), None);
+        assert_eq!(normalize("on-$"), None);
+        assert_eq!(normalize("online$"), None);
+        assert_eq!(normalize("document:scroll$"), None);
+
+        assert!(is_listener_attribute("onClick$"));
+        assert!(is_listener_attribute("on-window:resize"));
+        assert!(!is_listener_attribute("online"));
+        assert!(!is_listener_attribute("xlink:href"));
+    }
+
+    #[test]
+    fn parses_event_modifiers() {
+        let parse = |namespace: &str, event: &str| {
+            EventModifier::from_attribute(namespace, event)
+                .map(|(modifier, problem)| (modifier.to_string(), problem))
+        };
+        assert_eq!(
+            parse("preventdefault", "click"),
+            Some(("preventdefault:click".to_string(), None))
+        );
+        assert_eq!(
+            parse("stoppropagation", "qvisible"),
+            Some(("stoppropagation:qvisible".to_string(), None))
+        );
+        assert_eq!(
+            parse("preventDefault", "submit"),
+            Some((
+                "preventdefault:submit".to_string(),
+                Some(ModifierProblem::Namespace)
+            ))
+        );
+        assert_eq!(
+            parse("stoppropogation", "click"),
+            Some((
+                "stoppropagation:click".to_string(),
+                Some(ModifierProblem::Namespace)
+            ))
+        );
+        assert_eq!(
+            parse("preventdefault", "onClick$"),
+            Some((
+                "preventdefault:click".to_string(),
+                Some(ModifierProblem::ListenerName)
+            ))
+        );
+        assert_eq!(
+            parse("preventdefault", "clik"),
+            Some((
+                "preventdefault:clik".to_string(),
+                Some(ModifierProblem::UnknownEvent("click"))
+            ))
+        );
+        assert_eq!(
+            parse("preventdefault", "Click"),
+            Some((
+                "preventdefault:Click".to_string(),
+                Some(ModifierProblem::UnknownEvent("click"))
+            ))
+        );
+        assert_eq!(
+            parse("preventdefault", "cut"),
+            Some(("preventdefault:cut".to_string(), None))
+        );
+        assert_eq!(
+            parse("preventdefault", "cl ick").map(|(_, problem)| problem),
+            Some(Some(ModifierProblem::InvalidEvent))
+        );
+        assert_eq!(parse("xlink", "href"), None);
+        assert_eq!(parse("prevent", "click"), None);
+    }
+}
@@ -1,6 +1,6 @@
 use crate::component::ImportId;
 use oxc_allocator::{Box as OxcBox, IntoIn, Vec as OxcVec};
-use oxc_ast::ast::{ImportOrExportKind, Statement, WithClause};
+use oxc_ast::ast::{ImportOrExportKind, Statement, StringLiteral, WithClause};
 use oxc_ast::AstBuilder;
 use oxc_span::{Atom, SPAN};
 
@@ -12,6 +12,17 @@ pub trait AstBuilderExt<'a> {
     ) -> Statement<'a>;
     fn create_export_statement(self, name: &str, source: &str) -> Statement<'a>;
 
+    /// `export { local as exported, ... } from "source"`, from `(local, exported)` pairs.
+    fn create_export_from_statement(self, names: &[(&str, &str)], source: &str) -> Statement<'a>;
+
+    /// `export * from "source"`, or `export * as exported from "source"`.
+    #[allow(dead_code)]
+    fn create_export_all_statement(self, exported: Option<&str>, source: &str) -> Statement<'a>;
+
+    /// `export { default } from "source"`.
+    #[allow(dead_code)]
+    fn create_default_reexport_statement(self, source: &str) -> Statement<'a>;
+
     fn create_simple_import(self, name: &str) -> Statement<'a>;
 }
 
@@ -42,20 +53,20 @@ impl<'a> AstBuilderExt<'a> for AstBuilder<'a> {
     }
 
     fn create_export_statement(self, name: &str, source: &str) -> Statement<'a> {
-        let exported = self.module_export_name_identifier_name(SPAN, name);
-        let local_name = self.module_export_name_identifier_name(SPAN, name);
-        let export_specifier =
-            self.export_specifier(SPAN, exported, local_name, ImportOrExportKind::Value);
-        let mut export_specifiers = OxcVec::new_in(self.allocator);
-        export_specifiers.push(export_specifier);
-        let raw = format!(r#""{}""#, source);
-        let raw: Atom = raw.into_in(self.allocator);
-        let source_location = self.string_literal(SPAN, source, Some(raw));
+        self.create_export_from_statement(&[(name, name)], source)
+    }
+
+    fn create_export_from_statement(self, names: &[(&str, &str)], source: &str) -> Statement<'a> {
+        let export_specifiers = self.vec_from_iter(names.iter().map(|(local, exported)| {
+            let local = self.module_export_name_identifier_name(SPAN, *local);
+            let exported = self.module_export_name_identifier_name(SPAN, *exported);
+            self.export_specifier(SPAN, local, exported, ImportOrExportKind::Value)
+        }));
         let export_decl = self.export_named_declaration(
             SPAN,
             None,
             export_specifiers,
-            Some(source_location),
+            Some(source_literal(self, source)),
             ImportOrExportKind::Value,
             None::<OxcBox<'a, WithClause<'a>>>,
         );
@@ -63,6 +74,23 @@ impl<'a> AstBuilderExt<'a> for AstBuilder<'a> {
         Statement::ExportNamedDeclaration(OxcBox::new_in(export_decl, self.allocator))
     }
 
+    fn create_export_all_statement(self, exported: Option<&str>, source: &str) -> Statement<'a> {
+        let exported = exported.map(|name| self.module_export_name_identifier_name(SPAN, name));
+        let export_decl = self.export_all_declaration(
+            SPAN,
+            exported,
+            source_literal(self, source),
+            None::<OxcBox<'a, WithClause<'a>>>,
+            ImportOrExportKind::Value,
+        );
+
+        Statement::ExportAllDeclaration(OxcBox::new_in(export_decl, self.allocator))
+    }
+
+    fn create_default_reexport_statement(self, source: &str) -> Statement<'a> {
+        self.create_export_from_statement(&[("default", "default")], source)
+    }
+
     fn create_simple_import(self, name: &str) -> Statement<'a> {
         let raw: Atom = format!(r#""{}""#, name).into_in(self.allocator);
         let source = self.expression_string_literal(SPAN, name, Some(raw));
@@ -71,3 +99,48 @@ impl<'a> AstBuilderExt<'a> for AstBuilder<'a> {
         self.statement_expression(SPAN, import_expression)
     }
 }
+
+/// The double-quoted module specifier of an import or export declaration.
+fn source_literal<'a>(ast_builder: AstBuilder<'a>, source: &str) -> StringLiteral<'a> {
+    let raw: Atom = format!(r#""{}""#, source).into_in(ast_builder.allocator);
+    ast_builder.string_literal(SPAN, source, Some(raw))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use oxc_allocator::Allocator;
+    use oxc_codegen::Codegen;
+    use oxc_span::SourceType;
+
+    #[test]
+    fn creates_reexports() {
+        let allocator = Allocator::default();
+        let ast_builder = AstBuilder::new(&allocator);
+        let statements = [
+            ast_builder.create_export_from_statement(&[("a", "a"), ("b", "c")], "./ab"),
+            ast_builder.create_export_all_statement(None, "./all"),
+            ast_builder.create_export_all_statement(Some("ns"), "./ns"),
+            ast_builder.create_default_reexport_statement("./app"),
+        ];
+        let program = ast_builder.program(
+            SPAN,
+            SourceType::mjs(),
+            "",
+            ast_builder.vec(),
+            None,
+            ast_builder.vec(),
+            ast_builder.vec_from_iter(statements),
+        );
+
+        assert_eq!(
+            Codegen::new().build(&program).code,
+            concat!(
+                "export { a, b as c } from \"./ab\";\n",
+                "export * from \"./all\";\n",
+                "export * as ns from \"./ns\";\n",
+                "export { default } from \"./app\";\n",
+            )
+        );
+    }
+}
@@ -1,15 +1,15 @@
-use crate::component::MARKER_SUFFIX;
+use crate::component::Markers;
 use oxc_ast::ast::Expression;
 
 pub trait ExpressionExt {
-    fn is_qrl_replaceable(&self) -> bool;
+    fn is_qrl_replaceable(&self, markers: &Markers) -> bool;
 }
 
 impl ExpressionExt for Expression<'_> {
-    fn is_qrl_replaceable(&self) -> bool {
+    fn is_qrl_replaceable(&self, markers: &Markers) -> bool {
         if let Expression::CallExpression(call_xpr) = self {
             if let Expression::Identifier(id_ref) = &call_xpr.callee {
-                id_ref.name.ends_with(MARKER_SUFFIX)
+                markers.is_marker(&id_ref.name)
             } else {
                 false
             }
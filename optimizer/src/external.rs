@@ -0,0 +1,177 @@
+use crate::component::MARKER_SUFFIX;
+use crate::diagnostic::{Diagnostic, DiagnosticCode};
+use oxc_ast::ast::*;
+use oxc_ast::visit::walk;
+use oxc_ast::Visit;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::fmt::Debug;
+use std::hash::{Hash, Hasher};
+use std::path::{Component, Path};
+use std::sync::Arc;
+
+/// What the optimizer does with the modules outside of the app, e.g. linked packages or anything below
+/// `node_modules`, see [TransformOptions::forbid_external_markers](crate::transform::TransformOptions::forbid_external_markers).
+///
+/// Either way, external modules are emitted as they were provided, so that packages which were already optimized
+/// when they were published are not optimized a second time.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "camelCase"))]
+pub enum ExternalMarkers {
+    /// External modules are skipped silently.
+    Skip,
+    /// Every `$` marker call of an external module is reported with a [DiagnosticCode::ExternalMarker] error.
+    Error,
+}
+
+/// Decides which modules are outside of the app, given their relative path. By default, the modules below a
+/// `node_modules` directory and the ones outside of the source directory, whose relative path starts with `..`.
+///
+/// # Examples
+/// ```
+/// use qwik_optimizer::external::{ExternalMarkers, ExternalPaths};
+/// use qwik_optimizer::transform::TransformOptions;
+///
+/// let options = TransformOptions::builder()
+///     .forbid_external_markers(ExternalMarkers::Error)
+///     .external_paths(ExternalPaths::new(|path| path.starts_with("packages")))
+///     .build()?;
+/// # Ok::<(), qwik_optimizer::api::Error>(())
+/// ```
+#[derive(Clone)]
+pub struct ExternalPaths(Arc<ExternalFn>);
+
+type ExternalFn = dyn Fn(&Path) -> bool + Send + Sync;
+
+impl ExternalPaths {
+    /// Creates a predicate from `is_external`, which returns whether the module at a relative path is external.
+    pub fn new<F>(is_external: F) -> Self
+    where
+        F: Fn(&Path) -> bool + Send + Sync + 'static,
+    {
+        Self(Arc::new(is_external))
+    }
+
+    pub(crate) fn is_external(&self, path: &Path) -> bool {
+        (self.0)(path)
+    }
+}
+
+impl Default for ExternalPaths {
+    fn default() -> Self {
+        Self::new(is_external_path)
+    }
+}
+
+impl Debug for ExternalPaths {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("ExternalPaths(..)")
+    }
+}
+
+/// Predicates can not be compared, so a predicate is only equal to its clones. As a result, transforms cached with
+/// one predicate are never reused with another.
+impl Hash for ExternalPaths {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        (Arc::as_ptr(&self.0) as *const () as usize).hash(state);
+    }
+}
+
+/// The default [ExternalPaths]: whether `path` is below a `node_modules` directory or outside of the source directory.
+pub fn is_external_path(path: &Path) -> bool {
+    path.components().next() == Some(Component::ParentDir)
+        || path
+            .components()
+            .any(|component| component.as_os_str() == "node_modules")
+}
+
+/// Reports every `$` marker call of the external module `program` at `path`, see [ExternalMarkers::Error].
+pub(crate) fn external_marker_diagnostics(program: &Program, path: &Path) -> Vec<Diagnostic> {
+    let mut calls = MarkerCalls::default();
+    calls.visit_program(program);
+    calls
+        .calls
+        .into_iter()
+        .map(|(name, span)| {
+            Diagnostic::error(
+                DiagnosticCode::ExternalMarker,
+                format!(
+                    "`{name}` is called in `{}`, which is outside of the app and is not optimized",
+                    path.display()
+                ),
+            )
+            .with_label(span, None)
+            .with_suggestion(
+                "Publish the package already optimized, or move the module into the app"
+                    .to_string(),
+            )
+        })
+        .collect()
+}
+
+/// The names and spans of the `$` marker calls of a module.
+#[derive(Default)]
+struct MarkerCalls {
+    calls: Vec<(String, Span)>,
+}
+
+impl<'a> Visit<'a> for MarkerCalls {
+    fn visit_call_expression(&mut self, call: &CallExpression<'a>) {
+        if let Expression::Identifier(callee) = &call.callee {
+            if callee.name.ends_with(MARKER_SUFFIX) {
+                self.calls.push((callee.name.to_string(), call.span));
+            }
+        }
+        walk::walk_call_expression(self, call);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use oxc_allocator::Allocator;
+    use oxc_parser::Parser;
+    use oxc_span::SourceType;
+
+    #[test]
+    fn finds_external_paths() {
+        assert!(is_external_path(Path::new("node_modules/ui/button.tsx")));
+        assert!(is_external_path(Path::new(
+            "vendor/node_modules/ui/button.tsx"
+        )));
+        assert!(is_external_path(Path::new("../packages/ui/button.tsx")));
+        assert!(!is_external_path(Path::new("src/routes/index.tsx")));
+        assert!(!is_external_path(Path::new("src/node_modules_docs.tsx")));
+    }
+
+    #[test]
+    fn reports_external_markers() {
+        let allocator = Allocator::default();
+        let source = "export const App = component$(() => <button onClick$={$(() => 1)} />);";
+        let program = Parser::new(&allocator, source, SourceType::tsx())
+            .parse()
+            .program;
+
+        let diagnostics =
+            external_marker_diagnostics(&program, Path::new("node_modules/ui/app.tsx"));
+        let labels: Vec<_> = diagnostics
+            .iter()
+            .map(|diagnostic| {
+                let label = diagnostic.label.as_ref().unwrap();
+                &source[label.start as usize..label.end as usize]
+            })
+            .collect();
+        assert_eq!(
+            labels,
+            vec![
+                "component$(() => <button onClick$={$(() => 1)} />)",
+                "$(() => 1)"
+            ]
+        );
+        assert_eq!(
+            diagnostics[0].message,
+            "`component$` is called in `node_modules/ui/app.tsx`, which is outside of the app and is not optimized"
+        );
+    }
+}
@@ -0,0 +1,152 @@
+//! Snapshot tests of whole modules, for the optimizer itself and for downstream plugins regression-testing their
+//! options.
+//!
+//! A fixture is a single module in a fixture directory. It is transformed through the full pipeline, and its host
+//! module, every extracted segment and the manifest are snapshotted with `insta`, in a `snapshots` directory next to
+//! the fixture. Adding a case is a matter of dropping a file in the directory and accepting its new snapshots:
+//!
+//! ```no_run
+//! use qwik_optimizer::api::TransformOptions;
+//! use qwik_optimizer::fixture::assert_fixtures;
+//!
+//! assert_fixtures("tests/fixtures", TransformOptions::default());
+//! ```
+//!
+//! Available with the `test-utils` feature.
+
+use crate::error::Error;
+use crate::prelude::*;
+use crate::transform::{transform_module, TransformOptions, TransformResult};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// The extensions of the files of a fixture directory which are fixtures, see [fixtures].
+pub const FIXTURE_EXTENSIONS: [&str; 6] = ["tsx", "ts", "jsx", "js", "mdx", "md"];
+
+/// A module transformed by a snapshot test.
+#[derive(Debug, Clone)]
+pub struct Fixture {
+    /// The path of the fixture file.
+    pub path: PathBuf,
+    /// The file name of the fixture, which is also the path it is transformed at, so that the names of its segments
+    /// and its manifest do not depend on where the fixture directory is located.
+    pub name: String,
+    pub code: String,
+}
+
+impl Fixture {
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        let name = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .ok_or_else(|| {
+                Error::StringConversion(path.to_string_lossy().into(), "fixture file name".into())
+            })?
+            .to_string();
+        let code = fs::read_to_string(path)?;
+        Ok(Fixture {
+            path: path.to_path_buf(),
+            name,
+            code,
+        })
+    }
+
+    /// The name of the fixture without its extension, which prefixes the names of its snapshots.
+    pub fn stem(&self) -> &str {
+        self.name
+            .rsplit_once('.')
+            .map_or(self.name.as_str(), |(stem, _)| stem)
+    }
+
+    pub fn transform(&self, options: TransformOptions) -> Result<TransformResult> {
+        transform_module(&self.name, &self.code, options)
+    }
+
+    /// Transforms the fixture with `options` and asserts its snapshots: `<stem>_host` for the host module,
+    /// `<stem>_<symbol name>` for every segment and `<stem>_manifest` for the manifest.
+    ///
+    /// # Panics
+    /// If the fixture can not be transformed, or a snapshot does not match.
+    pub fn assert_snapshots(&self, options: TransformOptions) {
+        let result = self
+            .transform(options)
+            .unwrap_or_else(|e| panic!("Failed to transform fixture {:?}: {e}", self.path));
+        let snapshots = self
+            .path
+            .parent()
+            .unwrap_or(Path::new("."))
+            .join("snapshots");
+        let snapshots = std::path::absolute(&snapshots).unwrap_or(snapshots);
+
+        let mut settings = insta::Settings::clone_current();
+        settings.set_snapshot_path(snapshots);
+        settings.set_prepend_module_to_snapshot(false);
+        settings.set_input_file(&self.path);
+        settings.set_omit_expression(true);
+        settings.bind(|| {
+            let stem = self.stem();
+            for module in &result.modules {
+                insta::assert_yaml_snapshot!(format!("{stem}_host"), module.code);
+            }
+            for segment in &result.segments {
+                insta::assert_yaml_snapshot!(
+                    format!("{stem}_{}", segment.id.symbol_name),
+                    segment.code
+                );
+            }
+            #[cfg(all(feature = "manifest", feature = "serde"))]
+            insta::assert_yaml_snapshot!(format!("{stem}_manifest"), result.manifest);
+        });
+    }
+}
+
+/// The fixtures of `dir`, sorted by file name. Subdirectories, e.g. the `snapshots` directory, are not searched.
+pub fn fixtures<P: AsRef<Path>>(dir: P) -> Result<Vec<Fixture>> {
+    let mut paths = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        let is_fixture = path
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .is_some_and(|extension| FIXTURE_EXTENSIONS.contains(&extension));
+        if path.is_file() && is_fixture {
+            paths.push(path);
+        }
+    }
+    paths.sort();
+    paths.into_iter().map(Fixture::load).collect()
+}
+
+/// Asserts the snapshots of every fixture of `dir`, see [Fixture::assert_snapshots].
+///
+/// # Panics
+/// If `dir` can not be read, or any fixture fails.
+pub fn assert_fixtures<P: AsRef<Path>>(dir: P, options: TransformOptions) {
+    let dir = dir.as_ref();
+    let fixtures =
+        fixtures(dir).unwrap_or_else(|e| panic!("Failed to read fixture directory {dir:?}: {e}"));
+    for fixture in fixtures {
+        fixture.assert_snapshots(options.clone());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fixtures() {
+        assert_fixtures("./src/fixtures", TransformOptions::default());
+    }
+
+    #[test]
+    fn test_fixture_stem() {
+        let fixture = Fixture {
+            path: PathBuf::from("src/fixtures/counter.tsx"),
+            name: "counter.tsx".into(),
+            code: String::new(),
+        };
+        assert_eq!(fixture.stem(), "counter");
+    }
+}
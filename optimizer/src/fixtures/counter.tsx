@@ -0,0 +1,10 @@
+import { component$, useSignal } from '@qwik.dev/core';
+
+export const Counter = component$(() => {
+    const count = useSignal(0);
+    return (
+        <button onClick$={() => count.value++}>
+            {count.value}
+        </button>
+    );
+});
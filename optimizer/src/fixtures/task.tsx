@@ -0,0 +1,10 @@
+import { component$, useStore, useTask$ } from '@qwik.dev/core';
+
+export const Clock = component$(() => {
+    const state = useStore({ time: 0 });
+    useTask$(({ cleanup }) => {
+        const id = setInterval(() => state.time++, 1000);
+        cleanup(() => clearInterval(id));
+    });
+    return <span>{state.time}</span>;
+});
@@ -0,0 +1,314 @@
+use oxc_ast::ast::*;
+use oxc_ast::visit::walk_mut;
+use oxc_ast::{AstBuilder, VisitMut, NONE};
+use oxc_span::{GetSpan, SPAN};
+use oxc_syntax::scope::ScopeFlags;
+
+/// Removes the branches of `program` which can never run, once [defines](crate::transform::TransformOptions::defines)
+/// replaced the expressions they depend on with literals, so that dev-only or server-only code, and the imports only
+/// it uses, are neither kept in the host module nor extracted into segments.
+///
+/// Only conditions made of primitive literals are folded, which have no side effects to preserve:
+/// - `!`, `===`, `!==`, `==` and `!=` of literals are folded into a boolean,
+/// - `&&`, `||` and `??` with a literal on the left are replaced with the operand they evaluate to,
+/// - conditional expressions and `if` statements with a literal condition are replaced with the branch they take,
+///   and dropped when they have none.
+///
+/// The `var` declarations of a removed branch are hoisted, i.e. their bindings exist in the enclosing function even
+/// though the branch never runs, so the names they bind are kept in its place, e.g. `var a, b;` for
+/// `var a = 1, { b } = c;`. Function declarations are dropped with the branch: modules are strict, where they are
+/// scoped to their block.
+///
+/// # Examples
+/// ```javascript
+/// if ("production" !== "production") { console.log(debugInfo()); }
+/// const logger = false ? devLogger : prodLogger;
+/// ```
+/// becomes
+/// ```javascript
+/// const logger = prodLogger;
+/// ```
+pub(crate) fn prune_dead_branches<'a>(program: &mut Program<'a>, ast: AstBuilder<'a>) {
+    DeadBranches {
+        ast,
+        hoisted: Vec::new(),
+    }
+    .visit_program(program);
+}
+
+struct DeadBranches<'a> {
+    ast: AstBuilder<'a>,
+    /// The hoisted declarations of the branches removed from the statement being visited, which take its place in
+    /// the enclosing statements, see `visit_statements`.
+    hoisted: Vec<Statement<'a>>,
+}
+
+/// Collects the names bound by the `var` declarations of a removed branch, outside of nested functions and static
+/// blocks, which have a scope of their own, as declarators without initializers.
+struct Hoisted<'a> {
+    ast: AstBuilder<'a>,
+    declarators: oxc_allocator::Vec<'a, VariableDeclarator<'a>>,
+}
+
+impl<'a> VisitMut<'a> for Hoisted<'a> {
+    fn visit_variable_declaration(&mut self, decl: &mut VariableDeclaration<'a>) {
+        if decl.kind != VariableDeclarationKind::Var {
+            return;
+        }
+        let ast = self.ast;
+        let names = decl
+            .declarations
+            .iter()
+            .flat_map(|declarator| declarator.id.get_binding_identifiers());
+        self.declarators.extend(names.map(|id| {
+            let kind = ast.binding_pattern_kind_binding_identifier(id.span, id.name);
+            let pattern = ast.binding_pattern(kind, NONE, false);
+            ast.variable_declarator(id.span, VariableDeclarationKind::Var, pattern, None, false)
+        }));
+    }
+
+    fn visit_function(&mut self, _: &mut Function<'a>, _: ScopeFlags) {}
+
+    fn visit_arrow_function_expression(&mut self, _: &mut ArrowFunctionExpression<'a>) {}
+
+    fn visit_static_block(&mut self, _: &mut StaticBlock<'a>) {}
+}
+
+/// The value of an expression made of a primitive literal.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Constant<'e> {
+    Undefined,
+    Null,
+    Boolean(bool),
+    Number(f64),
+    String(&'e str),
+}
+
+impl<'e> Constant<'e> {
+    fn of(expr: &'e Expression) -> Option<Constant<'e>> {
+        match expr.without_parentheses() {
+            Expression::BooleanLiteral(literal) => Some(Constant::Boolean(literal.value)),
+            Expression::NullLiteral(_) => Some(Constant::Null),
+            Expression::NumericLiteral(literal) => Some(Constant::Number(literal.value)),
+            Expression::StringLiteral(literal) => Some(Constant::String(literal.value.as_str())),
+            Expression::UnaryExpression(unary)
+                if unary.operator == UnaryOperator::Void
+                    && Constant::of(&unary.argument).is_some() =>
+            {
+                Some(Constant::Undefined)
+            }
+            _ => None,
+        }
+    }
+
+    fn is_truthy(self) -> bool {
+        match self {
+            Constant::Undefined | Constant::Null => false,
+            Constant::Boolean(value) => value,
+            Constant::Number(value) => value != 0.0 && !value.is_nan(),
+            Constant::String(value) => !value.is_empty(),
+        }
+    }
+
+    fn is_nullish(self) -> bool {
+        matches!(self, Constant::Undefined | Constant::Null)
+    }
+
+    /// `self === other`.
+    fn strict_equals(self, other: Constant) -> bool {
+        self == other
+    }
+
+    /// `self == other`, `None` when it depends on type coercions which are not folded.
+    fn loose_equals(self, other: Constant) -> Option<bool> {
+        match (self, other) {
+            (a, b) if a.is_nullish() || b.is_nullish() => Some(a.is_nullish() && b.is_nullish()),
+            (a, b) if std::mem::discriminant(&a) == std::mem::discriminant(&b) => Some(a == b),
+            _ => None,
+        }
+    }
+}
+
+impl<'a> DeadBranches<'a> {
+    /// Keeps the hoisted declarations of `branch`, which is removed.
+    fn hoist(&mut self, branch: &mut Statement<'a>) {
+        let mut hoisted = Hoisted {
+            ast: self.ast,
+            declarators: self.ast.vec(),
+        };
+        hoisted.visit_statement(branch);
+        if !hoisted.declarators.is_empty() {
+            let declaration = self.ast.alloc_variable_declaration(
+                branch.span(),
+                VariableDeclarationKind::Var,
+                hoisted.declarators,
+                false,
+            );
+            self.hoisted
+                .push(Statement::VariableDeclaration(declaration));
+        }
+    }
+
+    /// The folded value of `expr`, whose operands are already folded.
+    fn fold(&self, expr: &mut Expression<'a>) -> Option<Expression<'a>> {
+        match expr {
+            Expression::UnaryExpression(unary) if unary.operator == UnaryOperator::LogicalNot => {
+                let value = Constant::of(&unary.argument)?.is_truthy();
+                Some(self.ast.expression_boolean_literal(unary.span, !value))
+            }
+            Expression::BinaryExpression(binary) => {
+                let left = Constant::of(&binary.left)?;
+                let right = Constant::of(&binary.right)?;
+                let value = match binary.operator {
+                    BinaryOperator::StrictEquality => left.strict_equals(right),
+                    BinaryOperator::StrictInequality => !left.strict_equals(right),
+                    BinaryOperator::Equality => left.loose_equals(right)?,
+                    BinaryOperator::Inequality => !left.loose_equals(right)?,
+                    _ => return None,
+                };
+                Some(self.ast.expression_boolean_literal(binary.span, value))
+            }
+            Expression::LogicalExpression(logical) => {
+                let left = Constant::of(&logical.left)?;
+                let takes_left = match logical.operator {
+                    LogicalOperator::And => !left.is_truthy(),
+                    LogicalOperator::Or => left.is_truthy(),
+                    LogicalOperator::Coalesce => !left.is_nullish(),
+                };
+                Some(match takes_left {
+                    true => self.ast.move_expression(&mut logical.left),
+                    false => self.ast.move_expression(&mut logical.right),
+                })
+            }
+            Expression::ConditionalExpression(conditional) => {
+                Some(match Constant::of(&conditional.test)?.is_truthy() {
+                    true => self.ast.move_expression(&mut conditional.consequent),
+                    false => self.ast.move_expression(&mut conditional.alternate),
+                })
+            }
+            _ => None,
+        }
+    }
+}
+
+impl<'a> VisitMut<'a> for DeadBranches<'a> {
+    fn visit_expression(&mut self, expr: &mut Expression<'a>) {
+        walk_mut::walk_expression(self, expr);
+        if let Some(folded) = self.fold(expr) {
+            *expr = folded;
+        }
+    }
+
+    fn visit_statement(&mut self, stmt: &mut Statement<'a>) {
+        walk_mut::walk_statement(self, stmt);
+        let Statement::IfStatement(if_stmt) = stmt else {
+            return;
+        };
+        let Some(truthy) = Constant::of(&if_stmt.test).map(Constant::is_truthy) else {
+            return;
+        };
+        let IfStatement {
+            consequent,
+            alternate,
+            ..
+        } = &mut **if_stmt;
+        let (taken, removed) = match truthy {
+            true => (Some(consequent), alternate.as_mut()),
+            false => (alternate.as_mut(), Some(consequent)),
+        };
+        let taken = match taken {
+            Some(taken) => self.ast.move_statement(taken),
+            // Unspanned, so that it is dropped from the enclosing statements, see `visit_statements`.
+            None => self.ast.statement_empty(SPAN),
+        };
+        if let Some(removed) = removed {
+            self.hoist(removed);
+        }
+        *stmt = taken;
+    }
+
+    fn visit_statements(&mut self, stmts: &mut oxc_allocator::Vec<'a, Statement<'a>>) {
+        let mut kept = self.ast.vec_with_capacity(stmts.len());
+        for mut stmt in stmts.drain(..) {
+            self.visit_statement(&mut stmt);
+            kept.extend(self.hoisted.drain(..));
+            if !matches!(&stmt, Statement::EmptyStatement(empty) if empty.span().is_unspanned()) {
+                kept.push(stmt);
+            }
+        }
+        *stmts = kept;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use oxc_allocator::Allocator;
+    use oxc_codegen::Codegen;
+    use oxc_parser::Parser;
+    use oxc_span::SourceType;
+
+    #[test]
+    fn prunes_dead_branches() {
+        let allocator = Allocator::default();
+        let source = r#"
+            if ("production" !== "production") { console.log("dev"); }
+            if (!false) start(); else stop();
+            if (null == void 0) { a(); } else if (1) { b(); }
+            const logger = false ? devLogger : prodLogger;
+            const name = (0 || "anonymous") ?? "none";
+            const checked = "a" == 1 && check();
+            const kept = flag && other();
+            ;
+        "#;
+        let mut program = Parser::new(&allocator, source, SourceType::mjs())
+            .parse()
+            .program;
+
+        prune_dead_branches(&mut program, AstBuilder::new(&allocator));
+        let code = Codegen::new().build(&program).code;
+        assert_eq!(
+            code,
+            "start();\n\
+             {\n\ta();\n}\n\
+             const logger = prodLogger;\n\
+             const name = \"anonymous\";\n\
+             const checked = \"a\" == 1 && check();\n\
+             const kept = flag && other();\n\
+             ;\n"
+        );
+    }
+
+    #[test]
+    fn keeps_hoisted_declarations_of_pruned_branches() {
+        let allocator = Allocator::default();
+        let source = r#"
+            function render() {
+                if (false) {
+                    var debug = createDebugger(), { level } = config;
+                    for (var i = 0; i < 3; i++) {}
+                    function trace() { var inner = 1; }
+                    const scoped = () => { var local = 2; };
+                    let block = 3;
+                } else {
+                    start();
+                }
+                return [debug, level, i, trace];
+            }
+        "#;
+        let mut program = Parser::new(&allocator, source, SourceType::mjs())
+            .parse()
+            .program;
+
+        prune_dead_branches(&mut program, AstBuilder::new(&allocator));
+        let code = Codegen::new().build(&program).code;
+        assert_eq!(
+            code,
+            "function render() {\n\
+             \tvar debug, level, i;\n\
+             \t{\n\t\tstart();\n\t}\n\
+             \treturn [\n\t\tdebug,\n\t\tlevel,\n\t\ti,\n\t\ttrace\n\t];\n\
+             }\n"
+        );
+    }
+}
@@ -0,0 +1,263 @@
+use crate::error::Error;
+use crate::prelude::*;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// The modules transformed when no include pattern is given.
+pub const DEFAULT_INCLUDE: &str = "**/*.{js,jsx,mjs,ts,tsx,mts}";
+
+/// The files and directories skipped when no pattern is given at all.
+pub const DEFAULT_EXCLUDE: [&str; 2] = ["**/*.d.ts", "**/node_modules/**"];
+
+/// A compiled glob pattern, matched against `/`-separated paths relative to the directory being searched.
+///
+/// Supports `*` (any characters of a path component), `?` (one character of a path component), `**` (any number of
+/// path components) and `{a,b}` (alternatives, which may contain the other wildcards).
+///
+/// ```
+/// use qwik_optimizer::glob::Glob;
+///
+/// let glob = Glob::new("**/*.{ts,tsx}")?;
+/// assert!(glob.matches("routes/index.tsx"));
+/// assert!(glob.matches("app.ts"));
+/// assert!(!glob.matches("app.js"));
+/// # Ok::<(), qwik_optimizer::api::Error>(())
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Glob {
+    pattern: String,
+    /// The path components of every alternative of the pattern.
+    alternatives: Vec<Vec<String>>,
+}
+
+impl Glob {
+    pub fn new(pattern: &str) -> Result<Glob> {
+        let alternatives = expand_braces(pattern)
+            .map_err(|reason| Error::InvalidGlob(pattern.to_string(), reason))?
+            .into_iter()
+            .map(|alternative| {
+                alternative
+                    .trim_start_matches("./")
+                    .split('/')
+                    .filter(|component| !component.is_empty())
+                    .map(str::to_string)
+                    .collect()
+            })
+            .collect();
+        Ok(Glob {
+            pattern: pattern.to_string(),
+            alternatives,
+        })
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.pattern
+    }
+
+    /// Whether the relative `path` matches the pattern.
+    pub fn matches(&self, path: &str) -> bool {
+        let path: Vec<&str> = path
+            .trim_start_matches("./")
+            .split('/')
+            .filter(|component| !component.is_empty())
+            .collect();
+        self.alternatives
+            .iter()
+            .any(|pattern| matches_components(pattern, &path))
+    }
+}
+
+/// The files below a directory selected by include and exclude patterns, e.g. `["**/*.tsx", "!**/*.spec.*"]`.
+#[derive(Debug, Clone)]
+pub struct FileSet {
+    include: Vec<Glob>,
+    exclude: Vec<Glob>,
+}
+
+impl FileSet {
+    /// Compiles `patterns`, where patterns starting with `!` exclude the files they match. Without include patterns,
+    /// the files matching [DEFAULT_INCLUDE] are included. Without any pattern, the files matching [DEFAULT_EXCLUDE]
+    /// are excluded as well.
+    pub fn new<S: AsRef<str>>(patterns: &[S]) -> Result<FileSet> {
+        let mut include = Vec::new();
+        let mut exclude = Vec::new();
+        for pattern in patterns {
+            match pattern.as_ref().strip_prefix('!') {
+                Some(pattern) => exclude.push(Glob::new(pattern)?),
+                None => include.push(Glob::new(pattern.as_ref())?),
+            }
+        }
+        if patterns.is_empty() {
+            for pattern in DEFAULT_EXCLUDE {
+                exclude.push(Glob::new(pattern)?);
+            }
+        }
+        if include.is_empty() {
+            include.push(Glob::new(DEFAULT_INCLUDE)?);
+        }
+        Ok(FileSet { include, exclude })
+    }
+
+    /// Whether the file at the relative `path` belongs to the set.
+    pub fn matches(&self, path: &str) -> bool {
+        self.include.iter().any(|glob| glob.matches(path))
+            && !self.exclude.iter().any(|glob| glob.matches(path))
+    }
+
+    /// Whether the directory at the relative `path` can be skipped, because an exclude pattern matches everything
+    /// below it, e.g. `node_modules` for `**/node_modules/**`.
+    fn excludes_dir(&self, path: &str) -> bool {
+        self.exclude.iter().any(|glob| {
+            glob.alternatives.iter().any(|pattern| {
+                pattern.last().is_some_and(|last| last == "**")
+                    && matches_components(&pattern[..pattern.len() - 1], &components(path))
+            })
+        })
+    }
+
+    /// The files of the set below `dir`, sorted by path. Directories are walked without following symbolic links.
+    pub fn find(&self, dir: &Path) -> Result<Vec<PathBuf>> {
+        let mut files = Vec::new();
+        self.walk(dir, "", &mut files)?;
+        files.sort();
+        Ok(files.into_iter().map(|path| dir.join(path)).collect())
+    }
+
+    fn walk(&self, dir: &Path, prefix: &str, files: &mut Vec<String>) -> Result<()> {
+        for entry in fs::read_dir(dir)? {
+            let entry = entry?;
+            let Some(name) = entry.file_name().to_str().map(str::to_string) else {
+                continue;
+            };
+            let path = if prefix.is_empty() {
+                name
+            } else {
+                format!("{prefix}/{name}")
+            };
+            let file_type = entry.file_type()?;
+            if file_type.is_dir() {
+                if !self.excludes_dir(&path) {
+                    self.walk(&entry.path(), &path, files)?;
+                }
+            } else if file_type.is_file() && self.matches(&path) {
+                files.push(path);
+            }
+        }
+        Ok(())
+    }
+}
+
+fn components(path: &str) -> Vec<&str> {
+    path.split('/')
+        .filter(|component| !component.is_empty())
+        .collect()
+}
+
+fn matches_components<S: AsRef<str>>(pattern: &[S], path: &[&str]) -> bool {
+    match pattern.split_first() {
+        None => path.is_empty(),
+        Some((first, rest)) if first.as_ref() == "**" => {
+            (0..=path.len()).any(|skipped| matches_components(rest, &path[skipped..]))
+        }
+        Some((first, rest)) => path.split_first().is_some_and(|(component, path)| {
+            let pattern: Vec<char> = first.as_ref().chars().collect();
+            let component: Vec<char> = component.chars().collect();
+            matches_component(&pattern, &component) && matches_components(rest, path)
+        }),
+    }
+}
+
+fn matches_component(pattern: &[char], text: &[char]) -> bool {
+    match pattern.split_first() {
+        None => text.is_empty(),
+        Some(('*', rest)) => {
+            (0..=text.len()).any(|skipped| matches_component(rest, &text[skipped..]))
+        }
+        Some(('?', rest)) => !text.is_empty() && matches_component(rest, &text[1..]),
+        Some((c, rest)) => text.first() == Some(c) && matches_component(rest, &text[1..]),
+    }
+}
+
+/// Expands the first `{a,b}` group of `pattern`, recursively, e.g. `*.{ts,tsx}` into `*.ts` and `*.tsx`.
+fn expand_braces(pattern: &str) -> std::result::Result<Vec<String>, String> {
+    let Some(open) = pattern.find('{') else {
+        return match pattern.contains('}') {
+            true => Err("unmatched `}`".to_string()),
+            false => Ok(vec![pattern.to_string()]),
+        };
+    };
+    let mut depth = 0;
+    let mut alternatives = Vec::new();
+    let mut start = open + 1;
+    for (i, c) in pattern[open..].char_indices().map(|(i, c)| (open + i, c)) {
+        match c {
+            '{' => depth += 1,
+            ',' if depth == 1 => {
+                alternatives.push(&pattern[start..i]);
+                start = i + 1;
+            }
+            '}' => {
+                depth -= 1;
+                if depth == 0 {
+                    alternatives.push(&pattern[start..i]);
+                    let (prefix, suffix) = (&pattern[..open], &pattern[i + 1..]);
+                    let mut expanded = Vec::new();
+                    for alternative in alternatives {
+                        expanded.extend(expand_braces(&format!("{prefix}{alternative}{suffix}"))?);
+                    }
+                    return Ok(expanded);
+                }
+            }
+            _ => {}
+        }
+    }
+    Err("unmatched `{`".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matches_globs() {
+        let glob = |pattern: &str| Glob::new(pattern).unwrap();
+
+        assert!(glob("**/*.tsx").matches("app.tsx"));
+        assert!(glob("**/*.tsx").matches("./routes/blog/index.tsx"));
+        assert!(!glob("*.tsx").matches("routes/index.tsx"));
+        assert!(glob("routes/**").matches("routes/blog/index.tsx"));
+        assert!(glob("**/*.spec.*").matches("components/button.spec.tsx"));
+        assert!(!glob("**/*.spec.*").matches("components/button.tsx"));
+        assert!(glob("src/?pp.{ts,{m,}js}").matches("src/app.mjs"));
+        assert!(glob("src/?pp.{ts,{m,}js}").matches("src/app.js"));
+        assert!(!glob("src/?pp.{ts,{m,}js}").matches("src/app.cjs"));
+        assert!(matches!(
+            Glob::new("**/*.{ts,tsx"),
+            Err(Error::InvalidGlob(_, _))
+        ));
+
+        let set = FileSet::new(&["**/*.tsx", "!**/*.spec.*"]).unwrap();
+        assert!(set.matches("routes/index.tsx"));
+        assert!(!set.matches("routes/index.spec.tsx"));
+        assert!(!set.matches("routes/index.ts"));
+
+        let set = FileSet::new::<&str>(&[]).unwrap();
+        assert!(set.matches("app.ts"));
+        assert!(!set.matches("types.d.ts"));
+        assert!(set.excludes_dir("node_modules"));
+        assert!(set.excludes_dir("packages/ui/node_modules"));
+        assert!(!set.excludes_dir("routes"));
+    }
+
+    #[test]
+    fn finds_files() {
+        let set = FileSet::new(&["**/test_example_1*.tsx", "!**/*_11.tsx"]).unwrap();
+        let files = set.find(Path::new("./src/test_input")).unwrap();
+        let names: Vec<_> = files
+            .iter()
+            .map(|path| path.file_name().unwrap().to_string_lossy())
+            .collect();
+        assert_eq!(names, ["test_example_1.tsx", "test_example_10.tsx"]);
+        assert!(files[0].starts_with("./src/test_input"));
+    }
+}
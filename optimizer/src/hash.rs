@@ -0,0 +1,107 @@
+use std::hash::Hasher;
+
+/// A [Hasher] whose output never changes, for names and keys which outlive the process computing them, e.g. the
+/// [ContentHash](crate::transform::SegmentNaming::ContentHash) file names of segments cached forever by browsers and
+/// CDNs, or the keys of the on-disk [TransformCache](crate::cache::TransformCache).
+///
+/// This is SipHash-1-3 with zero keys, the algorithm behind `DefaultHasher::new()` at the time of writing. Unlike
+/// `DefaultHasher`, whose algorithm the standard library may change in any release, it is fixed here.
+#[derive(Debug, Clone)]
+pub(crate) struct StableHasher {
+    v0: u64,
+    v1: u64,
+    v2: u64,
+    v3: u64,
+    /// The bytes written since the last complete word, in its low bytes.
+    tail: u64,
+    tail_len: usize,
+    length: usize,
+}
+
+impl StableHasher {
+    pub(crate) fn new() -> Self {
+        Self {
+            v0: 0x736f6d6570736575,
+            v1: 0x646f72616e646f6d,
+            v2: 0x6c7967656e657261,
+            v3: 0x7465646279746573,
+            tail: 0,
+            tail_len: 0,
+            length: 0,
+        }
+    }
+
+    fn round(&mut self) {
+        self.v0 = self.v0.wrapping_add(self.v1);
+        self.v1 = self.v1.rotate_left(13) ^ self.v0;
+        self.v0 = self.v0.rotate_left(32);
+        self.v2 = self.v2.wrapping_add(self.v3);
+        self.v3 = self.v3.rotate_left(16) ^ self.v2;
+        self.v0 = self.v0.wrapping_add(self.v3);
+        self.v3 = self.v3.rotate_left(21) ^ self.v0;
+        self.v2 = self.v2.wrapping_add(self.v1);
+        self.v1 = self.v1.rotate_left(17) ^ self.v2;
+        self.v2 = self.v2.rotate_left(32);
+    }
+
+    fn compress(&mut self, word: u64) {
+        self.v3 ^= word;
+        self.round();
+        self.v0 ^= word;
+    }
+}
+
+impl Default for StableHasher {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Hasher for StableHasher {
+    fn write(&mut self, bytes: &[u8]) {
+        self.length += bytes.len();
+        for &byte in bytes {
+            self.tail |= u64::from(byte) << (8 * self.tail_len);
+            self.tail_len += 1;
+            if self.tail_len == 8 {
+                let word = std::mem::take(&mut self.tail);
+                self.tail_len = 0;
+                self.compress(word);
+            }
+        }
+    }
+
+    fn finish(&self) -> u64 {
+        let mut state = self.clone();
+        state.compress(((self.length as u64 & 0xff) << 56) | self.tail);
+        state.v2 ^= 0xff;
+        for _ in 0..3 {
+            state.round();
+        }
+        state.v0 ^ state.v1 ^ state.v2 ^ state.v3
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hash(bytes: &[u8]) -> u64 {
+        let mut hasher = StableHasher::new();
+        hasher.write(bytes);
+        hasher.finish()
+    }
+
+    #[test]
+    fn hashes_are_stable() {
+        assert_eq!(hash(b""), 0xd1fba762150c532c);
+        assert_eq!(hash(b"export const a = 1;"), hash(b"export const a = 1;"));
+        assert_ne!(hash(b"export const a = 1;"), hash(b"export const a = 2;"));
+
+        // Writes may be split anywhere.
+        let mut split = StableHasher::new();
+        split.write(b"export const");
+        split.write(b" a = 1;");
+        assert_eq!(split.finish(), hash(b"export const a = 1;"));
+    }
+}
@@ -49,7 +49,7 @@ impl IllegalCode for Statement<'_> {
                 bid.and_then(|bid| bid.symbol_id.get())
                     .map(|id| IllegalCodeType::Class(id, cd.name().map(String::from)))
             }
-            s => None,
+            _ => None,
         }
     }
 }
@@ -59,14 +59,14 @@ mod tests {
     use super::*;
 
     use oxc_allocator::Allocator;
-    
+
     use oxc_parser::Parser;
     use oxc_semantic::{SemanticBuilder, SemanticBuilderReturn};
     use oxc_span::SourceType;
 
     fn parse_statement<'a>(source: &'a str, allocator: &'a Allocator) -> Statement<'a> {
         let source_type = SourceType::default();
-        let ret = Parser::new(&allocator, source, source_type).parse();
+        let ret = Parser::new(allocator, source, source_type).parse();
         let program = ret.program;
 
         let SemanticBuilderReturn {
@@ -1,11 +1,18 @@
 use crate::component::{Import, QWIK_CORE_SOURCE};
-use oxc_allocator::Allocator;
-use oxc_ast::ast::{ImportDeclaration, ImportOrExportKind, Program, Statement};
+use oxc_allocator::{Allocator, IntoIn};
+use oxc_ast::ast::{
+    ImportDeclaration, ImportDeclarationSpecifier, ImportOrExportKind, Program, Statement,
+};
 use oxc_semantic::{SemanticBuilder, SemanticBuilderReturn};
 use oxc_traverse::{traverse_mut, Traverse, TraverseCtx};
 use std::collections::BTreeSet;
 
 /// This struct is used to clean up unused imports in the AST.
+///
+/// [ImportCleanUp::clean_up] runs over the host module once all segments have been extracted and
+/// [ImportCleanUp::prune] over every generated segment module. Import specifiers which are no longer referenced are
+/// removed, and declarations left without any specifiers are dropped. Side-effect imports (e.g. `import "./global.css";`) are always preserved and, keeping their
+/// relative order, placed ahead of all other imports.
 pub(crate) struct ImportCleanUp;
 
 impl ImportCleanUp {
@@ -16,12 +23,8 @@ impl ImportCleanUp {
     pub fn clean_up<'a>(program: &mut Program<'a>, allocator: &'a Allocator) {
         let SemanticBuilderReturn {
             semantic,
-            errors: semantic_errors,
-        } = SemanticBuilder::new()
-            .with_check_syntax_error(true) // Enable extra syntax error checking
-            .with_build_jsdoc(true) // Enable JSDoc parsing
-            .with_cfg(true) // Build a Control Flow Graph
-            .build(program);
+            errors: _,
+        } = SemanticBuilder::new().build(program);
 
         let (symbols, scopes) = semantic.into_symbol_table_and_scope_tree();
 
@@ -30,6 +33,66 @@ impl ImportCleanUp {
         traverse_mut(transform, allocator, program, symbols, scopes);
     }
 
+    /// Removes unused import specifiers from `program` in place, leaving the order of the remaining imports intact.
+    pub fn prune(program: &mut Program<'_>) {
+        let SemanticBuilderReturn {
+            semantic,
+            errors: _,
+        } = SemanticBuilder::new().build(program);
+        let symbols = semantic.symbols();
+
+        let is_used = |specifier: &ImportDeclarationSpecifier| {
+            specifier
+                .local()
+                .symbol_id
+                .get()
+                .is_some_and(|symbol_id| symbols.symbol_is_used(symbol_id))
+        };
+
+        let mut unused: Vec<(usize, usize)> = Vec::new();
+        for (statement_index, statement) in program.body.iter().enumerate() {
+            if let Statement::ImportDeclaration(import) = statement {
+                if let Some(specifiers) = &import.specifiers {
+                    for (specifier_index, specifier) in specifiers.iter().enumerate() {
+                        if !is_used(specifier) {
+                            unused.push((statement_index, specifier_index));
+                        }
+                    }
+                }
+            }
+        }
+        drop(semantic);
+
+        for (statement_index, specifier_index) in unused.into_iter().rev() {
+            if let Statement::ImportDeclaration(import) = &mut program.body[statement_index] {
+                if let Some(specifiers) = &mut import.specifiers {
+                    specifiers.remove(specifier_index);
+                    if specifiers.is_empty() {
+                        program.body.remove(statement_index);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Renames the sources of all imports in `program` in place, see [ImportCleanUp::rename_qwik_imports].
+    pub fn rename_import_sources<'a>(program: &mut Program<'a>, allocator: &'a Allocator) {
+        for statement in program.body.iter_mut() {
+            if let Statement::ImportDeclaration(import) = statement {
+                let source = Self::rename_qwik_imports(import.source.value);
+                import.source.value = source.into_in(allocator);
+            }
+        }
+    }
+
+    /// Returns `true` for imports evaluated only for their side effects, e.g. `import "./global.css";`.
+    fn is_side_effect(import: &ImportDeclaration) -> bool {
+        import
+            .specifiers
+            .as_ref()
+            .is_none_or(|specifiers| specifiers.is_empty())
+    }
+
     /// This function renames the Qwik imports to the new qwik.dev imports.
     ///
     /// The following import sources are renamed:
@@ -58,20 +121,21 @@ impl ImportCleanUp {
     }
 }
 
+#[allow(dead_code)]
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
 struct Key(String);
 
 impl From<&ImportDeclaration<'_>> for Key {
     fn from(import: &ImportDeclaration) -> Self {
         let mut key = String::new();
-        for specifiers in &import.specifiers {
+        if let Some(specifiers) = &import.specifiers {
             for specifier in specifiers {
                 let local = specifier.local();
                 let local_name = local.name;
                 let name = specifier.name();
-                key.push_str(&name.to_string());
+                key.push_str(&name);
                 key.push('|');
-                key.push_str(&local_name.to_string());
+                key.push_str(&local_name);
                 key.push('|');
             }
         }
@@ -95,6 +159,17 @@ impl<'a> Traverse<'a> for ImportCleanUp {
         ctx: &mut TraverseCtx<'a>,
     ) {
         let mut imports: BTreeSet<Import> = BTreeSet::new();
+        let mut side_effects = Vec::new();
+
+        let mut index = 0;
+        while index < node.len() {
+            match &node[index] {
+                Statement::ImportDeclaration(import) if Self::is_side_effect(import) => {
+                    side_effects.push(node.remove(index));
+                }
+                _ => index += 1,
+            }
+        }
 
         node.retain_mut(|node| match node {
             Statement::ImportDeclaration(import) => {
@@ -116,7 +191,11 @@ impl<'a> Traverse<'a> for ImportCleanUp {
 
         imports.iter().for_each(|import| {
             node.insert(0, import.into_statement(ctx.ast.allocator));
-        })
+        });
+
+        side_effects.into_iter().rev().for_each(|import| {
+            node.insert(0, import);
+        });
     }
 }
 
@@ -153,6 +232,62 @@ mod tests {
         assert_eq!(lines[1], r#"b.foo();"#);
     }
 
+    #[test]
+    fn test_import_clean_up_preserves_side_effect_imports() {
+        let allocator = Allocator::new();
+        let source = r#"
+            import "./global.css";
+            import { a, b } from "x";
+            import c, * as d from "y";
+
+            b();
+        "#;
+
+        let parse_return = Parser::new(&allocator, source, SourceType::tsx()).parse();
+        let mut program = parse_return.program;
+        ImportCleanUp::clean_up(&mut program, &allocator);
+
+        let raw = Codegen::default().build(&program).code;
+        let lines: Vec<&str> = raw.lines().collect();
+        assert_eq!(
+            lines,
+            vec![
+                r#"import "./global.css";"#,
+                r#"import { b } from "x";"#,
+                "b();"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_prune_keeps_import_order() {
+        let allocator = Allocator::new();
+        let source = r#"
+            import { z } from "z";
+            import "./global.css";
+            import { a, b } from "a";
+            import { c } from "c";
+
+            z(b);
+        "#;
+
+        let parse_return = Parser::new(&allocator, source, SourceType::tsx()).parse();
+        let mut program = parse_return.program;
+        ImportCleanUp::prune(&mut program);
+
+        let raw = Codegen::default().build(&program).code;
+        let lines: Vec<&str> = raw.lines().collect();
+        assert_eq!(
+            lines,
+            vec![
+                r#"import { z } from "z";"#,
+                r#"import "./global.css";"#,
+                r#"import { b } from "a";"#,
+                "z(b);"
+            ]
+        );
+    }
+
     #[test]
     fn test_rename_qwik_imports() {
         let source = "@builder.io/qwik-city/foo";
@@ -0,0 +1,222 @@
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::borrow::Borrow;
+use std::cell::RefCell;
+use std::collections::HashSet;
+use std::fmt::{Debug, Display};
+use std::ops::Deref;
+use std::sync::Arc;
+
+/// An interned, immutable string, for the names repeated across the segments of a build: the symbol names, display
+/// names and hashes of an [Id](crate::component::Id), and the names of imports, e.g. `componentQrl`.
+///
+/// Equal names interned on the same thread share a single allocation, so cloning a name, e.g. from an `Id` into the
+/// [Manifest](crate::manifest::Manifest), does not copy it. Every thread interns names into a table of its own, so
+/// that the threads transforming modules in parallel never wait on each other. A name otherwise behaves like a
+/// `&str`: it derefs to one, compares with strings, and is serialized as one.
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Name(Arc<str>);
+
+impl Name {
+    /// Interns `name`, returning the name already interned if there is one.
+    pub fn new(name: &str) -> Name {
+        INTERNER.with_borrow_mut(|interner| interner.intern(name))
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+/// The names in use on a thread. Names which are no longer referenced anywhere else are dropped whenever the number
+/// of interned names doubles, so that a long-running process, e.g. a dev server, does not accumulate the names of
+/// every segment it ever extracted.
+struct Interner {
+    names: HashSet<Arc<str>>,
+    /// The number of names once unused names were last dropped.
+    retained: usize,
+}
+
+thread_local! {
+    static INTERNER: RefCell<Interner> = RefCell::new(Interner {
+        names: HashSet::new(),
+        retained: 0,
+    });
+}
+
+impl Interner {
+    /// Below this number of names, unused names are kept.
+    const MIN_PURGE: usize = 1024;
+
+    fn intern(&mut self, name: &str) -> Name {
+        if let Some(interned) = self.names.get(name) {
+            return Name(interned.clone());
+        }
+        if self.names.len() >= Self::MIN_PURGE.max(self.retained * 2) {
+            self.names.retain(|name| Arc::strong_count(name) > 1);
+            self.retained = self.names.len();
+        }
+        let interned: Arc<str> = Arc::from(name);
+        self.names.insert(interned.clone());
+        Name(interned)
+    }
+}
+
+impl Default for Name {
+    fn default() -> Self {
+        Name::new("")
+    }
+}
+
+impl Deref for Name {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl AsRef<str> for Name {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Borrow<str> for Name {
+    fn borrow(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Debug for Name {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        Debug::fmt(&*self.0, f)
+    }
+}
+
+impl Display for Name {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        Display::fmt(&*self.0, f)
+    }
+}
+
+impl From<&str> for Name {
+    fn from(name: &str) -> Self {
+        Name::new(name)
+    }
+}
+
+impl From<&String> for Name {
+    fn from(name: &String) -> Self {
+        Name::new(name)
+    }
+}
+
+impl From<String> for Name {
+    fn from(name: String) -> Self {
+        Name::new(&name)
+    }
+}
+
+impl From<Name> for String {
+    fn from(name: Name) -> Self {
+        name.0.to_string()
+    }
+}
+
+impl From<&Name> for String {
+    fn from(name: &Name) -> Self {
+        name.0.to_string()
+    }
+}
+
+impl PartialEq<str> for Name {
+    fn eq(&self, other: &str) -> bool {
+        &*self.0 == other
+    }
+}
+
+impl PartialEq<&str> for Name {
+    fn eq(&self, other: &&str) -> bool {
+        &*self.0 == *other
+    }
+}
+
+impl PartialEq<String> for Name {
+    fn eq(&self, other: &String) -> bool {
+        *self.0 == **other
+    }
+}
+
+impl PartialEq<Name> for str {
+    fn eq(&self, other: &Name) -> bool {
+        self == &*other.0
+    }
+}
+
+impl PartialEq<Name> for &str {
+    fn eq(&self, other: &Name) -> bool {
+        *self == &*other.0
+    }
+}
+
+impl PartialEq<Name> for String {
+    fn eq(&self, other: &Name) -> bool {
+        **self == *other.0
+    }
+}
+
+#[cfg(feature = "serde")]
+impl Serialize for Name {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.0)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> Deserialize<'de> for Name {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let name = <std::borrow::Cow<'de, str>>::deserialize(deserializer)?;
+        Ok(Name::new(&name))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn interns_names() {
+        let a = Name::new("App_component_ckEPmXZlub0");
+        let b = Name::from(String::from("App_component_ckEPmXZlub0"));
+        assert!(Arc::ptr_eq(&a.0, &b.0));
+        assert_eq!(a, "App_component_ckEPmXZlub0");
+        assert_eq!(a.to_string(), "App_component_ckEPmXZlub0");
+        assert!(a.starts_with("App_"));
+        assert_ne!(a, Name::new("App_component"));
+
+        // Names interned on other threads are equal, but not shared.
+        let other = std::thread::spawn(|| Name::new("App_component_ckEPmXZlub0"))
+            .join()
+            .unwrap();
+        assert_eq!(a, other);
+        assert!(!Arc::ptr_eq(&a.0, &other.0));
+    }
+
+    #[test]
+    fn drops_unused_names() {
+        let mut interner = Interner {
+            names: HashSet::new(),
+            retained: 0,
+        };
+        let kept = interner.intern("kept");
+        for i in 1..Interner::MIN_PURGE {
+            interner.intern(&format!("unused_{i}"));
+        }
+        interner.intern("last");
+
+        assert_eq!(interner.retained, 1);
+        assert_eq!(interner.names.len(), 2);
+        assert!(Arc::ptr_eq(&interner.intern("kept").0, &kept.0));
+    }
+}
@@ -0,0 +1,168 @@
+use crate::component::Id;
+use oxc_syntax::identifier::is_identifier_name;
+use oxc_syntax::keyword::is_reserved_keyword;
+use std::collections::HashMap;
+
+/// An [Id], or a set of them, that breaks an invariant the emitted code relies on.
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+pub enum NamingViolation {
+    #[error("`{0}` is not a valid JavaScript identifier")]
+    InvalidIdentifier(String),
+
+    #[error("The symbol name `{symbol_name}` does not end with its hash `{hash}`")]
+    MissingHash { symbol_name: String, hash: String },
+
+    #[error(
+        "The local file name `{local_file_name}` does not end with the symbol name `{symbol_name}`"
+    )]
+    FileNameMismatch {
+        local_file_name: String,
+        symbol_name: String,
+    },
+
+    #[error("The segments `{0}` and `{1}` have the same symbol name `{2}`")]
+    Collision(String, String, String),
+}
+
+/// Checks that `name` can be declared as a binding, e.g. `export const {name} = ...`, which is how segments are
+/// exported from their modules.
+pub fn check_identifier(name: &str) -> Result<(), NamingViolation> {
+    if is_identifier_name(name) && !is_reserved_keyword(name) {
+        Ok(())
+    } else {
+        Err(NamingViolation::InvalidIdentifier(name.to_string()))
+    }
+}
+
+/// Checks the invariants of a single [Id]: its symbol name is a valid identifier ending with its hash, and its local
+/// file name ends with its symbol name.
+pub fn check_id(id: &Id) -> Result<(), NamingViolation> {
+    check_identifier(&id.symbol_name)?;
+    if !id.symbol_name.ends_with(id.hash.as_str()) {
+        return Err(NamingViolation::MissingHash {
+            symbol_name: id.symbol_name.to_string(),
+            hash: id.hash.to_string(),
+        });
+    }
+    if !id.local_file_name.ends_with(id.symbol_name.as_str()) {
+        return Err(NamingViolation::FileNameMismatch {
+            local_file_name: id.local_file_name.clone(),
+            symbol_name: id.symbol_name.to_string(),
+        });
+    }
+    Ok(())
+}
+
+/// Checks every [Id] with [check_id], and that distinct display names, i.e. distinct segments of a module, never
+/// share a symbol name.
+pub fn check_ids<'i>(ids: impl IntoIterator<Item = &'i Id>) -> Result<(), NamingViolation> {
+    let mut display_name_by_symbol: HashMap<&str, &str> = HashMap::new();
+    for id in ids {
+        check_id(id)?;
+        match display_name_by_symbol.insert(&id.symbol_name, &id.display_name) {
+            Some(other) if other != id.display_name => {
+                return Err(NamingViolation::Collision(
+                    other.to_string(),
+                    id.display_name.to_string(),
+                    id.symbol_name.to_string(),
+                ))
+            }
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::component::{SourceInfo, SymbolFormat, Target};
+    use crate::segment::Segment;
+    use proptest::prelude::*;
+
+    fn segment() -> impl Strategy<Value = Segment> {
+        prop_oneof![
+            "[a-zA-Z0-9_$ .\\-'é]{1,8}".prop_map(Segment::Named),
+            ("[a-zA-Z0-9_\\-]{1,8}", 0..3usize).prop_map(|(name, i)| Segment::NamedQrl(name, i)),
+            (0..3usize).prop_map(Segment::IndexQrl),
+        ]
+    }
+
+    fn target() -> impl Strategy<Value = Target> {
+        prop_oneof![
+            Just(Target::Dev),
+            Just(Target::Test),
+            Just(Target::Lib),
+            Just(Target::Prod)
+        ]
+    }
+
+    fn id(path: &str, segments: &[Segment], target: &Target, format: &SymbolFormat) -> Id {
+        let source_info = SourceInfo::new(path).unwrap();
+        Id::with_format(&source_info, segments, target, &None, format)
+    }
+
+    proptest! {
+        #[test]
+        fn ids_are_valid(
+            segments in prop::collection::vec(segment(), 0..5),
+            target in target(),
+            prefix in prop::option::of("[a-z0-9\\-]{0,4}"),
+            hash_length in prop::option::of(0..40usize),
+        ) {
+            let format = SymbolFormat::new(prefix.as_deref(), hash_length);
+            let id = id("./src/app.tsx", &segments, &target, &format);
+            prop_assert_eq!(check_id(&id), Ok(()));
+        }
+
+        #[test]
+        fn hashes_ignore_leading_dot(
+            segments in prop::collection::vec(segment(), 1..5),
+            target in target(),
+        ) {
+            let format = SymbolFormat::default();
+            let dotted = id("./src/app.tsx", &segments, &target, &format);
+            let plain = id("src/app.tsx", &segments, &target, &format);
+            prop_assert_eq!(dotted.hash, plain.hash);
+            prop_assert_eq!(dotted.symbol_name, plain.symbol_name);
+        }
+
+        #[test]
+        fn distinct_segments_have_distinct_ids(
+            names in prop::collection::btree_set("[a-zA-Z][a-zA-Z0-9]{0,6}", 1..12),
+            target in target(),
+        ) {
+            let format = SymbolFormat::default();
+            let ids: Vec<Id> = names
+                .into_iter()
+                .map(|name| id("./app.tsx", &[Segment::NamedQrl(name, 0)], &target, &format))
+                .collect();
+            prop_assert_eq!(check_ids(&ids), Ok(()));
+        }
+    }
+
+    #[test]
+    fn reports_violations() {
+        assert!(check_identifier("App_component_ckEPmXZlub0").is_ok());
+        assert!(check_identifier("1_ckEPmXZlub0").is_err());
+        assert!(check_identifier("class").is_err());
+
+        let app = id(
+            "./app.tsx",
+            &[Segment::Named("App".into())],
+            &Target::Dev,
+            &SymbolFormat::default(),
+        );
+        let mut other = app.clone();
+        other.display_name = "app.tsx_Other".into();
+        assert!(matches!(
+            check_ids([&app, &other]),
+            Err(NamingViolation::Collision(..))
+        ));
+        other.local_file_name = "./app.tsx_Other".to_string();
+        assert!(matches!(
+            check_id(&other),
+            Err(NamingViolation::FileNameMismatch { .. })
+        ));
+    }
+}
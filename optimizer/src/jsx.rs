@@ -0,0 +1,702 @@
+use crate::component::{Import, ImportId, INLINED_QRL, NOOP_QRL, QRL, QRL_DEV, QWIK_CORE_SOURCE};
+use crate::events::is_listener_attribute;
+use crate::sync::QRL_SYNC;
+use oxc_allocator::{Allocator, Box as OxcBox, Vec as OxcVec};
+use oxc_ast::ast::*;
+use oxc_ast::visit::walk_mut;
+use oxc_ast::{AstBuilder, Visit, VisitMut};
+use oxc_semantic::{ScopeTree, SemanticBuilder, SymbolFlags, SymbolId, SymbolTable};
+use oxc_span::SPAN;
+use oxc_syntax::number::NumberBase;
+use std::collections::BTreeSet;
+
+/// Creates the virtual node of an intrinsic element: `_jsxQ(tag, varProps, constProps, children, flags, key)`.
+pub const JSX_Q: &str = "_jsxQ";
+
+/// Creates the virtual node of a component or fragment: `_jsxC(type, props, flags, key)`.
+pub const JSX_C: &str = "_jsxC";
+
+/// Keys the props of a component which never change: `{ title, [_IMMUTABLE]: { title: _IMMUTABLE } }`.
+pub const IMMUTABLE: &str = "_IMMUTABLE";
+
+/// The local name of the `Fragment` of the JSX runtime.
+const FRAGMENT: &str = "_Fragment";
+
+/// Set on `_jsxQ` calls whose listeners (the props ending with `$` or named like `on:click`) never change, and on
+/// `_jsxC` calls whose props never change, so the runtime does not compare them when the node is rendered again.
+pub const STATIC_LISTENERS: u32 = 1 << 0;
+
+/// Set on calls whose children never change, so the runtime skips diffing the whole subtree.
+pub const STATIC_SUBTREE: u32 = 1 << 1;
+
+/// The QRL constructors, which are constant as long as their captures are.
+const QRL_CALLS: [&str; 5] = [QRL, QRL_DEV, INLINED_QRL, NOOP_QRL, QRL_SYNC];
+
+/// Compiles the JSX of `program` to `_jsxQ` and `_jsxC` calls annotated with static flags, see [STATIC_LISTENERS] and
+/// [STATIC_SUBTREE], and imports them from Qwik. `Fragment` is imported from the JSX runtime of `import_source`.
+///
+/// An expression is constant when it only consists of literals, properties of imports (e.g. `styles.button`) and
+/// bindings declared at the top of the module which are never reassigned, e.g. imports, declarations and QRLs
+/// capturing nothing else. Props with a constant value are passed as the `constProps` of intrinsic elements, the
+/// others as their `varProps`, and listed under [IMMUTABLE] in the props of components. An element is static when it
+/// is intrinsic, all of its props are constant, and its children are static.
+///
+/// # Examples
+/// ```javascript
+/// <button class="btn" onClick$={handler} title={label}>Save</button>
+/// ```
+/// becomes, with `handler` imported and `label` a prop,
+/// ```javascript
+/// _jsxQ("button", { title: label }, { class: "btn", onClick$: handler }, "Save", 3, null)
+/// ```
+pub(crate) fn optimize_jsx<'a>(
+    program: &mut Program<'a>,
+    import_source: &str,
+    allocator: &'a Allocator,
+) {
+    if !has_jsx(program) {
+        return;
+    }
+    let semantic = SemanticBuilder::new().build(program).semantic;
+    let (symbols, scopes) = semantic.into_symbol_table_and_scope_tree();
+
+    let mut rewrite = JsxRewrite {
+        ast: AstBuilder::new(allocator),
+        symbols: &symbols,
+        scopes: &scopes,
+        used: BTreeSet::new(),
+    };
+    rewrite.visit_program(program);
+
+    let mut core = Vec::new();
+    for name in rewrite.used {
+        if name == FRAGMENT {
+            let fragment = ImportId::NamedWithAlias("Fragment".into(), FRAGMENT.into());
+            let runtime = format!("{import_source}/jsx-runtime");
+            program.body.insert(
+                0,
+                Import::new(vec![fragment], runtime).into_statement(allocator),
+            );
+        } else {
+            core.push(name.into());
+        }
+    }
+    if !core.is_empty() {
+        let import = Import::new(core, QWIK_CORE_SOURCE);
+        program.body.insert(0, import.into_statement(allocator));
+    }
+}
+
+fn has_jsx(program: &Program) -> bool {
+    #[derive(Default)]
+    struct Jsx(bool);
+
+    impl<'a> Visit<'a> for Jsx {
+        fn visit_jsx_element(&mut self, _: &JSXElement<'a>) {
+            self.0 = true;
+        }
+
+        fn visit_jsx_fragment(&mut self, _: &JSXFragment<'a>) {
+            self.0 = true;
+        }
+    }
+
+    let mut jsx = Jsx::default();
+    jsx.visit_program(program);
+    jsx.0
+}
+
+/// The text of a JSX text node as React and Babel compile it: lines are trimmed, blank lines dropped and the others
+/// joined with a space, and HTML entities are decoded.
+fn jsx_text(raw: &str) -> Option<String> {
+    let lines: Vec<&str> = raw.split('\n').collect();
+    let last = lines.len().saturating_sub(1);
+    let text = lines
+        .iter()
+        .enumerate()
+        .map(|(index, line)| {
+            let line = if index == 0 { line } else { line.trim_start() };
+            if index == last {
+                line
+            } else {
+                line.trim_end()
+            }
+        })
+        .filter(|line| !line.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ");
+    (!text.is_empty()).then(|| decode_entities(&text))
+}
+
+fn decode_entities(text: &str) -> String {
+    let mut decoded = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('&') {
+        decoded.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let entity = rest.find(';').map(|end| (&rest[1..end], end));
+        let char = entity.and_then(|(name, _)| match name {
+            "amp" => Some('&'),
+            "lt" => Some('<'),
+            "gt" => Some('>'),
+            "quot" => Some('"'),
+            "apos" => Some('\''),
+            "nbsp" => Some('\u{a0}'),
+            _ => name
+                .strip_prefix("#x")
+                .and_then(|hex| u32::from_str_radix(hex, 16).ok())
+                .or_else(|| name.strip_prefix('#').and_then(|dec| dec.parse().ok()))
+                .and_then(char::from_u32),
+        });
+        match (char, entity) {
+            (Some(char), Some((_, end))) => {
+                decoded.push(char);
+                rest = &rest[end + 1..];
+            }
+            _ => {
+                decoded.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    decoded.push_str(rest);
+    decoded
+}
+
+/// The props of an element, split by whether their value is constant.
+#[derive(Default)]
+struct Props<'a> {
+    var: Vec<ObjectPropertyKind<'a>>,
+    constant: Vec<ObjectPropertyKind<'a>>,
+    /// The names of the [constant](Props::constant) props.
+    constant_names: Vec<String>,
+    key: Option<Expression<'a>>,
+    children: Option<(Expression<'a>, bool)>,
+    has_spread: bool,
+    static_listeners: bool,
+}
+
+struct JsxRewrite<'s, 'a> {
+    ast: AstBuilder<'a>,
+    symbols: &'s SymbolTable,
+    scopes: &'s ScopeTree,
+    /// The functions the rewritten module calls.
+    used: BTreeSet<&'static str>,
+}
+
+impl<'a> JsxRewrite<'_, 'a> {
+    fn is_const(&self, expr: &Expression<'a>) -> bool {
+        match expr.without_parentheses() {
+            Expression::StringLiteral(_)
+            | Expression::NumericLiteral(_)
+            | Expression::BooleanLiteral(_)
+            | Expression::NullLiteral(_)
+            | Expression::BigIntLiteral(_) => true,
+            Expression::TemplateLiteral(template) => {
+                template.expressions.iter().all(|expr| self.is_const(expr))
+            }
+            Expression::Identifier(id_ref) => self.is_const_reference(id_ref),
+            expr if expr.is_member_expression() => expr
+                .as_member_expression()
+                .is_some_and(|member| self.is_const_member(member)),
+            Expression::UnaryExpression(unary) => self.is_const(&unary.argument),
+            Expression::BinaryExpression(binary) => {
+                self.is_const(&binary.left) && self.is_const(&binary.right)
+            }
+            Expression::LogicalExpression(logical) => {
+                self.is_const(&logical.left) && self.is_const(&logical.right)
+            }
+            Expression::ConditionalExpression(conditional) => {
+                self.is_const(&conditional.test)
+                    && self.is_const(&conditional.consequent)
+                    && self.is_const(&conditional.alternate)
+            }
+            Expression::CallExpression(call) => {
+                call.callee_name()
+                    .is_some_and(|name| QRL_CALLS.contains(&name))
+                    && call.arguments.iter().all(|argument| match argument {
+                        // The lazy import of the segment, or its inlined function.
+                        Argument::ArrowFunctionExpression(_) | Argument::FunctionExpression(_) => {
+                            true
+                        }
+                        // The captures.
+                        Argument::ArrayExpression(array) => array.elements.iter().all(|element| {
+                            element
+                                .as_expression()
+                                .is_some_and(|expr| self.is_const(expr))
+                        }),
+                        argument => argument
+                            .as_expression()
+                            .is_some_and(|expr| self.is_const(expr)),
+                    })
+            }
+            _ => false,
+        }
+    }
+
+    /// Whether `id_ref` is `undefined`, or a binding declared at the top of the module which is never reassigned.
+    fn is_const_reference(&self, id_ref: &IdentifierReference<'a>) -> bool {
+        let Some(symbol_id) = self.symbol_of(id_ref) else {
+            return id_ref.name == "undefined";
+        };
+        self.symbols.get_scope_id(symbol_id) == self.scopes.root_scope_id()
+            && self.symbols.get_flags(symbol_id).intersects(
+                SymbolFlags::Import
+                    | SymbolFlags::Variable
+                    | SymbolFlags::ConstVariable
+                    | SymbolFlags::Function
+                    | SymbolFlags::Class,
+            )
+            && !self
+                .symbols
+                .get_resolved_references(symbol_id)
+                .any(|reference| reference.is_write())
+    }
+
+    /// Whether `member` reads a property of an import, e.g. `styles.button` of a CSS module, which modules can not
+    /// change.
+    fn is_const_member(&self, member: &MemberExpression<'a>) -> bool {
+        let is_static_key = match member {
+            MemberExpression::StaticMemberExpression(_) => true,
+            MemberExpression::ComputedMemberExpression(computed) => {
+                computed.expression.is_string_literal()
+            }
+            MemberExpression::PrivateFieldExpression(_) => false,
+        };
+        is_static_key
+            && match member.object().without_parentheses() {
+                Expression::Identifier(id_ref) => self.symbol_of(id_ref).is_some_and(|symbol_id| {
+                    self.symbols
+                        .get_flags(symbol_id)
+                        .contains(SymbolFlags::Import)
+                }),
+                object => object
+                    .as_member_expression()
+                    .is_some_and(|object| self.is_const_member(object)),
+            }
+    }
+
+    fn symbol_of(&self, id_ref: &IdentifierReference<'a>) -> Option<SymbolId> {
+        let reference_id = id_ref.reference_id.get()?;
+        self.symbols.get_reference(reference_id).symbol_id()
+    }
+
+    fn call(&mut self, callee: &'static str, arguments: Vec<Expression<'a>>) -> Expression<'a> {
+        self.used.insert(callee);
+        self.ast.expression_call(
+            SPAN,
+            self.ast.expression_identifier(SPAN, callee),
+            None::<OxcBox<TSTypeParameterInstantiation>>,
+            self.ast
+                .vec_from_iter(arguments.into_iter().map(Argument::from)),
+            false,
+        )
+    }
+
+    fn object_or_null(&self, properties: Vec<ObjectPropertyKind<'a>>) -> Expression<'a> {
+        if properties.is_empty() {
+            self.ast.expression_null_literal(SPAN)
+        } else {
+            self.ast
+                .expression_object(SPAN, self.ast.vec_from_iter(properties), None)
+        }
+    }
+
+    fn property(&self, name: &str, value: Expression<'a>) -> ObjectPropertyKind<'a> {
+        let key = if oxc_syntax::identifier::is_identifier_name(name) {
+            self.ast.property_key_static_identifier(SPAN, name)
+        } else {
+            PropertyKey::from(self.ast.expression_string_literal(SPAN, name, None))
+        };
+        self.ast.object_property_kind_object_property(
+            SPAN,
+            PropertyKind::Init,
+            key,
+            value,
+            false,
+            false,
+            false,
+        )
+    }
+
+    /// `[_IMMUTABLE]: { name: _IMMUTABLE, ... }`, which marks the props of a component that never change.
+    fn immutable_props(&mut self, names: &[String]) -> ObjectPropertyKind<'a> {
+        self.used.insert(IMMUTABLE);
+        let marks = names
+            .iter()
+            .map(|name| self.property(name, self.ast.expression_identifier(SPAN, IMMUTABLE)))
+            .collect();
+        self.ast.object_property_kind_object_property(
+            SPAN,
+            PropertyKind::Init,
+            PropertyKey::from(self.ast.expression_identifier(SPAN, IMMUTABLE)),
+            self.object_or_null(marks),
+            false,
+            false,
+            true,
+        )
+    }
+
+    fn flags(&self, static_listeners: bool, static_subtree: bool) -> Expression<'a> {
+        let mut flags = 0;
+        if static_listeners {
+            flags |= STATIC_LISTENERS;
+        }
+        if static_subtree {
+            flags |= STATIC_SUBTREE;
+        }
+        self.ast
+            .expression_numeric_literal(SPAN, flags as f64, None, NumberBase::Decimal)
+    }
+
+    /// The props of `attributes`, converting the JSX of their values.
+    fn props(&mut self, attributes: &mut OxcVec<'a, JSXAttributeItem<'a>>) -> Props<'a> {
+        let mut props = Props {
+            static_listeners: true,
+            ..Props::default()
+        };
+        for attribute in attributes.drain(..) {
+            let attribute = match attribute {
+                JSXAttributeItem::Attribute(attribute) => attribute.unbox(),
+                JSXAttributeItem::SpreadAttribute(spread) => {
+                    let mut argument = spread.unbox().argument;
+                    self.visit_expression(&mut argument);
+                    props.has_spread = true;
+                    props.static_listeners = false;
+                    props.var.push(
+                        self.ast
+                            .object_property_kind_spread_property(SPAN, argument),
+                    );
+                    continue;
+                }
+            };
+            let name = match &attribute.name {
+                JSXAttributeName::Identifier(name) => name.name.to_string(),
+                JSXAttributeName::NamespacedName(name) => {
+                    format!("{}:{}", name.namespace.name, name.property.name)
+                }
+            };
+            let (value, is_const) = self.attribute_value(attribute.value);
+            match name.as_str() {
+                "key" => props.key = Some(value),
+                "children" => props.children = Some((value, is_const)),
+                _ => {
+                    if is_listener_attribute(&name) {
+                        props.static_listeners &= is_const;
+                    }
+                    let property = self.property(&name, value);
+                    if is_const {
+                        props.constant_names.push(name);
+                        props.constant.push(property);
+                    } else {
+                        props.var.push(property);
+                    }
+                }
+            }
+        }
+        props
+    }
+
+    /// The value of an attribute, and whether it is constant.
+    fn attribute_value(&mut self, value: Option<JSXAttributeValue<'a>>) -> (Expression<'a>, bool) {
+        match value {
+            None => (self.ast.expression_boolean_literal(SPAN, true), true),
+            Some(JSXAttributeValue::StringLiteral(string)) => {
+                let value = decode_entities(&string.value);
+                (
+                    self.ast.expression_string_literal(string.span, value, None),
+                    true,
+                )
+            }
+            Some(JSXAttributeValue::ExpressionContainer(container)) => {
+                match container.unbox().expression {
+                    JSXExpression::EmptyExpression(_) => {
+                        (self.ast.expression_boolean_literal(SPAN, true), true)
+                    }
+                    expression => {
+                        let mut expr = expression.into_expression();
+                        let is_const = self.is_const(&expr);
+                        self.visit_expression(&mut expr);
+                        (expr, is_const)
+                    }
+                }
+            }
+            Some(JSXAttributeValue::Element(mut element)) => {
+                let (expr, _) = self.element(&mut element);
+                (expr, false)
+            }
+            Some(JSXAttributeValue::Fragment(mut fragment)) => {
+                let (expr, _) = self.fragment(&mut fragment);
+                (expr, false)
+            }
+        }
+    }
+
+    /// The children of a node as a single expression, `None` if it has none, and whether they are all static.
+    fn children(
+        &mut self,
+        children: &mut OxcVec<'a, JSXChild<'a>>,
+    ) -> (Option<Expression<'a>>, bool) {
+        let mut converted = Vec::with_capacity(children.len());
+        let mut is_static = true;
+        for child in children.drain(..) {
+            let (expr, is_child_static) = match child {
+                JSXChild::Text(text) => match jsx_text(&text.value) {
+                    Some(text) => (self.ast.expression_string_literal(SPAN, text, None), true),
+                    None => continue,
+                },
+                JSXChild::Element(mut element) => self.element(&mut element),
+                JSXChild::Fragment(mut fragment) => self.fragment(&mut fragment),
+                JSXChild::ExpressionContainer(container) => match container.unbox().expression {
+                    JSXExpression::EmptyExpression(_) => continue,
+                    expression => {
+                        let mut expr = expression.into_expression();
+                        let is_const = self.is_const(&expr);
+                        self.visit_expression(&mut expr);
+                        (expr, is_const)
+                    }
+                },
+                JSXChild::Spread(spread) => {
+                    let mut expr = spread.unbox().expression;
+                    self.visit_expression(&mut expr);
+                    let elements = self
+                        .ast
+                        .vec1(self.ast.array_expression_element_spread_element(SPAN, expr));
+                    (self.ast.expression_array(SPAN, elements, None), false)
+                }
+            };
+            is_static &= is_child_static;
+            converted.push(expr);
+        }
+        let children = match converted.len() {
+            0 => None,
+            1 => converted.pop(),
+            _ => Some(
+                self.ast.expression_array(
+                    SPAN,
+                    self.ast
+                        .vec_from_iter(converted.into_iter().map(ArrayExpressionElement::from)),
+                    None,
+                ),
+            ),
+        };
+        (children, is_static)
+    }
+
+    /// The tag of an intrinsic element, e.g. `div` or `svg:rect`, or the expression of a component.
+    fn element_type(&self, name: &JSXElementName<'a>) -> Result<String, Expression<'a>> {
+        match name {
+            JSXElementName::Identifier(tag) => Ok(tag.name.to_string()),
+            JSXElementName::NamespacedName(name) => {
+                Ok(format!("{}:{}", name.namespace.name, name.property.name))
+            }
+            JSXElementName::IdentifierReference(id_ref) => {
+                Err(self.ast.expression_identifier(id_ref.span, id_ref.name))
+            }
+            JSXElementName::MemberExpression(member) => Err(self.member(member)),
+            JSXElementName::ThisExpression(this) => Err(self.ast.expression_this(this.span)),
+        }
+    }
+
+    fn member(&self, member: &JSXMemberExpression<'a>) -> Expression<'a> {
+        let object = match &member.object {
+            JSXMemberExpressionObject::IdentifierReference(id_ref) => {
+                self.ast.expression_identifier(id_ref.span, id_ref.name)
+            }
+            JSXMemberExpressionObject::MemberExpression(member) => self.member(member),
+            JSXMemberExpressionObject::ThisExpression(this) => self.ast.expression_this(this.span),
+        };
+        let property = self
+            .ast
+            .identifier_name(member.property.span, member.property.name);
+        Expression::from(
+            self.ast
+                .member_expression_static(member.span, object, property, false),
+        )
+    }
+
+    /// The call creating `element`, and whether the element is static.
+    fn element(&mut self, element: &mut JSXElement<'a>) -> (Expression<'a>, bool) {
+        let element_type = self.element_type(&element.opening_element.name);
+        let mut props = self.props(&mut element.opening_element.attributes);
+        let (children, static_children) = match self.children(&mut element.children) {
+            (None, _) => match props.children.take() {
+                Some((children, is_const)) => (Some(children), is_const),
+                None => (None, true),
+            },
+            children => children,
+        };
+        let key = props
+            .key
+            .take()
+            .unwrap_or_else(|| self.ast.expression_null_literal(SPAN));
+
+        match element_type {
+            Ok(tag) => {
+                let is_static = props.var.is_empty() && static_children;
+                let arguments = vec![
+                    self.ast.expression_string_literal(SPAN, tag, None),
+                    self.object_or_null(std::mem::take(&mut props.var)),
+                    self.object_or_null(std::mem::take(&mut props.constant)),
+                    children.unwrap_or_else(|| self.ast.expression_null_literal(SPAN)),
+                    self.flags(props.static_listeners, static_children),
+                    key,
+                ];
+                (self.call(JSX_Q, arguments), is_static)
+            }
+            Err(component) => {
+                let static_props = props.var.is_empty() && !props.has_spread;
+                let mut properties = props.constant;
+                properties.append(&mut props.var);
+                if let Some(children) = children {
+                    properties.push(self.property("children", children));
+                }
+                if !props.constant_names.is_empty() {
+                    properties.push(self.immutable_props(&props.constant_names));
+                }
+                let arguments = vec![
+                    component,
+                    self.object_or_null(properties),
+                    self.flags(static_props, static_children),
+                    key,
+                ];
+                (self.call(JSX_C, arguments), false)
+            }
+        }
+    }
+
+    /// The call creating `fragment`, and whether its children are static.
+    fn fragment(&mut self, fragment: &mut JSXFragment<'a>) -> (Expression<'a>, bool) {
+        let (children, static_children) = self.children(&mut fragment.children);
+        let mut properties = Vec::new();
+        if let Some(children) = children {
+            properties.push(self.property("children", children));
+        }
+        self.used.insert(FRAGMENT);
+        let arguments = vec![
+            self.ast.expression_identifier(SPAN, FRAGMENT),
+            self.object_or_null(properties),
+            self.flags(true, static_children),
+            self.ast.expression_null_literal(SPAN),
+        ];
+        (self.call(JSX_C, arguments), static_children)
+    }
+}
+
+impl<'a> VisitMut<'a> for JsxRewrite<'_, 'a> {
+    fn visit_expression(&mut self, expr: &mut Expression<'a>) {
+        let converted = match expr {
+            Expression::JSXElement(element) => Some(self.element(element).0),
+            Expression::JSXFragment(fragment) => Some(self.fragment(fragment).0),
+            _ => None,
+        };
+        match converted {
+            Some(converted) => *expr = converted,
+            None => walk_mut::walk_expression(self, expr),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use oxc_codegen::Codegen;
+    use oxc_parser::Parser;
+    use oxc_span::SourceType;
+
+    fn optimize(source: &str) -> String {
+        let allocator = Allocator::default();
+        let mut program = Parser::new(&allocator, source, SourceType::tsx())
+            .parse()
+            .program;
+        optimize_jsx(&mut program, QWIK_CORE_SOURCE, &allocator);
+        Codegen::new().build(&program).code
+    }
+
+    #[test]
+    fn annotates_static_flags() {
+        let code = optimize(
+            r#"
+            import { handler } from "./handlers";
+            const title = "Greeting";
+            export const Greeting = (props) => (
+                <>
+                    <h1 class="title" title={title}>Hello &amp; welcome</h1>
+                    <button onClick$={handler} disabled={props.busy} key="save">
+                        Save {props.label}
+                    </button>
+                    <Card.Body heading={title} {...props}><p /></Card.Body>
+                </>
+            );
+            "#,
+        );
+        assert!(code.starts_with(
+            "import { _IMMUTABLE, _jsxC, _jsxQ } from \"@qwik.dev/core\";\nimport { Fragment as _Fragment } from \"@qwik.dev/core/jsx-runtime\";"
+        ));
+        assert!(
+            code.contains(
+                r#"_jsxQ("h1", null, {
+		class: "title",
+		title
+	}, "Hello & welcome", 3, null)"#
+            ),
+            "{code}"
+        );
+        assert!(code.contains(
+            r#"_jsxQ("button", { disabled: props.busy }, { onClick$: handler }, ["Save ", props.label], 1, "save")"#
+        ), "{code}");
+        assert!(
+            code.contains(
+                r#"_jsxC(Card.Body, {
+		heading: title,
+		...props,
+		children: _jsxQ("p", null, null, null, 3, null),
+		[_IMMUTABLE]: { heading: _IMMUTABLE }
+	}, 2, null)"#
+            ),
+            "{code}"
+        );
+        assert!(code.contains("_jsxC(_Fragment, { children: ["), "{code}");
+    }
+
+    #[test]
+    fn detects_immutable_props() {
+        let code = optimize(
+            r#"
+            import styles from "./card.module.css";
+            let theme = "dark";
+            let count = 0;
+            export const increment = () => count++;
+            export const Card = (props) => <Panel class={styles.card} theme={theme} count={count} size={props.size} />;
+            "#,
+        );
+        assert!(
+            code.contains(
+                r#"_jsxC(Panel, {
+	class: styles.card,
+	theme,
+	count,
+	size: props.size,
+	[_IMMUTABLE]: {
+		class: _IMMUTABLE,
+		theme: _IMMUTABLE
+	}
+}, 2, null)"#
+            ),
+            "{code}"
+        );
+    }
+
+    #[test]
+    fn cleans_text() {
+        assert_eq!(jsx_text("\n    \n  "), None);
+        assert_eq!(
+            jsx_text("\n  Hello\n  world  \n").as_deref(),
+            Some("Hello world")
+        );
+        assert_eq!(
+            jsx_text(" a &lt;b&gt; &#65;&#x42; &unknown; ").as_deref(),
+            Some(" a <b> AB &unknown; ")
+        );
+    }
+}
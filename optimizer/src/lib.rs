@@ -1,3 +1,14 @@
+#[cfg(feature = "graph")]
+pub mod analyze;
+pub mod api;
+mod awaits;
+pub mod cache;
+mod capture;
+pub mod chunk;
+pub mod cjs;
+pub mod classify;
+mod comments;
+pub mod compare;
 pub mod component;
 pub(crate) mod error;
 pub(crate) mod ext;
@@ -8,9 +19,55 @@ pub mod source;
 pub mod macros;
 
 mod dead_code;
+mod defines;
+pub mod diagnostic;
+pub mod emit;
+pub mod entry_strategy;
+pub mod esm;
+pub mod events;
+pub mod external;
+#[cfg(any(test, feature = "test-utils"))]
+pub mod fixture;
+mod fold;
+pub mod glob;
+mod hash;
 mod illegal_code;
 mod import_clean_up;
+pub mod intern;
+pub mod invariants;
+pub mod jsx;
+pub mod limits;
+pub mod listener;
+pub mod manifest;
+mod methods;
+pub mod metrics;
+pub mod name_registry;
+mod nesting;
+#[cfg(feature = "napi")]
+pub mod node;
+mod pool;
+pub mod preprocess;
+mod pretty;
 mod processing_failure;
+mod props;
+mod recover;
 mod ref_counter;
+pub mod routes;
+pub mod search;
 mod segment;
+#[cfg(feature = "serde")]
+pub mod serve;
+mod shadowing;
+pub mod stats;
+pub mod strip;
+pub mod styles;
+pub mod symbol_registry;
+pub mod sync;
+pub mod trace;
 pub mod transform;
+mod transpile;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+pub mod watch;
+
+pub use transform::transform_module as transform;
@@ -0,0 +1,318 @@
+use crate::component::QrlComponent;
+use crate::diagnostic::{Diagnostic, DiagnosticCode};
+use crate::nesting::Nesting;
+use oxc_ast::ast::Program;
+use oxc_ast::{AstKind, Visit};
+use oxc_span::GetSpan;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// The resource limits enforced on every module by [hardened](crate::transform::TransformOptions::hardened)
+/// transforms. A module exceeding any of them is reported with a [DiagnosticCode::LimitExceeded] diagnostic and
+/// emitted empty, without segments.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "camelCase", default))]
+pub struct ResourceLimits {
+    /// The size of the source of a module, in bytes.
+    pub max_source_size: usize,
+    /// The nesting depth of the syntax tree of a module, e.g. of nested expressions, blocks or JSX elements.
+    pub max_depth: usize,
+    /// The number of segments extracted from a module.
+    pub max_segments: usize,
+}
+
+impl ResourceLimits {
+    pub const DEFAULT_MAX_SOURCE_SIZE: usize = 512 * 1024;
+    pub const DEFAULT_MAX_DEPTH: usize = 256;
+    pub const DEFAULT_MAX_SEGMENTS: usize = 1024;
+
+    /// The stack reserved for a hardened transform, in bytes per level of [ResourceLimits::max_depth].
+    ///
+    /// Sources nested deeper than the limit are rejected before they are parsed, see [Nesting], so the stack only
+    /// has to hold that many levels of the parser and of the passes after it, each of which takes a few frames.
+    const STACK_PER_LEVEL: usize = 64 * 1024;
+    const MIN_STACK_SIZE: usize = 4 * 1024 * 1024;
+
+    /// The stack size of the thread running a hardened transform, see [ResourceLimits::STACK_PER_LEVEL].
+    pub(crate) fn stack_size(&self) -> usize {
+        self.max_depth
+            .saturating_mul(Self::STACK_PER_LEVEL)
+            .max(Self::MIN_STACK_SIZE)
+    }
+
+    pub(crate) fn check_source_size(&self, source: &str) -> Option<Diagnostic> {
+        (source.len() > self.max_source_size).then(|| {
+            Diagnostic::error(
+                DiagnosticCode::LimitExceeded,
+                format!(
+                    "The module is {} bytes, more than the limit of {} bytes",
+                    source.len(),
+                    self.max_source_size
+                ),
+            )
+            .with_suggestion("Split the module into smaller modules")
+        })
+    }
+
+    /// Checks the nesting of `source` before it is parsed, see [Nesting]. JSX elements are only recognized if `jsx`
+    /// is set.
+    pub(crate) fn check_nesting(&self, source: &str, jsx: bool) -> Option<Diagnostic> {
+        let nesting = Nesting::of(source, jsx);
+        (nesting.depth > self.max_depth).then(|| {
+            let message = format!(
+                "The module is nested at least {} levels deep, more than the limit of {}",
+                nesting.depth, self.max_depth
+            );
+            let start = u32::try_from(nesting.offset).unwrap_or(u32::MAX);
+            Diagnostic::error(DiagnosticCode::LimitExceeded, message).with_label(
+                oxc_span::Span::new(start, start.saturating_add(1)),
+                Some("nested too deeply".to_string()),
+            )
+        })
+    }
+
+    pub(crate) fn check_depth(&self, program: &Program) -> Option<Diagnostic> {
+        let mut depth = Depth::default();
+        depth.visit_program(program);
+        (depth.max > self.max_depth).then(|| {
+            let message = format!(
+                "The module is nested {} levels deep, more than the limit of {}",
+                depth.max, self.max_depth
+            );
+            let diagnostic = Diagnostic::error(DiagnosticCode::LimitExceeded, message);
+            match depth.deepest {
+                Some(span) => diagnostic.with_label(span, Some("nested too deeply".to_string())),
+                None => diagnostic,
+            }
+        })
+    }
+
+    pub(crate) fn check_segment_count(&self, count: usize) -> Option<Diagnostic> {
+        (count > self.max_segments).then(|| {
+            Diagnostic::error(
+                DiagnosticCode::LimitExceeded,
+                format!(
+                    "The module has {count} segments, more than the limit of {}",
+                    self.max_segments
+                ),
+            )
+            .with_suggestion("Split the module into smaller modules")
+        })
+    }
+}
+
+impl Default for ResourceLimits {
+    fn default() -> Self {
+        Self {
+            max_source_size: Self::DEFAULT_MAX_SOURCE_SIZE,
+            max_depth: Self::DEFAULT_MAX_DEPTH,
+            max_segments: Self::DEFAULT_MAX_SEGMENTS,
+        }
+    }
+}
+
+/// The default of [TransformOptions::max_diagnostics](crate::transform::TransformOptions::max_diagnostics).
+pub const DEFAULT_MAX_DIAGNOSTICS: usize = 50;
+
+/// Keeps at most `max` of the diagnostics of a module, errors first and otherwise in their order, and reports the
+/// number of the others with a [DiagnosticCode::TooManyDiagnostics] summary.
+pub(crate) fn cap_diagnostics(diagnostics: &mut Vec<Diagnostic>, max: usize) {
+    if diagnostics.len() <= max {
+        return;
+    }
+    let errors = diagnostics.iter().filter(|d| d.is_error()).count();
+    let mut kept_errors = errors.min(max);
+    let mut kept_warnings = max - kept_errors;
+    let (omitted_errors, omitted_warnings) = (
+        errors - kept_errors,
+        diagnostics.len() - errors - kept_warnings,
+    );
+    diagnostics.retain(|diagnostic| {
+        let kept = if diagnostic.is_error() {
+            &mut kept_errors
+        } else {
+            &mut kept_warnings
+        };
+        let keep = *kept > 0;
+        *kept = kept.saturating_sub(1);
+        keep
+    });
+
+    let message = format!(
+        "{} more diagnostics were omitted ({omitted_errors} errors, {omitted_warnings} warnings), over the limit of {max}",
+        omitted_errors + omitted_warnings
+    );
+    let summary = if omitted_errors > 0 {
+        Diagnostic::error(DiagnosticCode::TooManyDiagnostics, message)
+    } else {
+        Diagnostic::warning(DiagnosticCode::TooManyDiagnostics, message)
+    };
+    diagnostics.push(summary.with_suggestion(
+        "Fix the reported problems first, or raise `max_diagnostics` to see all of them",
+    ));
+}
+
+/// The number of offenders listed by [segment cap](crate::transform::TransformOptions::max_segments_per_file)
+/// diagnostics.
+const TOP_OFFENDERS: usize = 5;
+
+/// Reports a module with more than `max` segments, listing the components (or markers, for segments outside of a
+/// component) most of them were extracted from.
+pub(crate) fn check_file_segments(segments: &[QrlComponent], max: usize) -> Option<Diagnostic> {
+    if segments.len() <= max {
+        return None;
+    }
+    let mut counts: BTreeMap<&str, usize> = BTreeMap::new();
+    for segment in segments {
+        let name = segment.root.as_deref().unwrap_or(&segment.ctx_name);
+        *counts.entry(name).or_default() += 1;
+    }
+    let message = format!(
+        "The module has {} segments, more than the limit of {max}. Most were extracted from {}",
+        segments.len(),
+        top_offenders(counts)
+    );
+    Some(
+        Diagnostic::error(DiagnosticCode::TooManySegments, message)
+            .with_suggestion("Split the largest components into modules of their own"),
+    )
+}
+
+/// Reports a build with more than `max` segments, listing the modules with the most segments.
+pub(crate) fn check_project_segments<'p>(
+    segments_by_module: impl IntoIterator<Item = (&'p str, usize)>,
+    max: usize,
+) -> Option<Diagnostic> {
+    let counts: BTreeMap<&str, usize> = segments_by_module.into_iter().collect();
+    let total: usize = counts.values().sum();
+    if total <= max {
+        return None;
+    }
+    let message = format!(
+        "The build has {total} segments, more than the limit of {max}. The modules with the most segments are {}",
+        top_offenders(counts)
+    );
+    Some(
+        Diagnostic::error(DiagnosticCode::TooManySegments, message).with_suggestion(
+            "Check whether these modules are generated, and exclude them from the optimizer if so",
+        ),
+    )
+}
+
+/// Formats the [TOP_OFFENDERS] names with the highest counts, e.g. `` `App` (120), `Table` (80) ``.
+fn top_offenders(counts: BTreeMap<&str, usize>) -> String {
+    let mut counts: Vec<_> = counts.into_iter().collect();
+    counts.sort_by(|(a_name, a), (b_name, b)| b.cmp(a).then(a_name.cmp(b_name)));
+    counts
+        .iter()
+        .take(TOP_OFFENDERS)
+        .map(|(name, count)| format!("`{name}` ({count})"))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Measures the maximum nesting depth of a syntax tree, remembering the first node at that depth.
+#[derive(Default)]
+struct Depth {
+    current: usize,
+    max: usize,
+    deepest: Option<oxc_span::Span>,
+}
+
+impl<'a> Visit<'a> for Depth {
+    fn enter_node(&mut self, kind: AstKind<'a>) {
+        self.current += 1;
+        if self.current > self.max {
+            self.max = self.current;
+            self.deepest = Some(kind.span());
+        }
+    }
+
+    fn leave_node(&mut self, _kind: AstKind<'a>) {
+        self.current -= 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use oxc_allocator::Allocator;
+    use oxc_parser::Parser;
+    use oxc_span::SourceType;
+
+    #[test]
+    fn checks_limits() {
+        let limits = ResourceLimits {
+            max_source_size: 64,
+            max_depth: 32,
+            max_segments: 2,
+        };
+
+        assert!(limits.check_source_size(&"a".repeat(64)).is_none());
+        assert!(limits.check_source_size(&"a".repeat(65)).is_some());
+        assert!(limits.check_segment_count(2).is_none());
+        assert!(limits.check_segment_count(3).is_some());
+
+        let allocator = Allocator::default();
+        let depth = |n: usize| {
+            let source = format!("const x = {}1{};", "(".repeat(n), ")".repeat(n));
+            let program = Parser::new(&allocator, allocator.alloc_str(&source), SourceType::mjs())
+                .parse()
+                .program;
+            limits.check_depth(&program)
+        };
+        assert!(depth(4).is_none());
+        let diagnostic = depth(64).unwrap();
+        assert_eq!(diagnostic.code, DiagnosticCode::LimitExceeded);
+        assert!(diagnostic.label.is_some());
+
+        let nesting = |n: usize| {
+            let source = format!("const x = {}1{};", "(".repeat(n), ")".repeat(n));
+            limits.check_nesting(&source, false)
+        };
+        assert!(nesting(4).is_none());
+        let diagnostic = nesting(64).unwrap();
+        assert_eq!(diagnostic.code, DiagnosticCode::LimitExceeded);
+        assert!(diagnostic.label.is_some());
+    }
+
+    #[test]
+    fn caps_diagnostics() {
+        let mut diagnostics: Vec<Diagnostic> = (0..4)
+            .map(|i| Diagnostic::warning(DiagnosticCode::SymbolCollision, format!("warning {i}")))
+            .chain(
+                (0..3)
+                    .map(|i| Diagnostic::error(DiagnosticCode::SyntaxError, format!("error {i}"))),
+            )
+            .collect();
+        cap_diagnostics(&mut diagnostics, 7);
+        assert_eq!(diagnostics.len(), 7);
+
+        cap_diagnostics(&mut diagnostics, 4);
+        let messages: Vec<&str> = diagnostics.iter().map(|d| d.message.as_str()).collect();
+        assert_eq!(
+            messages[..4],
+            ["warning 0", "error 0", "error 1", "error 2"]
+        );
+        let summary = &diagnostics[4];
+        assert_eq!(summary.code, DiagnosticCode::TooManyDiagnostics);
+        assert!(!summary.is_error());
+        assert!(summary
+            .message
+            .starts_with("3 more diagnostics were omitted (0 errors, 3 warnings)"));
+    }
+
+    #[test]
+    fn lists_top_offenders() {
+        let modules = [("a.tsx", 3), ("b.tsx", 9), ("c.tsx", 1), ("d.tsx", 9)];
+        assert!(check_project_segments(modules, 22).is_none());
+
+        let diagnostic = check_project_segments(modules, 21).unwrap();
+        assert_eq!(diagnostic.code, DiagnosticCode::TooManySegments);
+        assert!(diagnostic
+            .message
+            .ends_with("`b.tsx` (9), `d.tsx` (9), `a.tsx` (3), `c.tsx` (1)"));
+    }
+}
@@ -0,0 +1,86 @@
+use crate::component::{Id, QrlComponent};
+use std::fmt::Debug;
+use std::hash::{Hash, Hasher};
+use std::sync::Arc;
+
+/// A symbol extracted by the optimizer, which a [SymbolListener] is notified of.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ExtractedSymbol<'s> {
+    /// The id of the segment the symbol is exported from.
+    pub id: &'s Id,
+    /// The code of the segment.
+    pub code: &'s str,
+    /// The variables the symbol captures from its enclosing scope.
+    pub captures: &'s [String],
+    /// The relative path of the module the symbol was extracted from, e.g. `src/routes/index.tsx`.
+    pub origin: &'s str,
+}
+
+impl<'s> From<&'s QrlComponent> for ExtractedSymbol<'s> {
+    fn from(segment: &'s QrlComponent) -> Self {
+        ExtractedSymbol {
+            id: &segment.id,
+            code: &segment.code,
+            captures: &segment.qrl.captures,
+            origin: &segment.origin,
+        }
+    }
+}
+
+/// Is notified of every symbol extracted by the optimizer, once the module it was extracted from is transformed, so
+/// that integrations such as build-time analytics, symbol graphs or preloading services can register symbols as they
+/// are produced rather than re-parsing the [Manifest](crate::manifest::Manifest).
+///
+/// Modules are transformed in parallel, so the listener may be called from many threads at once. Transforms answered
+/// by a [TransformCache](crate::cache::TransformCache) do not notify the listener again.
+///
+/// # Examples
+/// ```
+/// use qwik_optimizer::listener::SymbolListener;
+/// use qwik_optimizer::transform::TransformOptions;
+/// use std::sync::{Arc, Mutex};
+///
+/// let symbols = Arc::new(Mutex::new(Vec::new()));
+/// let registry = symbols.clone();
+/// let options = TransformOptions {
+///     symbol_listener: Some(SymbolListener::new(move |symbol| {
+///         registry.lock().unwrap().push(symbol.id.symbol_name.clone());
+///     })),
+///     ..TransformOptions::default()
+/// };
+/// ```
+#[derive(Clone)]
+pub struct SymbolListener(Arc<ListenFn>);
+
+type ListenFn = dyn Fn(&ExtractedSymbol) + Send + Sync;
+
+impl SymbolListener {
+    /// Creates a listener calling `listen` with every extracted symbol.
+    pub fn new<F>(listen: F) -> Self
+    where
+        F: Fn(&ExtractedSymbol) + Send + Sync + 'static,
+    {
+        Self(Arc::new(listen))
+    }
+
+    /// Notifies the listener of every one of `segments`, in order.
+    pub(crate) fn notify(&self, segments: &[QrlComponent]) {
+        for segment in segments {
+            (self.0)(&ExtractedSymbol::from(segment));
+        }
+    }
+}
+
+impl Debug for SymbolListener {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("SymbolListener(..)")
+    }
+}
+
+/// Listeners can not be compared, so a listener is only equal to its clones. As a result, transforms cached with
+/// one listener are never reused with another.
+impl Hash for SymbolListener {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        (Arc::as_ptr(&self.0) as *const () as usize).hash(state);
+    }
+}
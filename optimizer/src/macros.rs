@@ -1,4 +1,3 @@
-
 #[macro_export]
 macro_rules! function_name {
     () => {{
@@ -18,20 +17,21 @@ macro_rules! _assert_valid_transform {
     ($input:literal) => {{
         let func_name = function_name!();
         let mut path = PathBuf::from("./src/test_input").join(format!("{func_name}.tsx"));
-        let mut lang = crate::component::Language::Typescript;
+        let mut lang = $crate::component::Language::Typescript;
 
         if !path.exists() {
             path = PathBuf::from("./src/test_input").join(format!("{func_name}.js"));
-            lang = crate::component::Language::Javascript;
+            lang = $crate::component::Language::Javascript;
         }
 
         println!("Loading test input file from path: {:?}", &path);
 
         let source_code = std::fs::read_to_string(&path).unwrap();
 
-        let source_input =
-            Source::from_source(source_code, lang, Some("test".to_string())).unwrap();
-        let result = transform(source_input).unwrap().optimized_app;
+        let source_input = test_source!(source_code, lang);
+        let result = transform(source_input, TransformOptions::default())
+            .unwrap()
+            .optimized_app;
 
         if $input == true {
             println!("{}", result);
@@ -68,21 +68,68 @@ macro_rules! assert_processing_errors {
     ($verifier:expr) => {{
         let func_name = function_name!();
         let mut path = PathBuf::from("./src/test_input").join(format!("{func_name}.tsx"));
-        let mut lang = crate::component::Language::Typescript;
+        let mut lang = $crate::component::Language::Typescript;
 
         if !path.exists() {
             path = PathBuf::from("./src/test_input").join(format!("{func_name}.js"));
-            lang = crate::component::Language::Javascript;
+            lang = $crate::component::Language::Javascript;
         }
 
         println!("Loading test input file from path: {:?}", &path);
 
         let source_code = std::fs::read_to_string(&path).unwrap();
 
-        let source_input =
-            Source::from_source(source_code, lang, Some("test".to_string())).unwrap();
-        let errors: Vec<ProcessingFailure> = transform(source_input).unwrap().errors;
+        let source_input = test_source!(source_code, lang);
+        let errors: Vec<ProcessingFailure> = transform(source_input, TransformOptions::default())
+            .unwrap()
+            .errors;
 
         ($verifier)(errors)
     }};
 }
+
+/// Creates the [Source](crate::source::Source) of the module `test` from `code`, a TypeScript module unless `lang`
+/// says otherwise.
+#[cfg(test)]
+#[macro_export]
+macro_rules! test_source {
+    ($code:expr) => {{
+        test_source!($code, $crate::component::Language::Typescript)
+    }};
+    ($code:expr, $lang:expr) => {{
+        $crate::source::Source::from_source($code, $lang, Some("test".to_string())).unwrap()
+    }};
+}
+
+/// Creates the [SegmentMetadata](crate::manifest::SegmentMetadata) of the `$` segment `symbol_name` extracted
+/// from `app.tsx`, with the given fields replaced, e.g. `segment_metadata!("s_a", root: Some("App".into()))`.
+#[cfg(test)]
+#[macro_export]
+macro_rules! segment_metadata {
+    ($symbol_name:expr $(, $field:ident: $value:expr)* $(,)?) => {{
+        let symbol_name: &str = $symbol_name;
+        $crate::manifest::SegmentMetadata {
+            $($field: $value,)*
+            ..$crate::manifest::SegmentMetadata {
+                origin: "app.tsx".to_string(),
+                symbol_name: symbol_name.to_string(),
+                display_name: symbol_name.to_string(),
+                hash: symbol_name.to_string(),
+                ctx_kind: $crate::manifest::SegmentKind::Function,
+                ctx_name: "$".to_string(),
+                root: None,
+                parent: None,
+                exported: None,
+                route_export: None,
+                entry: None,
+                captures: false,
+                inlined_captures: Default::default(),
+                route: None,
+                excerpt: None,
+                listener: None,
+                async_only: false,
+                metrics: Default::default(),
+            }
+        }
+    }};
+}
@@ -0,0 +1,526 @@
+use crate::component::{QrlComponent, QrlType, MARKER_SUFFIX};
+use crate::events::EventListener;
+#[cfg(feature = "manifest")]
+use crate::hash::StableHasher;
+use crate::metrics::SegmentMetrics;
+#[cfg(all(feature = "serde", feature = "manifest"))]
+use crate::prelude::*;
+use crate::routes::{RouteExportKind, RouteFile};
+#[cfg(feature = "manifest")]
+use base64::{engine, Engine};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+#[cfg(feature = "manifest")]
+use std::hash::{BuildHasher, Hasher, RandomState};
+
+/// Classifies the context a segment was extracted from.
+///
+/// [V 1.0 REF] see `SegmentKind` in `transform.rs`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "camelCase"))]
+pub enum SegmentKind {
+    Function,
+    EventHandler,
+    /// The task of `useTask$`, which runs on the server and in the browser.
+    Task,
+    /// The task of `useVisibleTask$`, which only runs in the browser.
+    VisibleTask,
+    /// The computation of `useComputed$`, a pure function of the signals it reads.
+    Computed,
+    /// The closure of `worker$`, which runs in a web worker. Its segments are grouped into the
+    /// [WORKER_ENTRY](crate::entry_strategy::WORKER_ENTRY), so that bundlers can build them into a worker chunk.
+    Worker,
+}
+
+/// Where the segments of a [SegmentKind] run.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "camelCase"))]
+pub enum SegmentEnvironment {
+    /// Only in the browser, e.g. event handlers. Server builds never need their code.
+    Browser,
+    /// On the server while rendering, and in the browser once resumed.
+    Both,
+    /// In a web worker of the browser, which has no DOM.
+    Worker,
+}
+
+impl SegmentKind {
+    /// Event handlers are markers named `on{Event}$` (e.g. `onClick$`) or `on-{event}$`. The core hooks
+    /// `useTask$`, `useVisibleTask$` and `useComputed$`, and `worker$`, have kinds of their own.
+    pub fn from_ctx_name(ctx_name: &str) -> SegmentKind {
+        match ctx_name {
+            "useTask$" => return SegmentKind::Task,
+            "useVisibleTask$" => return SegmentKind::VisibleTask,
+            "useComputed$" => return SegmentKind::Computed,
+            "worker$" => return SegmentKind::Worker,
+            _ => {}
+        }
+        let is_event_handler = ctx_name
+            .strip_prefix("on")
+            .and_then(|rest| rest.chars().next())
+            .map(|c| c.is_ascii_uppercase() || c == '-')
+            .unwrap_or(false);
+
+        if is_event_handler {
+            SegmentKind::EventHandler
+        } else {
+            SegmentKind::Function
+        }
+    }
+
+    pub fn environment(&self) -> SegmentEnvironment {
+        match self {
+            SegmentKind::EventHandler | SegmentKind::VisibleTask => SegmentEnvironment::Browser,
+            SegmentKind::Function | SegmentKind::Task | SegmentKind::Computed => {
+                SegmentEnvironment::Both
+            }
+            SegmentKind::Worker => SegmentEnvironment::Worker,
+        }
+    }
+
+    /// Whether the segments of the kind have no side effects, so that bundlers may evaluate them eagerly or drop
+    /// them when their result is unused.
+    pub fn is_pure(&self) -> bool {
+        *self == SegmentKind::Computed
+    }
+}
+
+impl From<&QrlType> for String {
+    /// The name of the marker which produced a segment, e.g. `component$` or `$`.
+    fn from(value: &QrlType) -> Self {
+        match value {
+            QrlType::PrefixedQrl(prefix) => format!("{prefix}{MARKER_SUFFIX}"),
+            QrlType::Qrl | QrlType::IndexedQrl(_) => MARKER_SUFFIX.to_string(),
+        }
+    }
+}
+
+/// Describes an extracted segment without its code.
+///
+/// This is the information bundlers and entry strategies need in order to decide how segments are grouped into
+/// entries (chunks).
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "camelCase"))]
+pub struct SegmentMetadata {
+    /// The relative path of the file the segment was extracted from.
+    pub origin: String,
+    pub symbol_name: String,
+    pub display_name: String,
+    pub hash: String,
+    pub ctx_kind: SegmentKind,
+    pub ctx_name: String,
+    /// The outermost named segment enclosing this one, e.g. `App` for `App_component_div_onClick`.
+    pub root: Option<String>,
+    /// The symbol name of the segment this one is nested in, e.g. `App_component_ckEPmXZlub0` for the listener of
+    /// a button of the `App` component, so that the preloader can prefetch the parents of a segment.
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub parent: Option<String>,
+    /// The name the host module exports the declaration the segment was extracted from as, see
+    /// [QrlComponent::exported].
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub exported: Option<String>,
+    /// The kind of qwik-city loader or action the segment is the server function of, see
+    /// [QrlComponent::route_export].
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub route_export: Option<RouteExportKind>,
+    /// The entry the segment was assigned to by the [EntryStrategy](crate::entry_strategy::EntryStrategy).
+    /// `None` means the segment is emitted as its own chunk.
+    pub entry: Option<String>,
+    /// Whether the segment captures variables from its enclosing scope.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub captures: bool,
+    /// The values of the `const` variables inlined into the segment rather than captured, by name, see
+    /// [TransformOptions::inline_const_captures](crate::transform::TransformOptions::inline_const_captures).
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "BTreeMap::is_empty")
+    )]
+    pub inlined_captures: BTreeMap<String, String>,
+    /// The qwik-city route owning the origin file, e.g. `/blog/`. `None` if the origin is not inside `routes/`.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub route: Option<String>,
+    /// The beginning of the source of the segment, see [QrlComponent::excerpt].
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub excerpt: Option<String>,
+    /// The listener the segment handles, see [QrlComponent::listener].
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub listener: Option<EventListener>,
+    /// Whether the segment awaits at the top level of its module, see [QrlComponent::async_only].
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub async_only: bool,
+    /// The estimated size, captures and imports of the segment.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub metrics: SegmentMetrics,
+}
+
+impl From<&QrlComponent> for SegmentMetadata {
+    fn from(comp: &QrlComponent) -> Self {
+        SegmentMetadata {
+            origin: comp.origin.clone(),
+            symbol_name: comp.id.symbol_name.to_string(),
+            display_name: comp.id.display_name.to_string(),
+            hash: comp.id.hash.to_string(),
+            ctx_kind: comp.ctx_kind,
+            ctx_name: comp.ctx_name.clone(),
+            root: comp.root.clone(),
+            parent: comp.parent.clone(),
+            exported: comp.exported.clone(),
+            route_export: comp.route_export,
+            entry: comp.entry.clone(),
+            captures: !comp.qrl.captures.is_empty(),
+            inlined_captures: comp.qrl.inlined_captures.clone(),
+            route: RouteFile::from_path(&comp.origin).map(|file| file.route),
+            excerpt: comp.excerpt.clone(),
+            listener: comp.listener.clone(),
+            async_only: comp.async_only,
+            metrics: comp.metrics.clone(),
+        }
+    }
+}
+
+/// The metadata of every segment produced by a build, sorted by symbol name.
+///
+/// A manifest can be persisted as JSON and later reloaded, e.g. to evaluate a different
+/// [EntryStrategy](crate::entry_strategy::EntryStrategy) with
+/// [simulate_strategy](crate::entry_strategy::simulate_strategy) without re-transforming any code.
+#[cfg(feature = "manifest")]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "camelCase"))]
+pub struct Manifest {
+    pub segments: Vec<SegmentMetadata>,
+    /// The [build id](crate::transform::TransformOptions::build_id) of the build which produced the segments.
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub build_id: Option<String>,
+}
+
+#[cfg(feature = "manifest")]
+impl Manifest {
+    pub fn new(mut segments: Vec<SegmentMetadata>) -> Self {
+        segments.sort_by(|a, b| a.symbol_name.cmp(&b.symbol_name));
+        Manifest {
+            segments,
+            build_id: None,
+        }
+    }
+
+    pub fn with_build_id(mut self, build_id: Option<String>) -> Self {
+        self.build_id = build_id;
+        self
+    }
+
+    #[cfg(feature = "serde")]
+    pub fn from_json<T: AsRef<str>>(json: T) -> Result<Self> {
+        Ok(serde_json::from_str(json.as_ref())?)
+    }
+
+    #[cfg(feature = "serde")]
+    pub fn to_json(&self) -> Result<String> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+
+    pub fn segment(&self, symbol_name: &str) -> Option<&SegmentMetadata> {
+        self.segments
+            .iter()
+            .find(|segment| segment.symbol_name == symbol_name)
+    }
+
+    /// The name every segment extracted from an exported declaration is exported as by its host module, by symbol
+    /// name, e.g. `App_component_ckEPmXZlub0` to `App`.
+    pub fn exports(&self) -> BTreeMap<&str, &str> {
+        self.segments
+            .iter()
+            .filter_map(|segment| {
+                let exported = segment.exported.as_deref()?;
+                Some((segment.symbol_name.as_str(), exported))
+            })
+            .collect()
+    }
+
+    /// The qwik-city loaders and actions of the build, by the relative path of their module and the name it exports
+    /// them as, e.g. `src/routes/index.tsx` and `useData`, so that the router can wire them without scanning sources.
+    pub fn route_exports(&self) -> BTreeMap<(&str, &str), RouteExportKind> {
+        self.segments
+            .iter()
+            .filter_map(|segment| {
+                let kind = segment.route_export?;
+                Some((
+                    (segment.origin.as_str(), segment.exported.as_deref()?),
+                    kind,
+                ))
+            })
+            .collect()
+    }
+
+    /// Groups the segments by their owning route, e.g. to prefetch or budget the bundles of a route. Segments which
+    /// do not belong to a route are omitted.
+    pub fn routes(&self) -> BTreeMap<&str, Vec<&SegmentMetadata>> {
+        let mut routes: BTreeMap<&str, Vec<&SegmentMetadata>> = BTreeMap::new();
+        for segment in &self.segments {
+            if let Some(route) = &segment.route {
+                routes.entry(route).or_default().push(segment);
+            }
+        }
+        routes
+    }
+}
+
+#[cfg(feature = "manifest")]
+impl Manifest {
+    /// Returns a copy in which every name derived from the source (display names, roots and entries) is replaced
+    /// with an opaque id, along with the [NameMapping] needed to translate the ids back.
+    ///
+    /// Ids are derived from the replaced names, so segments sharing a root or an entry still do after obfuscation
+    /// and the copy can be used with [simulate_strategy](crate::entry_strategy::simulate_strategy). Symbol names
+    /// are kept, which only hides component names under `Target::Prod` or `Target::Lib`, where symbol names are
+    /// `s_{hash}`.
+    ///
+    /// The ids are salted with a random value, so that they can not be guessed from likely names, and differ between
+    /// builds. Use [Manifest::obfuscated_with_salt] for reproducible ids.
+    pub fn obfuscated(&self) -> (Manifest, NameMapping) {
+        let salt = RandomState::new().build_hasher().finish();
+        self.obfuscated_with_salt(&format!("{salt:016x}"))
+    }
+
+    /// Like [Manifest::obfuscated], with ids salted with `salt`, which should be kept as private as the mapping.
+    pub fn obfuscated_with_salt(&self, salt: &str) -> (Manifest, NameMapping) {
+        let mut mapping = NameMapping::default();
+
+        let segments = self
+            .segments
+            .iter()
+            .map(|segment| SegmentMetadata {
+                display_name: mapping.insert(salt, "s", &segment.display_name),
+                root: segment
+                    .root
+                    .as_ref()
+                    .map(|root| mapping.insert(salt, "r", root)),
+                entry: segment
+                    .entry
+                    .as_ref()
+                    .map(|entry| mapping.insert(salt, "e", entry)),
+                excerpt: None,
+                inlined_captures: BTreeMap::new(),
+                ..segment.clone()
+            })
+            .collect();
+
+        (
+            Manifest::new(segments).with_build_id(self.build_id.clone()),
+            mapping,
+        )
+    }
+}
+
+/// Translates the opaque ids of an [obfuscated](Manifest::obfuscated) manifest back to the original names.
+///
+/// The mapping is meant to be kept private, e.g. to symbolicate production error reports, and never shipped.
+#[cfg(feature = "manifest")]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "camelCase"))]
+pub struct NameMapping {
+    /// Opaque id to original name.
+    pub names: BTreeMap<String, String>,
+}
+
+#[cfg(feature = "manifest")]
+impl NameMapping {
+    #[cfg(feature = "serde")]
+    pub fn from_json<T: AsRef<str>>(json: T) -> Result<Self> {
+        Ok(serde_json::from_str(json.as_ref())?)
+    }
+
+    #[cfg(feature = "serde")]
+    pub fn to_json(&self) -> Result<String> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+
+    /// The original name of `id`.
+    pub fn resolve(&self, id: &str) -> Option<&str> {
+        self.names.get(id).map(String::as_str)
+    }
+
+    fn insert(&mut self, salt: &str, prefix: &str, name: &str) -> String {
+        let mut hasher = StableHasher::new();
+        hasher.write(salt.as_bytes());
+        hasher.write_u8(0);
+        hasher.write(prefix.as_bytes());
+        hasher.write(name.as_bytes());
+        let hash = engine::general_purpose::URL_SAFE_NO_PAD
+            .encode(hasher.finish().to_le_bytes())
+            .replace(['-', '_'], "0");
+
+        let id = format!("{prefix}_{hash}");
+        self.names.insert(id.clone(), name.to_string());
+        id
+    }
+}
+
+#[cfg(feature = "manifest")]
+impl<'a> FromIterator<&'a QrlComponent> for Manifest {
+    fn from_iter<I: IntoIterator<Item = &'a QrlComponent>>(iter: I) -> Self {
+        Manifest::new(iter.into_iter().map(SegmentMetadata::from).collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn classifies_event_handlers() {
+        assert_eq!(
+            SegmentKind::from_ctx_name("onClick$"),
+            SegmentKind::EventHandler
+        );
+        assert_eq!(
+            SegmentKind::from_ctx_name("on-custom$"),
+            SegmentKind::EventHandler
+        );
+        assert_eq!(
+            SegmentKind::from_ctx_name("component$"),
+            SegmentKind::Function
+        );
+        assert_eq!(SegmentKind::from_ctx_name("once$"), SegmentKind::Function);
+        assert_eq!(SegmentKind::from_ctx_name("$"), SegmentKind::Function);
+    }
+
+    #[test]
+    fn classifies_hooks() {
+        let kind = SegmentKind::from_ctx_name;
+        assert_eq!(kind("useTask$"), SegmentKind::Task);
+        assert_eq!(kind("useVisibleTask$"), SegmentKind::VisibleTask);
+        assert_eq!(kind("useComputed$"), SegmentKind::Computed);
+        assert_eq!(kind("useTaskQrl"), SegmentKind::Function);
+
+        assert_eq!(
+            kind("useVisibleTask$").environment(),
+            SegmentEnvironment::Browser
+        );
+        assert_eq!(kind("onClick$").environment(), SegmentEnvironment::Browser);
+        assert_eq!(kind("useTask$").environment(), SegmentEnvironment::Both);
+        assert_eq!(kind("useComputed$").environment(), SegmentEnvironment::Both);
+        assert_eq!(kind("worker$"), SegmentKind::Worker);
+        assert_eq!(kind("worker$").environment(), SegmentEnvironment::Worker);
+        assert!(kind("useComputed$").is_pure());
+        assert!(!kind("useTask$").is_pure());
+    }
+
+    #[test]
+    #[cfg(feature = "manifest")]
+    fn manifest_round_trips_through_json() {
+        let segment = |symbol_name: &str| {
+            segment_metadata!(
+                symbol_name,
+                display_name: format!("app.tsx_{symbol_name}"),
+                ctx_name: "component$".to_string(),
+                root: Some("App".to_string()),
+            )
+        };
+        let manifest = Manifest::new(vec![segment("s_b"), segment("s_a")]);
+
+        assert_eq!(manifest.segments[0].symbol_name, "s_a");
+        let json = manifest.to_json().unwrap();
+        assert!(json.contains(r#""ctxKind": "function""#));
+        assert_eq!(Manifest::from_json(json).unwrap(), manifest);
+        assert!(Manifest::from_json("{").is_err());
+    }
+
+    #[test]
+    #[cfg(feature = "manifest")]
+    fn groups_segments_by_route() {
+        let segment = |symbol_name: &str, route: Option<&str>| {
+            segment_metadata!(
+                symbol_name,
+                ctx_name: "component$".to_string(),
+                route: route.map(String::from),
+            )
+        };
+        let manifest = Manifest::new(vec![
+            segment("s_a", Some("/blog/")),
+            segment("s_b", None),
+            segment("s_c", Some("/")),
+            segment("s_d", Some("/blog/")),
+        ]);
+
+        let routes = manifest.routes();
+
+        assert_eq!(
+            routes.keys().copied().collect::<Vec<_>>(),
+            vec!["/", "/blog/"]
+        );
+        let blog: Vec<&str> = routes["/blog/"]
+            .iter()
+            .map(|s| s.symbol_name.as_str())
+            .collect();
+        assert_eq!(blog, vec!["s_a", "s_d"]);
+    }
+
+    #[test]
+    #[cfg(feature = "manifest")]
+    fn obfuscates_names_consistently() {
+        let segment = |symbol_name: &str, display_name: &str| {
+            segment_metadata!(
+                symbol_name,
+                display_name: display_name.to_string(),
+                ctx_name: "component$".to_string(),
+                root: Some("SecretCheckout".to_string()),
+                entry: Some("app.tsx_entry_SecretCheckout".to_string()),
+            )
+        };
+        let manifest = Manifest::new(vec![
+            segment("s_a", "SecretCheckout_component"),
+            segment("s_b", "SecretCheckout_component_div_onClick"),
+        ]);
+
+        let (obfuscated, mapping) = manifest.obfuscated();
+
+        assert!(!obfuscated.to_json().unwrap().contains("Secret"));
+        let [a, b] = &obfuscated.segments[..] else {
+            panic!("expected two segments");
+        };
+        assert_eq!(a.symbol_name, "s_a");
+        assert_eq!(a.root, b.root);
+        assert_eq!(a.entry, b.entry);
+        assert_eq!(
+            mapping.resolve(&b.display_name),
+            Some("SecretCheckout_component_div_onClick")
+        );
+        assert_eq!(
+            mapping.resolve(a.root.as_deref().unwrap()),
+            Some("SecretCheckout")
+        );
+        assert_eq!(
+            NameMapping::from_json(mapping.to_json().unwrap()).unwrap(),
+            mapping
+        );
+
+        let salted = |salt: &str| manifest.obfuscated_with_salt(salt).0.segments[0].clone();
+        assert_eq!(salted("a").display_name, salted("a").display_name);
+        assert_ne!(salted("a").display_name, salted("b").display_name);
+        assert_ne!(a.display_name, salted("a").display_name);
+    }
+}
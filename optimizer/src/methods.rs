@@ -0,0 +1,158 @@
+use crate::component::MARKER_SUFFIX;
+use oxc_allocator::Box as OxcBox;
+use oxc_ast::ast::*;
+use oxc_ast::visit::walk_mut;
+use oxc_ast::{AstBuilder, VisitMut};
+use oxc_span::{GetSpan, SPAN};
+use std::borrow::Cow;
+
+/// Returns the name of `key` if it is a marker, e.g. `onClick$`, but not `$` itself.
+pub(crate) fn marker_key<'k>(key: &'k PropertyKey, computed: bool) -> Option<Cow<'k, str>> {
+    key.static_name().filter(|name| {
+        !computed && name.len() > MARKER_SUFFIX.len() && name.ends_with(MARKER_SUFFIX)
+    })
+}
+
+/// Wraps the value of every `$`-suffixed object method and class member of `program` in a `$()` call, so that they
+/// are extracted like any other segment.
+///
+/// Methods become function expressions, which keep their own `this`, `arguments` and generator or async kind. Class
+/// members whose value is already a marker call, or not a function, are left as they are.
+///
+/// # Examples
+/// ```javascript
+/// const handlers = { onClick$(event) { ... } };
+/// class Counter { onIncrement$() { ... } onReset$ = () => { ... }; }
+/// ```
+/// becomes
+/// ```javascript
+/// const handlers = { onClick$: $(function (event) { ... }) };
+/// class Counter { onIncrement$ = $(function () { ... }); onReset$ = $(() => { ... }); }
+/// ```
+pub(crate) fn wrap_method_markers<'a>(program: &mut Program<'a>, ast: AstBuilder<'a>) {
+    MethodMarkers { ast }.visit_program(program);
+}
+
+struct MethodMarkers<'a> {
+    ast: AstBuilder<'a>,
+}
+
+impl<'a> MethodMarkers<'a> {
+    /// `$(value)`
+    fn wrap(&self, value: Expression<'a>) -> Expression<'a> {
+        let span = value.span();
+        let callee = self.ast.expression_identifier(SPAN, MARKER_SUFFIX);
+        let argument = Argument::from(value);
+        self.ast.expression_call(
+            span,
+            callee,
+            None::<OxcBox<TSTypeParameterInstantiation>>,
+            self.ast.vec1(argument),
+            false,
+        )
+    }
+
+    fn is_function(value: &Expression) -> bool {
+        matches!(
+            value.without_parentheses(),
+            Expression::FunctionExpression(_) | Expression::ArrowFunctionExpression(_)
+        )
+    }
+}
+
+impl<'a> VisitMut<'a> for MethodMarkers<'a> {
+    fn visit_object_property(&mut self, prop: &mut ObjectProperty<'a>) {
+        walk_mut::walk_object_property(self, prop);
+        if prop.method
+            && prop.kind == PropertyKind::Init
+            && marker_key(&prop.key, prop.computed).is_some()
+        {
+            let value = self.ast.move_expression(&mut prop.value);
+            prop.value = self.wrap(value);
+            prop.method = false;
+        }
+    }
+
+    fn visit_class_body(&mut self, body: &mut ClassBody<'a>) {
+        walk_mut::walk_class_body(self, body);
+        for element in body.body.iter_mut() {
+            match element {
+                ClassElement::MethodDefinition(method)
+                    if method.kind == MethodDefinitionKind::Method
+                        && method.value.body.is_some()
+                        && marker_key(&method.key, method.computed).is_some() =>
+                {
+                    let span = method.span;
+                    let function = self.ast.move_function(&mut method.value);
+                    let value = self.wrap(Expression::FunctionExpression(OxcBox::new_in(
+                        function,
+                        self.ast.allocator,
+                    )));
+                    let key = self.ast.move_property_key(&mut method.key);
+                    *element = self.ast.class_element_property_definition(
+                        span,
+                        PropertyDefinitionType::PropertyDefinition,
+                        self.ast.vec(),
+                        key,
+                        Some(value),
+                        false,
+                        method.r#static,
+                        false,
+                        method.r#override,
+                        false,
+                        false,
+                        false,
+                        None::<OxcBox<TSTypeAnnotation>>,
+                        method.accessibility,
+                    );
+                }
+                ClassElement::PropertyDefinition(prop)
+                    if marker_key(&prop.key, prop.computed).is_some()
+                        && prop.value.as_ref().is_some_and(Self::is_function) =>
+                {
+                    if let Some(value) = prop.value.take() {
+                        prop.value = Some(self.wrap(value));
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use oxc_allocator::Allocator;
+    use oxc_codegen::Codegen;
+    use oxc_parser::Parser;
+    use oxc_span::SourceType;
+
+    #[test]
+    fn wraps_method_markers() {
+        let allocator = Allocator::default();
+        let source = r#"
+            const handlers = { onClick$(event) { return event; }, onHover() {}, [key$]() {} };
+            class Counter {
+                onIncrement$() { this.count++; }
+                onReset$ = () => {};
+                onDone$ = $(() => {});
+                count$ = 0;
+            }
+        "#;
+        let mut program = Parser::new(&allocator, source, SourceType::mjs())
+            .parse()
+            .program;
+
+        wrap_method_markers(&mut program, AstBuilder::new(&allocator));
+        let code = Codegen::new().build(&program).code;
+
+        assert!(code.contains("onClick$: $(function(event) {"));
+        assert!(code.contains("onHover() {}"));
+        assert!(code.contains("[key$]() {}"));
+        assert!(code.contains("onIncrement$ = $(function() {"));
+        assert!(code.contains("onReset$ = $(() => {});"));
+        assert!(code.contains("onDone$ = $(() => {});"));
+        assert!(code.contains("count$ = 0;"));
+    }
+}
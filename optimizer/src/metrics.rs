@@ -0,0 +1,83 @@
+use oxc_ast::ast::{Program, Statement};
+use oxc_codegen::{Codegen, CodegenOptions};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+
+/// The size and dependencies of a segment, recorded in the [Manifest](crate::manifest::Manifest) so that entry
+/// strategies and bundle analyzers can weigh segments against each other without loading their code.
+#[derive(Debug, Clone, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "camelCase"))]
+pub struct SegmentMetrics {
+    /// An estimate of the size of the segment once minified, in bytes: the size of its code printed without
+    /// whitespace or comments, before compression and mangling.
+    pub size: usize,
+    /// The number of variables the segment captures from its enclosing scope.
+    pub captures: usize,
+    /// The modules the segment imports from, sorted, e.g. `@qwik.dev/core` or `./utils`.
+    pub imports: Vec<String>,
+}
+
+impl SegmentMetrics {
+    /// Measures the module `program` generated for a segment capturing `captures` variables.
+    pub(crate) fn measure(program: &Program, captures: usize) -> SegmentMetrics {
+        let minified = Codegen::new()
+            .with_options(CodegenOptions {
+                minify: true,
+                comments: false,
+                annotation_comments: false,
+                ..CodegenOptions::default()
+            })
+            .build(program);
+        let imports: BTreeSet<String> = program
+            .body
+            .iter()
+            .filter_map(|statement| match statement {
+                Statement::ImportDeclaration(import) => Some(import.source.value.to_string()),
+                _ => None,
+            })
+            .collect();
+        SegmentMetrics {
+            size: minified.code.len(),
+            captures,
+            imports: imports.into_iter().collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use oxc_allocator::Allocator;
+    use oxc_parser::Parser;
+    use oxc_span::SourceType;
+
+    #[test]
+    fn measures_segments() {
+        let allocator = Allocator::default();
+        let code = r#"
+            import { useLexicalScope } from "@qwik.dev/core";
+            import { format } from "./utils";
+            import { sum } from "./utils";
+            // Formats the count.
+            export const s_abc = () => {
+                const [count] = useLexicalScope();
+                return format(sum(count, 1));
+            };
+        "#;
+        let program = Parser::new(&allocator, code, SourceType::mjs())
+            .parse()
+            .program;
+
+        let metrics = SegmentMetrics::measure(&program, 1);
+        assert_eq!(metrics.captures, 1);
+        assert_eq!(metrics.imports, ["./utils", "@qwik.dev/core"]);
+        let minified = concat!(
+            r#"import{useLexicalScope}from"@qwik.dev/core";import{format}from"./utils";"#,
+            r#"import{sum}from"./utils";export const s_abc=()=>{const[count]=useLexicalScope();"#,
+            "return format(sum(count,1))};"
+        );
+        assert_eq!(metrics.size, minified.len());
+    }
+}
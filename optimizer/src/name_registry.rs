@@ -0,0 +1,169 @@
+#[cfg(feature = "serde")]
+use crate::prelude::*;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::sync::Mutex;
+#[cfg(feature = "serde")]
+use std::{fs, path::Path};
+
+/// The file name of the registry, usually stored in [CACHE_DIR](crate::cache::CACHE_DIR).
+pub const NAME_REGISTRY_FILE: &str = "names.json";
+
+/// Remembers which index was given to every duplicate segment name, so that segment names stay stable while a file
+/// is edited and across dev-server restarts.
+///
+/// Without a registry, duplicate names within a scope are numbered in source order, e.g. the second `$` in `App` is
+/// named `App_1`. Inserting a new `$` before it renames it to `App_2`, which changes its symbol name and makes HMR
+/// remount the component. With a registry, every occurrence is identified by a fingerprint of the first line of its
+/// source text and keeps the index it was first given. New occurrences get the next unused index.
+///
+/// The registry can be shared between threads and is persisted as a small JSON sidecar file with [NameRegistry::load]
+/// and [NameRegistry::save].
+#[derive(Debug, Default)]
+pub struct NameRegistry {
+    files: Mutex<BTreeMap<String, FileNames>>,
+}
+
+/// The fingerprints of the occurrences of every qualified segment name of a file, indexed by their number.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(transparent))]
+pub(crate) struct FileNames {
+    slots: BTreeMap<String, Vec<u64>>,
+}
+
+impl NameRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Loads the registry persisted at `path`. A missing file yields an empty registry.
+    #[cfg(feature = "serde")]
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let files = match fs::read_to_string(path) {
+            Ok(json) => serde_json::from_str(&json)?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => BTreeMap::new(),
+            Err(e) => return Err(e.into()),
+        };
+        Ok(Self {
+            files: Mutex::new(files),
+        })
+    }
+
+    /// Persists the registry to `path`.
+    ///
+    /// The registry is written to a temporary file which then replaces `path`, so that a dev server starting up
+    /// concurrently reads either the previous or the new registry, never a partially written one.
+    #[cfg(feature = "serde")]
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let path = path.as_ref();
+        let json = serde_json::to_string(&*self.files())?;
+        if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+            fs::create_dir_all(dir)?;
+        }
+        let tmp = path.with_extension(format!("{}.tmp", std::process::id()));
+        fs::write(&tmp, json)?;
+        fs::rename(&tmp, path)?;
+        Ok(())
+    }
+
+    /// Forgets the names of `file`, e.g. after it was deleted.
+    pub fn remove(&self, file: &str) {
+        self.files().remove(file);
+    }
+
+    pub(crate) fn file_names(&self, file: &str) -> FileNames {
+        self.files().get(file).cloned().unwrap_or_default()
+    }
+
+    pub(crate) fn update(&self, file: &str, names: FileNames) {
+        self.files().insert(file.to_string(), names);
+    }
+
+    fn files(&self) -> std::sync::MutexGuard<'_, BTreeMap<String, FileNames>> {
+        self.files
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+/// Assigns indexes to the occurrences of qualified names during a single transform of a file.
+#[derive(Debug, Default)]
+pub(crate) struct NameClaims {
+    names: FileNames,
+    taken: HashMap<String, HashSet<usize>>,
+}
+
+impl NameClaims {
+    pub fn new(names: FileNames) -> Self {
+        Self {
+            names,
+            taken: HashMap::new(),
+        }
+    }
+
+    /// Returns the index of the occurrence of `fq_name` with the given `fingerprint`: the index it was given before
+    /// if it is known, otherwise the next unused one.
+    pub fn claim(&mut self, fq_name: &str, fingerprint: u64) -> usize {
+        let slots = self.names.slots.entry(fq_name.to_string()).or_default();
+        let taken = self.taken.entry(fq_name.to_string()).or_default();
+
+        let index = slots
+            .iter()
+            .enumerate()
+            .position(|(index, known)| *known == fingerprint && !taken.contains(&index))
+            .unwrap_or_else(|| {
+                slots.push(fingerprint);
+                slots.len() - 1
+            });
+        taken.insert(index);
+        index
+    }
+
+    pub fn into_names(self) -> FileNames {
+        self.names
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keeps_indexes_of_known_occurrences() {
+        let registry = NameRegistry::new();
+
+        let mut claims = NameClaims::new(registry.file_names("app.tsx"));
+        assert_eq!(claims.claim("App", 10), 0);
+        assert_eq!(claims.claim("App", 20), 1);
+        registry.update("app.tsx", claims.into_names());
+
+        // A new occurrence is inserted before the known ones, and a duplicate of the first one is added.
+        let mut claims = NameClaims::new(registry.file_names("app.tsx"));
+        assert_eq!(claims.claim("App", 30), 2);
+        assert_eq!(claims.claim("App", 10), 0);
+        assert_eq!(claims.claim("App", 20), 1);
+        assert_eq!(claims.claim("App", 10), 3);
+        assert_eq!(claims.claim("Other", 10), 0);
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn persists_to_disk() {
+        let path = std::env::temp_dir()
+            .join(format!("qwik-optimizer-names-{}", std::process::id()))
+            .join(NAME_REGISTRY_FILE);
+        let registry = NameRegistry::new();
+        let mut claims = NameClaims::new(FileNames::default());
+        claims.claim("App", 10);
+        registry.update("app.tsx", claims.into_names());
+
+        registry.save(&path).unwrap();
+        let loaded = NameRegistry::load(&path).unwrap();
+
+        assert_eq!(loaded.file_names("app.tsx"), registry.file_names("app.tsx"));
+        assert!(NameRegistry::load(path.with_file_name("missing.json")).is_ok());
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+}
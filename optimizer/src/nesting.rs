@@ -0,0 +1,684 @@
+//! Measures how deeply the parser recurses into a source without parsing it, so that
+//! [hardened](crate::transform::TransformOptions::hardened) transforms reject sources nested too deeply before the
+//! parser overflows its stack on them.
+//!
+//! The source is scanned once, without recursion, counting every level the parser recurses into: brackets,
+//! template literals and JSX elements, as well as operators nested in each other without brackets, e.g.
+//! `a = b = c`, `!!x`, `a ? b : c ? d : e` or `else if` chains. Where the grammar is ambiguous, e.g. whether a `/`
+//! starts a regular expression, the scan keeps counting rather than skipping text, so that it errs on the side of
+//! deeper nesting.
+
+/// A level of nesting opened in the source.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Level {
+    /// The top level of the module, never closed.
+    Module,
+    /// `(`, which is the head of a statement such as `if (…)` when `head` is set.
+    Paren {
+        head: bool,
+    },
+    Bracket,
+    /// `{`, which is closed as an operand when `operand` is set, e.g. an object literal or the body of a function
+    /// expression, or as a statement otherwise.
+    Brace {
+        operand: bool,
+    },
+    /// `<`, which may open type arguments: closed by `>`, or by the end of the statement or of the enclosing bracket.
+    Angle,
+    Template,
+    /// The opening tag of a JSX element, up to its `>`.
+    JsxTag,
+    /// The children of a JSX element, up to its closing tag.
+    JsxChildren,
+}
+
+struct Frame {
+    level: Level,
+    /// The operators nested in each other without brackets at this level, e.g. `a = b = c` or `!!x`.
+    chain: usize,
+    /// The `else if` branches at this level, which are nested in each other across statements.
+    else_ifs: usize,
+}
+
+impl Frame {
+    fn new(level: Level) -> Self {
+        Self {
+            level,
+            chain: 0,
+            else_ifs: 0,
+        }
+    }
+
+    /// The number of levels of the frame.
+    fn depth(&self) -> usize {
+        1 + self.chain + self.else_ifs
+    }
+}
+
+/// The previous token, which tells a regular expression from a division, a JSX element from a comparison and a
+/// block from an object literal.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Token {
+    /// An operator, keyword or opening bracket, followed by an operand.
+    Operator,
+    /// The arrow of an arrow function, followed by an operand or a block.
+    Arrow,
+    /// The start of a statement, e.g. after `;`, `else` or `if (…)`.
+    Statement,
+    /// An operand, e.g. an identifier, a literal or a closing bracket.
+    Operand,
+    /// The `)` of a call or of parameters, followed by an operator or a block.
+    Paren,
+    /// The `}` closing a block, followed by a statement.
+    Block,
+}
+
+/// The keywords nesting the operand, statement or type following them, e.g. `!typeof typeof x` or `if (a) if (b)`.
+const NESTING_KEYWORDS: &[&str] = &[
+    "typeof",
+    "void",
+    "delete",
+    "await",
+    "new",
+    "yield",
+    "extends",
+    "keyof",
+    "readonly",
+    "infer",
+    "unique",
+    "as",
+    "satisfies",
+    "for",
+    "while",
+    "with",
+    "do",
+    "else",
+];
+
+/// The keywords followed by an operand, e.g. `return /a/`.
+const OPERAND_KEYWORDS: &[&str] = &[
+    "return",
+    "throw",
+    "case",
+    "in",
+    "of",
+    "instanceof",
+    "default",
+];
+
+/// The punctuators of JavaScript and TypeScript, the longest first for each prefix.
+const PUNCTUATORS: &[&str] = &[
+    ">>>=", "...", "===", "!==", "**=", "<<=", ">>=", ">>>", "&&=", "||=", "??=", "=>", "==", "!=",
+    "<=", ">=", "**", "?.", "??", "&&", "||", "++", "--", "<<", ">>", "+=", "-=", "*=", "/=", "%=",
+    "&=", "|=", "^=", "=", "!", "~", "?", ":", "<", ">", "+", "-", "*", "/", "%", "&", "|", "^",
+    ".", "@", "#",
+];
+
+/// The deepest nesting of a source, and the offset at which it is first reached.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Nesting {
+    pub depth: usize,
+    pub offset: usize,
+}
+
+impl Nesting {
+    /// Scans `source`, whose `<` opens JSX elements where an operand is expected when `jsx` is set.
+    pub(crate) fn of(source: &str, jsx: bool) -> Self {
+        let mut scanner = Scanner {
+            source: source.as_bytes(),
+            jsx,
+            pos: 0,
+            frames: vec![Frame::new(Level::Module)],
+            depth: 0,
+            deepest: Nesting {
+                depth: 0,
+                offset: 0,
+            },
+            prev: Token::Statement,
+            member: false,
+            after_else: false,
+            head: false,
+            body: None,
+        };
+        while scanner.pos < scanner.source.len() {
+            match scanner.top().level {
+                Level::Template => scanner.template(),
+                Level::JsxTag => scanner.jsx_tag(),
+                Level::JsxChildren => scanner.jsx_children(),
+                _ => scanner.code(),
+            }
+        }
+        scanner.deepest
+    }
+}
+
+struct Scanner<'s> {
+    source: &'s [u8],
+    jsx: bool,
+    pos: usize,
+    frames: Vec<Frame>,
+    /// The sum of the depths of the frames but the module.
+    depth: usize,
+    deepest: Nesting,
+    prev: Token,
+    /// Whether the previous token is `.`, so that the next word is a property name rather than a keyword.
+    member: bool,
+    /// Whether the previous token is `else`.
+    after_else: bool,
+    /// Whether the next `(` is the head of a statement, e.g. after `if`.
+    head: bool,
+    /// The number of frames at which the keyword `function` or `class` was seen, and whether it starts an
+    /// expression, until its body is opened.
+    body: Option<(usize, bool)>,
+}
+
+impl Scanner<'_> {
+    fn top(&mut self) -> &mut Frame {
+        self.frames
+            .last_mut()
+            .expect("the module frame is never closed")
+    }
+
+    fn peek(&self, offset: usize) -> u8 {
+        self.source.get(self.pos + offset).copied().unwrap_or(0)
+    }
+
+    fn record(&mut self) {
+        if self.depth > self.deepest.depth {
+            self.deepest = Nesting {
+                depth: self.depth,
+                offset: self.pos,
+            };
+        }
+    }
+
+    fn push(&mut self, level: Level) {
+        self.frames.push(Frame::new(level));
+        self.depth += 1;
+        self.record();
+    }
+
+    fn pop(&mut self) {
+        if self.frames.len() > 1 {
+            let frame = self.frames.pop().expect("more than one frame");
+            self.depth -= frame.depth();
+        }
+    }
+
+    fn nest(&mut self) {
+        self.top().chain += 1;
+        self.depth += 1;
+        self.record();
+    }
+
+    /// Ends the operators nested at the current level, e.g. at `,`.
+    fn end_chain(&mut self) {
+        let chain = std::mem::take(&mut self.top().chain);
+        self.depth -= chain;
+    }
+
+    /// Ends the statement at the current level, e.g. at `;`, closing the type arguments left open in it.
+    fn end_statement(&mut self) {
+        while self.top().level == Level::Angle {
+            self.pop();
+        }
+        self.end_chain();
+        self.body = None;
+    }
+
+    /// Closes the innermost frame if it is a bracket matching `matches`, after the type arguments left open in it,
+    /// and returns it.
+    fn close(&mut self, matches: fn(Level) -> bool) -> Option<Level> {
+        while self.top().level == Level::Angle {
+            self.pop();
+        }
+        let level = self.top().level;
+        matches(level).then(|| {
+            self.pop();
+            level
+        })
+    }
+
+    fn skip_while(&mut self, f: impl Fn(u8) -> bool) {
+        while self.pos < self.source.len() && f(self.source[self.pos]) {
+            self.pos += 1;
+        }
+    }
+
+    /// Skips the string or JSX attribute starting at the current quote, up to the closing quote, or up to the end of
+    /// the line if `line` is set.
+    fn skip_string(&mut self, escapes: bool, line: bool) {
+        let quote = self.source[self.pos];
+        self.pos += 1;
+        while self.pos < self.source.len() {
+            match self.source[self.pos] {
+                b'\\' if escapes => self.pos += 2,
+                b'\n' if line => return,
+                c => {
+                    self.pos += 1;
+                    if c == quote {
+                        return;
+                    }
+                }
+            }
+        }
+    }
+
+    /// Skips the regular expression starting at the current `/`, if it is closed on the same line.
+    fn skip_regex(&mut self) -> bool {
+        let mut end = self.pos + 1;
+        let mut class = false;
+        while let Some(&c) = self.source.get(end) {
+            match c {
+                b'\\' => end += 1,
+                b'\n' | b'\r' => return false,
+                b'[' => class = true,
+                b']' => class = false,
+                b'/' if !class => {
+                    self.pos = end + 1;
+                    self.skip_while(is_word);
+                    return true;
+                }
+                _ => {}
+            }
+            end += 1;
+        }
+        false
+    }
+
+    /// Whether the statement ends at the line break at the current position, i.e. whether the next line starts with
+    /// a word, a literal or a string after an operand.
+    fn ends_statement(&self) -> bool {
+        let rest = &self.source[self.pos..];
+        let next = rest.iter().find(|c| !c.is_ascii_whitespace());
+        matches!(self.prev, Token::Operand | Token::Block)
+            && next.is_some_and(|&c| is_word(c) || matches!(c, b'\'' | b'"' | b'`' | b'@'))
+    }
+
+    /// Whether the `<` at the current position opens a JSX element rather than type parameters, e.g. `<T,>`.
+    fn opens_jsx(&self) -> bool {
+        let start = self.pos + 1;
+        let mut end = start;
+        while self.source.get(end).is_some_and(|&c| is_word(c)) {
+            end += 1;
+        }
+        let name = &self.source[start..end];
+        let rest = &self.source[end..];
+        let after = rest.iter().position(|c| !c.is_ascii_whitespace());
+        let after = &rest[after.unwrap_or(rest.len())..];
+        match (name.first(), self.peek(1)) {
+            (_, b'>') => true,
+            (Some(c), _) if !c.is_ascii_digit() => {
+                !after.starts_with(b",") && !after.starts_with(b"extends")
+            }
+            _ => false,
+        }
+    }
+
+    fn code(&mut self) {
+        let c = self.source[self.pos];
+        let after_else = std::mem::take(&mut self.after_else);
+        match c {
+            b'\n' => {
+                if self.ends_statement() {
+                    self.end_statement();
+                }
+                self.after_else = after_else;
+                self.pos += 1;
+            }
+            c if c.is_ascii_whitespace() => {
+                self.after_else = after_else;
+                self.pos += 1;
+            }
+            b'/' if self.peek(1) == b'/' => {
+                self.after_else = after_else;
+                self.skip_while(|c| c != b'\n');
+            }
+            b'/' if self.peek(1) == b'*' => {
+                self.after_else = after_else;
+                self.pos += 2;
+                while self.pos < self.source.len() && !self.source[self.pos..].starts_with(b"*/") {
+                    self.pos += 1;
+                }
+                self.pos += 2;
+            }
+            b'/' if matches!(self.prev, Token::Operator | Token::Arrow | Token::Statement)
+                && self.skip_regex() =>
+            {
+                self.prev = Token::Operand;
+            }
+            b'\'' | b'"' => {
+                self.skip_string(true, true);
+                self.prev = Token::Operand;
+            }
+            b'`' => {
+                self.pos += 1;
+                self.push(Level::Template);
+            }
+            b'0'..=b'9' => {
+                self.skip_while(|c| is_word(c) || c == b'.');
+                self.prev = Token::Operand;
+            }
+            b'.' if self.peek(1).is_ascii_digit() => {
+                self.skip_while(|c| is_word(c) || c == b'.');
+                self.prev = Token::Operand;
+            }
+            c if is_word(c) => self.word(after_else),
+            b'(' => {
+                let head = std::mem::take(&mut self.head);
+                self.pos += 1;
+                self.push(Level::Paren { head });
+                self.prev = Token::Operator;
+            }
+            b'[' => {
+                self.pos += 1;
+                self.push(Level::Bracket);
+                self.prev = Token::Operator;
+            }
+            b'{' => self.open_brace(),
+            b')' => {
+                self.pos += 1;
+                self.prev = match self.close(|l| matches!(l, Level::Paren { .. })) {
+                    Some(Level::Paren { head: true }) => Token::Statement,
+                    _ => Token::Paren,
+                };
+            }
+            b']' => {
+                self.pos += 1;
+                self.close(|l| l == Level::Bracket);
+                self.prev = Token::Operand;
+            }
+            b'}' => {
+                self.pos += 1;
+                self.prev = match self.close(|l| matches!(l, Level::Brace { .. })) {
+                    Some(Level::Brace { operand: true }) => Token::Operand,
+                    _ => Token::Block,
+                };
+            }
+            b';' => {
+                self.pos += 1;
+                self.end_statement();
+                self.prev = Token::Statement;
+            }
+            b',' => {
+                self.pos += 1;
+                self.end_chain();
+                self.prev = Token::Operator;
+            }
+            _ => self.punctuator(),
+        }
+    }
+
+    fn word(&mut self, after_else: bool) {
+        let start = self.pos;
+        self.skip_while(is_word);
+        if std::mem::take(&mut self.member) {
+            self.prev = Token::Operand;
+            return;
+        }
+        let word = std::str::from_utf8(&self.source[start..self.pos]).unwrap_or_default();
+        match word {
+            "if" => {
+                match after_else {
+                    true => {
+                        self.top().else_ifs += 1;
+                        self.depth += 1;
+                        self.record();
+                    }
+                    false => {
+                        let else_ifs = std::mem::take(&mut self.top().else_ifs);
+                        self.depth -= else_ifs;
+                    }
+                }
+                self.nest();
+                self.head = true;
+                self.prev = Token::Statement;
+            }
+            "function" | "class" => {
+                let expression = matches!(self.prev, Token::Operator | Token::Arrow);
+                self.body = Some((self.frames.len(), expression));
+                if word == "class" {
+                    self.nest();
+                }
+                self.prev = Token::Operator;
+            }
+            "else" | "do" => {
+                self.after_else = word == "else";
+                self.nest();
+                self.prev = Token::Statement;
+            }
+            "try" | "finally" => self.prev = Token::Statement,
+            word if NESTING_KEYWORDS.contains(&word) => {
+                self.nest();
+                self.head |= matches!(word, "for" | "while" | "with");
+                self.prev = match word {
+                    "for" | "while" | "with" => Token::Statement,
+                    _ => Token::Operator,
+                };
+            }
+            word if OPERAND_KEYWORDS.contains(&word) => self.prev = Token::Operator,
+            _ => self.prev = Token::Operand,
+        }
+    }
+
+    fn open_brace(&mut self) {
+        self.pos += 1;
+        let body = match self.body {
+            Some((frames, expression)) if frames == self.frames.len() => {
+                self.body = None;
+                Some(expression)
+            }
+            _ => None,
+        };
+        let block = body.is_some()
+            || matches!(
+                self.prev,
+                Token::Arrow | Token::Statement | Token::Paren | Token::Block | Token::Operand
+            );
+        self.push(Level::Brace {
+            operand: body.unwrap_or(!block),
+        });
+        self.prev = match block {
+            true => Token::Statement,
+            false => Token::Operator,
+        };
+    }
+
+    fn punctuator(&mut self) {
+        let rest = &self.source[self.pos..];
+        let Some(punctuator) = PUNCTUATORS.iter().find(|p| rest.starts_with(p.as_bytes())) else {
+            // Anything else, e.g. an escape sequence in an identifier.
+            self.pos += 1;
+            self.prev = Token::Operand;
+            return;
+        };
+        let operand = matches!(
+            self.prev,
+            Token::Operator | Token::Arrow | Token::Statement | Token::Block
+        );
+        match *punctuator {
+            "<" if self.jsx && operand && self.opens_jsx() => {
+                self.pos += 1;
+                self.push(Level::JsxTag);
+                return;
+            }
+            "<" => {
+                self.pos += 1;
+                self.push(Level::Angle);
+                self.prev = Token::Operator;
+                return;
+            }
+            ">" | ">>" | ">>>" => {
+                let mut closed = false;
+                for _ in 0..punctuator.len() {
+                    if self.top().level == Level::Angle {
+                        self.pop();
+                        closed = true;
+                    }
+                }
+                self.pos += punctuator.len();
+                self.prev = match closed {
+                    true => Token::Operand,
+                    false => Token::Operator,
+                };
+                return;
+            }
+            "." | "?." | "#" => self.member = true,
+            "=>" => {
+                self.nest();
+                self.pos += 2;
+                self.prev = Token::Arrow;
+                return;
+            }
+            "++" | "--" if !operand => {
+                self.pos += 2;
+                return;
+            }
+            "+" | "-" | "++" | "--" if operand => self.nest(),
+            "!" | "~" | "?" | ":" | "**" | "=" => self.nest(),
+            p if p.ends_with('=') && !matches!(p, "==" | "===" | "!=" | "!==" | "<=" | ">=") => {
+                self.nest()
+            }
+            _ => {}
+        }
+        self.pos += punctuator.len();
+        self.prev = Token::Operator;
+    }
+
+    fn template(&mut self) {
+        match self.source[self.pos] {
+            b'\\' => self.pos += 2,
+            b'`' => {
+                self.pos += 1;
+                self.pop();
+                self.prev = Token::Operand;
+            }
+            b'$' if self.peek(1) == b'{' => {
+                self.pos += 2;
+                self.push(Level::Brace { operand: true });
+                self.prev = Token::Operator;
+            }
+            _ => self.pos += 1,
+        }
+    }
+
+    fn jsx_tag(&mut self) {
+        match self.source[self.pos] {
+            b'"' | b'\'' => self.skip_string(false, false),
+            b'{' => {
+                self.pos += 1;
+                self.push(Level::Brace { operand: true });
+                self.prev = Token::Operator;
+            }
+            b'<' => {
+                self.pos += 1;
+                self.push(Level::JsxTag);
+            }
+            b'/' if self.peek(1) == b'>' => {
+                self.pos += 2;
+                self.pop();
+                self.prev = Token::Operand;
+            }
+            b'>' => {
+                self.pos += 1;
+                self.top().level = Level::JsxChildren;
+            }
+            _ => self.pos += 1,
+        }
+    }
+
+    fn jsx_children(&mut self) {
+        match self.source[self.pos] {
+            b'{' => {
+                self.pos += 1;
+                self.push(Level::Brace { operand: true });
+                self.prev = Token::Operator;
+            }
+            b'<' if self.peek(1) == b'/' => {
+                self.skip_while(|c| c != b'>');
+                self.pos += 1;
+                self.pop();
+                self.prev = Token::Operand;
+            }
+            b'<' => {
+                self.pos += 1;
+                self.push(Level::JsxTag);
+            }
+            _ => self.pos += 1,
+        }
+    }
+}
+
+/// Whether `c` may be part of an identifier, a keyword or a number. Non-ASCII characters are taken as part of
+/// identifiers.
+fn is_word(c: u8) -> bool {
+    c.is_ascii_alphanumeric() || matches!(c, b'_' | b'$') || c >= 0x80
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn depth(source: &str) -> usize {
+        Nesting::of(source, true).depth
+    }
+
+    #[test]
+    fn measures_nesting() {
+        let n = 100;
+        let nested = [
+            format!("x = {}1{};", "(".repeat(n), ")".repeat(n)),
+            format!("x = {}1{};", "[".repeat(n), "]".repeat(n)),
+            format!("x = {}{};", "{a:".repeat(n), "}".repeat(n)),
+            format!("x = {}1;", "!".repeat(n)),
+            format!("x = {}1;", "- ".repeat(n)),
+            format!("x = {}1;", "typeof ".repeat(n)),
+            format!("{}1;", "a = ".repeat(n)),
+            format!("{}1;", "a => ".repeat(n)),
+            format!("{}1;", "a ? b : ".repeat(n)),
+            format!("{}1;", "a ** ".repeat(n)),
+            format!("{}1;", "if (a) ".repeat(n)),
+            format!("if (a) b;{}", "else if (a) b;\n".repeat(n)),
+            format!("x = {}1{};", "`${".repeat(n), "}`".repeat(n)),
+            format!("x = {}{};", "<a>".repeat(n), "</a>".repeat(n)),
+            format!("x = <>{}</>;", "<a b={<c/>}>{".repeat(n)),
+            format!("let x: {}T{};", "Array<".repeat(n), ">".repeat(n)),
+            format!("let x: {}T;", "keyof ".repeat(n)),
+            format!("x = {}{};", "class extends ".repeat(n), "{}".repeat(n)),
+            // A division, which the scan does not mistake for a regular expression skipping the parentheses.
+            format!("x = {{}}/{}1{}/1;", "(".repeat(n), ")".repeat(n)),
+            format!("if (a) /'/; x = {}1{};", "(".repeat(n), ")".repeat(n)),
+            format!("if (a) (b) / {}1{} / 1;", "(".repeat(n), ")".repeat(n)),
+        ];
+        for source in &nested {
+            assert!(depth(source) >= n, "{} in {source}", depth(source));
+        }
+
+        let flat = [
+            "const a = 1;\n".repeat(n),
+            "if (a) { b(); } else { c(); }\n".repeat(n),
+            "x = a < b;\n".repeat(n),
+            "a = b\n".repeat(n),
+            "class A { f(): string { return 'a'; }\n g() {} }\n".repeat(n),
+            format!("x = <p>{}</p>;", "Don't (\n".repeat(n)),
+            format!("x = /{}/;", "\\(".repeat(n)),
+            format!("x = \"{}\";", "(".repeat(n)),
+            format!("// {}\nx = 1;", "-".repeat(n)),
+            format!("/* {} */", "(".repeat(n)),
+            format!("x = `{}`;", "(".repeat(n)),
+            format!("const f = <T,>(a: T) => a;\n{}", "x = [1];\n".repeat(n)),
+            std::fs::read_to_string("src/test_input/test_example_1.tsx").unwrap(),
+        ];
+        for source in &flat {
+            assert!(depth(source) < 16, "{} in {source}", depth(source));
+        }
+    }
+
+    #[test]
+    fn locates_deepest_level() {
+        let source = "f(); g((((1))));";
+        let nesting = Nesting::of(source, false);
+        assert_eq!(nesting.depth, 4);
+        assert_eq!(&source[nesting.offset..], "1))));");
+    }
+}
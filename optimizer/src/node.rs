@@ -0,0 +1,82 @@
+use crate::transform::{self, TransformModulesOptions, TransformOptions, TransformResult};
+use napi::bindgen_prelude::*;
+use napi::{Env, JsUnknown, Task};
+use napi_derive::napi;
+use serde::Deserialize;
+use std::path::PathBuf;
+
+/// The options accepted by `transformFs`: either the `paths` of the files to transform, or a `srcDir` whose files
+/// matching `patterns` are transformed, see [FileSet](crate::glob::FileSet).
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TransformFsArgs {
+    #[serde(default)]
+    pub paths: Vec<PathBuf>,
+    #[serde(default)]
+    pub src_dir: Option<PathBuf>,
+    #[serde(default)]
+    pub patterns: Vec<String>,
+    #[serde(default)]
+    pub options: TransformOptions,
+}
+
+/// The pending work of a `transformFs` or `transformModules` call.
+pub enum Transform {
+    Fs(Box<TransformFsArgs>),
+    Modules(TransformModulesOptions),
+}
+
+/// Runs the transform on the libuv thread pool so that the Node event loop is not blocked.
+impl Task for Transform {
+    type Output = TransformResult;
+    type JsValue = JsUnknown;
+
+    fn compute(&mut self) -> napi::Result<Self::Output> {
+        let result = match self {
+            Transform::Fs(args) => match &args.src_dir {
+                Some(src_dir) => {
+                    transform::transform_dir(src_dir, &args.patterns, args.options.clone())
+                }
+                None => transform::transform_fs(args.paths.clone(), args.options.clone()),
+            },
+            Transform::Modules(config) => transform::transform_modules(config.clone()),
+        };
+        result.map_err(|e| napi::Error::from_reason(e.to_string()))
+    }
+
+    fn resolve(&mut self, env: Env, output: Self::Output) -> napi::Result<Self::JsValue> {
+        env.to_js_value(&output)
+    }
+}
+
+/// Transforms the files listed in `args.paths` or found in `args.srcDir`, resolving to the merged `TransformResult`.
+#[napi(
+    js_name = "transformFs",
+    ts_args_type = "args: { paths?: string[], srcDir?: string, patterns?: string[], options?: TransformOptions }",
+    ts_return_type = "Promise<TransformResult>"
+)]
+pub fn transform_fs(env: Env, args: JsUnknown) -> napi::Result<AsyncTask<Transform>> {
+    let args: TransformFsArgs = env.from_js_value(args)?;
+    Ok(AsyncTask::new(Transform::Fs(Box::new(args))))
+}
+
+/// Transforms the in-memory modules described by `options`, resolving to the merged `TransformResult`.
+#[napi(
+    js_name = "transformModules",
+    ts_args_type = "options: TransformModulesOptions",
+    ts_return_type = "Promise<TransformResult>"
+)]
+pub fn transform_modules(env: Env, options: JsUnknown) -> napi::Result<AsyncTask<Transform>> {
+    let config: TransformModulesOptions = env.from_js_value(options)?;
+    Ok(AsyncTask::new(Transform::Modules(config)))
+}
+
+/// Prepares the process for transforming modules with `options`, see [warm_up](transform::warm_up).
+#[napi(js_name = "warmUp", ts_args_type = "options?: TransformOptions")]
+pub fn warm_up(env: Env, options: Option<JsUnknown>) -> napi::Result<()> {
+    let options: TransformOptions = match options {
+        Some(options) => env.from_js_value(options)?,
+        None => TransformOptions::default(),
+    };
+    transform::warm_up(&options).map_err(|e| napi::Error::from_reason(e.to_string()))
+}
@@ -0,0 +1,111 @@
+use oxc_allocator::Allocator;
+use std::ops::Deref;
+use std::sync::{Mutex, PoisonError};
+
+/// The arenas of the modules being transformed, reused across modules rather than allocated for every one of them.
+///
+/// An arena grows to fit the largest module it was used for and keeps its memory once it is reset, so transforming
+/// thousands of modules, e.g. with [transform_fs](crate::transform::transform_fs), only ever holds one arena per
+/// thread rather than reallocating one per module. The pool retains at most one arena per thread of the rayon pool,
+/// and none larger than [AllocatorPool::MAX_RETAINED_CAPACITY], which bounds the memory it holds on to between
+/// builds.
+pub(crate) struct AllocatorPool {
+    allocators: Mutex<Vec<Allocator>>,
+}
+
+/// The pool shared by every transform.
+pub(crate) static ALLOCATORS: AllocatorPool = AllocatorPool::new();
+
+impl AllocatorPool {
+    /// Arenas larger than this, in bytes, are dropped rather than returned to the pool, so that a single huge module
+    /// does not hold on to its memory for the rest of the process. Fits the arena of a module of a few hundred
+    /// kilobytes.
+    const MAX_RETAINED_CAPACITY: usize = 16 * 1024 * 1024;
+
+    pub const fn new() -> Self {
+        AllocatorPool {
+            allocators: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// An empty arena, returned to the pool once dropped.
+    pub fn get(&self) -> PooledAllocator<'_> {
+        let allocator = self
+            .allocators
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .pop()
+            .unwrap_or_default();
+        PooledAllocator {
+            allocator: Some(allocator),
+            pool: self,
+        }
+    }
+
+    fn put(&self, mut allocator: Allocator) {
+        if allocator.capacity() > Self::MAX_RETAINED_CAPACITY {
+            return;
+        }
+        allocator.reset();
+        if let Ok(mut allocators) = self.allocators.lock() {
+            if allocators.len() < rayon::current_num_threads() {
+                allocators.push(allocator);
+            }
+        }
+    }
+}
+
+/// An arena borrowed from an [AllocatorPool].
+pub(crate) struct PooledAllocator<'p> {
+    allocator: Option<Allocator>,
+    pool: &'p AllocatorPool,
+}
+
+impl Deref for PooledAllocator<'_> {
+    type Target = Allocator;
+
+    fn deref(&self) -> &Allocator {
+        self.allocator.as_ref().unwrap()
+    }
+}
+
+impl Drop for PooledAllocator<'_> {
+    fn drop(&mut self) {
+        if let Some(allocator) = self.allocator.take() {
+            self.pool.put(allocator);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reuses_allocators() {
+        let pool = AllocatorPool::new();
+        {
+            let allocator = pool.get();
+            allocator.alloc_str("export const App = component$(() => <div />);");
+            assert!(allocator.used_bytes() > 0);
+        }
+        assert_eq!(pool.allocators.lock().unwrap().len(), 1);
+
+        let allocator = pool.get();
+        assert_eq!(allocator.used_bytes(), 0);
+        assert!(pool.allocators.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn retains_an_allocator_per_thread() {
+        let pool = AllocatorPool::new();
+        let allocators: Vec<_> = (0..rayon::current_num_threads() + 2)
+            .map(|_| pool.get())
+            .collect();
+        drop(allocators);
+        assert_eq!(
+            pool.allocators.lock().unwrap().len(),
+            rayon::current_num_threads()
+        );
+    }
+}
@@ -0,0 +1,175 @@
+use crate::error::Error;
+use crate::prelude::*;
+use oxc_ast::ast::*;
+use oxc_ast::AstBuilder;
+use oxc_span::{GetSpan, SPAN};
+use std::fmt::Debug;
+use std::hash::{Hash, Hasher};
+use std::path::Path;
+use std::sync::Arc;
+
+/// The exports of Markdown modules which are passed through untouched, see [Passthrough].
+pub const FRONT_MATTER_EXPORTS: [&str; 2] = ["frontmatter", "head"];
+
+/// The module a [Preprocessor] is asked to preprocess.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PreprocessRequest<'r> {
+    /// The relative path of the module, e.g. `src/routes/docs/index.mdx`.
+    pub path: &'r Path,
+    /// The source of the module.
+    pub source: &'r str,
+}
+
+/// Turns the source of a module into the JavaScript or TypeScript the optimizer transforms, e.g. Markdown into JSX,
+/// so that modules whose extension is not JS or TS, such as the `.md` and `.mdx` routes of Qwik City, can be
+/// transformed without an upstream plugin writing the compiled code to disk first.
+///
+/// # Examples
+/// ```
+/// use qwik_optimizer::preprocess::Preprocessor;
+/// use qwik_optimizer::transform::TransformOptions;
+///
+/// let options = TransformOptions {
+///     preprocessor: Some(Preprocessor::new(|request| {
+///         match request.path.extension().is_some_and(|extension| extension == "mdx") {
+///             true => Ok(Some(format!("export default () => <>{}</>;", request.source))),
+///             false => Ok(None),
+///         }
+///     })),
+///     ..TransformOptions::default()
+/// };
+/// ```
+#[derive(Clone)]
+pub struct Preprocessor(Arc<PreprocessFn>);
+
+type PreprocessFn =
+    dyn Fn(&PreprocessRequest) -> std::result::Result<Option<String>, String> + Send + Sync;
+
+impl Preprocessor {
+    /// Creates a preprocessor from `preprocess`, which returns the compiled source of a module, `None` to transform
+    /// its source as it is, or a message describing why it can not be compiled.
+    pub fn new<F>(preprocess: F) -> Self
+    where
+        F: Fn(&PreprocessRequest) -> std::result::Result<Option<String>, String>
+            + Send
+            + Sync
+            + 'static,
+    {
+        Self(Arc::new(preprocess))
+    }
+
+    /// Preprocesses the module at `path`, failing with [Error::Preprocess] if it can not be compiled.
+    pub(crate) fn preprocess(&self, path: &Path, source: &str) -> Result<Option<String>> {
+        (self.0)(&PreprocessRequest { path, source })
+            .map_err(|message| Error::Preprocess(path.to_string_lossy().to_string(), message))
+    }
+}
+
+impl Debug for Preprocessor {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("Preprocessor(..)")
+    }
+}
+
+/// Preprocessors can not be compared, so a preprocessor is only equal to its clones. As a result, transforms cached
+/// with one preprocessor are never reused with another.
+impl Hash for Preprocessor {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        (Arc::as_ptr(&self.0) as *const () as usize).hash(state);
+    }
+}
+
+/// The [front-matter exports](FRONT_MATTER_EXPORTS) of a Markdown module, which are emitted exactly as they were
+/// written: compiled MDX exports its front-matter and document head as plain data, which no `$` call should be
+/// extracted from, nor any of the rewrites of the optimizer applied to.
+///
+/// The value of each export is replaced with a placeholder string before the module is transformed, so that the
+/// binding still exists for the segments referencing it, and [Passthrough::restore] swaps the original text back
+/// once the module has been generated.
+///
+/// # Examples
+/// ```javascript
+/// export const frontmatter = { "title": "Hello" };
+/// export default () => <button onClick$={() => console.log(frontmatter.title)}>Hello</button>;
+/// ```
+/// emits `export const frontmatter = { "title": "Hello" };` as it was, while the listener is extracted.
+#[derive(Debug, Default)]
+pub(crate) struct Passthrough {
+    /// The placeholders and the original text of the values they replace.
+    regions: Vec<(String, String)>,
+}
+
+impl Passthrough {
+    /// Replaces the values of the front-matter exports of `program`, parsed from `source`, with placeholders.
+    pub fn new<'a>(program: &mut Program<'a>, source: &str, ast: AstBuilder<'a>) -> Passthrough {
+        let mut passthrough = Passthrough::default();
+        for statement in program.body.iter_mut() {
+            let Statement::ExportNamedDeclaration(export) = statement else {
+                continue;
+            };
+            let Some(Declaration::VariableDeclaration(declaration)) = &mut export.declaration
+            else {
+                continue;
+            };
+            for declarator in declaration.declarations.iter_mut() {
+                let BindingPatternKind::BindingIdentifier(id) = &declarator.id.kind else {
+                    continue;
+                };
+                if !FRONT_MATTER_EXPORTS.contains(&id.name.as_str()) {
+                    continue;
+                }
+                let Some(init) = &mut declarator.init else {
+                    continue;
+                };
+                let span = init.span();
+                let placeholder = format!("qwik:passthrough:{}", passthrough.regions.len());
+                *init = ast.expression_string_literal(SPAN, ast.atom(&placeholder), None);
+                passthrough.regions.push((
+                    format!("\"{placeholder}\""),
+                    source[span.start as usize..span.end as usize].to_string(),
+                ));
+            }
+        }
+        passthrough
+    }
+
+    /// Puts the original values of the front-matter exports back into `code` generated from the module.
+    pub fn restore(&self, code: &str) -> String {
+        self.regions
+            .iter()
+            .fold(code.to_string(), |code, (placeholder, text)| {
+                code.replacen(placeholder, text, 1)
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use oxc_allocator::Allocator;
+    use oxc_codegen::Codegen;
+    use oxc_parser::Parser;
+    use oxc_span::SourceType;
+
+    #[test]
+    fn passes_front_matter_through() {
+        let allocator = Allocator::default();
+        let source = "export const frontmatter = { title: `Hello ${1 + 1}` };\n\
+            export const head = ({ resolveValue }) => ({ title: $(() => 1) });\n\
+            export const other = { a: 1 };\n";
+        let mut program = Parser::new(&allocator, source, SourceType::jsx())
+            .parse()
+            .program;
+
+        let passthrough = Passthrough::new(&mut program, source, AstBuilder::new(&allocator));
+        let code = Codegen::new().build(&program).code;
+        assert!(code.contains("export const frontmatter = \"qwik:passthrough:0\";"));
+        assert!(code.contains("export const head = \"qwik:passthrough:1\";"));
+        assert_eq!(
+            passthrough.restore(&code),
+            "export const frontmatter = { title: `Hello ${1 + 1}` };\n\
+            export const head = ({ resolveValue }) => ({ title: $(() => 1) });\n\
+            export const other = { a: 1 };\n"
+        );
+    }
+}
@@ -0,0 +1,75 @@
+use std::fmt::{Debug, Display, Formatter};
+
+/// Maximum number of lines of generated code rendered by the `Display`/`Debug` implementations of results.
+pub(crate) const MAX_CODE_LINES: usize = 12;
+
+/// Wraps a code body so that it renders with at most [MAX_CODE_LINES] lines, followed by a marker noting how
+/// many lines were omitted.
+///
+/// This keeps `dbg!` and log output of transform results readable, since the derived `Debug` output would
+/// otherwise contain every emitted module in full.
+pub(crate) struct Truncated<'a>(pub &'a str);
+
+impl Truncated<'_> {
+    fn omitted(&self) -> usize {
+        self.0.lines().count().saturating_sub(MAX_CODE_LINES)
+    }
+
+    fn head(&self) -> String {
+        self.0
+            .lines()
+            .take(MAX_CODE_LINES)
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+impl Display for Truncated<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.head())?;
+        match self.omitted() {
+            0 => Ok(()),
+            n => write!(f, "\n... ({n} more lines)"),
+        }
+    }
+}
+
+impl Debug for Truncated<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self.omitted() {
+            0 => write!(f, "{:?}", self.0),
+            n => write!(f, "{:?} ... ({n} more lines)", self.head()),
+        }
+    }
+}
+
+/// Writes `text` indented by `prefix`, applying the prefix to every line.
+pub(crate) fn write_indented(f: &mut Formatter<'_>, prefix: &str, text: &str) -> std::fmt::Result {
+    for line in text.lines() {
+        writeln!(f, "{prefix}{line}")?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn short_code_is_not_truncated() {
+        let code = "const a = 1;\nconst b = 2;";
+        assert_eq!(Truncated(code).to_string(), code);
+        assert_eq!(format!("{:?}", Truncated(code)), format!("{:?}", code));
+    }
+
+    #[test]
+    fn long_code_is_truncated() {
+        let code = (0..20)
+            .map(|i| format!("const a{i} = {i};"))
+            .collect::<Vec<_>>()
+            .join("\n");
+        let rendered = Truncated(&code).to_string();
+        assert_eq!(rendered.lines().count(), MAX_CODE_LINES + 1);
+        assert!(rendered.ends_with("... (8 more lines)"));
+    }
+}
@@ -1,8 +1,17 @@
+use crate::capture::Unserializable;
+use crate::error::Error;
 use crate::illegal_code::IllegalCodeType;
+#[cfg(feature = "serde")]
+use serde::{Serialize, Serializer};
+use std::fmt::Display;
 
 #[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Clone)]
 pub enum ProcessingFailure {
     IllegalCode(IllegalCodeType),
+    /// A variable captured by a segment is reassigned.
+    MutableCapture(String),
+    /// A variable captured by a segment holds a value that can not be serialized, e.g. a function.
+    UnserializableCapture(String, Unserializable),
 }
 
 impl From<&IllegalCodeType> for ProcessingFailure {
@@ -10,3 +19,28 @@ impl From<&IllegalCodeType> for ProcessingFailure {
         ProcessingFailure::IllegalCode(value.clone())
     }
 }
+
+impl Display for ProcessingFailure {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ProcessingFailure::IllegalCode(illegal_code_type) => {
+                write!(f, "{}", Error::IllegalCode(illegal_code_type.clone()))
+            }
+            ProcessingFailure::MutableCapture(name) => {
+                write!(f, "{}", Error::MutableCapture(name.clone()))
+            }
+            ProcessingFailure::UnserializableCapture(name, kind) => {
+                write!(f, "{}", Error::UnserializableCapture(name.clone(), *kind))
+            }
+        }
+    }
+}
+
+/// Failures are serialized as their message, since they reference semantic ids which are meaningless outside of
+/// a single transform.
+#[cfg(feature = "serde")]
+impl Serialize for ProcessingFailure {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.to_string())
+    }
+}
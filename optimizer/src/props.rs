@@ -0,0 +1,417 @@
+use crate::component::{Import, QWIK_CORE_SOURCE};
+use crate::styles::COMPONENT;
+use oxc_allocator::{Allocator, Box as OxcBox, CloneIn};
+use oxc_ast::ast::*;
+use oxc_ast::visit::{walk, walk_mut};
+use oxc_ast::{AstBuilder, Visit, VisitMut};
+use oxc_semantic::{SemanticBuilder, SymbolId, SymbolTable};
+use oxc_span::{GetSpan, Span, SPAN};
+use std::collections::{HashMap, HashSet};
+
+/// Copies the props of a component, except for the given keys: `_restProps(props, ["a", "b"])`.
+pub(crate) const REST_PROPS: &str = "_restProps";
+
+/// The name given to the props of a component, unless the component already uses it.
+const PROPS: &str = "props";
+
+/// The name given to the props of a component in compact output, see [EmitStyle](crate::transform::EmitStyle).
+const COMPACT_PROPS: &str = "_p";
+
+/// Rewrites the components of `program` which destructure their props in their parameter list, so that every prop
+/// is read from the props object where it is used. Destructuring reads the props once, when the component renders,
+/// which loses the reactivity of props backed by signals.
+///
+/// Props with a default value are read with `??`. The rest element, if any, is declared at the top of the component
+/// with [REST_PROPS]. Components whose pattern nests other patterns or has computed keys, or which assign to a
+/// destructured prop, are left as they are. With `compact`, the props are named [COMPACT_PROPS] rather than
+/// [PROPS].
+///
+/// # Examples
+/// ```javascript
+/// export const Greeting = component$(({ name, punctuation = "!", ...rest }) => <p {...rest}>{name}{punctuation}</p>);
+/// ```
+/// becomes
+/// ```javascript
+/// export const Greeting = component$((props) => {
+///     const rest = _restProps(props, ["name", "punctuation"]);
+///     return <p {...rest}>{props.name}{props.punctuation ?? "!"}</p>;
+/// });
+/// ```
+pub(crate) fn destructure_props<'a>(
+    program: &mut Program<'a>,
+    allocator: &'a Allocator,
+    compact: bool,
+) {
+    let mut components = DestructuringComponents::default();
+    components.visit_program(program);
+    if components.spans.is_empty() {
+        return;
+    }
+
+    // The references to the destructured props are resolved by a separate semantic analysis, which is only built
+    // for the modules having such components.
+    let semantic = SemanticBuilder::new().build(program).semantic;
+    let source_text = program.source_text;
+    let (symbols, _) = semantic.into_symbol_table_and_scope_tree();
+
+    let mut rewrite = PropsRewrite {
+        ast: AstBuilder::new(allocator),
+        symbols: &symbols,
+        source_text,
+        functions: components.spans.into_iter().collect(),
+        props: HashMap::new(),
+        uses_rest_props: false,
+        props_name: if compact { COMPACT_PROPS } else { PROPS },
+    };
+    rewrite.visit_program(program);
+
+    if rewrite.uses_rest_props && !imports(program, REST_PROPS) {
+        let import = Import::new(vec![REST_PROPS.into()], QWIK_CORE_SOURCE);
+        program.body.insert(0, import.into_statement(allocator));
+    }
+}
+
+/// Whether `program` already imports `name`.
+fn imports(program: &Program, name: &str) -> bool {
+    program.body.iter().any(|statement| match statement {
+        Statement::ImportDeclaration(import) => import
+            .specifiers
+            .iter()
+            .flatten()
+            .any(|specifier| specifier.local().name == name),
+        _ => false,
+    })
+}
+
+/// The function of `component$(fn)`, if it destructures its props.
+fn component_function<'c, 'a>(call: &'c CallExpression<'a>) -> Option<&'c Expression<'a>> {
+    if call.callee_name() != Some(COMPONENT) {
+        return None;
+    }
+    let function = call.arguments.first()?.as_expression()?;
+    let params = match function.without_parentheses() {
+        Expression::ArrowFunctionExpression(arrow) => &arrow.params,
+        Expression::FunctionExpression(function) => &function.params,
+        _ => return None,
+    };
+    match params.items.first() {
+        Some(param) if params.items.len() == 1 && params.rest.is_none() => param
+            .pattern
+            .kind
+            .is_destructuring_pattern()
+            .then_some(function),
+        _ => None,
+    }
+}
+
+/// Finds the spans of the functions passed to `component$` which destructure their props.
+#[derive(Default)]
+struct DestructuringComponents {
+    spans: Vec<Span>,
+}
+
+impl<'a> Visit<'a> for DestructuringComponents {
+    fn visit_call_expression(&mut self, call: &CallExpression<'a>) {
+        if let Some(function) = component_function(call) {
+            self.spans.push(function.span());
+        }
+        walk::walk_call_expression(self, call);
+    }
+}
+
+/// How a destructured prop is read from the props object.
+struct PropAccess<'a> {
+    props: &'a str,
+    key: Atom<'a>,
+    default: Option<Expression<'a>>,
+}
+
+/// A destructured prop of a component.
+struct Prop<'a> {
+    symbol_id: SymbolId,
+    key: Atom<'a>,
+    default: Option<Expression<'a>>,
+}
+
+struct PropsRewrite<'s, 'a> {
+    ast: AstBuilder<'a>,
+    symbols: &'s SymbolTable,
+    source_text: &'a str,
+    functions: HashSet<Span>,
+    props: HashMap<SymbolId, PropAccess<'a>>,
+    uses_rest_props: bool,
+    /// The preferred name of the props, [PROPS] or [COMPACT_PROPS].
+    props_name: &'static str,
+}
+
+impl<'a> PropsRewrite<'_, 'a> {
+    /// A name for the props of the component at `span`, which its source does not use already.
+    fn props_name(&self, span: Span) -> &'a str {
+        let source = span.source_text(self.source_text);
+        let props = self.props_name.trim_start_matches('_');
+        let name = std::iter::once(self.props_name.to_string())
+            .chain((1..).map(|i| format!("_{props}{i}")))
+            .find(|name| !source.contains(name.as_str()))
+            .unwrap_or_default();
+        self.ast.allocator.alloc_str(&name)
+    }
+
+    /// The props destructured by `pattern`, or `None` if the pattern can not be rewritten.
+    fn props_of(&self, pattern: &ObjectPattern<'a>) -> Option<Vec<Prop<'a>>> {
+        let mut props = Vec::with_capacity(pattern.properties.len());
+        for property in &pattern.properties {
+            if property.computed {
+                return None;
+            }
+            let key = self.ast.atom(&property.key.static_name()?);
+            let (binding, default) = match &property.value.kind {
+                BindingPatternKind::BindingIdentifier(binding) => (binding, None),
+                BindingPatternKind::AssignmentPattern(assignment) => match &assignment.left.kind {
+                    BindingPatternKind::BindingIdentifier(binding) => {
+                        (binding, Some(assignment.right.clone_in(self.ast.allocator)))
+                    }
+                    _ => return None,
+                },
+                _ => return None,
+            };
+            let symbol_id = binding.symbol_id.get()?;
+            let is_assigned = self
+                .symbols
+                .get_resolved_references(symbol_id)
+                .any(|reference| reference.is_write());
+            if is_assigned {
+                return None;
+            }
+            props.push(Prop {
+                symbol_id,
+                key,
+                default,
+            });
+        }
+        match &pattern.rest {
+            Some(rest)
+                if !matches!(rest.argument.kind, BindingPatternKind::BindingIdentifier(_)) =>
+            {
+                None
+            }
+            _ => Some(props),
+        }
+    }
+
+    /// Replaces the destructuring parameter of `params` with the props object, and returns the declaration of its
+    /// rest element, if any.
+    fn rewrite_params(
+        &mut self,
+        params: &mut FormalParameters<'a>,
+        span: Span,
+    ) -> Option<Option<Statement<'a>>> {
+        let param = params.items.first_mut()?;
+        let BindingPatternKind::ObjectPattern(pattern) = &param.pattern.kind else {
+            return None;
+        };
+        let props = self.props_of(pattern)?;
+
+        let name = self.props_name(span);
+        let keys: Vec<Atom<'a>> = props.iter().map(|prop| prop.key).collect();
+        for prop in props {
+            self.props.insert(
+                prop.symbol_id,
+                PropAccess {
+                    props: name,
+                    key: prop.key,
+                    default: prop.default,
+                },
+            );
+        }
+
+        // The props object is declared where the pattern was, so that segments nested in the component, which are
+        // told apart from their enclosing scope by span, do not capture it.
+        let identifier = self
+            .ast
+            .binding_pattern_kind_binding_identifier(pattern.span, name);
+        let BindingPatternKind::ObjectPattern(pattern) =
+            std::mem::replace(&mut param.pattern.kind, identifier)
+        else {
+            unreachable!()
+        };
+        let rest = pattern.unbox().rest.map(|rest| {
+            self.uses_rest_props = true;
+            let omitted = self.ast.vec_from_iter(keys.into_iter().map(|key| {
+                ArrayExpressionElement::from(self.ast.expression_string_literal(SPAN, key, None))
+            }));
+            let arguments = self.ast.vec_from_array([
+                Argument::from(self.ast.expression_identifier(SPAN, name)),
+                Argument::from(self.ast.expression_array(SPAN, omitted, None)),
+            ]);
+            let init = self.ast.expression_call(
+                SPAN,
+                self.ast.expression_identifier(SPAN, REST_PROPS),
+                None::<OxcBox<TSTypeParameterInstantiation>>,
+                arguments,
+                false,
+            );
+            let declarator = self.ast.variable_declarator(
+                SPAN,
+                VariableDeclarationKind::Const,
+                rest.unbox().argument,
+                Some(init),
+                false,
+            );
+            Statement::from(self.ast.declaration_variable(
+                SPAN,
+                VariableDeclarationKind::Const,
+                self.ast.vec1(declarator),
+                false,
+            ))
+        });
+        Some(rest)
+    }
+
+    /// `props.key`, `props["data-key"]` or `(props.key ?? default)`.
+    fn access(&self, access: &PropAccess<'a>, span: Span) -> Expression<'a> {
+        let object = self.ast.expression_identifier(SPAN, access.props);
+        let member = if oxc_syntax::identifier::is_identifier_name(&access.key) {
+            let property = self.ast.identifier_name(SPAN, access.key);
+            self.ast
+                .member_expression_static(span, object, property, false)
+        } else {
+            let property = self.ast.expression_string_literal(SPAN, access.key, None);
+            self.ast
+                .member_expression_computed(span, object, property, false)
+        };
+        let member = Expression::from(member);
+        match &access.default {
+            Some(default) => self.ast.expression_parenthesized(
+                span,
+                self.ast.expression_logical(
+                    span,
+                    member,
+                    LogicalOperator::Coalesce,
+                    default.clone_in(self.ast.allocator),
+                ),
+            ),
+            None => member,
+        }
+    }
+
+    fn prop_of(&self, expr: &Expression<'a>) -> Option<&PropAccess<'a>> {
+        let Expression::Identifier(id_ref) = expr else {
+            return None;
+        };
+        let reference_id = id_ref.reference_id.get()?;
+        let symbol_id = self.symbols.get_reference(reference_id).symbol_id()?;
+        self.props.get(&symbol_id)
+    }
+}
+
+/// Prepends `statement` to the body of a function, turning the expression body of an arrow function into a block.
+fn prepend<'a>(
+    ast: AstBuilder<'a>,
+    body: &mut FunctionBody<'a>,
+    expression: &mut bool,
+    statement: Statement<'a>,
+) {
+    if *expression {
+        *expression = false;
+        if let Some(Statement::ExpressionStatement(returned)) = body.statements.pop() {
+            let returned = returned.unbox().expression;
+            body.statements
+                .push(ast.statement_return(returned.span(), Some(returned)));
+        }
+    }
+    body.statements.insert(0, statement);
+}
+
+impl<'a> VisitMut<'a> for PropsRewrite<'_, 'a> {
+    fn visit_arrow_function_expression(&mut self, arrow: &mut ArrowFunctionExpression<'a>) {
+        if self.functions.contains(&arrow.span) {
+            if let Some(Some(rest)) = self.rewrite_params(&mut arrow.params, arrow.span) {
+                prepend(self.ast, &mut arrow.body, &mut arrow.expression, rest);
+            }
+        }
+        walk_mut::walk_arrow_function_expression(self, arrow);
+    }
+
+    fn visit_function(
+        &mut self,
+        function: &mut Function<'a>,
+        flags: oxc_syntax::scope::ScopeFlags,
+    ) {
+        if self.functions.contains(&function.span) {
+            if let Some(Some(rest)) = self.rewrite_params(&mut function.params, function.span) {
+                if let Some(body) = &mut function.body {
+                    prepend(self.ast, body, &mut false, rest);
+                }
+            }
+        }
+        walk_mut::walk_function(self, function, flags);
+    }
+
+    fn visit_expression(&mut self, expr: &mut Expression<'a>) {
+        if let Some(access) = self.prop_of(expr) {
+            *expr = self.access(access, expr.span());
+            return;
+        }
+        walk_mut::walk_expression(self, expr);
+    }
+
+    fn visit_object_property(&mut self, property: &mut ObjectProperty<'a>) {
+        // `{ name }` becomes `{ name: props.name }`.
+        if property.shorthand && self.prop_of(&property.value).is_some() {
+            property.shorthand = false;
+        }
+        walk_mut::walk_object_property(self, property);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use oxc_codegen::Codegen;
+    use oxc_parser::Parser;
+    use oxc_span::SourceType;
+
+    fn rewrite(source: &str) -> String {
+        let allocator = Allocator::default();
+        let mut program = Parser::new(&allocator, source, SourceType::tsx())
+            .parse()
+            .program;
+        destructure_props(&mut program, &allocator, false);
+        Codegen::new().build(&program).code
+    }
+
+    #[test]
+    fn rewrites_destructured_props() {
+        let code = rewrite(
+            r#"
+            import { component$ } from "@qwik.dev/core";
+            export const Greeting = component$(({ name, "data-id": id, punctuation = "!", ...rest }: Props) => {
+                const onClick = () => console.log(name);
+                return <p {...rest} id={id} data={{ name }}>{name}{punctuation}</p>;
+            });
+            "#,
+        );
+        assert!(code.starts_with("import { _restProps } from \"@qwik.dev/core\";"));
+        assert!(code.contains("component$((props: Props) => {"));
+        assert!(code.contains("const rest = _restProps(props, [\n"));
+        assert!(code.contains("console.log(props.name)"));
+        assert!(code.contains("id={props[\"data-id\"]}"));
+        assert!(code.contains("data={{ name: props.name }}"));
+        assert!(code.contains("{props.punctuation ?? \"!\"}"));
+    }
+
+    #[test]
+    fn keeps_unsupported_patterns() {
+        for source in [
+            "component$(({ user: { name } }) => <p>{name}</p>);",
+            "component$(({ count }) => { count++; return <p>{count}</p>; });",
+            "component$(({ [key]: value }) => <p>{value}</p>);",
+        ] {
+            let code = rewrite(source);
+            assert!(!code.contains("props"), "{code}");
+        }
+
+        let code = rewrite("component$(({ props }) => <p>{props}</p>);");
+        assert!(code.contains("component$((_props1) => <p>{_props1.props}</p>)"));
+    }
+}
@@ -0,0 +1,192 @@
+use oxc_allocator::Allocator;
+use oxc_parser::Parser;
+use oxc_span::SourceType;
+
+/// The number of times a source is re-parsed before giving up on recovering it.
+const MAX_ATTEMPTS: usize = 32;
+
+/// A source whose top-level statements failing to parse are blanked out, so that the rest of the module can still be
+/// transformed, see [TransformOptions::lenient](crate::transform::TransformOptions::lenient).
+///
+/// Blanked regions keep their line breaks and, unless none of their lines can hold their placeholder, their length,
+/// so the spans of the recovered source are the spans of the original one. Each region is replaced with a
+/// placeholder string statement, which [Recovered::restore] swaps back for the original text once the module has
+/// been generated.
+pub(crate) struct Recovered {
+    pub source: String,
+    /// The placeholders and the original text of the regions they replace.
+    regions: Vec<(String, String)>,
+    /// The offsets of the recovered source after which it is longer than the original one, and by how much.
+    shifts: Vec<(usize, usize)>,
+}
+
+impl Recovered {
+    /// Blanks the top-level statements of `source` with syntax errors until it parses. `None` if it still does not
+    /// parse once everything that failed is blanked.
+    pub fn new(source: &str, source_type: SourceType) -> Option<Recovered> {
+        let mut recovered = Recovered {
+            source: source.to_string(),
+            regions: Vec::new(),
+            shifts: Vec::new(),
+        };
+        for _ in 0..MAX_ATTEMPTS {
+            let allocator = Allocator::default();
+            let parsed = Parser::new(&allocator, &recovered.source, source_type).parse();
+            if parsed.errors.is_empty() {
+                return Some(recovered);
+            }
+            let mut offsets: Vec<usize> = parsed
+                .errors
+                .iter()
+                .map(|error| {
+                    error
+                        .labels
+                        .as_ref()
+                        .and_then(|labels| labels.first())
+                        .map_or(source.len(), |label| label.offset())
+                })
+                .collect();
+            // From the end, so that lengthening a region does not move the offsets left to blank.
+            offsets.sort_unstable_by(|a, b| b.cmp(a));
+            let mut progressed = false;
+            for offset in offsets {
+                let (start, end) = statement_region(&recovered.source, offset);
+                progressed |= recovered.blank(source, start, end);
+            }
+            if !progressed {
+                return None;
+            }
+        }
+        None
+    }
+
+    /// Blanks `start..end`, returning `false` if there was nothing left to blank.
+    fn blank(&mut self, original: &str, start: usize, end: usize) -> bool {
+        let region = &self.source[start..end];
+        if region.trim().is_empty() {
+            return false;
+        }
+        let mut blanked: String = region
+            .chars()
+            .flat_map(|c| match c {
+                '\n' | '\r' => vec![c],
+                c => vec![' '; c.len_utf8()],
+            })
+            .collect();
+        // The placeholder is written over the first line long enough to hold it, or over the first line, which it
+        // lengthens, if there is none.
+        let placeholder = format!("\"qwik:broken:{}\";", self.regions.len());
+        let mut lines = Vec::new();
+        let mut line_start = 0;
+        for line in blanked.split_inclusive('\n') {
+            lines.push((line_start, line.trim_end_matches(['\r', '\n']).len()));
+            line_start += line.len();
+        }
+        let (line_start, line_len) = lines
+            .iter()
+            .find(|(_, len)| *len >= placeholder.len())
+            .unwrap_or(&lines[0]);
+        let replaced = *line_len.min(&placeholder.len());
+        blanked.replace_range(*line_start..line_start + replaced, &placeholder);
+
+        let text = &original[self.original_offset(start)..self.original_offset(end)];
+        self.regions
+            .push((placeholder.clone(), text.trim_end().to_string()));
+        if replaced < placeholder.len() {
+            let (at, by) = (start + line_start + replaced, placeholder.len() - replaced);
+            for (offset, _) in self.shifts.iter_mut().filter(|(offset, _)| *offset >= at) {
+                *offset += by;
+            }
+            self.shifts.push((at, by));
+        }
+        self.source.replace_range(start..end, &blanked);
+        true
+    }
+
+    /// The offset of the original source corresponding to `offset` of the recovered one.
+    fn original_offset(&self, offset: usize) -> usize {
+        self.shifts
+            .iter()
+            .filter(|(at, _)| *at <= offset)
+            .fold(offset, |offset, (_, by)| offset - by)
+    }
+
+    /// Puts the original text of the blanked regions back into `code` generated from the recovered source.
+    pub fn restore(&self, code: &str) -> String {
+        self.regions
+            .iter()
+            .fold(code.to_string(), |code, (placeholder, text)| {
+                code.replacen(placeholder, text, 1)
+            })
+    }
+}
+
+/// The top-level region of `source` around `offset`: from the closest line starting a top-level statement at or
+/// before `offset` to the next one after it.
+fn statement_region(source: &str, offset: usize) -> (usize, usize) {
+    let offset = offset.min(source.len());
+    let mut lines = Vec::new();
+    let mut line_start = 0;
+    for line in source.split_inclusive('\n') {
+        lines.push((line_start, line));
+        line_start += line.len();
+    }
+    let current = lines
+        .iter()
+        .rposition(|(start, _)| *start <= offset)
+        .unwrap_or(0);
+    let start = lines[..=current.min(lines.len().saturating_sub(1))]
+        .iter()
+        .rev()
+        .find(|(_, line)| starts_statement(line))
+        .map_or(0, |(start, _)| *start);
+    let end = lines[current + 1..]
+        .iter()
+        .find(|(_, line)| starts_statement(line))
+        .map_or(source.len(), |(start, _)| *start);
+    (start, end)
+}
+
+/// Whether `line` starts a top-level statement: it is not indented and does not close a previous one.
+fn starts_statement(line: &str) -> bool {
+    line.chars()
+        .next()
+        .is_some_and(|c| !c.is_whitespace() && !matches!(c, '}' | ')' | ']'))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn blanks_broken_statements() {
+        let source = "import { $ } from \"@qwik.dev/core\";\n\
+            export const broken = (() => {\n  return 1 +;\n});\n\
+            export const ok = $(() => 1);\n";
+        let recovered = Recovered::new(source, SourceType::mjs()).unwrap();
+
+        assert_eq!(recovered.source.len(), source.len());
+        assert!(recovered.source.contains("\"qwik:broken:0\";"));
+        assert!(!recovered.source.contains("return 1 +"));
+        assert!(recovered.source.contains("export const ok = $(() => 1);"));
+        assert_eq!(
+            recovered.restore("a;\n\"qwik:broken:0\";\nb;\n"),
+            "a;\nexport const broken = (() => {\n  return 1 +;\n});\nb;\n"
+        );
+
+        let short = "let a = ;\nexport const ok = 1;\nlet b = ;\n";
+        let recovered = Recovered::new(short, SourceType::mjs()).unwrap();
+        assert_eq!(
+            recovered.source,
+            "\"qwik:broken:0\";\nexport const ok = 1;\n\"qwik:broken:1\";\n"
+        );
+        assert_eq!(
+            recovered.restore(&recovered.source),
+            "let a = ;\nexport const ok = 1;\nlet b = ;\n"
+        );
+
+        let unclosed = "export const App = () => {\n  return 1;\n";
+        let recovered = Recovered::new(unclosed, SourceType::mjs()).unwrap();
+        assert!(recovered.source.trim().starts_with("\"qwik:broken:0\";"));
+    }
+}
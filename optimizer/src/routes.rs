@@ -0,0 +1,381 @@
+use crate::component::Markers;
+use crate::diagnostic::{Diagnostic, DiagnosticCode};
+use oxc_ast::ast::{
+    CallExpression, Declaration, ExportNamedDeclaration, Expression, JSXElement, JSXFragment,
+    Program, Statement,
+};
+use oxc_ast::visit::walk;
+use oxc_ast::Visit;
+use oxc_span::Span;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::path::{Component, Path};
+
+/// The name of the qwik-city directory whose contents define the routes of an application.
+pub const ROUTES_DIR: &str = "routes";
+
+/// The exports qwik-city invokes to handle a request, see
+/// [endpoints](https://qwik.dev/docs/advanced/routing/#endpoints).
+pub const REQUEST_HANDLERS: [&str; 8] = [
+    "onRequest",
+    "onGet",
+    "onPost",
+    "onPut",
+    "onPatch",
+    "onDelete",
+    "onHead",
+    "onOptions",
+];
+
+/// Returns `true` if `program`, the module at `path`, is an endpoint (or middleware) module: a [RouteFile] which
+/// exports at least one of the [REQUEST_HANDLERS] but no default export, contains no JSX and calls neither
+/// [Markers] nor `componentQrl`, i.e. no component is rendered for it and nothing is meant to run on the client.
+///
+/// Endpoint modules only ever run on the server, so extracting segments from them is pointless.
+pub(crate) fn is_endpoint_only(program: &Program, path: &Path, markers: &Markers) -> bool {
+    if RouteFile::from_path(path).is_none() {
+        return false;
+    }
+    let mut has_request_handler = false;
+
+    for statement in &program.body {
+        match statement {
+            Statement::ExportDefaultDeclaration(_) => return false,
+            Statement::ExportNamedDeclaration(export) => {
+                for name in exported_names(export) {
+                    if name == "default" {
+                        return false;
+                    }
+                    has_request_handler |= REQUEST_HANDLERS.contains(&name);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    if !has_request_handler {
+        return false;
+    }
+    let mut client = ClientCode {
+        markers,
+        found: false,
+    };
+    client.visit_program(program);
+    !client.found
+}
+
+/// Finds code meant for the client: JSX, and calls of [Markers] and of `componentQrl`, the marker of already
+/// optimized components.
+struct ClientCode<'m> {
+    markers: &'m Markers,
+    found: bool,
+}
+
+impl<'a> Visit<'a> for ClientCode<'_> {
+    fn visit_jsx_element(&mut self, _: &JSXElement<'a>) {
+        self.found = true;
+    }
+
+    fn visit_jsx_fragment(&mut self, _: &JSXFragment<'a>) {
+        self.found = true;
+    }
+
+    fn visit_call_expression(&mut self, call: &CallExpression<'a>) {
+        let name = match &call.callee.without_parentheses() {
+            Expression::Identifier(id) => Some(id.name.as_str()),
+            Expression::StaticMemberExpression(member) => Some(member.property.name.as_str()),
+            _ => None,
+        };
+        self.found |=
+            name.is_some_and(|name| self.markers.is_marker(name) || name == "componentQrl");
+        walk::walk_call_expression(self, call);
+    }
+}
+
+fn exported_names<'b>(export: &'b ExportNamedDeclaration) -> impl Iterator<Item = &'b str> {
+    let declared: Vec<&str> = match &export.declaration {
+        Some(Declaration::VariableDeclaration(decl)) => decl
+            .declarations
+            .iter()
+            .filter_map(|d| d.id.get_identifier_name())
+            .map(|name| name.as_str())
+            .collect(),
+        Some(Declaration::FunctionDeclaration(func)) => {
+            func.id.iter().map(|id| id.name.as_str()).collect()
+        }
+        _ => Vec::new(),
+    };
+    let specified = export
+        .specifiers
+        .iter()
+        .map(|specifier| specifier.exported.name().as_str());
+
+    declared.into_iter().chain(specified)
+}
+
+/// The loaders and actions of qwik-city, whose segments run on the server and which the router looks up by the
+/// name their module exports them as, see [loaders](https://qwik.dev/docs/route-loader/) and
+/// [actions](https://qwik.dev/docs/action/).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "camelCase"))]
+pub enum RouteExportKind {
+    /// `routeLoader$`, run before the route is rendered.
+    Loader,
+    /// `routeAction$`, run when a form of the route is submitted.
+    Action,
+    /// `globalAction$`, an action which can be submitted from any route.
+    GlobalAction,
+}
+
+impl RouteExportKind {
+    /// The kind of route export created by the marker `name`, e.g. [RouteExportKind::Loader] for `routeLoader$`.
+    pub fn from_marker(name: &str) -> Option<Self> {
+        match name {
+            "routeLoader$" => Some(RouteExportKind::Loader),
+            "routeAction$" => Some(RouteExportKind::Action),
+            "globalAction$" => Some(RouteExportKind::GlobalAction),
+            _ => None,
+        }
+    }
+
+    pub fn marker(self) -> &'static str {
+        match self {
+            RouteExportKind::Loader => "routeLoader$",
+            RouteExportKind::Action => "routeAction$",
+            RouteExportKind::GlobalAction => "globalAction$",
+        }
+    }
+}
+
+/// Checks the name a route export created at `span` is exported as, `None` if it is not the value of a top-level
+/// named export. The router only finds loaders and actions by their export name, so any other one is an error, and
+/// their names are expected to start with `use`, as they are used like hooks.
+pub(crate) fn route_export_diagnostic(
+    kind: RouteExportKind,
+    exported: Option<&str>,
+    span: Span,
+) -> Option<Diagnostic> {
+    let marker = kind.marker();
+    match exported {
+        Some(name) if name != "default" => (!name.starts_with("use")).then(|| {
+            Diagnostic::warning(
+                DiagnosticCode::InvalidRouteExport,
+                format!(
+                    "`{name}` is created with `{marker}` but its name does not start with `use`"
+                ),
+            )
+            .with_label(span, None)
+            .with_suggestion(format!("Rename it like a hook, e.g. `use{name}`"))
+        }),
+        _ => Some(
+            Diagnostic::error(
+                DiagnosticCode::InvalidRouteExport,
+                format!("`{marker}` must be the value of a top-level named export"),
+            )
+            .with_label(span, None)
+            .with_suggestion(format!(
+                "Export it from the module, e.g. `export const useData = {marker}(...)`"
+            )),
+        ),
+    }
+}
+
+/// The role a file plays within qwik-city's `routes/` directory.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "camelCase"))]
+pub enum RouteFileKind {
+    /// `index.tsx`, or a named `index@name.tsx`, which renders the page of its directory.
+    Index,
+    /// `layout.tsx`, or a named `layout-name.tsx`, which wraps the pages of its directory and all subdirectories.
+    Layout,
+    /// Any other module placed in a route directory, e.g. a component only used by that route.
+    Module,
+}
+
+/// A file located inside qwik-city's `routes/` directory.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "camelCase"))]
+pub struct RouteFile {
+    /// The pathname of the route owning the file, e.g. `/blog/[slug]/`.
+    pub route: String,
+    pub kind: RouteFileKind,
+}
+
+impl RouteFile {
+    /// Resolves the route owning `path`, or `None` if the path is not inside a `routes/` directory.
+    ///
+    /// Follows qwik-city's conventions: the pathname is derived from the directories below the innermost `routes/`
+    /// directory, grouped layout directories such as `(auth)` do not contribute to the pathname, and every pathname
+    /// ends with a trailing slash.
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Option<RouteFile> {
+        let components: Vec<&str> = path
+            .as_ref()
+            .components()
+            .filter_map(|c| match c {
+                Component::Normal(name) => name.to_str(),
+                _ => None,
+            })
+            .collect();
+
+        let routes_dir = components.iter().rposition(|c| *c == ROUTES_DIR)?;
+        let (file_name, dirs) = components[routes_dir + 1..].split_last()?;
+
+        let mut route = String::from("/");
+        for dir in dirs.iter().filter(|dir| !Self::is_group(dir)) {
+            route.push_str(dir);
+            route.push('/');
+        }
+
+        Some(RouteFile {
+            route,
+            kind: Self::kind_of(file_name),
+        })
+    }
+
+    fn is_group(dir: &str) -> bool {
+        dir.starts_with('(') && dir.ends_with(')')
+    }
+
+    fn kind_of(file_name: &str) -> RouteFileKind {
+        let stem = file_name.split('.').next().unwrap_or(file_name);
+        let stem = stem.trim_end_matches('!');
+
+        if stem == "index" || stem.starts_with("index@") {
+            RouteFileKind::Index
+        } else if stem == "layout" || stem.starts_with("layout-") {
+            RouteFileKind::Layout
+        } else {
+            RouteFileKind::Module
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::diagnostic::Severity;
+
+    fn route_file(route: &str, kind: RouteFileKind) -> Option<RouteFile> {
+        Some(RouteFile {
+            route: route.to_string(),
+            kind,
+        })
+    }
+
+    #[test]
+    fn resolves_index_and_layout_files() {
+        assert_eq!(
+            RouteFile::from_path("src/routes/index.tsx"),
+            route_file("/", RouteFileKind::Index)
+        );
+        assert_eq!(
+            RouteFile::from_path("/app/src/routes/blog/[slug]/index@blog.tsx"),
+            route_file("/blog/[slug]/", RouteFileKind::Index)
+        );
+        assert_eq!(
+            RouteFile::from_path("./routes/blog/layout.tsx"),
+            route_file("/blog/", RouteFileKind::Layout)
+        );
+        assert_eq!(
+            RouteFile::from_path("routes/layout-narrow!.tsx"),
+            route_file("/", RouteFileKind::Layout)
+        );
+    }
+
+    #[test]
+    fn skips_groups_and_tags_other_modules() {
+        assert_eq!(
+            RouteFile::from_path("src/routes/(auth)/login/header.tsx"),
+            route_file("/login/", RouteFileKind::Module)
+        );
+    }
+
+    fn is_endpoint_at(path: &str, source: &str) -> bool {
+        let allocator = oxc_allocator::Allocator::default();
+        let program = oxc_parser::Parser::new(&allocator, source, oxc_span::SourceType::tsx())
+            .parse()
+            .program;
+        is_endpoint_only(&program, Path::new(path), &Markers::default())
+    }
+
+    fn is_endpoint(source: &str) -> bool {
+        is_endpoint_at("src/routes/api/index.ts", source)
+    }
+
+    #[test]
+    fn detects_endpoint_only_modules() {
+        assert!(is_endpoint("export const onGet = () => {};"));
+        assert!(is_endpoint(
+            "function onPost() {} const onPut = 1; export { onPost, onPut as onPatch };"
+        ));
+        assert!(!is_endpoint(
+            "export const onGet = () => {}; export default component$(() => 1);"
+        ));
+        assert!(!is_endpoint(
+            "const App = 1; export { App as default, App as onGet };"
+        ));
+        assert!(!is_endpoint(
+            "export const useData = routeLoader$(() => 1);"
+        ));
+        assert!(!is_endpoint(
+            "export const onGet = () => {}; export const App = component$(() => <div />);"
+        ));
+        assert!(!is_endpoint(
+            "export const onGet = () => {}; export const App = componentQrl(qrl(() => import(\"./app\"), \"App\"));"
+        ));
+        assert!(!is_endpoint(
+            "export const onPost = () => {}; const load = server$(() => 1);"
+        ));
+        assert!(!is_endpoint(
+            "export const onGet = () => {}; export const Header = () => <header />;"
+        ));
+        assert!(!is_endpoint(
+            "export const onGet = () => {}; export const Items = () => <>{1}</>;"
+        ));
+        assert!(!is_endpoint_at(
+            "src/routes/about/index.tsx",
+            "export const Header = () => 1; export { Header as default };"
+        ));
+        assert!(!is_endpoint_at(
+            "src/components/api.ts",
+            "export const onGet = () => {};"
+        ));
+    }
+
+    #[test]
+    fn validates_route_exports() {
+        let span = Span::new(0, 1);
+        assert_eq!(
+            RouteExportKind::from_marker("routeAction$"),
+            Some(RouteExportKind::Action)
+        );
+        assert_eq!(RouteExportKind::from_marker("server$"), None);
+        assert_eq!(
+            route_export_diagnostic(RouteExportKind::Loader, Some("useData"), span),
+            None
+        );
+
+        let misnamed =
+            route_export_diagnostic(RouteExportKind::Loader, Some("data"), span).unwrap();
+        assert_eq!(misnamed.severity, Severity::Warning);
+
+        for exported in [None, Some("default")] {
+            let invalid =
+                route_export_diagnostic(RouteExportKind::GlobalAction, exported, span).unwrap();
+            assert_eq!(invalid.severity, Severity::Error);
+            assert_eq!(
+                invalid.message,
+                "`globalAction$` must be the value of a top-level named export"
+            );
+        }
+    }
+
+    #[test]
+    fn ignores_files_outside_routes() {
+        assert_eq!(RouteFile::from_path("src/components/button.tsx"), None);
+        assert_eq!(RouteFile::from_path("src/routes"), None);
+    }
+}
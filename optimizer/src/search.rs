@@ -0,0 +1,158 @@
+#[cfg(feature = "manifest")]
+use crate::manifest::Manifest;
+use crate::manifest::SegmentMetadata;
+use crate::transform::TransformResult;
+use std::fmt::Display;
+
+/// Selects segments by one of their names, e.g. to find the segment a production error points at.
+///
+/// Parsed from a string with [From]: `name:onClick`, `origin:src/app.tsx` and `hash:ckEP` select by the given
+/// property, any other string by any of them, e.g. `onClick`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum SymbolQuery {
+    /// Segments whose display name contains the given text, e.g. `onClick` for `App_component_button_onClick`.
+    DisplayName(String),
+    /// Segments extracted from the given file. Matches origins ending with the given path, e.g. `app.tsx` for
+    /// `src/app.tsx`, but not for `src/myapp.tsx`.
+    Origin(String),
+    /// Segments whose hash starts with the given prefix, e.g. `ckEP` for the segment `s_ckEPmXZlub0`.
+    HashPrefix(String),
+    /// Segments matching any of the other queries.
+    Any(String),
+}
+
+impl SymbolQuery {
+    pub fn matches(&self, segment: &SegmentMetadata) -> bool {
+        match self {
+            SymbolQuery::DisplayName(text) => segment.display_name.contains(text.as_str()),
+            SymbolQuery::Origin(path) => {
+                let origin = segment.origin.trim_start_matches("./");
+                let path = path.trim_start_matches("./");
+                origin
+                    .strip_suffix(path)
+                    .is_some_and(|dir| dir.is_empty() || dir.ends_with('/'))
+            }
+            SymbolQuery::HashPrefix(prefix) => segment.hash.starts_with(prefix.as_str()),
+            SymbolQuery::Any(text) => [
+                SymbolQuery::DisplayName(text.clone()),
+                SymbolQuery::Origin(text.clone()),
+                SymbolQuery::HashPrefix(text.clone()),
+            ]
+            .iter()
+            .any(|query| query.matches(segment)),
+        }
+    }
+}
+
+impl From<&str> for SymbolQuery {
+    fn from(query: &str) -> Self {
+        if let Some(name) = query.strip_prefix("name:") {
+            SymbolQuery::DisplayName(name.to_string())
+        } else if let Some(origin) = query.strip_prefix("origin:") {
+            SymbolQuery::Origin(origin.to_string())
+        } else if let Some(hash) = query.strip_prefix("hash:") {
+            SymbolQuery::HashPrefix(hash.to_string())
+        } else {
+            SymbolQuery::Any(query.to_string())
+        }
+    }
+}
+
+impl Display for SymbolQuery {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SymbolQuery::DisplayName(name) => write!(f, "name:{name}"),
+            SymbolQuery::Origin(origin) => write!(f, "origin:{origin}"),
+            SymbolQuery::HashPrefix(hash) => write!(f, "hash:{hash}"),
+            SymbolQuery::Any(text) => write!(f, "{text}"),
+        }
+    }
+}
+
+/// The segments of `segments` matching `query`, in their order.
+pub fn find_symbols<'s>(
+    segments: &'s [SegmentMetadata],
+    query: &SymbolQuery,
+) -> Vec<&'s SegmentMetadata> {
+    segments
+        .iter()
+        .filter(|segment| query.matches(segment))
+        .collect()
+}
+
+impl TransformResult {
+    /// The metadata of the segments matching `query`, sorted by symbol name like the [Manifest].
+    ///
+    /// ```
+    /// use qwik_optimizer::api::TransformOptions;
+    ///
+    /// let code = "export const App = component$(() => <button onClick$={$(() => {})} />);";
+    /// let result = qwik_optimizer::transform("src/app.tsx", code, TransformOptions::default())?;
+    /// assert_eq!(result.find_symbol("origin:app.tsx").len(), 2);
+    /// assert_eq!(result.find_symbol("App_component").len(), 2);
+    /// # Ok::<(), qwik_optimizer::api::Error>(())
+    /// ```
+    pub fn find_symbol<Q: Into<SymbolQuery>>(&self, query: Q) -> Vec<SegmentMetadata> {
+        let query = query.into();
+        let mut segments: Vec<SegmentMetadata> = self
+            .segments
+            .iter()
+            .map(SegmentMetadata::from)
+            .filter(|segment| query.matches(segment))
+            .collect();
+        segments.sort_by(|a, b| a.symbol_name.cmp(&b.symbol_name));
+        segments
+    }
+}
+
+#[cfg(feature = "manifest")]
+impl Manifest {
+    /// The segments of the manifest matching `query`, e.g. to look up the symbols of a production build.
+    pub fn find_symbol<Q: Into<SymbolQuery>>(&self, query: Q) -> Vec<&SegmentMetadata> {
+        find_symbols(&self.segments, &query.into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn segment(origin: &str, display_name: &str, hash: &str) -> SegmentMetadata {
+        segment_metadata!(
+            &format!("{display_name}_{hash}"),
+            origin: origin.to_string(),
+            display_name: display_name.to_string(),
+            hash: hash.to_string(),
+        )
+    }
+
+    #[test]
+    fn finds_symbols() {
+        let segments = [
+            segment("src/app.tsx", "App_component", "ckEPmXZlub0"),
+            segment(
+                "./src/app.tsx",
+                "App_component_button_onClick",
+                "2uQ5aG4N6CE",
+            ),
+            segment("src/myapp.tsx", "MyApp_component", "ckX1b2c3d4e"),
+        ];
+        let names = |query: &str| -> Vec<&str> {
+            find_symbols(&segments, &SymbolQuery::from(query))
+                .into_iter()
+                .map(|segment| segment.display_name.as_str())
+                .collect()
+        };
+
+        assert_eq!(names("onClick"), ["App_component_button_onClick"]);
+        assert_eq!(
+            names("origin:app.tsx"),
+            ["App_component", "App_component_button_onClick"]
+        );
+        assert_eq!(names("origin:./src/myapp.tsx"), ["MyApp_component"]);
+        assert_eq!(names("hash:ck"), ["App_component", "MyApp_component"]);
+        assert_eq!(names("name:ckEP"), Vec::<&str>::new());
+        assert_eq!(names("2uQ5"), ["App_component_button_onClick"]);
+        assert_eq!(SymbolQuery::from("hash:ck").to_string(), "hash:ck");
+    }
+}
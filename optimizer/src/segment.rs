@@ -1,4 +1,5 @@
 use crate::component::*;
+use crate::name_registry::NameClaims;
 use oxc_allocator::{Allocator, Box as OxcBox, FromIn};
 use oxc_ast::ast::{BindingIdentifier, BindingPattern, BindingPatternKind, TSTypeAnnotation};
 use oxc_ast::AstBuilder;
@@ -25,6 +26,11 @@ enum UniqueName {
 
 pub(crate) struct SegmentBuilder {
     names: HashMap<String, usize>,
+    /// When present, QRL segments are numbered by the [NameRegistry](crate::name_registry::NameRegistry) rather
+    /// than in source order.
+    claims: Option<NameClaims>,
+    /// Decides which names are QRL segments, see [Markers].
+    markers: Markers,
 }
 
 fn make_fq_name(segment_names: &Vec<String>) -> String {
@@ -83,12 +89,12 @@ fn make_unique_segment_name(
             },
             Some(count) => match new_segment_name {
                 SegmentName::AnchoredQrl(name) => {
-                    let name = UniqueName::Name(name.clone(), count.clone());
+                    let name = UniqueName::Name(name.clone(), *count);
                     *count += 1;
                     name
                 }
                 SegmentName::UnanchoredQrl => {
-                    let name = UniqueName::Index(count.clone());
+                    let name = UniqueName::Index(*count);
                     *count += 1;
                     name
                 }
@@ -111,12 +117,12 @@ enum SegmentName {
 }
 
 impl SegmentName {
-    fn new(name0: String) -> Self {
-        let name = name0.strip_suffix(MARKER_SUFFIX);
+    fn new(name0: String, markers: &Markers) -> Self {
+        let name = markers.prefix(&name0);
 
         match name {
             None => SegmentName::Name(name0),
-            Some(name) if name.is_empty() => SegmentName::UnanchoredQrl,
+            Some("") => SegmentName::UnanchoredQrl,
             Some(name) => SegmentName::AnchoredQrl(name.to_string()),
         }
     }
@@ -134,16 +140,64 @@ impl SegmentBuilder {
     pub(crate) fn new() -> Self {
         SegmentBuilder {
             names: HashMap::new(),
+            claims: None,
+            markers: Markers::default(),
         }
     }
 
+    pub(crate) fn with_claims(claims: NameClaims) -> Self {
+        SegmentBuilder {
+            claims: Some(claims),
+            ..Self::new()
+        }
+    }
+
+    /// Treats the names of `markers` as QRL segments, in addition to the ones named with the [MARKER_SUFFIX].
+    pub(crate) fn with_markers(mut self, markers: Markers) -> Self {
+        self.markers = markers;
+        self
+    }
+
+    pub(crate) fn has_claims(&self) -> bool {
+        self.claims.is_some()
+    }
+
+    pub(crate) fn into_claims(self) -> Option<NameClaims> {
+        self.claims
+    }
+
+    #[cfg(test)]
     pub fn new_segment<T: AsRef<str>>(&mut self, input: T, segments: &[Segment]) -> Segment {
+        self.new_segment_at(input, segments, 0)
+    }
+
+    /// Like [SegmentBuilder::new_segment], where `fingerprint` identifies the occurrence of a QRL segment for the
+    /// [NameClaims], if any.
+    pub fn new_segment_at<T: AsRef<str>>(
+        &mut self,
+        input: T,
+        segments: &[Segment],
+        fingerprint: u64,
+    ) -> Segment {
         let input = input.as_ref();
-        let segment_name = SegmentName::new(input.to_string());
+        let segment_name = SegmentName::new(input.to_string(), &self.markers);
 
         let segment_names: Vec<String> = segments.iter().map(|s| s.into()).collect();
 
-        let unique_name = make_unique_segment_name(segment_names, &segment_name, &mut self.names);
+        let unique_name = match (&mut self.claims, &segment_name) {
+            (Some(claims), SegmentName::AnchoredQrl(name)) => {
+                let mut names = segment_names;
+                names.push(name.clone());
+                UniqueName::Name(
+                    name.clone(),
+                    claims.claim(&make_fq_name(&names), fingerprint),
+                )
+            }
+            (Some(claims), SegmentName::UnanchoredQrl) => {
+                UniqueName::Index(claims.claim(&make_fq_name(&segment_names), fingerprint))
+            }
+            _ => make_unique_segment_name(segment_names, &segment_name, &mut self.names),
+        };
 
         match unique_name {
             UniqueName::Name(name, index) => {
@@ -175,6 +229,7 @@ impl Segment {
         }
     }
 
+    #[allow(clippy::wrong_self_convention)]
     fn into_binding_identifier<'a>(&self, allocator: &'a Allocator) -> BindingIdentifier<'a> {
         let ast_builder = AstBuilder::new(allocator);
         match self {
@@ -186,6 +241,7 @@ impl Segment {
         }
     }
 
+    #[allow(clippy::wrong_self_convention)]
     fn into_binding_pattern<'a>(&self, allocator: &'a Allocator) -> BindingPattern<'a> {
         let ast_builder = AstBuilder::new(allocator);
         let id = OxcBox::new_in(self.into_binding_identifier(allocator), allocator);
@@ -242,6 +298,29 @@ mod tests {
         assert_eq!(segment, Segment::NamedQrl("bar".to_string(), 1));
     }
 
+    #[test]
+    fn test_new_segment_for_custom_markers() {
+        let markers = Markers::default().with_suffix("_").with_name("defer");
+        let mut builder = SegmentBuilder::new().with_markers(markers);
+        let segments = vec![Segment::Named("foo".to_string())];
+        assert_eq!(
+            builder.new_segment("lazy_", &segments),
+            Segment::NamedQrl("lazy".to_string(), 0)
+        );
+        assert_eq!(
+            builder.new_segment("defer", &segments),
+            Segment::NamedQrl("defer".to_string(), 0)
+        );
+        assert_eq!(
+            builder.new_segment("bar$", &segments),
+            Segment::NamedQrl("bar".to_string(), 0)
+        );
+        assert_eq!(
+            builder.new_segment("deferred", &segments),
+            Segment::Named("deferred".to_string())
+        );
+    }
+
     #[test]
     fn test_new_segment_unique_name_for_anonymous_qrl() {
         let mut builder = SegmentBuilder::new();
@@ -253,6 +332,31 @@ mod tests {
         assert_eq!(segment, Segment::IndexQrl(1));
     }
 
+    #[test]
+    fn test_new_segment_with_claims() {
+        let mut builder = SegmentBuilder::with_claims(NameClaims::default());
+        let segments = vec![Segment::Named("foo".to_string())];
+        assert_eq!(
+            builder.new_segment_at("$", &segments, 2),
+            Segment::IndexQrl(0)
+        );
+        assert_eq!(
+            builder.new_segment_at("bar$", &segments, 1),
+            Segment::NamedQrl("bar".to_string(), 0)
+        );
+
+        let names = builder.into_claims().unwrap().into_names();
+        let mut builder = SegmentBuilder::with_claims(NameClaims::new(names));
+        assert_eq!(
+            builder.new_segment_at("$", &segments, 1),
+            Segment::IndexQrl(1)
+        );
+        assert_eq!(
+            builder.new_segment_at("$", &segments, 2),
+            Segment::IndexQrl(0)
+        );
+    }
+
     #[test]
     fn test_non_unique_for_non_qrl() {
         let mut builder = SegmentBuilder::new();
@@ -263,4 +367,23 @@ mod tests {
         let segment = builder.new_segment("bar", &segments);
         assert_eq!(segment, Segment::Named("bar".to_string()));
     }
+
+    proptest::proptest! {
+        #[test]
+        fn qrl_segments_are_unique(
+            markers in proptest::collection::vec(("[ab]?\\$", 0..2usize), 1..16),
+        ) {
+            let mut builder = SegmentBuilder::new();
+            let parents = [
+                vec![Segment::Named("foo".to_string())],
+                vec![Segment::Named("bar".to_string())],
+            ];
+            let mut seen = Vec::new();
+            for (marker, parent) in markers {
+                let segment = builder.new_segment(marker, &parents[parent]);
+                proptest::prop_assert!(!seen.contains(&(parent, segment.clone())));
+                seen.push((parent, segment));
+            }
+        }
+    }
 }
@@ -0,0 +1,255 @@
+use crate::prelude::*;
+use crate::transform::{self, TransformModulesOptions, TransformOptions, TransformResult};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::io::{BufRead, Write};
+use std::path::PathBuf;
+
+/// The JSON-RPC error codes returned by [serve].
+pub mod error_code {
+    pub const PARSE_ERROR: i64 = -32700;
+    pub const INVALID_REQUEST: i64 = -32600;
+    pub const METHOD_NOT_FOUND: i64 = -32601;
+    pub const INVALID_PARAMS: i64 = -32602;
+    /// The request was valid, but the transform failed.
+    pub const TRANSFORM_FAILED: i64 = -32000;
+}
+
+#[derive(Deserialize)]
+struct Request {
+    jsonrpc: String,
+    #[serde(default)]
+    id: Option<Value>,
+    method: String,
+    #[serde(default)]
+    params: Value,
+}
+
+#[derive(Serialize)]
+struct Response {
+    jsonrpc: &'static str,
+    id: Value,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    result: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<ResponseError>,
+}
+
+#[derive(Serialize)]
+struct ResponseError {
+    code: i64,
+    message: String,
+}
+
+/// The params of the `transformFs` method.
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct TransformFsParams {
+    paths: Vec<PathBuf>,
+    #[serde(default)]
+    options: TransformOptions,
+}
+
+/// What to do after a request was handled.
+enum Control {
+    Continue,
+    Shutdown,
+}
+
+/// Serves transform requests read from `input`, writing the responses to `output`, until `input` is exhausted or a
+/// `shutdown` request is received.
+///
+/// Requests and responses are [JSON-RPC 2.0](https://www.jsonrpc.org/specification) messages, one per line. This
+/// allows JS build tools to spawn the optimizer once and reuse it for every build, without linking the napi addon.
+/// The supported methods are:
+///
+/// - `transformModules`, taking [TransformModulesOptions].
+/// - `transformFs`, taking `{ paths: string[], options?: TransformOptions }`.
+/// - `shutdown`, taking no params.
+///
+/// Both transform methods respond with a [TransformResult]. Requests are handled one at a time and in order; each
+/// transform is itself parallel. Notifications, i.e. requests without an `id`, are handled without a response.
+pub fn serve<R: BufRead, W: Write>(input: R, mut output: W) -> Result<()> {
+    for line in input.lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let (response, control) = handle(&line);
+        if let Some(response) = response {
+            serde_json::to_writer(&mut output, &response)?;
+            output.write_all(b"\n")?;
+            output.flush()?;
+        }
+        if let Control::Shutdown = control {
+            break;
+        }
+    }
+    Ok(())
+}
+
+fn handle(line: &str) -> (Option<Response>, Control) {
+    let request = match serde_json::from_str::<Value>(line) {
+        Ok(value) => value,
+        Err(e) => {
+            return (
+                Some(Response::error(Value::Null, error_code::PARSE_ERROR, e)),
+                Control::Continue,
+            )
+        }
+    };
+    let id = request.get("id").cloned().unwrap_or(Value::Null);
+    let request = match serde_json::from_value::<Request>(request) {
+        Ok(request) if request.jsonrpc == "2.0" => request,
+        Ok(_) => {
+            return (
+                Some(Response::error(
+                    id,
+                    error_code::INVALID_REQUEST,
+                    "Only JSON-RPC 2.0 is supported",
+                )),
+                Control::Continue,
+            )
+        }
+        Err(e) => {
+            return (
+                Some(Response::error(id, error_code::INVALID_REQUEST, e)),
+                Control::Continue,
+            )
+        }
+    };
+
+    let (result, control) = match request.method.as_str() {
+        "transformModules" => (
+            params::<TransformModulesOptions>(request.params)
+                .and_then(|config| to_result(transform::transform_modules(config))),
+            Control::Continue,
+        ),
+        "transformFs" => (
+            params::<TransformFsParams>(request.params).and_then(|params| {
+                to_result(transform::transform_fs(params.paths, params.options))
+            }),
+            Control::Continue,
+        ),
+        "shutdown" => (Ok(Value::Null), Control::Shutdown),
+        method => (
+            Err(ResponseError {
+                code: error_code::METHOD_NOT_FOUND,
+                message: format!("Unknown method '{method}'"),
+            }),
+            Control::Continue,
+        ),
+    };
+
+    let response = request.id.map(|id| match result {
+        Ok(result) => Response {
+            jsonrpc: "2.0",
+            id,
+            result: Some(result),
+            error: None,
+        },
+        Err(error) => Response {
+            jsonrpc: "2.0",
+            id,
+            result: None,
+            error: Some(error),
+        },
+    });
+    (response, control)
+}
+
+fn params<T: for<'de> Deserialize<'de>>(params: Value) -> std::result::Result<T, ResponseError> {
+    serde_json::from_value(params).map_err(|e| ResponseError {
+        code: error_code::INVALID_PARAMS,
+        message: e.to_string(),
+    })
+}
+
+fn to_result(result: Result<TransformResult>) -> std::result::Result<Value, ResponseError> {
+    result
+        .and_then(|result| Ok(serde_json::to_value(result)?))
+        .map_err(|e| ResponseError {
+            code: error_code::TRANSFORM_FAILED,
+            message: e.to_string(),
+        })
+}
+
+impl Response {
+    fn error<M: ToString>(id: Value, code: i64, message: M) -> Self {
+        Response {
+            jsonrpc: "2.0",
+            id,
+            result: None,
+            error: Some(ResponseError {
+                code,
+                message: message.to_string(),
+            }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn responses(input: &str) -> Vec<Value> {
+        let mut output = Vec::new();
+        serve(input.as_bytes(), &mut output).unwrap();
+        String::from_utf8(output)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn serves_transform_requests_until_shutdown() {
+        let input = [
+            r#"{"jsonrpc":"2.0","id":1,"method":"transformFs","params":{"paths":["src/test_input/test_example_1.tsx"]}}"#,
+            r#"{"jsonrpc":"2.0","method":"transformFs","params":{"paths":[]}}"#,
+            r#"{"jsonrpc":"2.0","id":2,"method":"shutdown"}"#,
+            r#"{"jsonrpc":"2.0","id":3,"method":"transformFs","params":{"paths":[]}}"#,
+        ]
+        .join("\n");
+
+        let responses = responses(&input);
+
+        assert_eq!(responses.len(), 2);
+        assert_eq!(responses[0]["id"], 1);
+        assert!(!responses[0]["result"]["segments"]
+            .as_array()
+            .unwrap()
+            .is_empty());
+        assert_eq!(responses[1]["id"], 2);
+        assert_eq!(responses[1]["result"], Value::Null);
+    }
+
+    #[test]
+    fn reports_invalid_requests() {
+        let input = [
+            "not json",
+            r#"{"jsonrpc":"1.0","id":1,"method":"shutdown"}"#,
+            r#"{"jsonrpc":"2.0","id":2,"method":"optimize"}"#,
+            r#"{"jsonrpc":"2.0","id":3,"method":"transformFs","params":{}}"#,
+            r#"{"jsonrpc":"2.0","id":4,"method":"transformFs","params":{"paths":["missing.tsx"]}}"#,
+        ]
+        .join("\n");
+
+        let codes: Vec<_> = responses(&input)
+            .iter()
+            .map(|response| (response["id"].clone(), response["error"]["code"].clone()))
+            .collect();
+
+        assert_eq!(
+            codes,
+            vec![
+                (Value::Null, error_code::PARSE_ERROR.into()),
+                (1.into(), error_code::INVALID_REQUEST.into()),
+                (2.into(), error_code::METHOD_NOT_FOUND.into()),
+                (3.into(), error_code::INVALID_PARAMS.into()),
+                (4.into(), error_code::TRANSFORM_FAILED.into()),
+            ]
+        );
+    }
+}
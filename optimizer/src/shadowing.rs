@@ -0,0 +1,247 @@
+use crate::component::QWIK_CORE_SOURCE;
+use oxc_ast::ast::*;
+use oxc_ast::visit::walk_mut;
+use oxc_ast::{AstBuilder, Visit, VisitMut};
+use oxc_semantic::{SemanticBuilder, SymbolId, SymbolTable};
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+/// Hands out names which are not used by a module yet, e.g. `qrl$1` when the module already binds `qrl`.
+#[derive(Debug, Default)]
+pub(crate) struct UniqueNames {
+    taken: HashSet<String>,
+}
+
+impl UniqueNames {
+    /// Creates a service handing out names other than the `taken` ones.
+    pub fn new<I: IntoIterator<Item = String>>(taken: I) -> Self {
+        Self {
+            taken: taken.into_iter().collect(),
+        }
+    }
+
+    /// Returns `name` if it is not taken yet, or else the first of `name$1`, `name$2`... which is not, and takes it.
+    pub fn unique(&mut self, name: &str) -> String {
+        let unique = match self.taken.contains(name) {
+            false => name.to_string(),
+            true => (1..)
+                .map(|n| format!("{name}${n}"))
+                .find(|alias| !self.taken.contains(alias))
+                .unwrap_or_default(),
+        };
+        self.taken.insert(unique.clone());
+        unique
+    }
+}
+
+/// Renames the identifiers the optimizer imports from `@qwik.dev/core`, e.g. `qrl`, `useLexicalScope` or `_jsxQ`,
+/// when the generated `program` also declares a binding of the same name, which would otherwise shadow them or be
+/// declared twice.
+///
+/// The import of such an identifier is aliased with a [unique name](UniqueNames::unique), and so are its call sites
+/// added by the optimizer along with the references resolved to the import. The bindings of the module itself are
+/// never renamed, as they may be exported.
+///
+/// # Examples
+/// ```javascript
+/// import { qrl } from "@qwik.dev/core";
+/// export const App_component = () => {
+///     const qrl = "https://qwik.dev";
+///     const onClick = qrl(() => import("./app.tsx_App_component_onClick"), "App_component_onClick", [qrl]);
+/// };
+/// ```
+/// becomes
+/// ```javascript
+/// import { qrl as qrl$1 } from "@qwik.dev/core";
+/// export const App_component = () => {
+///     const qrl = "https://qwik.dev";
+///     const onClick = qrl$1(() => import("./app.tsx_App_component_onClick"), "App_component_onClick", [qrl]);
+/// };
+/// ```
+pub(crate) fn avoid_shadowing<'a>(program: &mut Program<'a>, ast: AstBuilder<'a>) {
+    let mut names = Names::default();
+    names.visit_program(program);
+    let colliding: Vec<String> = names
+        .imported
+        .iter()
+        .filter(|name| names.bindings.contains(*name))
+        .cloned()
+        .collect();
+    if colliding.is_empty() {
+        return;
+    }
+
+    let symbols = SemanticBuilder::new()
+        .build(program)
+        .semantic
+        .into_symbol_table_and_scope_tree()
+        .0;
+    let mut unique = UniqueNames::new(names.all);
+    let aliases: BTreeMap<String, String> = colliding
+        .into_iter()
+        .map(|name| {
+            let alias = unique.unique(&name);
+            (name, alias)
+        })
+        .collect();
+
+    // The imports are at the top level of the program, and aliased first so that their symbols are known when the
+    // references are renamed.
+    let mut imports = HashMap::new();
+    for statement in program.body.iter_mut() {
+        let Statement::ImportDeclaration(import) = statement else {
+            continue;
+        };
+        let source = import.source.value;
+        for specifier in import.specifiers.iter_mut().flatten() {
+            let ImportDeclarationSpecifier::ImportSpecifier(specifier) = specifier else {
+                continue;
+            };
+            let Some(alias) = aliases.get(specifier.local.name.as_str()) else {
+                continue;
+            };
+            if is_core_import(&source, specifier) {
+                if let Some(symbol_id) = specifier.local.symbol_id.get() {
+                    imports.insert(symbol_id, alias.clone());
+                }
+                specifier.local.name = ast.atom(alias);
+            }
+        }
+    }
+
+    Aliases {
+        ast,
+        symbols: &symbols,
+        aliases: &aliases,
+        imports: &imports,
+    }
+    .visit_program(program);
+}
+
+/// Whether `specifier` imports a name of `@qwik.dev/core` without renaming it, which is how the optimizer imports
+/// the identifiers it adds.
+fn is_core_import(source: &str, specifier: &ImportSpecifier) -> bool {
+    source == QWIK_CORE_SOURCE && specifier.imported.name() == specifier.local.name
+}
+
+/// The names of a program: the ones imported from `@qwik.dev/core`, the bindings declared by the program itself, and
+/// every name bound or referenced.
+#[derive(Default)]
+struct Names {
+    imported: Vec<String>,
+    bindings: HashSet<String>,
+    all: HashSet<String>,
+}
+
+impl<'a> Visit<'a> for Names {
+    fn visit_import_declaration(&mut self, import: &ImportDeclaration<'a>) {
+        // Imports of the same name from other modules are left to `ImportCleanUp`, as the optimizer imports some
+        // markers from both `@qwik.dev/core` and `@qwik.dev/router`.
+        for specifier in import.specifiers.iter().flatten() {
+            let name = specifier.local().name.to_string();
+            if let ImportDeclarationSpecifier::ImportSpecifier(specifier) = specifier {
+                if is_core_import(&import.source.value, specifier) {
+                    self.imported.push(name.clone());
+                }
+            }
+            self.all.insert(name);
+        }
+    }
+
+    fn visit_binding_identifier(&mut self, id: &BindingIdentifier<'a>) {
+        self.bindings.insert(id.name.to_string());
+        self.all.insert(id.name.to_string());
+    }
+
+    fn visit_identifier_reference(&mut self, id: &IdentifierReference<'a>) {
+        self.all.insert(id.name.to_string());
+    }
+}
+
+/// Renames the references of the aliased imports.
+struct Aliases<'s, 'a> {
+    ast: AstBuilder<'a>,
+    symbols: &'s SymbolTable,
+    aliases: &'s BTreeMap<String, String>,
+    /// The aliases of the imports, by symbol.
+    imports: &'s HashMap<SymbolId, String>,
+}
+
+impl<'a> VisitMut<'a> for Aliases<'_, 'a> {
+    fn visit_call_expression(&mut self, call: &mut CallExpression<'a>) {
+        // The calls added by the optimizer, e.g. `qrl(...)`, have no span. Such a call may resolve to a binding of the
+        // module shadowing the import, which is precisely what the alias avoids.
+        if let Expression::Identifier(callee) = &mut call.callee {
+            if callee.span.is_unspanned() {
+                if let Some(alias) = self.aliases.get(callee.name.as_str()) {
+                    callee.name = self.ast.atom(alias);
+                }
+            }
+        }
+        walk_mut::walk_call_expression(self, call);
+    }
+
+    fn visit_identifier_reference(&mut self, id: &mut IdentifierReference<'a>) {
+        let alias = id
+            .reference_id
+            .get()
+            .and_then(|reference_id| self.symbols.get_reference(reference_id).symbol_id())
+            .and_then(|symbol_id| self.imports.get(&symbol_id));
+        if let Some(alias) = alias {
+            id.name = self.ast.atom(alias);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use oxc_allocator::Allocator;
+    use oxc_codegen::Codegen;
+    use oxc_parser::Parser;
+    use oxc_span::SourceType;
+
+    #[test]
+    fn hands_out_unique_names() {
+        let mut names = UniqueNames::new(["qrl".to_string(), "qrl$1".to_string()]);
+        assert_eq!(names.unique("qrl"), "qrl$2");
+        assert_eq!(names.unique("qrl"), "qrl$3");
+        assert_eq!(names.unique("_jsxQ"), "_jsxQ");
+        assert_eq!(names.unique("_jsxQ"), "_jsxQ$1");
+    }
+
+    #[test]
+    fn aliases_shadowed_imports() {
+        let allocator = Allocator::default();
+        let source = r#"
+            import { qrl, componentQrl } from "@qwik.dev/core";
+            import { useLexicalScope } from "./hooks";
+            export const App = () => {
+                const qrl = "https://qwik.dev";
+                return useLexicalScope(qrl);
+            };
+            export const Other = componentQrl(qrl(() => import("./other"), "Other"));
+        "#;
+        let mut program = Parser::new(&allocator, source, SourceType::mjs())
+            .parse()
+            .program;
+
+        avoid_shadowing(&mut program, AstBuilder::new(&allocator));
+        let code = Codegen::new().build(&program).code;
+        assert!(
+            code.contains(r#"import { qrl as qrl$1, componentQrl } from "@qwik.dev/core";"#),
+            "{code}"
+        );
+        assert!(
+            code.contains("const qrl = \"https://qwik.dev\";\n\treturn useLexicalScope(qrl);"),
+            "{code}"
+        );
+        assert!(
+            code.contains(r#"componentQrl(qrl$1(() => import("./other"), "Other"))"#),
+            "{code}"
+        );
+        assert!(
+            code.contains(r#"import { useLexicalScope } from "./hooks";"#),
+            "{code}"
+        );
+    }
+}
@@ -18,6 +18,13 @@ impl Source {
         Ok(Source::ScriptFile { text, source_info })
     }
 
+    /// Creates a source from in-memory `text` for a module located at `path`.
+    pub fn from_module<P: AsRef<Path>, T: AsRef<str>>(path: P, text: T) -> Result<Self> {
+        let source_info = SourceInfo::new(path)?;
+        let text = text.as_ref().to_string();
+        Ok(Source::ScriptFile { text, source_info })
+    }
+
     pub fn from_source<T: AsRef<str>>(
         text: T,
         language: Language,
@@ -32,6 +39,13 @@ impl Source {
         Ok(Source::ScriptFile { text, source_info })
     }
 
+    /// Creates a source from in-memory `text` for a virtual module, see [SourceInfo::virtual_module].
+    pub fn from_virtual<I: AsRef<str>, T: AsRef<str>>(id: I, text: T, language: Language) -> Self {
+        let source_info = SourceInfo::virtual_module(id, language);
+        let text = text.as_ref().to_string();
+        Source::ScriptFile { text, source_info }
+    }
+
     pub fn source_code(&self) -> &str {
         match self {
             Source::ScriptFile { text, .. } => text.as_ref(),
@@ -60,4 +74,15 @@ mod tests {
         assert_eq!(source.source_info(), &expected_source_info);
         assert_eq!(source.source_code(), &expected_source);
     }
+
+    #[test]
+    fn can_load_from_module() {
+        let source = Source::from_module("routes/index.tsx", "export default 1;").unwrap();
+
+        assert_eq!(
+            source.source_info(),
+            &SourceInfo::new("routes/index.tsx").unwrap()
+        );
+        assert_eq!(source.source_code(), "export default 1;");
+    }
 }
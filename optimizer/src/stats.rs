@@ -0,0 +1,109 @@
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::iter::Sum;
+use std::ops::Add;
+use std::time::Duration;
+
+/// What transforming a module cost, attached to every [OptimizationResult](crate::transform::OptimizationResult) so
+/// that integrators can log per-file costs, and regressions can be tracked across releases of the optimizer and of
+/// `oxc`.
+///
+/// Times are wall-clock milliseconds. They are always zero on `wasm32-unknown-unknown`, which has no clock.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "camelCase"))]
+pub struct TransformStats {
+    /// The time spent parsing the module, including a second parse of its recovered source in
+    /// [lenient](crate::transform::TransformOptions::lenient) mode.
+    pub parse_ms: f64,
+    /// The time spent analyzing and rewriting the module, i.e. everything but parsing and code generation.
+    pub transform_ms: f64,
+    /// The time spent generating the code of the host module and of its segments.
+    pub codegen_ms: f64,
+    /// The size of the source of the module, in bytes.
+    pub bytes_in: usize,
+    /// The size of the code of the host module and of its segments, in bytes.
+    pub bytes_out: usize,
+    /// The number of segments extracted from the module.
+    pub segment_count: usize,
+}
+
+impl TransformStats {
+    /// The time spent transforming the module, in milliseconds.
+    pub fn total_ms(&self) -> f64 {
+        self.parse_ms + self.transform_ms + self.codegen_ms
+    }
+}
+
+/// Adds up the stats of many modules, e.g. of every module of a [TransformResult](crate::transform::TransformResult).
+impl Add for TransformStats {
+    type Output = TransformStats;
+
+    fn add(self, other: TransformStats) -> TransformStats {
+        TransformStats {
+            parse_ms: self.parse_ms + other.parse_ms,
+            transform_ms: self.transform_ms + other.transform_ms,
+            codegen_ms: self.codegen_ms + other.codegen_ms,
+            bytes_in: self.bytes_in + other.bytes_in,
+            bytes_out: self.bytes_out + other.bytes_out,
+            segment_count: self.segment_count + other.segment_count,
+        }
+    }
+}
+
+impl Sum for TransformStats {
+    fn sum<I: Iterator<Item = TransformStats>>(iter: I) -> TransformStats {
+        iter.fold(TransformStats::default(), Add::add)
+    }
+}
+
+/// Measures the time spent in a phase of the transform, without panicking where there is no clock.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Stopwatch {
+    #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+    start: std::time::Instant,
+}
+
+impl Stopwatch {
+    pub fn start() -> Stopwatch {
+        Stopwatch {
+            #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+            start: std::time::Instant::now(),
+        }
+    }
+
+    pub fn elapsed(&self) -> Duration {
+        #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+        return self.start.elapsed();
+        #[cfg(all(target_arch = "wasm32", target_os = "unknown"))]
+        Duration::ZERO
+    }
+}
+
+/// `duration` in milliseconds.
+pub(crate) fn millis(duration: Duration) -> f64 {
+    duration.as_secs_f64() * 1000.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn adds_up_stats() {
+        let stats = TransformStats {
+            parse_ms: 1.0,
+            transform_ms: 2.0,
+            codegen_ms: 0.5,
+            bytes_in: 100,
+            bytes_out: 150,
+            segment_count: 2,
+        };
+        let total: TransformStats = [stats, stats].into_iter().sum();
+        assert_eq!(total.total_ms(), 7.0);
+        assert_eq!(total.bytes_in, 200);
+        assert_eq!(total.bytes_out, 300);
+        assert_eq!(total.segment_count, 4);
+        assert_eq!(millis(Duration::from_micros(1500)), 1.5);
+    }
+}
@@ -0,0 +1,141 @@
+use oxc_allocator::Box as OxcBox;
+use oxc_ast::ast::*;
+use oxc_ast::AstBuilder;
+use oxc_span::SPAN;
+
+/// The markers whose segments only ever run on the server. In client builds their code is replaced with a
+/// `_noopQrl` stub, so that the client can still reference the segment (e.g. to invoke it on the server) without
+/// downloading its code or the server-only modules it imports.
+pub const SERVER_MARKERS: [&str; 4] = ["server$", "routeLoader$", "routeAction$", "globalAction$"];
+
+/// The error thrown by the stub replacing a [stripped export](crate::transform::TransformOptions::strip_exports).
+pub const STRIPPED_EXPORT_ERROR: &str =
+    "Symbol removed by Qwik Optimizer, it can not be called from the current platform";
+
+/// Replaces the value of every named export of `program` listed in `names` with a function throwing
+/// [STRIPPED_EXPORT_ERROR].
+///
+/// Runs before the segments of `program` are extracted, so that nothing referenced only by a stripped export is
+/// emitted and its imports are removed by the clean up of the host module.
+///
+/// # Examples
+/// ```javascript
+/// export const onGet = async ({ json }) => json(200, await db.query());
+/// export function onPost() { ... }
+/// ```
+/// becomes
+/// ```javascript
+/// export const onGet = () => { throw "Symbol removed by Qwik Optimizer, ..."; };
+/// export const onPost = () => { throw "Symbol removed by Qwik Optimizer, ..."; };
+/// ```
+pub(crate) fn strip_exports<'a>(program: &mut Program<'a>, names: &[String], ast: AstBuilder<'a>) {
+    if names.is_empty() {
+        return;
+    }
+    let is_stripped = |name: &str| names.iter().any(|stripped| stripped == name);
+
+    for statement in program.body.iter_mut() {
+        let Statement::ExportNamedDeclaration(export) = statement else {
+            continue;
+        };
+        match &mut export.declaration {
+            Some(Declaration::VariableDeclaration(decl)) => {
+                for declarator in decl.declarations.iter_mut() {
+                    let stripped = declarator
+                        .id
+                        .get_identifier_name()
+                        .is_some_and(|name| is_stripped(&name));
+                    if stripped {
+                        declarator.init = Some(throwing_function(ast));
+                    }
+                }
+            }
+            Some(Declaration::FunctionDeclaration(func)) => {
+                let Some(name) = func
+                    .id
+                    .as_ref()
+                    .map(|id| id.name)
+                    .filter(|n| is_stripped(n))
+                else {
+                    continue;
+                };
+                let declarator = ast.variable_declarator(
+                    SPAN,
+                    VariableDeclarationKind::Const,
+                    ast.binding_pattern(
+                        ast.binding_pattern_kind_binding_identifier(SPAN, name),
+                        None::<OxcBox<TSTypeAnnotation>>,
+                        false,
+                    ),
+                    Some(throwing_function(ast)),
+                    false,
+                );
+                export.declaration = Some(Declaration::VariableDeclaration(
+                    ast.alloc_variable_declaration(
+                        SPAN,
+                        VariableDeclarationKind::Const,
+                        ast.vec1(declarator),
+                        false,
+                    ),
+                ));
+            }
+            _ => {}
+        }
+    }
+}
+
+/// `() => { throw "Symbol removed by Qwik Optimizer, ..."; }`
+fn throwing_function(ast: AstBuilder<'_>) -> Expression<'_> {
+    let error = ast.expression_string_literal(SPAN, STRIPPED_EXPORT_ERROR, None);
+    let body = ast.function_body(SPAN, ast.vec(), ast.vec1(ast.statement_throw(SPAN, error)));
+    let params = ast.formal_parameters(
+        SPAN,
+        FormalParameterKind::ArrowFormalParameters,
+        ast.vec(),
+        None::<OxcBox<BindingRestElement>>,
+    );
+    ast.expression_arrow_function(
+        SPAN,
+        false,
+        false,
+        None::<OxcBox<TSTypeParameterDeclaration>>,
+        params,
+        None::<OxcBox<TSTypeAnnotation>>,
+        body,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use oxc_allocator::Allocator;
+    use oxc_codegen::Codegen;
+    use oxc_parser::Parser;
+    use oxc_span::SourceType;
+
+    #[test]
+    fn replaces_stripped_exports() {
+        let allocator = Allocator::default();
+        let source = r#"
+            import { db } from "./db";
+            export const onGet = async () => db.query();
+            export function onPost() { return db.insert(); }
+            export const keep = 1;
+        "#;
+        let mut program = Parser::new(&allocator, source, SourceType::mjs())
+            .parse()
+            .program;
+
+        strip_exports(
+            &mut program,
+            &["onGet".to_string(), "onPost".to_string()],
+            AstBuilder::new(&allocator),
+        );
+        let code = Codegen::new().build(&program).code;
+
+        let stub = format!("() => {{\n\tthrow \"{STRIPPED_EXPORT_ERROR}\";\n}}");
+        assert!(code.contains(&format!("export const onGet = {stub};")));
+        assert!(code.contains(&format!("export const onPost = {stub};")));
+        assert!(code.contains("export const keep = 1;"));
+    }
+}
@@ -0,0 +1,167 @@
+use oxc_allocator::Vec as OxcVec;
+use oxc_ast::ast::*;
+use oxc_ast::visit::walk_mut::walk_jsx_opening_element;
+use oxc_ast::{AstBuilder, VisitMut};
+use oxc_span::SPAN;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// The marker registering a stylesheet which only applies to the component calling it.
+pub const USE_STYLES_SCOPED: &str = "useStylesScoped$";
+
+/// The marker declaring a component.
+pub(crate) const COMPONENT: &str = "component$";
+
+/// Prefix of the class which scopes the elements of a component, e.g. `⭐️J4uyIhaBNR4`.
+pub const SCOPE_CLASS_PREFIX: &str = "⭐️";
+
+/// A stylesheet registered with `useStylesScoped$`, recorded so that bundlers can emit it.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "camelCase"))]
+pub struct ScopedStyle {
+    /// The symbol name of the segment extracted from the `useStylesScoped$` argument.
+    pub symbol_name: String,
+    /// The symbol name of the component the styles are scoped to.
+    pub component: String,
+    /// The scope id, which is the hash of the component's `Id`.
+    pub scope_id: String,
+    /// The stylesheet, if it was given as a string literal. Otherwise it is only known to the bundler, e.g. when
+    /// imported from a `.css` file.
+    pub style: Option<String>,
+}
+
+impl ScopedStyle {
+    /// The class added to every element rendered by the component.
+    pub fn scope_class(&self) -> String {
+        scope_class(&self.scope_id)
+    }
+}
+
+pub(crate) fn scope_class(scope_id: &str) -> String {
+    format!("{SCOPE_CLASS_PREFIX}{scope_id}")
+}
+
+/// Returns the stylesheet passed to `useStylesScoped$`, if it is known statically.
+pub(crate) fn static_style(arg: &Argument) -> Option<String> {
+    match arg {
+        Argument::StringLiteral(s) => Some(s.value.to_string()),
+        Argument::TemplateLiteral(t) if t.expressions.is_empty() => t
+            .quasis
+            .first()
+            .map(|quasi| quasi.value.cooked.unwrap_or(quasi.value.raw).to_string()),
+        _ => None,
+    }
+}
+
+/// Adds the scope class to the `class` attribute of every intrinsic element (e.g. `<div>`), creating the attribute
+/// where it is missing. Elements of other components are left untouched since they are scoped by their own styles.
+pub(crate) struct ScopeClasses<'a> {
+    ast: AstBuilder<'a>,
+    class: String,
+}
+
+impl<'a> ScopeClasses<'a> {
+    pub fn new(ast: AstBuilder<'a>, scope_id: &str) -> Self {
+        Self {
+            ast,
+            class: scope_class(scope_id),
+        }
+    }
+
+    fn scoped_value(&self, value: Option<JSXAttributeValue<'a>>) -> JSXAttributeValue<'a> {
+        match value {
+            Some(JSXAttributeValue::StringLiteral(s)) if s.value.is_empty() => {
+                self.string_value(self.class.clone())
+            }
+            Some(JSXAttributeValue::StringLiteral(s)) => {
+                self.string_value(format!("{} {}", self.class, s.value))
+            }
+            Some(JSXAttributeValue::ExpressionContainer(mut container)) => {
+                match container.expression.as_expression_mut() {
+                    Some(expr) => {
+                        let expr = self.ast.move_expression(expr);
+                        let mut elements = OxcVec::new_in(self.ast.allocator);
+                        elements.push(ArrayExpressionElement::from(
+                            self.ast
+                                .expression_string_literal(SPAN, self.class.as_str(), None),
+                        ));
+                        elements.push(ArrayExpressionElement::from(expr));
+                        let array = self.ast.expression_array(SPAN, elements, None);
+                        self.ast
+                            .jsx_attribute_value_expression_container(SPAN, array.into())
+                    }
+                    None => self.string_value(self.class.clone()),
+                }
+            }
+            _ => self.string_value(self.class.clone()),
+        }
+    }
+
+    fn string_value(&self, value: String) -> JSXAttributeValue<'a> {
+        self.ast
+            .jsx_attribute_value_string_literal(SPAN, value.as_str(), None)
+    }
+}
+
+impl<'a> VisitMut<'a> for ScopeClasses<'a> {
+    fn visit_jsx_opening_element(&mut self, element: &mut JSXOpeningElement<'a>) {
+        if matches!(element.name, JSXElementName::Identifier(_)) {
+            let class = element.attributes.iter_mut().find_map(|item| match item {
+                JSXAttributeItem::Attribute(attr)
+                    if attr.is_identifier("class") || attr.is_identifier("className") =>
+                {
+                    Some(attr)
+                }
+                _ => None,
+            });
+
+            match class {
+                Some(attr) => {
+                    let value = attr.value.take();
+                    attr.value = Some(self.scoped_value(value));
+                }
+                None => {
+                    let name = self.ast.jsx_attribute_name_identifier(SPAN, "class");
+                    let value = self.string_value(self.class.clone());
+                    element
+                        .attributes
+                        .push(
+                            self.ast
+                                .jsx_attribute_item_attribute(SPAN, name, Some(value)),
+                        );
+                }
+            }
+        }
+
+        walk_jsx_opening_element(self, element);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use oxc_allocator::Allocator;
+    use oxc_codegen::Codegen;
+    use oxc_parser::Parser;
+    use oxc_span::SourceType;
+
+    #[test]
+    fn adds_scope_class_to_intrinsic_elements() {
+        let allocator = Allocator::default();
+        let source = r#"
+            const a = <div class="a"><p class={b}></p><span /><Header class="c" /></div>;
+        "#;
+        let mut program = Parser::new(&allocator, source, SourceType::tsx())
+            .parse()
+            .program;
+
+        ScopeClasses::new(AstBuilder::new(&allocator), "abc").visit_program(&mut program);
+
+        let code = Codegen::new().build(&program).code;
+        assert_eq!(
+            code,
+            "const a = <div class=\"⭐️abc a\"><p class={[\"⭐️abc\", b]}></p><span class=\"⭐️abc\" /><Header class=\"c\" /></div>;\n"
+        );
+    }
+}
@@ -0,0 +1,150 @@
+use crate::component::QrlComponent;
+use crate::error::Error;
+use crate::intern::Name;
+use crate::manifest::SegmentMetadata;
+use crate::prelude::*;
+use dashmap::mapref::entry::Entry;
+use dashmap::DashMap;
+#[cfg(feature = "serde")]
+use serde::{Serialize, Serializer};
+
+/// The metadata of every segment of a build, by symbol name, collected while its files are transformed in parallel.
+///
+/// This is the single source of truth for the segments of a [TransformResult](crate::transform::TransformResult):
+/// its [Manifest](crate::manifest::Manifest) and [EntryPlan](crate::entry_strategy::EntryPlan) are built from it.
+/// Registering a segment under a symbol name already registered with different metadata is a conflict, e.g. two
+/// files claiming the same symbol. Registering the same segment twice is not.
+///
+/// Iteration is sorted by symbol name, regardless of the order in which the threads registered the segments, and the
+/// registry is serialized as that sorted list.
+#[derive(Debug, Default)]
+pub struct SymbolRegistry {
+    segments: DashMap<Name, SegmentMetadata>,
+}
+
+impl SymbolRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers `metadata` under its symbol name. Fails with [Error::SymbolConflict] if another segment was
+    /// registered under the same name.
+    pub fn register(&self, metadata: SegmentMetadata) -> Result<()> {
+        match self.segments.entry(Name::new(&metadata.symbol_name)) {
+            Entry::Occupied(existing) if *existing.get() == metadata => Ok(()),
+            Entry::Occupied(existing) => Err(Error::SymbolConflict(
+                metadata.symbol_name,
+                existing.get().origin.clone(),
+                metadata.origin,
+            )),
+            Entry::Vacant(entry) => {
+                entry.insert(metadata);
+                Ok(())
+            }
+        }
+    }
+
+    /// Registers the metadata of every one of `segments`, see [SymbolRegistry::register].
+    pub fn register_all<'a, I: IntoIterator<Item = &'a QrlComponent>>(
+        &self,
+        segments: I,
+    ) -> Result<()> {
+        segments
+            .into_iter()
+            .try_for_each(|segment| self.register(SegmentMetadata::from(segment)))
+    }
+
+    pub fn get(&self, symbol_name: &str) -> Option<SegmentMetadata> {
+        self.segments
+            .get(symbol_name)
+            .map(|metadata| metadata.clone())
+    }
+
+    pub fn contains(&self, symbol_name: &str) -> bool {
+        self.segments.contains_key(symbol_name)
+    }
+
+    pub fn len(&self) -> usize {
+        self.segments.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.segments.is_empty()
+    }
+
+    /// The metadata of every registered segment, sorted by symbol name.
+    pub fn segments(&self) -> Vec<SegmentMetadata> {
+        let mut segments: Vec<_> = self
+            .segments
+            .iter()
+            .map(|metadata| metadata.clone())
+            .collect();
+        segments.sort_by(|a, b| a.symbol_name.cmp(&b.symbol_name));
+        segments
+    }
+
+    /// Consumes the registry, returning the metadata of every registered segment sorted by symbol name.
+    pub fn into_segments(self) -> Vec<SegmentMetadata> {
+        let mut segments: Vec<_> = self
+            .segments
+            .into_iter()
+            .map(|(_, metadata)| metadata)
+            .collect();
+        segments.sort_by(|a, b| a.symbol_name.cmp(&b.symbol_name));
+        segments
+    }
+
+    #[cfg(feature = "serde")]
+    pub fn to_json(&self) -> Result<String> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl Serialize for SymbolRegistry {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        self.segments().serialize(serializer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rayon::prelude::*;
+
+    fn metadata(symbol_name: &str, origin: &str) -> SegmentMetadata {
+        segment_metadata!(symbol_name, origin: origin.into())
+    }
+
+    #[test]
+    fn iterates_in_symbol_order() {
+        let registry = SymbolRegistry::new();
+        (0..100).into_par_iter().for_each(|i| {
+            registry
+                .register(metadata(&format!("s_{i:03}"), "app.tsx"))
+                .unwrap();
+        });
+        let names: Vec<_> = registry
+            .segments()
+            .into_iter()
+            .map(|segment| segment.symbol_name)
+            .collect();
+        let expected: Vec<_> = (0..100).map(|i| format!("s_{i:03}")).collect();
+        assert_eq!(names, expected);
+        assert_eq!(registry.into_segments().len(), 100);
+    }
+
+    #[test]
+    fn detects_conflicts() {
+        let registry = SymbolRegistry::new();
+        registry.register(metadata("s_a", "app.tsx")).unwrap();
+        registry.register(metadata("s_a", "app.tsx")).unwrap();
+        assert_eq!(registry.len(), 1);
+        assert!(matches!(
+            registry.register(metadata("s_a", "other.tsx")),
+            Err(Error::SymbolConflict(symbol, first, second))
+                if symbol == "s_a" && first == "app.tsx" && second == "other.tsx"
+        ));
+        assert_eq!(registry.get("s_a").unwrap().origin, "app.tsx");
+    }
+}
@@ -0,0 +1,144 @@
+use oxc_allocator::{Allocator, CloneIn};
+use oxc_ast::ast::*;
+use oxc_ast::{AstBuilder, Visit};
+use oxc_codegen::{Codegen, CodegenOptions};
+use oxc_semantic::{SemanticBuilder, SymbolTable};
+use oxc_span::{GetSpan, SourceType, Span, SPAN};
+use oxc_transformer::{JsxOptions, TransformOptions, Transformer};
+use std::path::Path;
+
+/// The marker of synchronous event handlers, e.g. `sync$((event) => event.preventDefault())`. Their function runs
+/// synchronously while the event is dispatched, so it is not extracted into a segment: it is serialized as text
+/// instead, which Qwik evaluates in the browser.
+pub const SYNC_MARKER: &str = "sync$";
+
+/// The function replacing [SYNC_MARKER]: `_qrlSync(fn, "serialized fn")`.
+pub const QRL_SYNC: &str = "_qrlSync";
+
+/// Whether `expr` is a function that can be passed to [SYNC_MARKER].
+pub(crate) fn is_function(expr: &Expression) -> bool {
+    matches!(
+        expr.without_parentheses(),
+        Expression::ArrowFunctionExpression(_) | Expression::FunctionExpression(_)
+    )
+}
+
+/// Returns the name and location of every reference of `function` to a binding declared outside of it, including
+/// imports and top-level declarations of the module. The serialized text of a synchronous function is evaluated on
+/// its own, so none of them would be defined. References to globals, e.g. `document`, are allowed.
+pub(crate) fn external_references(
+    function: &Expression,
+    symbols: &SymbolTable,
+) -> Vec<(String, Span)> {
+    let mut references = ExternalReferences {
+        span: function.span(),
+        symbols,
+        found: Vec::new(),
+    };
+    references.visit_expression(function);
+    references.found
+}
+
+/// Prints `function` minified, as the text passed to [QRL_SYNC]. The text is evaluated as JavaScript, so TypeScript
+/// syntax, e.g. `(event: Event) => ...`, is stripped from it whether or not the module is transpiled.
+pub(crate) fn serialize(function: &Expression) -> String {
+    let allocator = Allocator::default();
+    let ast = AstBuilder::new(&allocator);
+    let function = function.clone_in(&allocator);
+    let mut program = ast.program(
+        SPAN,
+        SourceType::ts(),
+        "",
+        ast.vec(),
+        None,
+        ast.vec(),
+        ast.vec1(ast.statement_expression(SPAN, function)),
+    );
+    let (symbols, scopes) = SemanticBuilder::new()
+        .build(&program)
+        .semantic
+        .into_symbol_table_and_scope_tree();
+    let options = TransformOptions {
+        jsx: JsxOptions::disable(),
+        ..TransformOptions::default()
+    };
+    Transformer::new(&allocator, Path::new(""), &options).build_with_symbols_and_scopes(
+        symbols,
+        scopes,
+        &mut program,
+    );
+    let code = Codegen::new()
+        .with_options(CodegenOptions {
+            minify: true,
+            ..CodegenOptions::default()
+        })
+        .build(&program)
+        .code;
+    code.trim_end().trim_end_matches(';').to_string()
+}
+
+struct ExternalReferences<'s> {
+    span: Span,
+    symbols: &'s SymbolTable,
+    found: Vec<(String, Span)>,
+}
+
+impl<'a> Visit<'a> for ExternalReferences<'_> {
+    fn visit_identifier_reference(&mut self, id_ref: &IdentifierReference<'a>) {
+        let symbol_id = id_ref
+            .reference_id
+            .get()
+            .and_then(|ref_id| self.symbols.get_reference(ref_id).symbol_id());
+        if let Some(symbol_id) = symbol_id {
+            if !self
+                .span
+                .contains_inclusive(self.symbols.get_span(symbol_id))
+            {
+                self.found.push((id_ref.name.to_string(), id_ref.span));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use oxc_parser::Parser;
+
+    #[test]
+    fn finds_external_references() {
+        let allocator = Allocator::default();
+        let source = r#"
+            import { helper } from "./helper";
+            const limit = 10;
+            sync$((event, target) => {
+                const local = target.value.length;
+                if (local > limit || helper(event)) event.preventDefault();
+                console.log(document.title);
+            });
+        "#;
+        let program = Parser::new(&allocator, source, SourceType::mjs())
+            .parse()
+            .program;
+        let semantic = SemanticBuilder::new().build(&program).semantic;
+        let Some(Statement::ExpressionStatement(statement)) = program.body.last() else {
+            unreachable!()
+        };
+        let Expression::CallExpression(call) = &statement.expression else {
+            unreachable!()
+        };
+        let function = call.arguments[0].to_expression();
+
+        let names: Vec<String> = external_references(function, semantic.symbols())
+            .into_iter()
+            .map(|(name, _)| name)
+            .collect();
+        assert_eq!(names, ["limit", "helper"]);
+        assert!(is_function(function));
+        assert_eq!(
+            serialize(function).lines().count(),
+            1,
+            "serialized functions are minified"
+        );
+    }
+}
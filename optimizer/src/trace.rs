@@ -0,0 +1,117 @@
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::fmt::Display;
+
+/// The category of rule that produced a [Decision].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum DecisionKind {
+    /// How the display name and symbol name of a segment were derived.
+    Naming,
+    /// Which entry (chunk) a segment was assigned to.
+    EntryStrategy,
+    /// Whether the `$` expression was extracted into its own module or left inline.
+    Extraction,
+    /// How an identifier referenced by the segment was classified (import, lexical capture, etc.).
+    Capture,
+}
+
+impl Display for DecisionKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            DecisionKind::Naming => "naming",
+            DecisionKind::EntryStrategy => "entry-strategy",
+            DecisionKind::Extraction => "extraction",
+            DecisionKind::Capture => "capture",
+        };
+        write!(f, "{name}")
+    }
+}
+
+/// A single rule which fired while processing a `$` call site.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Decision {
+    pub kind: DecisionKind,
+    pub detail: String,
+}
+
+/// Every [Decision] recorded for one `$` call site, in the order the rules fired.
+///
+/// Trace entries are only recorded when [`TransformOptions::trace`](crate::transform::TransformOptions::trace)
+/// is enabled.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct TraceEntry {
+    /// The name of the marker function being called, e.g. `component$`.
+    pub call_site: String,
+    /// The byte offset of the call expression in the original source.
+    pub start: u32,
+    /// The symbol name of the segment this call site produced.
+    pub symbol_name: String,
+    pub decisions: Vec<Decision>,
+}
+
+impl TraceEntry {
+    pub(crate) fn new(
+        call_site: impl Into<String>,
+        start: u32,
+        symbol_name: impl Into<String>,
+    ) -> Self {
+        Self {
+            call_site: call_site.into(),
+            start,
+            symbol_name: symbol_name.into(),
+            decisions: Vec::new(),
+        }
+    }
+
+    pub(crate) fn record<T: Into<String>>(&mut self, kind: DecisionKind, detail: T) {
+        self.decisions.push(Decision {
+            kind,
+            detail: detail.into(),
+        });
+    }
+
+    /// Returns the decisions of the given kind.
+    pub fn decisions_of(&self, kind: DecisionKind) -> impl Iterator<Item = &Decision> {
+        self.decisions.iter().filter(move |d| d.kind == kind)
+    }
+}
+
+impl Display for TraceEntry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(
+            f,
+            "{} @ {} -> {}",
+            self.call_site, self.start, self.symbol_name
+        )?;
+        for decision in &self.decisions {
+            writeln!(f, "  [{}] {}", decision.kind, decision.detail)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn records_decisions_in_order() {
+        let mut entry = TraceEntry::new("component$", 10, "App_component_abc");
+        entry.record(DecisionKind::Naming, "first");
+        entry.record(DecisionKind::Capture, "second");
+        entry.record(DecisionKind::Naming, "third");
+
+        let naming: Vec<&str> = entry
+            .decisions_of(DecisionKind::Naming)
+            .map(|d| d.detail.as_str())
+            .collect();
+        assert_eq!(naming, vec!["first", "third"]);
+        assert_eq!(
+            entry.to_string(),
+            "component$ @ 10 -> App_component_abc\n  [naming] first\n  [capture] second\n  [naming] third\n"
+        );
+    }
+}
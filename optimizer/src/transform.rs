@@ -1,8 +1,11 @@
 #![allow(unused)]
 
 use crate::dead_code::DeadCode;
+use crate::defines::{replace_defines, validate_defines};
+use crate::diagnostic::{Diagnostic, DiagnosticCode, Severity};
 use crate::error::Error;
 use crate::ext::*;
+use crate::fold::prune_dead_branches;
 use crate::prelude::*;
 use crate::ref_counter::RefCounter;
 use crate::segment::{Segment, SegmentBuilder};
@@ -18,26 +21,71 @@ use oxc_ast::{
 use oxc_codegen::{Codegen, CodegenOptions, Context, Gen};
 use oxc_index::Idx;
 use std::borrow::{Borrow, Cow};
-use std::collections::{BTreeSet, HashMap, HashSet};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
 
+use crate::awaits::{is_async_function, top_level_awaits};
+use crate::capture::{
+    inject_lexical_scope, inlinable_value, inline_constants, Captures, Unserializable,
+    USE_LEXICAL_SCOPE,
+};
+use crate::chunk::ChunkResolver;
+use crate::cjs::{to_commonjs, ModuleFormat};
+use crate::classify::{classify_program, classify_source, ModuleKind};
+use crate::comments::PreservedComments;
 use crate::component::*;
+use crate::entry_strategy::EntryPlan;
+use crate::esm::validate_esm;
+use crate::events::{EventListener, EventModifier, ModifierProblem};
+use crate::external::{
+    external_marker_diagnostics, is_external_path, ExternalMarkers, ExternalPaths,
+};
+use crate::glob::FileSet;
+use crate::hash::StableHasher;
 use crate::import_clean_up::ImportCleanUp;
+use crate::intern::Name;
+use crate::limits::{
+    cap_diagnostics, check_file_segments, check_project_segments, ResourceLimits,
+    DEFAULT_MAX_DIAGNOSTICS,
+};
+use crate::listener::SymbolListener;
 use crate::macros::*;
+#[cfg(feature = "manifest")]
+use crate::manifest::{Manifest, NameMapping};
+use crate::manifest::{SegmentKind, SegmentMetadata};
+use crate::methods::{marker_key, wrap_method_markers};
+use crate::name_registry::{NameClaims, NameRegistry};
+use crate::preprocess::{Passthrough, Preprocessor};
+use crate::pretty::{write_indented, Truncated};
+use crate::routes::{is_endpoint_only, route_export_diagnostic, RouteExportKind};
+use crate::shadowing::avoid_shadowing;
 use crate::source::Source;
+use crate::stats::{millis, Stopwatch, TransformStats};
+use crate::strip::{strip_exports, SERVER_MARKERS, STRIPPED_EXPORT_ERROR};
+use crate::styles::{static_style, ScopeClasses, ScopedStyle, COMPONENT, USE_STYLES_SCOPED};
+use crate::symbol_registry::SymbolRegistry;
+use crate::sync::{self, QRL_SYNC, SYNC_MARKER};
+use crate::trace::{DecisionKind, TraceEntry};
+use crate::transpile::Transpile;
+use base64::{engine, Engine};
 use oxc_parser::Parser;
 use oxc_semantic::{
-    NodeId, ReferenceId, ScopeFlags, ScopeId, SemanticBuilder, SemanticBuilderReturn, SymbolFlags,
-    SymbolId,
+    NodeId, ReferenceFlags, ReferenceId, ScopeFlags, ScopeId, SemanticBuilder,
+    SemanticBuilderReturn, SymbolFlags, SymbolId,
 };
 use oxc_span::*;
 use oxc_traverse::{traverse_mut, Ancestor, Traverse, TraverseCtx};
+use rayon::prelude::*;
+#[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
 use std::cell::{Cell, RefCell};
 use std::fmt::{write, Display, Pointer};
+use std::hash::Hasher;
 use std::ops::Deref;
-use std::path::{Components, PathBuf};
+use std::path::{Components, Path, PathBuf};
+use std::time::Duration;
 
-#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Default, Serialize)]
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct OptimizedApp {
     pub body: String,
     pub components: Vec<QrlComponent>,
@@ -45,7 +93,10 @@ pub struct OptimizedApp {
 
 use crate::ext::*;
 use crate::illegal_code::{IllegalCode, IllegalCodeType};
+use crate::pool::ALLOCATORS;
 use crate::processing_failure::ProcessingFailure;
+use crate::props::destructure_props;
+use crate::recover::Recovered;
 
 impl OptimizedApp {
     fn get_component(&self, name: String) -> Option<&QrlComponent> {
@@ -81,17 +132,149 @@ impl Display for OptimizedApp {
     }
 }
 
+impl std::fmt::Debug for OptimizedApp {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("OptimizedApp")
+            .field("body", &Truncated(&self.body))
+            .field("components", &self.components)
+            .finish()
+    }
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "camelCase"))]
 pub struct OptimizationResult {
     optimized_app: OptimizedApp,
+    /// Serialized as messages, and not deserialized: the [diagnostics](OptimizationResult::diagnostics) report them
+    /// as well.
+    #[cfg_attr(feature = "serde", serde(skip_deserializing))]
     errors: Vec<ProcessingFailure>,
+    diagnostics: Vec<Diagnostic>,
+    trace: Vec<TraceEntry>,
+    entry_plan: EntryPlan,
+    styles: Vec<ScopedStyle>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    kind: ModuleKind,
+    #[cfg_attr(feature = "serde", serde(default))]
+    stats: TransformStats,
 }
 
 impl OptimizationResult {
-    pub fn new(optimized_app: OptimizedApp, errors: Vec<ProcessingFailure>) -> Self {
+    pub fn new(
+        optimized_app: OptimizedApp,
+        errors: Vec<ProcessingFailure>,
+        diagnostics: Vec<Diagnostic>,
+        trace: Vec<TraceEntry>,
+        entry_plan: EntryPlan,
+        styles: Vec<ScopedStyle>,
+    ) -> Self {
         Self {
             optimized_app,
             errors,
+            diagnostics,
+            trace,
+            entry_plan,
+            styles,
+            kind: ModuleKind::Transformed,
+            stats: TransformStats::default(),
+        }
+    }
+
+    /// Records the time spent parsing the module and generating its code, see [TransformStats].
+    fn with_timings(mut self, parse_time: Duration, codegen_time: Duration) -> Self {
+        self.stats.parse_ms = millis(parse_time);
+        self.stats.codegen_ms = millis(codegen_time);
+        self
+    }
+
+    /// The result of a module emitted as it was provided, see [ModuleKind].
+    fn unchanged(source_text: &str, kind: ModuleKind, entry_strategy: EntryStrategy) -> Self {
+        let optimized_app = OptimizedApp {
+            body: source_text.to_string(),
+            components: Vec::new(),
+        };
+        Self {
+            kind,
+            ..Self::new(
+                optimized_app,
+                Vec::new(),
+                Vec::new(),
+                Vec::new(),
+                EntryPlan::new(entry_strategy, &[]),
+                Vec::new(),
+            )
+        }
+    }
+
+    /// The result of a module which was not transformed because of `diagnostic`: it is emitted empty.
+    fn rejected(diagnostic: Diagnostic, entry_strategy: EntryStrategy) -> Self {
+        Self::new(
+            OptimizedApp::default(),
+            Vec::new(),
+            vec![diagnostic],
+            Vec::new(),
+            EntryPlan::new(entry_strategy, &[]),
+            Vec::new(),
+        )
+    }
+
+    pub fn optimized_app(&self) -> &OptimizedApp {
+        &self.optimized_app
+    }
+
+    pub fn errors(&self) -> &[ProcessingFailure] {
+        &self.errors
+    }
+
+    /// Every problem found in the module, including syntax errors, located in its source.
+    pub fn diagnostics(&self) -> &[Diagnostic] {
+        &self.diagnostics
+    }
+
+    /// The decision trace recorded for each `$` call site. Empty unless [TransformOptions::trace] is enabled.
+    pub fn trace(&self) -> &[TraceEntry] {
+        &self.trace
+    }
+
+    /// The assignment of every extracted component to an entry under [TransformOptions::entry_strategy].
+    pub fn entry_plan(&self) -> &EntryPlan {
+        &self.entry_plan
+    }
+
+    /// The stylesheets registered with `useStylesScoped$`, for the bundler to emit.
+    pub fn styles(&self) -> &[ScopedStyle] {
+        &self.styles
+    }
+
+    /// Whether the module was transformed, or emitted as it was provided because there was nothing to transform.
+    pub fn kind(&self) -> ModuleKind {
+        self.kind
+    }
+
+    /// What transforming the module cost.
+    pub fn stats(&self) -> &TransformStats {
+        &self.stats
+    }
+}
+
+/// Renders a tree-like summary of the result, truncating every code body.
+impl Display for OptimizationResult {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let components = &self.optimized_app.components;
+
+        writeln!(f, "OptimizationResult")?;
+        writeln!(f, "├── body:")?;
+        write_indented(f, "│   ", &Truncated(&self.optimized_app.body).to_string())?;
+        writeln!(f, "├── components[{}]:", components.len())?;
+        for comp in components {
+            write_indented(f, "│   ", &comp.to_string())?;
+        }
+        writeln!(f, "└── errors[{}]:", self.errors.len())?;
+        for error in &self.errors {
+            writeln!(f, "    {:?}", error)?;
         }
+        Ok(())
     }
 }
 
@@ -102,6 +285,18 @@ pub struct TransformGenerator<'gen> {
 
     pub errors: Vec<ProcessingFailure>,
 
+    pub diagnostics: Vec<Diagnostic>,
+
+    /// The first error which stopped the extraction of a segment, returned once the module is traversed.
+    failure: Option<Error>,
+
+    pub trace: Vec<TraceEntry>,
+
+    pub styles: Vec<ScopedStyle>,
+
+    /// The `useStylesScoped$` segments (symbol name and static stylesheet) of every enclosing `component$` call.
+    scoped_styles_stack: Vec<Vec<(String, Option<String>)>>,
+
     depth: usize,
 
     segment_stack: Vec<Segment>,
@@ -114,45 +309,125 @@ pub struct TransformGenerator<'gen> {
 
     qrl_stack: Vec<Qrl>,
 
+    /// The length of `qrl_stack` when entering each `$`-suffixed object or class member, whose value is replaced by
+    /// the QRL pushed while traversing it, see [wrap_method_markers].
+    member_qrl_stack: Vec<usize>,
+
+    /// The length of `qrl_stack` when entering each conditional or logical expression. Its operands are replaced in
+    /// place by the QRLs of their `$()` calls, so that every branch references its own segment, and the QRLs pushed
+    /// while traversing it are discarded rather than replacing the whole expression.
+    branch_qrl_stack: Vec<usize>,
+
+    /// Whether each enclosing plain object property or assignment pushed a segment naming the standalone marker call
+    /// it is assigned, see [TransformGenerator::push_standalone_segment].
+    standalone_stack: Vec<bool>,
+
+    /// The number of enclosing calls whose segments are stripped, see [TransformOptions::strips_segment].
+    stripped_depth: usize,
+
     import_stack: Vec<BTreeSet<Import>>,
 
+    /// Whether each enclosing JSX opening element is an intrinsic element, whose listener attributes are normalized.
+    intrinsic_stack: Vec<bool>,
+
+    /// The listeners of the enclosing JSX attributes of intrinsic elements, with the spans of their possible values,
+    /// i.e. the value and the branches of conditional values, see [branch_spans].
+    listener_stack: Vec<(Vec<Span>, EventListener)>,
+
     import_by_symbol: HashMap<SymbolId, Import>,
 
     removed: HashMap<SymbolId, IllegalCodeType>,
 
+    /// Variables initialized with a value that can not be captured. Only tracked with
+    /// [TransformOptions::strict_captures].
+    unserializable: HashMap<SymbolId, Unserializable>,
+
+    /// The printed values of the `const` variables which are inlined into the segments capturing them. Only tracked
+    /// with [TransformOptions::inline_const_captures].
+    constants: HashMap<SymbolId, String>,
+
     source_info: &'gen SourceInfo,
 
-    target: Target,
+    /// The text of the module, used to fingerprint segments for the [NameRegistry].
+    source_text: &'gen str,
+
+    /// The directives and comments of the module copied into its segments.
+    preserved_comments: PreservedComments<'gen>,
 
-    scope: Option<String>,
+    /// The first extracted segment of every [content key](QrlComponent::content_key), with
+    /// [TransformOptions::dedupe_segments].
+    segment_by_content: HashMap<u64, QrlComponent>,
 
-    minify: bool,
+    /// The index in `components` of the segment extracted from every marker call, by the span of the call.
+    segment_by_span: HashMap<Span, usize>,
+
+    /// The index in `components` of every segment nested in a marker call, with the spans of the enclosing marker
+    /// calls, innermost first, resolved into its parent by [TransformGenerator::link_parents].
+    enclosing_markers: Vec<(usize, Vec<Span>)>,
+
+    /// The provenance comment of the segment emitted to every file name, with
+    /// [TransformOptions::provenance_comments].
+    provenance_by_file: BTreeMap<String, String>,
+
+    /// The call which first produced every symbol name of the module, see [TransformGenerator::claim_symbol_name].
+    span_by_symbol_name: HashMap<Name, Span>,
+
+    /// The time spent generating the code of the host module and of the segments, see [TransformStats::codegen_ms].
+    codegen_time: Duration,
+
+    /// The name every top-level binding of the module is exported as, see [QrlComponent::exported].
+    exported_names: HashMap<String, String>,
+
+    options: TransformOptions,
 }
 
 impl<'gen> TransformGenerator<'gen> {
     fn new(
         source_info: &'gen SourceInfo,
-        minify: bool,
-        target: Target,
-        scope: Option<String>,
+        source_text: &'gen str,
+        preserved_comments: PreservedComments<'gen>,
+        options: TransformOptions,
+        claims: Option<NameClaims>,
     ) -> Self {
         Self {
             components: Vec::new(),
             app: OptimizedApp::default(),
             errors: Vec::new(),
+            diagnostics: Vec::new(),
+            trace: Vec::new(),
+            styles: Vec::new(),
+            scoped_styles_stack: Vec::new(),
             depth: 0,
             segment_stack: Vec::new(),
-            segment_builder: SegmentBuilder::new(),
+            segment_builder: claims
+                .map_or_else(SegmentBuilder::new, SegmentBuilder::with_claims)
+                .with_markers(options.markers.clone()),
             symbol_by_name: Default::default(),
             component_stack: Vec::new(),
             qrl_stack: Vec::new(),
+            member_qrl_stack: Vec::new(),
+            branch_qrl_stack: Vec::new(),
+            standalone_stack: Vec::new(),
+            stripped_depth: 0,
             import_stack: vec![BTreeSet::new()],
+            intrinsic_stack: Vec::new(),
+            listener_stack: Vec::new(),
             import_by_symbol: Default::default(),
             removed: HashMap::new(),
+            unserializable: HashMap::new(),
+            constants: HashMap::new(),
             source_info,
-            target,
-            scope,
-            minify,
+            source_text,
+            preserved_comments,
+            segment_by_content: HashMap::new(),
+            segment_by_span: HashMap::new(),
+            enclosing_markers: Vec::new(),
+            provenance_by_file: BTreeMap::new(),
+            span_by_symbol_name: HashMap::new(),
+            codegen_time: Duration::ZERO,
+            exported_names: HashMap::new(),
+            failure: None,
+            options,
         }
     }
 
@@ -202,32 +477,550 @@ impl<'gen> TransformGenerator<'gen> {
         }
     }
 
-    fn new_segment<T: AsRef<str>>(&mut self, input: T) -> Segment {
-        self.segment_builder.new_segment(input, &self.segment_stack)
+    /// Creates the segment for the node at `span`.
+    ///
+    /// For the [NameRegistry], an occurrence of a QRL segment is identified by the first line of its source text, so
+    /// that editing the body of a segment (or of a segment nested in it) does not change its identity.
+    fn new_segment<T: AsRef<str>>(&mut self, input: T, span: Span) -> Segment {
+        let fingerprint = if self.segment_builder.has_claims() {
+            let text = span.source_text(self.source_text);
+            let mut hasher = StableHasher::new();
+            hasher.write(text.lines().next().unwrap_or_default().trim().as_bytes());
+            hasher.finish()
+        } else {
+            0
+        };
+        self.segment_builder
+            .new_segment_at(input, &self.segment_stack, fingerprint)
+    }
+
+    /// The origin of the segment whose source is at `span`, if enabled by [TransformOptions::embed_origin].
+    fn qrl_dev(&self, span: Span, comp: &QrlComponent) -> Option<QrlDev> {
+        (self.options.embed_origin && self.options.target == Target::Lib).then(|| QrlDev {
+            file: comp.origin.clone(),
+            lo: span.start,
+            hi: span.end,
+            display_name: comp.id.display_name.to_string(),
+        })
+    }
+
+    /// The provenance comment of the segment whose source is at `span`, e.g. `src/app.tsx:3:31 app.tsx_App_component`,
+    /// if enabled by [TransformOptions::provenance_comments].
+    fn provenance(&self, span: Span, comp: &QrlComponent) -> Option<String> {
+        if !self.options.provenance_comments || self.options.target != Target::Dev {
+            return None;
+        }
+        let before = &self.source_text[..span.start as usize];
+        let line = before.matches('\n').count() + 1;
+        let column = before
+            .rsplit('\n')
+            .next()
+            .unwrap_or_default()
+            .chars()
+            .count()
+            + 1;
+        let comment = format!("{}:{line}:{column} {}", comp.origin, comp.id.display_name);
+        Some(comment.replace("*/", "* /"))
+    }
+
+    /// Annotates the QRLs in `code` with the provenance of the segments they import, e.g.
+    /// `import(/* src/app.tsx:3:31 app.tsx_App_component */ "./app.tsx_App_component_ckEPmXZlub0")`.
+    fn annotate_qrls(code: String, provenance_by_specifier: &[(String, String)]) -> String {
+        provenance_by_specifier
+            .iter()
+            .fold(code, |code, (specifier, comment)| {
+                let import = format!("import(\"{specifier}\")");
+                code.replace(&import, &format!("import(/* {comment} */ \"{specifier}\")"))
+            })
+    }
+
+    /// The listener declared by `attribute` if it is set on an intrinsic element, e.g. `<button onClick$={...} />`.
+    /// Listener-like props of components are passed on as they are.
+    fn listener_of(&self, attribute: &JSXAttribute) -> Option<EventListener> {
+        if self.intrinsic_stack.last() != Some(&true) {
+            return None;
+        }
+        match &attribute.name {
+            JSXAttributeName::Identifier(name) => EventListener::from_attribute(&name.name),
+            JSXAttributeName::NamespacedName(name) => EventListener::from_attribute(&format!(
+                "{}:{}",
+                name.namespace.name, name.property.name
+            )),
+        }
+    }
+
+    /// The `preventdefault:` or `stoppropagation:` modifier set by `attribute` on an intrinsic element, and the
+    /// problem found in it, see [EventModifier::from_attribute].
+    fn modifier_of(
+        &self,
+        attribute: &JSXAttribute,
+    ) -> Option<(EventModifier, Option<ModifierProblem>)> {
+        if self.intrinsic_stack.last() != Some(&true) {
+            return None;
+        }
+        match &attribute.name {
+            JSXAttributeName::NamespacedName(name) => {
+                EventModifier::from_attribute(&name.namespace.name, &name.property.name)
+            }
+            JSXAttributeName::Identifier(_) => None,
+        }
+    }
+
+    /// Registers the symbol name of the segment on top of the stack, extracted by the call at `span`.
+    ///
+    /// Names are sanitized into identifiers, so distinct segments can produce the same symbol name, e.g. from the
+    /// keys `a-b$` and `a_b$`. The index of a colliding segment is then incremented until its name is unique, which
+    /// depends only on the order of the segments in the module, and a [DiagnosticCode::SymbolCollision] is reported.
+    fn claim_symbol_name(&mut self, span: Span) {
+        let id = Id::from_options(self.source_info, &self.segment_stack, &self.options);
+        let Some(first) = self.span_by_symbol_name.get(&id.symbol_name).copied() else {
+            self.span_by_symbol_name.insert(id.symbol_name, span);
+            return;
+        };
+
+        let symbol_name = loop {
+            match self.segment_stack.last_mut() {
+                Some(Segment::NamedQrl(_, index) | Segment::IndexQrl(index)) => *index += 1,
+                _ => return,
+            }
+            let renamed = Id::from_options(self.source_info, &self.segment_stack, &self.options);
+            if !self.span_by_symbol_name.contains_key(&renamed.symbol_name) {
+                break renamed.symbol_name;
+            }
+        };
+        self.diagnostics.push(
+            Diagnostic::warning(
+                DiagnosticCode::SymbolCollision,
+                format!(
+                    "The segment is named `{}` like a previous segment, and was renamed to `{symbol_name}`",
+                    id.symbol_name
+                ),
+            )
+            .with_label(
+                span,
+                Some(format!(
+                    "renamed, the previous segment starts at offset {}",
+                    first.start
+                )),
+            )
+            .with_suggestion("Rename one of the segments, so that their names differ in letters or digits"),
+        );
+        self.span_by_symbol_name.insert(symbol_name, span);
+    }
+
+    /// Returns `true` if a reference to `symbol_id` at the current node crosses a segment boundary, i.e. the
+    /// innermost marker call enclosing the reference does not enclose the declaration of the symbol.
+    fn is_captured(&self, symbol_id: SymbolId, ctx: &TraverseCtx<'_>) -> bool {
+        let declaration = ctx.symbols().get_span(symbol_id);
+        self.marker_spans(ctx)
+            .next()
+            .is_some_and(|segment| !segment.contains_inclusive(declaration))
+    }
+
+    /// The spans of the marker calls enclosing the current node, innermost first.
+    fn marker_spans<'t>(&'t self, ctx: &'t TraverseCtx<'_>) -> impl Iterator<Item = Span> + 't {
+        ctx.ancestors().filter_map(|ancestor| match ancestor {
+            Ancestor::CallExpressionArguments(call) => match call.callee() {
+                Expression::Identifier(callee) if self.options.markers.is_marker(&callee.name) => {
+                    Some(*call.span())
+                }
+                _ => None,
+            },
+            _ => None,
+        })
+    }
+
+    /// The name the module exports the declaration being traversed as, if it is a top-level declaration rather than
+    /// nested in a function or class, see [QrlComponent::exported].
+    fn exported_name(&self, ctx: &TraverseCtx<'gen>) -> Option<String> {
+        let mut declared = None;
+        for ancestor in ctx.ancestors() {
+            match ancestor {
+                Ancestor::VariableDeclaratorInit(declarator) => {
+                    declared = declarator.id().get_identifier_name();
+                }
+                Ancestor::ExportDefaultDeclarationDeclaration(_) => return Some("default".into()),
+                Ancestor::FunctionBody(_)
+                | Ancestor::ArrowFunctionExpressionBody(_)
+                | Ancestor::ClassBody(_) => return None,
+                _ => {}
+            }
+        }
+        self.exported_names.get(declared?.as_str()).cloned()
+    }
+
+    /// Reports a loader or action created at `span` which the router can not find by its export name, see
+    /// [RouteExportKind]. Returns whether it is exported by name, i.e. whether its segment is a route export.
+    fn check_route_export(
+        &mut self,
+        kind: RouteExportKind,
+        span: Span,
+        ctx: &TraverseCtx<'gen>,
+    ) -> bool {
+        // Only the direct value of the declaration is exported, not e.g. `export const x = wrap(routeLoader$(...))`.
+        let exported = match ctx.parent() {
+            Ancestor::VariableDeclaratorInit(_) => self.exported_name(ctx),
+            _ => None,
+        };
+        let diagnostic = route_export_diagnostic(kind, exported.as_deref(), span);
+        let valid = diagnostic
+            .as_ref()
+            .is_none_or(|diagnostic| diagnostic.severity == Severity::Warning);
+        self.diagnostics.extend(diagnostic);
+        valid
+    }
+
+    /// The value of `result`, or `None` once its error is recorded as the [failure](Self::failure) of the transform.
+    fn succeeded<T>(&mut self, result: Option<Result<T>>) -> Option<T> {
+        match result? {
+            Ok(value) => Some(value),
+            Err(error) => {
+                self.failure.get_or_insert(error);
+                None
+            }
+        }
+    }
+
+    /// Re-exports every segment extracted from an exported declaration from the host module, see
+    /// [TransformOptions::reexport_segments].
+    fn reexport_segments(&self, program: &mut Program<'gen>, ast: AstBuilder<'gen>) {
+        for comp in &self.components {
+            if comp.exported.is_some() {
+                program
+                    .body
+                    .push(ast.create_export_statement(&comp.id.symbol_name, comp.qrl.specifier()));
+            }
+        }
+    }
+
+    /// Sets the [parent](QrlComponent::parent) of every extracted segment nested in another one, to the innermost
+    /// enclosing segment which was extracted. Parents are only known once they are extracted themselves, after the
+    /// segments nested in them.
+    fn link_parents(&mut self) {
+        for (index, markers) in std::mem::take(&mut self.enclosing_markers) {
+            let Some(parent) = markers
+                .iter()
+                .find_map(|span| self.segment_by_span.get(span))
+                .map(|parent| self.components[*parent].id.clone())
+            else {
+                continue;
+            };
+            let comp = &mut self.components[index];
+            comp.parent = Some(parent.symbol_name.to_string());
+            comp.id.parent_hash = Some(parent.hash);
+        }
+    }
+
+    /// The [excerpt](QrlComponent::excerpt) of the segment whose source is at `span`, if enabled by
+    /// [TransformOptions::source_excerpt_length].
+    fn source_excerpt(&self, span: Span) -> Option<String> {
+        let length = self
+            .options
+            .source_excerpt_length
+            .filter(|_| self.options.target == Target::Dev)?;
+        let text = span.source_text(self.source_text);
+        match text.char_indices().nth(length) {
+            Some((end, _)) => Some(format!("{}…", &text[..end])),
+            None => Some(text.to_string()),
+        }
+    }
+
+    /// Replaces `sync$(fn)` with `_qrlSync(fn, "fn")`, reporting every reference of `fn` to a variable declared
+    /// outside of it.
+    fn exit_sync_call(&mut self, node: &mut CallExpression<'gen>, ctx: &mut TraverseCtx<'gen>) {
+        // A synchronous function may not reference any import, so the ones recorded for it are dropped.
+        self.import_stack.pop();
+
+        let Some(function) = node
+            .arguments
+            .first()
+            .and_then(Argument::as_expression)
+            .filter(|expr| sync::is_function(expr))
+        else {
+            self.diagnostics.push(
+                Diagnostic::error(
+                    DiagnosticCode::InvalidQrlArgument,
+                    format!("`{SYNC_MARKER}` expects a function as its first argument"),
+                )
+                .with_label(node.span, None)
+                .with_suggestion(format!(
+                    "Pass the event handler, e.g. `{SYNC_MARKER}((event) => event.preventDefault())`"
+                )),
+            );
+            return;
+        };
+        if is_async_function(function) {
+            self.diagnostics.push(
+                Diagnostic::error(
+                    DiagnosticCode::AwaitInSyncContext,
+                    format!("`{SYNC_MARKER}` functions run synchronously while the event is dispatched, and can not be `async`"),
+                )
+                .with_label(function.span(), None)
+                .with_suggestion("Remove `async`, and do the asynchronous work in a `$` handler of the same event"),
+            );
+        }
+        for (name, span) in sync::external_references(function, ctx.symbols()) {
+            self.diagnostics.push(
+                Diagnostic::error(
+                    DiagnosticCode::SyncCapture,
+                    format!("`{SYNC_MARKER}` functions can not reference `{name}`, which is declared outside of them"),
+                )
+                .with_label(span, Some("referenced here".to_string()))
+                .with_suggestion("Declare the value in the function, or read it from the event or the element it receives"),
+            );
+        }
+
+        let text = ctx.ast.atom(&sync::serialize(function));
+        let function = node.arguments.remove(0);
+        let reference = ctx.create_unbound_reference(QRL_SYNC, ReferenceFlags::Read);
+        let callee = Expression::Identifier(OxcBox::new_in(
+            ctx.ast
+                .identifier_reference_with_reference_id(SPAN, QRL_SYNC, reference),
+            ctx.ast.allocator,
+        ));
+        let mut arguments = ctx.ast.vec_with_capacity(2);
+        arguments.push(function);
+        arguments.push(Argument::StringLiteral(OxcBox::new_in(
+            ctx.ast.string_literal(SPAN, text, None),
+            ctx.ast.allocator,
+        )));
+        *node = ctx.ast.call_expression(
+            node.span,
+            callee,
+            None::<OxcBox<TSTypeParameterInstantiation>>,
+            arguments,
+            false,
+        );
+        if let Some(imports) = self.import_stack.last_mut() {
+            imports.insert(Import::new(vec![QRL_SYNC.into()], QWIK_CORE_SOURCE));
+        }
+    }
+
+    /// Whether the parent of the marker call being exited replaces it with the QRL the call pushes onto `qrl_stack`:
+    /// arguments, variable initializers, JSX attribute values and the values of `$`-suffixed members.
+    ///
+    /// Every marker call is already replaced in place with its QRL, so a standalone call anywhere else, e.g. in an
+    /// array or as the value of a plain property, pushes nothing: an enclosing parent would otherwise pop its QRL and
+    /// be replaced by it as a whole.
+    fn consumes_qrl(ctx: &TraverseCtx<'gen>) -> bool {
+        match ctx.parent() {
+            Ancestor::CallExpressionArguments(_)
+            | Ancestor::NewExpressionArguments(_)
+            | Ancestor::VariableDeclaratorInit(_) => true,
+            Ancestor::JSXExpressionContainerExpression(_) => {
+                matches!(ctx.ancestor(1), Ancestor::JSXAttributeValue(_))
+            }
+            Ancestor::ObjectPropertyValue(property) => {
+                marker_key(property.key(), *property.computed()).is_some()
+            }
+            Ancestor::PropertyDefinitionValue(property) => {
+                marker_key(property.key(), *property.computed()).is_some()
+            }
+            _ => false,
+        }
+    }
+
+    /// The name a standalone marker call assigned to `target` is named after, e.g. `onTick` for
+    /// `window.onTick = event$(...)`.
+    fn assignment_name<'t>(target: &'t AssignmentTarget) -> Option<Cow<'t, str>> {
+        match target {
+            AssignmentTarget::AssignmentTargetIdentifier(id) => Some(id.name.as_str().into()),
+            AssignmentTarget::StaticMemberExpression(member) => {
+                Some(member.property.name.as_str().into())
+            }
+            _ => None,
+        }
+    }
+
+    /// Pushes a segment named `name` for the property or assignment being entered, if its value is a standalone
+    /// marker call, see [TransformGenerator::pop_standalone_segment].
+    fn push_standalone_segment(&mut self, name: Option<Cow<str>>, span: Span) {
+        if let Some(name) = &name {
+            let segment: Segment = self.new_segment(name, span);
+            self.segment_stack.push(segment);
+        }
+        self.standalone_stack.push(name.is_some());
+    }
+
+    /// Pops the segment pushed for the property or assignment being exited, whose marker call is replaced by now.
+    fn pop_standalone_segment(&mut self) {
+        if self.standalone_stack.pop().unwrap_or_default() {
+            self.segment_stack.pop();
+        }
+    }
+
+    /// Pops the QRL extracted from the value of the `$`-suffixed member being exited, if any. Inlined and stripped
+    /// segments replace their call in place, and never push one.
+    fn pop_member_qrl(&mut self) -> Option<Qrl> {
+        let depth = self.member_qrl_stack.pop()?;
+        (self.qrl_stack.len() > depth)
+            .then(|| self.qrl_stack.pop())
+            .flatten()
+    }
+
+    /// Discards the QRLs pushed by the operands of the conditional or logical expression being exited, which already
+    /// replaced their `$()` calls, e.g. in `cond ? $(a) : $(b)` or `handler ?? $(fallback)`.
+    fn discard_branch_qrls(&mut self) {
+        if let Some(depth) = self.branch_qrl_stack.pop() {
+            self.qrl_stack.truncate(depth);
+        }
+    }
+
+    /// Records why the segment for `comp` was named, extracted and which imports it captured.
+    fn record_trace(
+        &mut self,
+        call_site: &str,
+        start: u32,
+        comp: &QrlComponent,
+        imports: &[Import],
+    ) {
+        let mut entry = TraceEntry::new(call_site, start, &comp.id.symbol_name);
+        entry.record(
+            DecisionKind::Naming,
+            format!(
+                "segments `{}` produced display name `{}`",
+                self.render_segments(),
+                comp.id.display_name
+            ),
+        );
+        entry.record(
+            DecisionKind::Naming,
+            format!(
+                "target {:?} produced symbol name `{}`",
+                self.options.target, comp.id.symbol_name
+            ),
+        );
+        entry.record(
+            DecisionKind::EntryStrategy,
+            format!(
+                "{:?} assigned entry `{}`",
+                self.options.entry_strategy,
+                comp.entry.as_deref().unwrap_or("<own chunk>")
+            ),
+        );
+        entry.record(
+            DecisionKind::Extraction,
+            format!("extracted to `{}`", comp.qrl.rel_path.to_string_lossy()),
+        );
+        if imports.is_empty() {
+            entry.record(DecisionKind::Capture, "no imports captured");
+        }
+        for import in imports {
+            entry.record(DecisionKind::Capture, format!("captured import {import}"));
+        }
+        for name in &comp.qrl.captures {
+            entry.record(
+                DecisionKind::Capture,
+                format!("captured `{name}` from the enclosing scope"),
+            );
+        }
+        self.trace.push(entry);
+    }
+}
+
+/// The name every top-level binding declared or listed by the exports of `body` is exported as. A binding exported
+/// under several names keeps the first one.
+fn exported_names(body: &[Statement]) -> HashMap<String, String> {
+    let mut names = HashMap::new();
+    for statement in body {
+        let Statement::ExportNamedDeclaration(export) = statement else {
+            continue;
+        };
+        if let Some(Declaration::VariableDeclaration(declaration)) = &export.declaration {
+            for name in declaration
+                .declarations
+                .iter()
+                .filter_map(|declarator| declarator.id.get_identifier_name())
+            {
+                names.entry(name.to_string()).or_insert(name.to_string());
+            }
+        }
+        if export.source.is_none() {
+            for specifier in &export.specifiers {
+                names
+                    .entry(specifier.local.name().to_string())
+                    .or_insert(specifier.exported.name().to_string());
+            }
+        }
+    }
+    names
+}
+
+/// Transpiles the host module and generates its code.
+fn render_host<'a>(
+    program: &mut Program<'a>,
+    source_info: &SourceInfo,
+    options: &TransformOptions,
+    preserved_comments: &PreservedComments,
+    allocator: &'a Allocator,
+) -> String {
+    Transpile::transpile(program, &source_info.rel_path, options, allocator);
+    avoid_shadowing(program, AstBuilder::new(allocator));
+    if options.module_format == ModuleFormat::Cjs {
+        to_commonjs(program, options.require_dynamic_imports, allocator);
     }
+
+    let codegen_options = CodegenOptions {
+        annotation_comments: true,
+        minify: options.is_minify() || options.is_compact(),
+        ..Default::default()
+    };
+
+    preserved_comments.print(Codegen::new(), codegen_options, program)
 }
 
-const DEBUG: bool = true;
+const DEBUG: bool = false;
 const DUMP_FINAL_AST: bool = false;
 
 impl<'a> Traverse<'a> for TransformGenerator<'a> {
+    fn enter_program(&mut self, node: &mut Program<'a>, _ctx: &mut TraverseCtx<'a>) {
+        self.exported_names = exported_names(&node.body);
+    }
+
     fn exit_program(&mut self, node: &mut Program<'a>, ctx: &mut TraverseCtx<'a>) {
         if let Some(tree) = self.import_stack.pop() {
             tree.iter().for_each(|import| {
                 node.body.insert(0, import.into_in(ctx.ast.allocator));
             });
         }
+        if self.options.reexport_segments {
+            self.reexport_segments(node, ctx.ast);
+        }
 
         ImportCleanUp::clean_up(node, ctx.ast.allocator);
+        self.link_parents();
 
-        let codegen_options = CodegenOptions {
-            annotation_comments: true,
-            minify: self.minify,
-            ..Default::default()
-        };
-        let codegen = Codegen::new().with_options(codegen_options);
+        let stopwatch = Stopwatch::start();
+        let mut body = render_host(
+            node,
+            self.source_info,
+            &self.options,
+            &self.preserved_comments,
+            ctx.ast.allocator,
+        );
+        self.codegen_time += stopwatch.elapsed();
 
-        let body = codegen.build(node).code;
+        if !self.provenance_by_file.is_empty() {
+            let provenance_by_specifier: Vec<(String, String)> = self
+                .components
+                .iter()
+                .filter_map(|comp| {
+                    let comment = self.provenance_by_file.get(&comp.id.local_file_name)?;
+                    Some((comp.qrl.specifier().to_string(), comment.clone()))
+                })
+                .collect();
+            body = Self::annotate_qrls(body, &provenance_by_specifier);
+            let mut components = std::mem::take(&mut self.components);
+            for comp in &mut components {
+                let code =
+                    Self::annotate_qrls(std::mem::take(&mut comp.code), &provenance_by_specifier);
+                comp.code = match self.provenance_by_file.get(&comp.id.local_file_name) {
+                    Some(comment) => format!("/* {comment} */\n{code}"),
+                    None => code,
+                };
+            }
+            self.components = components;
+        }
 
         self.app = OptimizedApp {
             body,
@@ -247,62 +1040,333 @@ impl<'a> Traverse<'a> for TransformGenerator<'a> {
         self.debug(format!("ENTER: CallExpression, {:?}", node), ctx);
 
         let name = node.callee_name().unwrap_or_default().to_string();
-        if (name.ends_with(MARKER_SUFFIX)) {
+        if self.options.markers.is_marker(&name) {
             self.import_stack.push(BTreeSet::new());
         }
+        if name == COMPONENT {
+            self.scoped_styles_stack.push(Vec::new());
+        }
+        if self.options.strips_segment(&name) {
+            self.stripped_depth += 1;
+        }
+        if let Some(listener) = use_on_listener(node) {
+            let spans = match node.arguments.get(1).and_then(Argument::as_expression) {
+                Some(handler) => branch_spans(handler),
+                None => Vec::new(),
+            };
+            self.listener_stack.push((spans, listener));
+        }
 
-        let segment: Segment = self.new_segment(name);
-        println!("push segment: {segment}");
+        let segment: Segment = if name == SYNC_MARKER {
+            // `sync$` functions are serialized in place rather than extracted, see `exit_sync_call`.
+            Segment::Named(name.trim_end_matches(MARKER_SUFFIX).to_string())
+        } else {
+            self.new_segment(name, node.span)
+        };
         self.segment_stack.push(segment);
     }
 
     fn exit_call_expression(&mut self, node: &mut CallExpression<'a>, ctx: &mut TraverseCtx<'a>) {
+        let call_site = node.callee_name().unwrap_or_default().to_string();
+        let stripped = self.options.strips_segment(&call_site);
+        if stripped {
+            self.stripped_depth -= 1;
+        }
+        // Segments nested in a stripped segment are stripped along with it.
+        let within_stripped = self.stripped_depth > 0;
+        let scoped_styles = if call_site == COMPONENT {
+            self.scoped_styles_stack.pop().unwrap_or_default()
+        } else {
+            Vec::new()
+        };
+        if use_on_listener(node).is_some() {
+            self.listener_stack.pop();
+        }
+        if call_site == SYNC_MARKER {
+            self.exit_sync_call(node, ctx);
+            self.segment_stack.pop();
+            return;
+        }
+
         let segment = self.segment_stack.last();
 
         if let Some(segment) = segment {
-            // let callee_name = node.callee_name().unwrap_or_default();
             if segment.is_qrl() {
-                let comp = node.arguments.first().map(|arg0| {
-                    let imports = self
-                        .import_stack
-                        .pop()
-                        .unwrap_or_default()
-                        .iter()
-                        .cloned()
-                        .collect();
+                let span = node.span;
+                let start = span.start;
+
+                if !node.arguments.first().is_some_and(Argument::is_expression) {
+                    self.diagnostics.push(
+                        Diagnostic::error(
+                            DiagnosticCode::InvalidQrlArgument,
+                            format!("`{call_site}` expects an expression as its first argument"),
+                        )
+                        .with_label(node.span, None)
+                        .with_suggestion(format!(
+                            "Pass the value to extract, e.g. `{call_site}(() => ...)`"
+                        )),
+                    );
+                    self.import_stack.pop();
+                    self.segment_stack.pop();
+                    return;
+                }
+
+                self.claim_symbol_name(node.span);
+                let route_export = RouteExportKind::from_marker(&call_site)
+                    .filter(|&kind| self.check_route_export(kind, span, ctx));
+
+                // The elements of a component with scoped styles are tagged with the scope class before the component
+                // is extracted. The scope id is the hash of the component's `Id`.
+                if !scoped_styles.is_empty() {
+                    let id = Id::from_options(self.source_info, &self.segment_stack, &self.options);
+                    if let Some(arg0) = node.arguments.first_mut() {
+                        ScopeClasses::new(ctx.ast, &id.hash).visit_argument(arg0);
+                    }
+                }
+
+                let style = node.arguments.first().and_then(static_style);
+
+                let (mut captures, mut inlined, failures) = node
+                    .arguments
+                    .first()
+                    .map(|arg0| {
+                        let removed = &self.removed;
+                        Captures::collect(
+                            arg0,
+                            ctx.symbols(),
+                            ctx.scopes(),
+                            &|symbol_id| removed.contains_key(&symbol_id),
+                            &self.unserializable,
+                            &self.constants,
+                        )
+                    })
+                    .unwrap_or_default();
+                if !inlined.is_empty() {
+                    let segment = node
+                        .arguments
+                        .first_mut()
+                        .and_then(|arg0| arg0.as_expression_mut());
+                    let inlined_into =
+                        segment.map(|expr| inline_constants(expr, &inlined, ctx.ast));
+                    // Only functions can declare the constants they use, anything else captures them.
+                    if !self.succeeded(inlined_into).unwrap_or_default() {
+                        captures.extend(std::mem::take(&mut inlined).into_keys());
+                        captures.sort();
+                    }
+                }
+                for (failure, span) in failures {
+                    self.diagnostics
+                        .push(Diagnostic::from_failure(&failure, span));
+                    self.errors.push(failure);
+                }
+                // Workers have no container to restore captures from with `useLexicalScope`, so they capture nothing.
+                if SegmentKind::from_ctx_name(&call_site) == SegmentKind::Worker {
+                    for name in std::mem::take(&mut captures) {
+                        self.diagnostics.push(
+                            Diagnostic::error(
+                                DiagnosticCode::WorkerCapture,
+                                format!("`{call_site}` closures run in a web worker and can not capture `{name}`"),
+                            )
+                            .with_label(span, None)
+                            .with_suggestion("Pass the value as an argument of the worker, or declare it in the closure"),
+                        );
+                    }
+                }
 
-                    QrlComponent::from_call_expression_argument(
+                let imports: Vec<Import> = self
+                    .import_stack
+                    .pop()
+                    .unwrap_or_default()
+                    .into_iter()
+                    .collect();
+                let comp = node.arguments.first().and_then(|arg0| {
+                    let traced_imports = self.options.trace.then(|| imports.clone());
+
+                    let stopwatch = Stopwatch::start();
+                    let comp = QrlComponent::from_call_expression_argument(
                         arg0,
-                        imports,
+                        imports.clone(),
+                        captures,
                         &self.segment_stack,
-                        &self.target,
-                        &self.scope,
+                        &self.options,
                         self.source_info,
-                        self.minify,
+                        &self.preserved_comments,
                         ctx.ast.allocator,
-                    )
+                    );
+                    self.codegen_time += stopwatch.elapsed();
+                    let mut comp = self.succeeded(Some(comp))?;
+                    // The QRL of a segment nested in another one is imported by the module of the enclosing segment.
+                    if self.import_stack.len() > 1 {
+                        let segment_dir = self.options.segment_dir(self.source_info);
+                        comp.qrl.import_specifier =
+                            comp.import_specifier(&segment_dir, self.options.import_extensions);
+                    }
+
+                    comp.qrl.inlined_captures = inlined;
+                    comp.excerpt = self.source_excerpt(arg0.span());
+                    comp.qrl.dev = self.qrl_dev(arg0.span(), &comp);
+                    if let Some(comment) = self.provenance(arg0.span(), &comp) {
+                        self.provenance_by_file
+                            .entry(comp.id.local_file_name.clone())
+                            .or_insert(comment);
+                    }
+                    comp.listener = self
+                        .listener_stack
+                        .last()
+                        .filter(|(spans, _)| spans.contains(&node.span))
+                        .map(|(_, listener)| listener.clone());
+                    if comp.listener.is_some() {
+                        comp.ctx_kind = SegmentKind::EventHandler;
+                    }
+                    let awaits = arg0
+                        .as_expression()
+                        .map(top_level_awaits)
+                        .unwrap_or_default();
+                    comp.async_only = !awaits.is_empty();
+                    // Handlers are loaded while their event is dispatched, which must not wait for anything else.
+                    if comp.ctx_kind == SegmentKind::EventHandler {
+                        for span in awaits {
+                            self.diagnostics.push(
+                                Diagnostic::error(
+                                    DiagnosticCode::AwaitInSyncContext,
+                                    "An event handler awaits outside of its function, while its segment is loaded",
+                                )
+                                .with_label(span, None)
+                                .with_suggestion("Await in the handler instead, e.g. `async (event) => { await ... }`"),
+                            );
+                        }
+                    }
+                    comp.entry = self
+                        .options
+                        .entry_strategy
+                        .entry_for(&SegmentMetadata::from(&comp));
+
+                    if let Some(imports) = traced_imports {
+                        self.record_trace(&call_site, start, &comp, &imports);
+                    }
+                    Some(comp)
+                });
+
+                // Small segments are kept in place, see `TransformOptions::inline_threshold_bytes`. The QRLs of the
+                // segments nested in them import their segments relative to the module of the segment, so they are
+                // not moved into another module.
+                let in_place = !stripped
+                    && (self.options.is_inline()
+                        || (comp.as_ref().is_some_and(|comp| self.options.inlines(comp))
+                            && !imports.iter().any(Import::creates_qrl)));
+
+                // A duplicate of an extracted segment is replaced with that segment, so that its QRL points at it.
+                let dedupe =
+                    self.options.dedupe_segments && !stripped && !within_stripped && !in_place;
+                let mut duplicate = false;
+                let comp = comp.map(|comp| {
+                    if !dedupe {
+                        return comp;
+                    }
+                    let key = comp.content_key();
+                    match self.segment_by_content.get(&key) {
+                        Some(first) => {
+                            duplicate = true;
+                            first.clone()
+                        }
+                        None => {
+                            self.segment_by_content.insert(key, comp.clone());
+                            comp
+                        }
+                    }
                 });
 
                 if let Some(comp) = &comp {
-                    let qrl = &comp.qrl;
-                    let qrl = qrl.clone();
-                    *node = qrl.into_call_expression(
-                        ctx,
-                        &mut self.symbol_by_name,
-                        &mut self.import_by_symbol,
-                    );
+                    if stripped {
+                        *node = comp
+                            .qrl
+                            .into_noop_call_expression(ctx, &mut self.symbol_by_name);
+                    } else if in_place {
+                        let mut expr = node.arguments.remove(0).into_expression();
+                        inject_lexical_scope(&mut expr, &comp.qrl.captures, ctx.ast);
+                        *node = comp.qrl.into_inlined_call_expression(
+                            expr,
+                            ctx,
+                            &mut self.symbol_by_name,
+                        );
+                    } else {
+                        let qrl = &comp.qrl;
+                        let qrl = qrl.clone();
+                        *node = qrl.into_call_expression(
+                            ctx,
+                            &mut self.symbol_by_name,
+                            &mut self.import_by_symbol,
+                        );
+                    }
+                }
+
+                if let Some(comp) = &comp {
+                    if call_site == USE_STYLES_SCOPED {
+                        if let Some(scoped_styles) = self.scoped_styles_stack.last_mut() {
+                            scoped_styles.push((comp.id.symbol_name.to_string(), style));
+                        }
+                    }
+                    for (symbol_name, style) in scoped_styles {
+                        self.styles.push(ScopedStyle {
+                            symbol_name,
+                            component: comp.id.symbol_name.to_string(),
+                            scope_id: comp.id.hash.to_string(),
+                            style,
+                        });
+                    }
                 }
 
-                if let Some(comp) = comp {
-                    let import: Import = comp.qrl.qrl_type.clone().into();
-                    self.qrl_stack.push(comp.qrl.clone());
-                    self.components.push(comp);
+                if let Some(comp) = comp.as_ref().filter(|_| stripped || in_place) {
+                    // Inlined and stripped segments are not extracted. Under `EntryStrategy::Inline` all segments stay
+                    // in the host, so their imports are added to the host rather than to the enclosing segment. Small
+                    // segments inlined anyway stay in the enclosing segment, which needs their imports.
+                    let local_qrl = if stripped { NOOP_QRL } else { INLINED_QRL };
+                    let mut added = vec![Import::new(vec![local_qrl.into()], QWIK_CORE_SOURCE)];
+                    if let QrlType::PrefixedQrl(_) = &comp.qrl.qrl_type {
+                        added.push(comp.qrl.qrl_type.clone().into());
+                    }
+                    if !stripped && !comp.qrl.captures.is_empty() {
+                        added.push(Import::new(
+                            vec![USE_LEXICAL_SCOPE.into()],
+                            QWIK_CORE_SOURCE,
+                        ));
+                    }
+                    if in_place && !self.options.is_inline() {
+                        added.extend(imports);
+                    }
+                    let imports = if self.options.is_inline() {
+                        self.import_stack.first_mut()
+                    } else {
+                        self.import_stack.last_mut()
+                    };
+                    if let Some(imports) = imports {
+                        imports.extend(added);
+                    }
+                } else if let Some(mut comp) = comp {
+                    let import = comp.qrl.import();
+                    if Self::consumes_qrl(ctx) {
+                        self.qrl_stack.push(comp.qrl.clone());
+                    }
+                    if !within_stripped && !duplicate {
+                        let index = self.components.len();
+                        self.segment_by_span.insert(span, index);
+                        let markers: Vec<Span> = self.marker_spans(ctx).collect();
+                        if markers.is_empty() {
+                            comp.exported = self.exported_name(ctx);
+                            comp.route_export = route_export;
+                        } else {
+                            self.enclosing_markers.push((index, markers));
+                        }
+                        self.components.push(comp);
+                    }
                     let parent_scope = ctx
                         .ancestor_scopes()
                         .last()
                         .map(|s| s.index())
                         .unwrap_or_default();
-                    self.import_stack.last_mut().unwrap().insert(import);
+                    if let Some(imports) = self.import_stack.last_mut() {
+                        imports.insert(import);
+                    }
                 }
             }
         }
@@ -312,20 +1376,152 @@ impl<'a> Traverse<'a> for TransformGenerator<'a> {
     fn enter_function(&mut self, node: &mut Function<'a>, ctx: &mut TraverseCtx<'a>) {
         let segment: Segment = node
             .name()
-            .map(|n| self.new_segment(n))
-            .unwrap_or(self.new_segment("$"));
-        println!("push segment: {segment}");
+            .map(|n| self.new_segment(n, node.span))
+            .unwrap_or(self.new_segment("$", node.span));
         self.segment_stack.push(segment);
     }
 
     fn exit_function(&mut self, node: &mut Function<'a>, ctx: &mut TraverseCtx<'a>) {
-        let popped = self.segment_stack.pop();
-        println!("pop segment: {popped:?}");
+        self.segment_stack.pop();
     }
 
-    fn exit_argument(&mut self, node: &mut Argument<'a>, ctx: &mut TraverseCtx<'a>) {
-        if let Argument::CallExpression(call_expr) = node {
-            let qrl = self.qrl_stack.pop();
+    fn enter_class(&mut self, node: &mut Class<'a>, ctx: &mut TraverseCtx<'a>) {
+        if let Some(id) = &node.id {
+            let segment: Segment = self.new_segment(id.name, node.span);
+            self.segment_stack.push(segment);
+        }
+    }
+
+    fn exit_class(&mut self, node: &mut Class<'a>, ctx: &mut TraverseCtx<'a>) {
+        if node.id.is_some() {
+            self.segment_stack.pop();
+        }
+    }
+
+    fn enter_object_property(&mut self, node: &mut ObjectProperty<'a>, ctx: &mut TraverseCtx<'a>) {
+        if let Some(name) = marker_key(&node.key, node.computed) {
+            // The member names the `$()` call wrapping its value, like the name of a variable does.
+            let name = name.strip_suffix(MARKER_SUFFIX).unwrap_or_default();
+            let segment: Segment = self.new_segment(name, node.span);
+            self.segment_stack.push(segment);
+            self.member_qrl_stack.push(self.qrl_stack.len());
+        } else {
+            // So does a plain property whose value is a standalone marker call, e.g. `{ onSave: $(...) }`.
+            let name = node
+                .key
+                .static_name()
+                .filter(|_| !node.computed && node.value.is_qrl_replaceable(&self.options.markers));
+            self.push_standalone_segment(name, node.span);
+        }
+    }
+
+    fn exit_object_property(&mut self, node: &mut ObjectProperty<'a>, ctx: &mut TraverseCtx<'a>) {
+        if marker_key(&node.key, node.computed).is_some() {
+            if let Some(qrl) = self.pop_member_qrl() {
+                node.value =
+                    qrl.into_expression(ctx, &mut self.symbol_by_name, &mut self.import_by_symbol);
+            }
+            self.segment_stack.pop();
+        } else {
+            self.pop_standalone_segment();
+        }
+    }
+
+    fn enter_assignment_expression(
+        &mut self,
+        node: &mut AssignmentExpression<'a>,
+        ctx: &mut TraverseCtx<'a>,
+    ) {
+        // A standalone marker call assigned to a variable or property is named after it, like a declared variable.
+        let name = Self::assignment_name(&node.left)
+            .filter(|_| node.right.is_qrl_replaceable(&self.options.markers));
+        self.push_standalone_segment(name, node.span);
+    }
+
+    fn exit_assignment_expression(
+        &mut self,
+        node: &mut AssignmentExpression<'a>,
+        ctx: &mut TraverseCtx<'a>,
+    ) {
+        self.pop_standalone_segment();
+    }
+
+    fn enter_property_definition(
+        &mut self,
+        node: &mut PropertyDefinition<'a>,
+        ctx: &mut TraverseCtx<'a>,
+    ) {
+        if let Some(name) = marker_key(&node.key, node.computed) {
+            let name = name.strip_suffix(MARKER_SUFFIX).unwrap_or_default();
+            let segment: Segment = self.new_segment(name, node.span);
+            self.segment_stack.push(segment);
+            self.member_qrl_stack.push(self.qrl_stack.len());
+        }
+    }
+
+    fn exit_property_definition(
+        &mut self,
+        node: &mut PropertyDefinition<'a>,
+        ctx: &mut TraverseCtx<'a>,
+    ) {
+        if marker_key(&node.key, node.computed).is_some() {
+            if let Some(qrl) = self.pop_member_qrl() {
+                node.value = Some(qrl.into_expression(
+                    ctx,
+                    &mut self.symbol_by_name,
+                    &mut self.import_by_symbol,
+                ));
+            }
+            self.segment_stack.pop();
+        }
+    }
+
+    fn enter_conditional_expression(
+        &mut self,
+        node: &mut ConditionalExpression<'a>,
+        ctx: &mut TraverseCtx<'a>,
+    ) {
+        self.branch_qrl_stack.push(self.qrl_stack.len());
+    }
+
+    fn exit_conditional_expression(
+        &mut self,
+        node: &mut ConditionalExpression<'a>,
+        ctx: &mut TraverseCtx<'a>,
+    ) {
+        self.discard_branch_qrls();
+    }
+
+    fn enter_logical_expression(
+        &mut self,
+        node: &mut LogicalExpression<'a>,
+        ctx: &mut TraverseCtx<'a>,
+    ) {
+        self.branch_qrl_stack.push(self.qrl_stack.len());
+    }
+
+    fn exit_logical_expression(
+        &mut self,
+        node: &mut LogicalExpression<'a>,
+        ctx: &mut TraverseCtx<'a>,
+    ) {
+        self.discard_branch_qrls();
+    }
+
+    fn exit_argument(&mut self, node: &mut Argument<'a>, ctx: &mut TraverseCtx<'a>) {
+        if self.options.is_inline() {
+            return;
+        }
+        if let Argument::CallExpression(call_expr) = node {
+            // The stubs of stripped segments and inlined segments are complete, and never pushed a QRL.
+            let is_noop_qrl =
+                |call: &CallExpression| matches!(call.callee_name(), Some(NOOP_QRL | INLINED_QRL));
+            let is_stub = is_noop_qrl(call_expr)
+                || matches!(call_expr.arguments.first(), Some(Argument::CallExpression(inner)) if is_noop_qrl(inner));
+            if is_stub {
+                return;
+            }
+            let qrl = self.qrl_stack.pop();
 
             if let Some(qrl) = qrl {
                 let idr = qrl.into_identifier_reference(
@@ -355,9 +1551,25 @@ impl<'a> Traverse<'a> for TransformGenerator<'a> {
             .map(|s| s.to_string())
             .collect();
 
-        let s: Segment = self.new_segment(segment_name);
+        let s: Segment = self.new_segment(segment_name, node.span);
         self.segment_stack.push(s);
 
+        if self.options.strict_captures {
+            let symbol_id = id.get_binding_identifier().and_then(|b| b.symbol_id.get());
+            let kind = node.init.as_ref().and_then(Unserializable::of);
+            if let (Some(symbol_id), Some(kind)) = (symbol_id, kind) {
+                self.unserializable.insert(symbol_id, kind);
+            }
+        }
+
+        if self.options.inline_const_captures && node.kind == VariableDeclarationKind::Const {
+            let symbol_id = id.get_binding_identifier().and_then(|b| b.symbol_id.get());
+            let value = node.init.as_ref().and_then(inlinable_value);
+            if let (Some(symbol_id), Some(value)) = (symbol_id, value) {
+                self.constants.insert(symbol_id, value);
+            }
+        }
+
         if let Some(name) = id.get_identifier_name() {
             /// Adds symbol and import information in the case this declaration ends up being referenced in
             /// an exported component.
@@ -366,7 +1578,7 @@ impl<'a> Traverse<'a> for TransformGenerator<'a> {
                 let symbol_id = id.get_binding_identifier().and_then(|b| b.symbol_id.get());
                 if let Some(symbol_id) = symbol_id {
                     self.symbol_by_name.insert(name.to_string(), symbol_id);
-                    let import_id = ImportId::Named(name.to_string());
+                    let import_id = ImportId::Named(name.as_str().into());
                     self.import_by_symbol.insert(
                         symbol_id,
                         Import::new(
@@ -395,8 +1607,7 @@ impl<'a> Traverse<'a> for TransformGenerator<'a> {
             }
         }
 
-        let popped = self.segment_stack.pop();
-        println!("pop segment: {popped:?}");
+        self.segment_stack.pop();
     }
 
     fn enter_expression_statement(
@@ -419,9 +1630,8 @@ impl<'a> Traverse<'a> for TransformGenerator<'a> {
 
     fn enter_jsx_element(&mut self, node: &mut JSXElement<'a>, ctx: &mut TraverseCtx<'a>) {
         if let Some(name) = node.opening_element.name.get_identifier_name() {
-            let segment: Segment = self.new_segment(name);
+            let segment: Segment = self.new_segment(name, node.span);
             self.debug(format!("ENTER: JSXElementName {segment}"), ctx);
-            println!("push segment: {segment}");
             self.segment_stack.push(segment);
         }
     }
@@ -435,17 +1645,67 @@ impl<'a> Traverse<'a> for TransformGenerator<'a> {
         self.descend();
     }
 
+    fn enter_jsx_opening_element(
+        &mut self,
+        node: &mut JSXOpeningElement<'a>,
+        ctx: &mut TraverseCtx<'a>,
+    ) {
+        self.intrinsic_stack
+            .push(matches!(node.name, JSXElementName::Identifier(_)));
+    }
+
+    fn exit_jsx_opening_element(
+        &mut self,
+        node: &mut JSXOpeningElement<'a>,
+        ctx: &mut TraverseCtx<'a>,
+    ) {
+        self.intrinsic_stack.pop();
+    }
+
     fn enter_jsx_attribute(&mut self, node: &mut JSXAttribute<'a>, ctx: &mut TraverseCtx<'a>) {
         self.ascend();
         self.debug("ENTER: JSXAttribute", ctx);
         // JSX Attributes should be treated as part of the segment scope.
-        let segment: Segment = self.new_segment(node.name.get_identifier().name);
+        let segment: Segment = self.new_segment(node.name.get_identifier().name, node.span);
         self.segment_stack.push(segment);
+        if let Some(listener) = self.listener_of(node) {
+            if let Some(JSXAttributeValue::ExpressionContainer(container)) = &node.value {
+                let spans = match container.expression.as_expression() {
+                    Some(expr) => branch_spans(expr),
+                    None => Vec::new(),
+                };
+                self.listener_stack.push((spans, listener));
+            }
+        }
     }
 
     fn exit_jsx_attribute(&mut self, node: &mut JSXAttribute<'a>, ctx: &mut TraverseCtx<'a>) {
-        let popped = self.segment_stack.pop();
-        println!("pop segment: {popped:?}");
+        self.segment_stack.pop();
+        // Listeners of intrinsic elements are emitted with their serialized name, e.g. `on:click` for `onClick$`.
+        if let Some(listener) = self.listener_of(node) {
+            if matches!(node.value, Some(JSXAttributeValue::ExpressionContainer(_))) {
+                self.listener_stack.pop();
+            }
+            node.name = ctx.ast.jsx_attribute_name_namespaced_name(
+                node.name.span(),
+                ctx.ast.jsx_identifier(SPAN, listener.scope.namespace()),
+                ctx.ast.jsx_identifier(SPAN, ctx.ast.atom(&listener.name)),
+            );
+        } else if let Some((modifier, problem)) = self.modifier_of(node) {
+            // Modifiers are emitted as the runtime reads them, e.g. `preventdefault:click` for
+            // `preventDefault:onClick$`.
+            if let (JSXAttributeName::NamespacedName(name), Some(problem)) = (&node.name, problem) {
+                self.diagnostics.push(
+                    modifier_diagnostic(&name.to_string(), &modifier, problem)
+                        .with_label(name.span, None),
+                );
+            }
+            node.name = ctx.ast.jsx_attribute_name_namespaced_name(
+                node.name.span(),
+                ctx.ast.jsx_identifier(SPAN, modifier.kind.namespace()),
+                ctx.ast.jsx_identifier(SPAN, ctx.ast.atom(&modifier.event)),
+            );
+        }
         self.debug("EXIT: JSXAttribute", ctx);
         self.descend();
     }
@@ -470,7 +1730,7 @@ impl<'a> Traverse<'a> for TransformGenerator<'a> {
 
     fn exit_return_statement(&mut self, node: &mut ReturnStatement<'a>, ctx: &mut TraverseCtx<'a>) {
         if let Some(expr) = &node.argument {
-            if expr.is_qrl_replaceable() {
+            if expr.is_qrl_replaceable(&self.options.markers) {
                 let qrl = self.qrl_stack.pop();
                 if let Some(qrl) = qrl {
                     let expression = qrl.into_expression(
@@ -489,17 +1749,16 @@ impl<'a> Traverse<'a> for TransformGenerator<'a> {
         node: &mut OxcVec<'a, Statement<'a>>,
         ctx: &mut TraverseCtx<'a>,
     ) {
+        // Function and class declarations within a segment are kept, as the segment can use them itself. They can
+        // not be captured by a nested segment though, which is reported by `exit_identifier_reference`.
         node.retain(|s| {
-            let not_dead = !s.is_dead_code();
-            let mut legal = true;
             if self.is_recording() {
                 if let Some(e) = s.is_illegal_code_in_qrl() {
-                    legal = false;
                     self.removed.insert(e.symbol_id(), e.clone());
                 }
             }
 
-            legal && not_dead
+            !s.is_dead_code()
         });
     }
 
@@ -558,15 +1817,13 @@ impl<'a> Traverse<'a> for TransformGenerator<'a> {
                 if let Some(symbol_id) = specifier.local().symbol_id.get() {
                     let source = node.source.value;
 
-                    let local_name = specifier
-                        .local()
-                        .name
-                        .strip_suffix(MARKER_SUFFIX)
+                    let markers = &self.options.markers;
+                    let local_name = markers
+                        .prefix(&specifier.local().name)
                         .map(|s| format!("{}{}", s, QRL_SUFFIX));
 
-                    let name = specifier
-                        .name()
-                        .strip_suffix(MARKER_SUFFIX)
+                    let name = markers
+                        .prefix(&specifier.name())
                         .map(|s| format!("{}{}", s, QRL_SUFFIX))
                         .unwrap_or(specifier.name().to_string());
 
@@ -628,48 +1885,195 @@ impl<'a> Traverse<'a> for TransformGenerator<'a> {
             .reference_id
             .get()
             .and_then(|ref_id| ctx.symbols().references.get(ref_id))
+            .filter(|refr| refr.is_value())
             .and_then(|refr| refr.symbol_id())
+            .filter(|symbol_id| self.is_captured(*symbol_id, ctx))
             .and_then(|symbol_id| self.removed.get(&symbol_id))
         {
-            self.errors.push(illegal_code_type.into());
+            let failure = illegal_code_type.into();
+            self.diagnostics
+                .push(Diagnostic::from_failure(&failure, id_ref.span));
+            self.errors.push(failure);
         }
 
         // Whilst visiting each identifier reference, we check if that references refers to an import.
         // If so, we store on the current import stack so that it can be used later in the `exit_expression`
         // logic that ends up creating a new module/component.,f
         let ref_id = id_ref.reference_id();
-        if let Some(symbol_id) = ctx.symbols().get_reference(ref_id).symbol_id() {
+        let reference = ctx.symbols().get_reference(ref_id);
+        if let Some(symbol_id) = reference.symbol_id() {
             if let Some(import) = self.import_by_symbol.get(&symbol_id) {
-                let import = import.clone();
-                if !id_ref.name.ends_with(MARKER_SUFFIX) {
-                    self.import_stack.last_mut().unwrap().insert(import);
+                // References in types, e.g. `(props: Props) => ...`, only need the import as a type, and no import
+                // at all once the types are erased.
+                let import = match reference.is_value() {
+                    true => Some(import.clone()),
+                    false if self.options.transpile_ts => None,
+                    false => Some(import.clone().into_type_only()),
+                };
+                if let Some(import) =
+                    import.filter(|_| !self.options.markers.is_marker(&id_ref.name))
+                {
+                    if let Some(imports) = self.import_stack.last_mut() {
+                        imports.insert(import);
+                    }
                 }
             }
         }
     }
 }
 
-#[derive(Debug, Deserialize, Copy, Clone, PartialEq, Eq)]
-#[serde(rename_all = "camelCase")]
+/// The listener registered by `call` if it calls one of the [USE_ON_HOOKS](crate::events::USE_ON_HOOKS) with the name
+/// of an event, e.g. `useOnDocument("scroll", $(() => ...))`.
+fn use_on_listener(call: &CallExpression) -> Option<EventListener> {
+    let hook = call.callee_name()?;
+    match call.arguments.first()? {
+        Argument::StringLiteral(event) => EventListener::from_use_on(hook, &event.value),
+        _ => None,
+    }
+}
+
+/// Reports the `problem` found in the modifier attribute `written`, which is emitted as `modifier`.
+fn modifier_diagnostic(
+    written: &str,
+    modifier: &EventModifier,
+    problem: ModifierProblem,
+) -> Diagnostic {
+    let code = DiagnosticCode::InvalidEventModifier;
+    match problem {
+        ModifierProblem::Namespace => Diagnostic::warning(
+            code,
+            format!("`{written}` is not an event modifier, and is emitted as `{modifier}`"),
+        )
+        .with_suggestion(format!("Write `{modifier}`")),
+        ModifierProblem::ListenerName => Diagnostic::warning(
+            code,
+            format!(
+                "`{written}` names a listener rather than an event, and is emitted as `{modifier}`"
+            ),
+        )
+        .with_suggestion(format!("Write `{modifier}`")),
+        ModifierProblem::UnknownEvent(known) => Diagnostic::warning(
+            code,
+            format!(
+                "`{written}` modifies the `{}` event, which is not a DOM event",
+                modifier.event
+            ),
+        )
+        .with_suggestion(format!(
+            "Write `{}:{known}` if the `{known}` event was meant",
+            modifier.kind.namespace()
+        )),
+        ModifierProblem::InvalidEvent => Diagnostic::warning(
+            code,
+            format!("`{written}` does not name an event, and has no effect"),
+        )
+        .with_suggestion(format!(
+            "Write the name of the DOM event, e.g. `{}:click`",
+            modifier.kind.namespace()
+        )),
+    }
+}
+
+/// The spans of the values `expr` can evaluate to: `expr` itself, and recursively the branches of conditional and
+/// logical expressions, e.g. `$(a)` and `$(b)` in `cond ? $(a) : $(b)`.
+fn branch_spans(expr: &Expression) -> Vec<Span> {
+    let mut spans = vec![expr.span()];
+    match expr.without_parentheses() {
+        Expression::ConditionalExpression(conditional) => {
+            spans.extend(branch_spans(&conditional.consequent));
+            spans.extend(branch_spans(&conditional.alternate));
+        }
+        Expression::LogicalExpression(logical) => {
+            spans.extend(branch_spans(&logical.left));
+            spans.extend(branch_spans(&logical.right));
+        }
+        _ => {}
+    }
+    spans
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "camelCase"))]
 pub enum MinifyMode {
+    /// Removes whitespace, and compresses the code with `oxc_minifier` if the `minifier` feature is enabled.
     Simplify,
+    #[default]
     None,
 }
 
-#[derive(Debug, Copy, Clone, Deserialize)]
-#[serde(rename_all = "camelCase")]
-pub enum EntryStrategy {
-    Inline,
-    Hoist,
-    Single,
-    Hook,
-    Segment,
-    Component,
-    Smart,
+/// Controls the formatting of the emitted code, independently of [MinifyMode].
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "camelCase"))]
+pub enum EmitStyle {
+    /// Indented and spread over lines, for reading the output.
+    #[default]
+    Pretty,
+    /// Without any whitespace the code does not need, and with short names for the identifiers the optimizer
+    /// introduces, e.g. `_p` for the props of a component whose props were destructured. Leaves compression and
+    /// mangling to the bundler.
+    Compact,
+    /// [EmitStyle::Compact] under [Target::Prod], [EmitStyle::Pretty] under other targets.
+    ByTarget,
+}
+
+/// Determines the file name of every extracted segment, i.e. its [Id::local_file_name] and the module its QRL
+/// imports. The segments of different files never share a name with any of the strategies.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "camelCase"))]
+pub enum SegmentNaming {
+    /// `{path}_{symbol_name}`, e.g. `src/routes/index.tsx_App_component_ckEPmXZlub0`, which places segments next to
+    /// the file they were extracted from.
+    #[default]
+    DirectoryMirrored,
+    /// `{file_name}_{display_name}_{hash}`, e.g. `index.tsx_App_component_ckEPmXZlub0`, which places every segment
+    /// in the same directory while keeping its name readable.
+    DisplayName,
+    /// A hash of the code of the segment, e.g. `Ri0UZyr8W0k`. The name only changes when the code changes, and is
+    /// computed with a fixed algorithm, so that segments can be cached forever by content-addressed storage.
+    ContentHash,
 }
 
-#[derive(Debug, Deserialize)]
-#[serde(rename_all = "camelCase")]
+impl SegmentNaming {
+    pub(crate) fn file_name(&self, id: &Id, code: &str) -> String {
+        match self {
+            SegmentNaming::DirectoryMirrored => id.local_file_name.clone(),
+            SegmentNaming::DisplayName => format!("{}_{}", id.display_name, id.hash),
+            SegmentNaming::ContentHash => {
+                let mut hasher = StableHasher::new();
+                hasher.write(code.as_bytes());
+                engine::general_purpose::URL_SAFE_NO_PAD
+                    .encode(hasher.finish().to_le_bytes())
+                    .replace(['-', '_'], "0")
+            }
+        }
+    }
+}
+
+/// Controls how file paths appear in emitted code, segment metadata and the manifest.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "camelCase"))]
+pub enum PathPrivacy {
+    /// Paths are emitted as they were provided.
+    #[default]
+    None,
+    /// Paths are made relative to the [root](TransformOptions::root_dir) and stripped of `..` components, so that
+    /// neither the location of the project nor the directories above it are revealed.
+    Relative,
+    /// Like [PathPrivacy::Relative], but every directory is replaced with a hash of it and only the file name is
+    /// kept. Segments are no longer tagged with their qwik-city route in this mode, as the `routes/` directory can
+    /// not be recognized.
+    Hashed,
+}
+
+pub use crate::entry_strategy::EntryStrategy;
+
+#[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "camelCase"))]
 pub struct TransformFsOptions {
     pub src_dir: String,
     pub root_dir: Option<String>,
@@ -693,153 +2097,4584 @@ pub struct TransformFsOptions {
     pub is_server: Option<bool>,
 }
 
-#[derive(Debug, Deserialize)]
-#[serde(rename_all = "camelCase")]
-pub struct TransformModuleInput {
-    pub path: String,
-    pub dev_path: Option<String>,
-    pub code: String,
-}
-
-#[derive(Debug, Deserialize)]
-#[serde(rename_all = "camelCase")]
-pub struct TransformModulesOptions {
-    pub src_dir: String,
-    pub root_dir: Option<String>,
-    pub input: Vec<TransformModuleInput>,
-    pub source_maps: bool,
+/// Options controlling the transformation of a single [Source].
+#[derive(Debug, Clone, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "camelCase", default))]
+pub struct TransformOptions {
+    pub target: Target,
+    /// Identifies the package being built, e.g. `@my/lib`. It is hashed into every symbol name, so that the symbols
+    /// of different packages never collide. Under [Target::Lib], segments are also emitted below it, e.g.
+    /// `@my/lib/src/app.tsx_s_ckEPmXZlub0`, and QRLs import them from there, so that applications consuming the
+    /// library resolve its segments through the package.
+    pub scope: Option<String>,
     pub minify: MinifyMode,
+    /// The formatting of the emitted code.
+    pub emit: EmitStyle,
+    /// When `true`, TypeScript-only syntax (type annotations, interfaces, `satisfies`, etc.) is erased from the
+    /// host module and from every extracted segment, so that the emitted modules are plain ESM JavaScript.
     pub transpile_ts: bool,
+    /// When `true`, JSX in the host module and in every extracted segment is compiled using the automatic
+    /// runtime, so that the emitted modules can be executed without a further transpilation step.
     pub transpile_jsx: bool,
-    pub preserve_filenames: bool,
+    /// The `jsxImportSource` used by the automatic JSX runtime. Defaults to `@qwik.dev/core`.
+    pub jsx_import_source: Option<String>,
+    /// When `true` together with [transpile_jsx](TransformOptions::transpile_jsx), JSX is compiled to the `_jsxQ` and
+    /// `_jsxC` calls of Qwik rather than with the automatic runtime, annotated with the flags of the nodes whose
+    /// listeners or children never change, so the runtime can skip diffing them. See [STATIC_LISTENERS](crate::jsx::STATIC_LISTENERS) and
+    /// [STATIC_SUBTREE](crate::jsx::STATIC_SUBTREE).
+    pub optimize_jsx: bool,
+    /// Lowers syntax newer than the given target in the host module and every extracted segment, e.g. `es2020` or
+    /// `chrome80`, accepting the same targets as esbuild. Transforms requiring runtime helpers import them from
+    /// `@oxc-project/runtime`, except for class features: public class fields are lowered to plain assignments,
+    /// while modules using private class members keep their classes as they are. Fails the transform with
+    /// [Error::UnsupportedTarget] for unknown targets.
+    pub es_target: Option<String>,
+    /// The module system of the emitted host modules and segments. Defaults to [ModuleFormat::Esm], while
+    /// [ModuleFormat::Cjs] converts their imports and exports into `require` calls and getters on `exports`, for
+    /// SSR hosts which can not load ES modules.
+    pub module_format: ModuleFormat,
+    /// Under [ModuleFormat::Cjs], `import()` calls, e.g. those of QRLs loading their segment, are converted into
+    /// `Promise.resolve().then(() => require(...))`, for hosts which do not support `import()` in CommonJS modules
+    /// either. Ignored with ES modules.
+    pub require_dynamic_imports: bool,
+    /// Determines the file names of the extracted segments, see [SegmentNaming].
+    pub segment_naming: SegmentNaming,
+    /// Maps every segment to the specifier its `qrl` imports it from, e.g. its content hashed file name or URL on a
+    /// CDN. Segments it returns `None` for are imported from their file name.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub chunk_resolver: Option<ChunkResolver>,
+    /// Compiles modules into the JavaScript or TypeScript that is transformed, e.g. the `.md` and `.mdx` routes of
+    /// Qwik City into JSX. Modules it returns `None` for are transformed as they are. The
+    /// [front-matter exports](crate::preprocess::FRONT_MATTER_EXPORTS) of Markdown modules are always emitted
+    /// untouched, whether they were compiled by the preprocessor or by an upstream plugin.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub preprocessor: Option<Preprocessor>,
+    /// Is notified of every extracted symbol with its id, code, captures and origin, once the module it was
+    /// extracted from is transformed.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub symbol_listener: Option<SymbolListener>,
+    /// When set, the modules outside of the app, as decided by [external_paths](TransformOptions::external_paths),
+    /// are emitted as they were provided with [ModuleKind::External], rather than optimized a second time.
+    /// [ExternalMarkers::Error] also reports their `$` marker calls.
+    pub forbid_external_markers: Option<ExternalMarkers>,
+    /// Decides which modules are outside of the app for
+    /// [forbid_external_markers](TransformOptions::forbid_external_markers). Defaults to the modules below
+    /// `node_modules` and the ones outside of the source directory, see [is_external_path](crate::external::is_external_path).
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub external_paths: Option<ExternalPaths>,
+    /// When `true`, a [TraceEntry] describing which rules fired is recorded for every `$` call site and exposed
+    /// via [OptimizationResult::trace].
+    pub trace: bool,
+    /// Determines how extracted segments are grouped into entries. See [EntryPlan].
     pub entry_strategy: EntryStrategy,
-    pub explicit_extensions: bool,
-    pub mode: Target,
-    pub scope: Option<String>,
-
-    pub core_module: Option<String>,
+    /// When `true`, every emitted artifact (module code, segment names and hashes, manifests and module paths)
+    /// is independent of the machine the build runs on: absolute input paths are made relative to the
+    /// [root](TransformOptions::root_dir) before they are used. The optimizer never emits timestamps or host names.
+    pub reproducible: bool,
+    /// Rewrites every file path that ends up in emitted code, segment metadata, the manifest and module paths, so
+    /// that production artifacts do not leak directory structures or user names of the build machine.
+    pub path_privacy: PathPrivacy,
+    /// The absolute path of the root of the project, e.g. the directory of its `package.json`, which
+    /// [private](TransformOptions::path_privacy) paths are made relative to. The directory of paths outside of it
+    /// is replaced with a hash, so that it is not revealed. Without it, every absolute path is outside of it.
+    pub root_dir: Option<PathBuf>,
+    /// When `true`, the [Manifest] of a [TransformResult] has every display name, root and entry replaced with an
+    /// opaque id, and the private [NameMapping] translating them back is returned separately. Intended for
+    /// [Target::Prod], where symbol names do not contain display names either.
+    pub obfuscate_names: bool,
+    /// Identifies the build, e.g. a release tag or commit. It is recorded in the [Manifest], so that the chunks of
+    /// two builds deployed side by side (e.g. blue/green) can be told apart.
+    pub build_id: Option<String>,
+    /// When `true`, the [build id](TransformOptions::build_id) is appended to the file name of every segment, e.g.
+    /// `app.tsx_App_component_s_abc_v42`, so that the segments of different builds never collide. Has no effect
+    /// without a build id.
+    pub build_id_in_file_names: bool,
+    /// When `true`, captures which can not be serialized fail the transform with [Error::InvalidCaptures] instead
+    /// of only being reported: reassigned variables, function declarations, and variables holding a function or a
+    /// class instance. Without it, the latter are not detected and only fail at runtime.
+    pub strict_captures: bool,
+    /// When `true`, captured `const` variables holding a small primitive literal or a frozen literal, e.g.
+    /// `const label = "Save"` or `const sizes = Object.freeze([1, 2])`, are declared again in the segment instead of
+    /// being passed through `useLexicalScope`, so that they are not serialized into the state of the page. The inlined
+    /// values are recorded in [SegmentMetadata::inlined_captures]. Literals longer than
+    /// [MAX_INLINED_CAPTURE_LENGTH](crate::api::MAX_INLINED_CAPTURE_LENGTH) are still captured.
+    pub inline_const_captures: bool,
+    /// The prefix of symbol names under [Target::Lib] and [Target::Prod], e.g. a package name so that the symbols of
+    /// the packages of a monorepo never collide. Sanitized into an identifier, defaults to `s`.
+    pub symbol_prefix: Option<String>,
+    /// The number of characters of the hash in symbol names, between 4 and 22. Defaults to 11. Shorter hashes keep
+    /// the names of small apps short, longer hashes make collisions in large code bases less likely.
+    pub hash_length: Option<usize>,
+    /// How the names of segments are sanitized into identifiers, e.g. those of components named in a non-Latin
+    /// script. Defaults to [SanitizeMode::Hashed], which keeps them apart while keeping symbol and file names ASCII.
+    pub sanitize_mode: SanitizeMode,
+    /// Whether the module is built for the server (`Some(true)`) or for the client (`Some(false)`). In client
+    /// builds, the segments of [SERVER_MARKERS] (e.g. `server$` and `routeLoader$`) are replaced with `_noopQrl`
+    /// stubs instead of being extracted, and the imports only they used are dropped.
+    pub is_server: Option<bool>,
+    /// Member expressions replaced with a value before the segments are extracted, given as the text of a JSON value
+    /// or of any other expression, e.g. `import.meta.env.DEV` with `false` or `process.env.NODE_ENV` with
+    /// `"production"`, so that every segment sees the values of the build. The branches the values make unreachable,
+    /// e.g. `if (import.meta.env.DEV) { ... }`, are then removed from the host module and the segments, along with
+    /// the imports only they used. Serialized as a map of JSON values.
+    #[cfg_attr(feature = "serde", serde(with = "crate::defines::json_values"))]
+    pub defines: BTreeMap<String, String>,
+    /// Named exports whose value is replaced with a function throwing [STRIPPED_EXPORT_ERROR], e.g. the request
+    /// handlers (`onGet`, `onPost`, ...) of qwik-city routes in client builds.
     pub strip_exports: Option<Vec<String>>,
+    /// Functions whose first argument is extracted into a segment, in addition to the ones named with a `$` suffix,
+    /// e.g. the `lazy_` wrapper of a design system with the suffix `_`, or any exactly named function. See [Markers].
+    pub markers: Markers,
+    /// Markers whose segments are replaced with `_noopQrl` stubs instead of being extracted, matched as prefixes of
+    /// the marker name. E.g. `["useVisibleTask$"]` in server builds, so that client-only code using browser APIs
+    /// is never emitted for the server.
     pub strip_ctx_name: Option<Vec<String>>,
-    pub strip_event_handlers: bool,
-    pub reg_ctx_name: Option<Vec<String>>,
-    pub is_server: Option<bool>,
+    /// When `true`, references to CommonJS globals (`require`, `module`, `exports`, `__dirname` and `__filename`)
+    /// are reported as [DiagnosticCode::NonEsm] errors, since they fail once the emitted modules are loaded as ESM.
+    pub strict_esm: bool,
+    /// When `true`, the transform is safe to run on untrusted code, e.g. in an online playground: modules exceeding
+    /// the [limits](TransformOptions::limits) are reported with [DiagnosticCode::LimitExceeded], and unexpected
+    /// failures of the optimizer with [DiagnosticCode::InternalError], rather than exhausting resources or
+    /// panicking. Modules nested deeper than [ResourceLimits::max_depth] are rejected before they are parsed, and
+    /// the others are transformed on a thread whose stack can hold that many levels, or on the calling thread on
+    /// wasm32, which cannot spawn threads.
+    ///
+    /// Panics are only caught when the optimizer is built with `panic = "unwind"`, the default of native targets.
+    /// Builds with `panic = "abort"`, the default on wasm32, still abort on them.
+    pub hardened: bool,
+    /// The number of segments a module may have before it is reported with [DiagnosticCode::TooManySegments],
+    /// listing the components most of them were extracted from. Catches generated modules with thousands of
+    /// handlers before they slow down the build.
+    pub max_segments_per_file: Option<usize>,
+    /// The number of segments all modules of a [transform_fs] or [transform_modules] call may have in total before
+    /// the module with the most segments is reported with [DiagnosticCode::TooManySegments], listing the modules
+    /// with the most segments.
+    pub max_segments_per_project: Option<usize>,
+    /// Comments copied from a module into its segments, given as patterns matched against the text of the comment,
+    /// e.g. `eslint-disable` or `@license`. Comments at the top of the module are copied into every segment, the
+    /// others into the segment containing them. Directives such as `"use client"` are always copied.
+    pub preserve_comments: Vec<String>,
+    /// The number of diagnostics reported for a module, errors first. The others are summarized by a single
+    /// [DiagnosticCode::TooManyDiagnostics] diagnostic, so that a broken module does not flood the overlay of a dev
+    /// server. Defaults to [DEFAULT_MAX_DIAGNOSTICS], `None` reports all of them.
+    pub max_diagnostics: Option<usize>,
+    /// The resource limits of [hardened](TransformOptions::hardened) transforms.
+    pub limits: ResourceLimits,
+    /// When `true`, segments of a module whose code is identical apart from their symbol name, which capture the
+    /// same variables and were extracted from the same marker, are emitted once: the QRLs of the duplicates point
+    /// at the symbol of the first one. Cuts the number of chunks of repetitive code, e.g. the listeners of a
+    /// generated table.
+    pub dedupe_segments: bool,
+    /// When `true`, the host module also re-exports every segment extracted from one of its exported declarations,
+    /// e.g. `export { App_component_ckEPmXZlub0 } from "./app.tsx_App_component_ckEPmXZlub0"`, so that consumers
+    /// importing a segment from the module it was extracted from, e.g. tests of a library, keep working. Such hosts
+    /// load these segments eagerly, which is why they are only listed in the [Manifest] by default, see
+    /// [QrlComponent::exported].
+    pub reexport_segments: bool,
+    /// When `true`, segments are imported with the extension of the file they are emitted to, e.g.
+    /// `import("./app.tsx_App_component_ckEPmXZlub0.tsx")`, for hosts which resolve specifiers exactly, such as
+    /// browsers loading the output unbundled or Deno. Without it, the extension is left to the bundler to resolve.
+    pub import_extensions: bool,
+    /// Under [Target::Dev], the number of characters of the original source of every segment recorded as its
+    /// [excerpt](QrlComponent::excerpt) and in the [Manifest], e.g. `() => store.count++`. Longer sources are
+    /// truncated with `…`. No excerpts are recorded without it, or under other targets.
+    pub source_excerpt_length: Option<usize>,
+    /// Under [Target::Lib], creates QRLs with `qrlDEV` and the origin of their segment, i.e. its path relative to the
+    /// package and its span, so that applications consuming the library can attribute its segments in devtools.
+    /// Ignored under other targets, so production builds never embed origins.
+    pub embed_origin: bool,
+    /// Under [Target::Dev], annotates every QRL and the header of every segment module with a comment locating the
+    /// source of the segment, e.g. `/* src/app.tsx:3:31 app.tsx_App_component */`, so that generated chunks can be
+    /// traced back to their origin in the network tab. Ignored under other targets.
+    pub provenance_comments: bool,
+    /// When `true`, the output does not depend on the platform the sources were written on or on the order they were
+    /// given in, e.g. for snapshot tests: a leading byte order mark is dropped and line endings are converted to
+    /// `\n` before parsing, so that code, spans and excerpts are identical, and segments are sorted by hash, and
+    /// modules by path.
+    pub normalize_output: bool,
+    /// When `true`, modules with syntax errors are still transformed, e.g. while they are being edited in a dev
+    /// server: the top-level statements which fail to parse are passed through untouched, the rest of the module is
+    /// transformed as usual, and the syntax errors are reported as diagnostics. Without it, a module with syntax
+    /// errors is only transformed as far as the parser could recover.
+    pub lenient: bool,
+    /// Segments whose generated code is smaller than this number of bytes and which capture nothing are kept in
+    /// place as `inlinedQrl` calls instead of being extracted into their own file, e.g. trivial handlers such as
+    /// `onClick$={() => console.log("clicked")}`, which cuts the number of tiny chunks. Segments exported for the
+    /// router, e.g. those of `routeLoader$`, and segments containing other extracted segments are always extracted.
+    /// `None` extracts every segment.
+    pub inline_threshold_bytes: Option<usize>,
 }
 
-pub fn transform_fs(config: TransformFsOptions) -> Result<(OptimizedApp)> {
-    Err(Error::Generic("Not yet implemented".to_string()))
+impl TransformOptions {
+    /// Whether the module at `path` is left as it was provided, see [TransformOptions::forbid_external_markers].
+    fn is_external(&self, path: &Path) -> bool {
+        self.forbid_external_markers.is_some()
+            && match &self.external_paths {
+                Some(external_paths) => external_paths.is_external(path),
+                None => is_external_path(path),
+            }
+    }
+
+    /// Whether modules without any Qwik code are still rewritten, e.g. transpiled or minified, rather than emitted
+    /// as they were provided.
+    fn rewrites_plain_modules(&self) -> bool {
+        self.transpile_ts
+            || self.transpile_jsx
+            || self.es_target.is_some()
+            || self.module_format != ModuleFormat::Esm
+            || self.minify != MinifyMode::None
+            || self.strip_exports.is_some()
+            || !self.defines.is_empty()
+            || self.strict_esm
+    }
+
+    /// Starts building options from the [defaults](TransformOptions::default).
+    ///
+    /// # Examples
+    /// ```
+    /// use qwik_optimizer::api::{EntryStrategy, Target, TransformOptions};
+    ///
+    /// let options = TransformOptions::builder()
+    ///     .mode(Target::Prod)
+    ///     .entry_strategy(EntryStrategy::Component)
+    ///     .transpile_ts(true)
+    ///     .transpile_jsx(true)
+    ///     .es_target("es2020")
+    ///     .build()?;
+    /// # Ok::<(), qwik_optimizer::api::Error>(())
+    /// ```
+    pub fn builder() -> TransformOptionsBuilder {
+        TransformOptionsBuilder(TransformOptions::default())
+    }
+
+    pub(crate) fn is_minify(&self) -> bool {
+        self.minify == MinifyMode::Simplify
+    }
+
+    /// Whether the code is emitted in the [EmitStyle::Compact] style.
+    pub(crate) fn is_compact(&self) -> bool {
+        match self.emit {
+            EmitStyle::Pretty => false,
+            EmitStyle::Compact => true,
+            EmitStyle::ByTarget => self.target == Target::Prod,
+        }
+    }
+
+    /// Whether `$` calls are rewritten to `inlinedQrl` calls in place, rather than extracted into segments. Used for
+    /// SSR-only builds and unit tests, which do not need any code splitting.
+    pub(crate) fn is_inline(&self) -> bool {
+        self.entry_strategy == EntryStrategy::Inline
+    }
+
+    /// Whether `segment` is small enough to be kept in place as an `inlinedQrl` call rather than extracted, see
+    /// [TransformOptions::inline_threshold_bytes].
+    pub(crate) fn inlines(&self, segment: &QrlComponent) -> bool {
+        self.inline_threshold_bytes
+            .is_some_and(|threshold| segment.code.len() < threshold)
+            && segment.qrl.captures.is_empty()
+            && segment.route_export.is_none()
+    }
+
+    /// Whether the segments of the marker `ctx_name` (e.g. `server$`) are replaced with stubs in this build.
+    pub(crate) fn strips_segment(&self, ctx_name: &str) -> bool {
+        let is_server_only = self.is_server == Some(false) && SERVER_MARKERS.contains(&ctx_name);
+        let is_stripped = self
+            .strip_ctx_name
+            .iter()
+            .flatten()
+            .any(|prefix| ctx_name.starts_with(prefix.as_str()));
+        self.markers.is_marker(ctx_name) && (is_server_only || is_stripped)
+    }
+
+    pub(crate) fn symbol_format(&self) -> SymbolFormat {
+        SymbolFormat::new(self.symbol_prefix.as_deref(), self.hash_length)
+            .with_sanitize(self.sanitize_mode)
+    }
+
+    /// The directory segments are emitted below, see [TransformOptions::scope].
+    pub(crate) fn scope_dir(&self) -> Option<&str> {
+        self.scope
+            .as_deref()
+            .filter(|_| self.target == Target::Lib)
+            .map(|scope| scope.trim_end_matches('/'))
+            .filter(|scope| !scope.is_empty())
+    }
+
+    /// The directory the segments of the module at `source_info` are emitted to, relative to the output directory:
+    /// the directory of the module under [SegmentNaming::DirectoryMirrored], the output directory itself otherwise,
+    /// below the [scope directory](TransformOptions::scope_dir) if any.
+    pub(crate) fn segment_dir(&self, source_info: &SourceInfo) -> PathBuf {
+        let dir = match self.segment_naming {
+            SegmentNaming::DirectoryMirrored => source_info.rel_dir.clone(),
+            SegmentNaming::DisplayName | SegmentNaming::ContentHash => PathBuf::new(),
+        };
+        match self.scope_dir() {
+            Some(scope_dir) => Path::new(scope_dir).join(dir),
+            None => dir,
+        }
+    }
+
+    /// The build id to append to segment file names, if any.
+    pub(crate) fn file_name_build_id(&self) -> Option<&str> {
+        self.build_id
+            .as_deref()
+            .filter(|_| self.build_id_in_file_names)
+    }
+
+    /// The [PathPrivacy] in effect, [TransformOptions::reproducible] implies at least [PathPrivacy::Relative].
+    pub(crate) fn path_privacy(&self) -> PathPrivacy {
+        match self.path_privacy {
+            PathPrivacy::None if self.reproducible => PathPrivacy::Relative,
+            privacy => privacy,
+        }
+    }
 }
 
-pub fn transform_modules(config: TransformModulesOptions) -> Result<(OptimizedApp)> {
-    Err(Error::Generic("Not yet implemented".to_string()))
+impl Default for TransformOptions {
+    fn default() -> Self {
+        Self {
+            target: Target::Dev,
+            scope: None,
+            minify: MinifyMode::None,
+            emit: EmitStyle::Pretty,
+            transpile_ts: false,
+            transpile_jsx: false,
+            jsx_import_source: None,
+            optimize_jsx: false,
+            es_target: None,
+            module_format: ModuleFormat::Esm,
+            require_dynamic_imports: false,
+            segment_naming: SegmentNaming::default(),
+            chunk_resolver: None,
+            preprocessor: None,
+            symbol_listener: None,
+            forbid_external_markers: None,
+            external_paths: None,
+            trace: false,
+            entry_strategy: EntryStrategy::default(),
+            reproducible: false,
+            path_privacy: PathPrivacy::None,
+            root_dir: None,
+            obfuscate_names: false,
+            build_id: None,
+            build_id_in_file_names: false,
+            strict_captures: false,
+            inline_const_captures: false,
+            symbol_prefix: None,
+            hash_length: None,
+            sanitize_mode: SanitizeMode::default(),
+            is_server: None,
+            defines: BTreeMap::new(),
+            strip_exports: None,
+            markers: Markers::default(),
+            strip_ctx_name: None,
+            strict_esm: false,
+            hardened: false,
+            max_segments_per_file: None,
+            max_segments_per_project: None,
+            preserve_comments: Vec::new(),
+            max_diagnostics: Some(DEFAULT_MAX_DIAGNOSTICS),
+            limits: ResourceLimits::default(),
+            dedupe_segments: false,
+            reexport_segments: false,
+            import_extensions: false,
+            source_excerpt_length: None,
+            embed_origin: false,
+            provenance_comments: false,
+            normalize_output: false,
+            lenient: false,
+            inline_threshold_bytes: None,
+        }
+    }
 }
 
-pub fn transform(script_source: Source) -> Result<OptimizationResult> {
-    let allocator = Allocator::default();
-    let source_text = script_source.source_code();
-    let source_info = script_source.source_info();
-    let source_type = script_source.source_info().try_into()?;
+/// Builds [TransformOptions], see [TransformOptions::builder]. Options without a setter keep their default, and can
+/// still be set on the built options.
+#[derive(Debug, Clone, Default)]
+pub struct TransformOptionsBuilder(TransformOptions);
 
-    let mut errors = Vec::new();
+impl TransformOptionsBuilder {
+    /// Sets [TransformOptions::target], which determines how symbols are named, e.g. [Target::Prod].
+    pub fn mode(mut self, target: Target) -> Self {
+        self.0.target = target;
+        self
+    }
 
-    let parse_return = Parser::new(&allocator, source_text, source_type).parse();
-    errors.extend(parse_return.errors);
+    /// Sets [TransformOptions::es_target], the syntax the emitted modules are lowered to, e.g. `es2020`.
+    pub fn es_target<S: Into<String>>(mut self, es_target: S) -> Self {
+        self.0.es_target = Some(es_target.into());
+        self
+    }
 
-    let mut program = parse_return.program;
+    /// Sets [TransformOptions::module_format], e.g. [ModuleFormat::Cjs].
+    pub fn module_format(mut self, module_format: ModuleFormat) -> Self {
+        self.0.module_format = module_format;
+        self
+    }
 
-    let SemanticBuilderReturn {
-        semantic,
-        errors: semantic_errors,
-    } = SemanticBuilder::new()
-        .with_check_syntax_error(true) // Enable extra syntax error checking
-        .with_build_jsdoc(true) // Enable JSDoc parsing
-        .with_cfg(true) // Build a Control Flow Graph
-        .build(&program);
+    /// Enables [TransformOptions::require_dynamic_imports].
+    pub fn require_dynamic_imports(mut self) -> Self {
+        self.0.require_dynamic_imports = true;
+        self
+    }
 
-    let mut transform = &mut TransformGenerator::new(source_info, false, Target::Dev, None);
+    /// Sets [TransformOptions::entry_strategy].
+    pub fn entry_strategy(mut self, entry_strategy: EntryStrategy) -> Self {
+        self.0.entry_strategy = entry_strategy;
+        self
+    }
 
-    let (symbols, scopes) = semantic.into_symbol_table_and_scope_tree();
+    /// Sets [TransformOptions::scope].
+    pub fn scope<S: Into<String>>(mut self, scope: S) -> Self {
+        self.0.scope = Some(scope.into());
+        self
+    }
 
-    traverse_mut(transform, &allocator, &mut program, symbols, scopes);
+    /// Sets [TransformOptions::root_dir].
+    pub fn root_dir<P: Into<PathBuf>>(mut self, root_dir: P) -> Self {
+        self.0.root_dir = Some(root_dir.into());
+        self
+    }
 
-    Ok(OptimizationResult::new(
-        transform.app.clone(),
-        transform.errors.clone(),
-    ))
-}
+    /// Sets [TransformOptions::transpile_ts].
+    pub fn transpile_ts(mut self, transpile_ts: bool) -> Self {
+        self.0.transpile_ts = transpile_ts;
+        self
+    }
 
-#[cfg(test)]
-mod tests {
+    /// Sets [TransformOptions::transpile_jsx].
+    pub fn transpile_jsx(mut self, transpile_jsx: bool) -> Self {
+        self.0.transpile_jsx = transpile_jsx;
+        self
+    }
 
-    use super::*;
-    use insta::assert_yaml_snapshot;
-    use std::path::PathBuf;
+    /// Sets [TransformOptions::optimize_jsx].
+    pub fn optimize_jsx(mut self, optimize_jsx: bool) -> Self {
+        self.0.optimize_jsx = optimize_jsx;
+        self
+    }
 
-    #[test]
-    fn test_example_1() {
-        assert_valid_transform_debug!();
+    /// Sets [TransformOptions::max_diagnostics].
+    pub fn max_diagnostics(mut self, max_diagnostics: Option<usize>) -> Self {
+        self.0.max_diagnostics = max_diagnostics;
+        self
     }
 
-    #[test]
-    fn test_example_2() {
-        assert_valid_transform!();
+    /// Sets [TransformOptions::minify].
+    pub fn minify(mut self, minify: MinifyMode) -> Self {
+        self.0.minify = minify;
+        self
     }
 
-    #[test]
-    fn test_example_3() {
-        assert_valid_transform!();
+    /// Sets [TransformOptions::emit].
+    pub fn emit(mut self, emit: EmitStyle) -> Self {
+        self.0.emit = emit;
+        self
     }
 
-    #[test]
-    fn test_example_4() {
-        assert_valid_transform!();
+    /// Sets [TransformOptions::source_excerpt_length].
+    pub fn source_excerpt_length(mut self, length: usize) -> Self {
+        self.0.source_excerpt_length = Some(length);
+        self
     }
 
-    #[test]
-    fn test_example_5() {
-        assert_valid_transform!();
+    /// Enables [TransformOptions::embed_origin].
+    pub fn embed_origin(mut self) -> Self {
+        self.0.embed_origin = true;
+        self
     }
 
-    #[test]
-    fn test_example_6() {
-        assert_valid_transform!();
+    /// Enables [TransformOptions::provenance_comments].
+    pub fn provenance_comments(mut self) -> Self {
+        self.0.provenance_comments = true;
+        self
     }
 
-    #[test]
-    fn test_example_7() {
-        assert_valid_transform_debug!();
+    /// Enables [TransformOptions::normalize_output].
+    pub fn normalize_output(mut self) -> Self {
+        self.0.normalize_output = true;
+        self
     }
 
-    #[test]
-    fn test_example_8() {
-        assert_valid_transform_debug!();
+    /// Sets [TransformOptions::preprocessor].
+    pub fn preprocessor(mut self, preprocessor: Preprocessor) -> Self {
+        self.0.preprocessor = Some(preprocessor);
+        self
     }
 
-    // #[test]
-    fn test_example_9() {
-        // Not removing:
-        // const decl8 = 1, decl9;
-        assert_valid_transform_debug!();
+    /// Sets [TransformOptions::forbid_external_markers].
+    pub fn forbid_external_markers(mut self, mode: ExternalMarkers) -> Self {
+        self.0.forbid_external_markers = Some(mode);
+        self
     }
 
-    // #[test]
-    fn test_example_10() {
-        // Not converting:
-        // const a = ident1 + ident3;
-        // const b = ident1 + ident3;
-        // to:
-        // ident1, ident3;
-        // ident1, ident3;
-        assert_valid_transform!();
+    /// Sets [TransformOptions::external_paths].
+    pub fn external_paths(mut self, external_paths: ExternalPaths) -> Self {
+        self.0.external_paths = Some(external_paths);
+        self
     }
 
-    #[test]
-    fn test_example_11() {
-        assert_valid_transform!();
+    /// Adds `suffix` to the suffixes of [TransformOptions::markers], e.g. `_` for `lazy_`.
+    pub fn marker_suffix<S: Into<String>>(mut self, suffix: S) -> Self {
+        self.0.markers = self.0.markers.with_suffix(suffix);
+        self
     }
 
-    #[test]
-    fn test_example_capture_imports() {
-        assert_valid_transform!();
+    /// Adds `name` to the exactly named functions of [TransformOptions::markers].
+    pub fn marker_name<S: Into<String>>(mut self, name: S) -> Self {
+        self.0.markers = self.0.markers.with_name(name);
+        self
+    }
+
+    /// Sets [TransformOptions::symbol_listener].
+    pub fn symbol_listener(mut self, listener: SymbolListener) -> Self {
+        self.0.symbol_listener = Some(listener);
+        self
+    }
+
+    /// Enables [TransformOptions::inline_const_captures].
+    pub fn inline_const_captures(mut self) -> Self {
+        self.0.inline_const_captures = true;
+        self
+    }
+
+    /// Sets [TransformOptions::inline_threshold_bytes].
+    pub fn inline_threshold_bytes(mut self, bytes: usize) -> Self {
+        self.0.inline_threshold_bytes = Some(bytes);
+        self
+    }
+
+    /// Enables [TransformOptions::lenient].
+    pub fn lenient(mut self) -> Self {
+        self.0.lenient = true;
+        self
+    }
+
+    /// Enables [TransformOptions::reexport_segments].
+    pub fn reexport_segments(mut self) -> Self {
+        self.0.reexport_segments = true;
+        self
+    }
+
+    /// Enables [TransformOptions::import_extensions].
+    pub fn import_extensions(mut self) -> Self {
+        self.0.import_extensions = true;
+        self
+    }
+
+    /// Adds a [define](TransformOptions::defines), replacing `expression` with `value`, the text of a JSON value or
+    /// of any other expression.
+    pub fn define<K: Into<String>, V: Into<String>>(mut self, expression: K, value: V) -> Self {
+        self.0.defines.insert(expression.into(), value.into());
+        self
+    }
+
+    /// Sets [TransformOptions::is_server].
+    pub fn is_server(mut self, is_server: bool) -> Self {
+        self.0.is_server = Some(is_server);
+        self
+    }
+
+    /// Returns the options, failing with [Error::UnsupportedTarget] if the [es target](Self::es_target) is unknown.
+    pub fn build(self) -> Result<TransformOptions> {
+        Transpile::validate(&self.0)?;
+        validate_defines(&self.0.defines)?;
+        Ok(self.0)
+    }
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "camelCase"))]
+pub struct TransformModuleInput {
+    pub path: String,
+    pub dev_path: Option<String>,
+    pub code: String,
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "camelCase"))]
+pub struct TransformModulesOptions {
+    pub src_dir: String,
+    pub root_dir: Option<String>,
+    pub input: Vec<TransformModuleInput>,
+    pub source_maps: bool,
+    pub minify: MinifyMode,
+    pub transpile_ts: bool,
+    pub transpile_jsx: bool,
+    pub preserve_filenames: bool,
+    pub entry_strategy: EntryStrategy,
+    pub explicit_extensions: bool,
+    pub mode: Target,
+    pub scope: Option<String>,
+
+    pub core_module: Option<String>,
+    pub strip_exports: Option<Vec<String>>,
+    pub strip_ctx_name: Option<Vec<String>>,
+    pub strip_event_handlers: bool,
+    pub reg_ctx_name: Option<Vec<String>>,
+    pub is_server: Option<bool>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub hardened: bool,
+}
+
+impl From<&TransformModulesOptions> for TransformOptions {
+    fn from(config: &TransformModulesOptions) -> Self {
+        TransformOptions {
+            target: config.mode,
+            scope: config.scope.clone(),
+            minify: config.minify,
+            transpile_ts: config.transpile_ts,
+            transpile_jsx: config.transpile_jsx,
+            entry_strategy: config.entry_strategy,
+            is_server: config.is_server,
+            strip_exports: config.strip_exports.clone(),
+            strip_ctx_name: config.strip_ctx_name.clone(),
+            hardened: config.hardened,
+            root_dir: config.root_dir.as_ref().map(PathBuf::from),
+            ..TransformOptions::default()
+        }
+    }
+}
+
+/// A single host module of a [TransformResult].
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "camelCase"))]
+pub struct TransformedModule {
+    /// The path of the input file, as it was provided.
+    pub path: PathBuf,
+    pub code: String,
+    /// Serialized as messages, and not deserialized: the [diagnostics](TransformedModule::diagnostics) report them
+    /// as well.
+    #[cfg_attr(feature = "serde", serde(skip_deserializing))]
+    pub errors: Vec<ProcessingFailure>,
+    pub diagnostics: Vec<Diagnostic>,
+    /// Whether [code](TransformedModule::code) is the transformed module, or its source as it was provided.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub kind: ModuleKind,
+    /// What transforming the module cost.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub stats: TransformStats,
+}
+
+/// The merged result of transforming many files.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "camelCase"))]
+pub struct TransformResult {
+    /// The host modules, in input order.
+    pub modules: Vec<TransformedModule>,
+    /// The extracted segments of every module, in input order.
+    pub segments: Vec<QrlComponent>,
+    pub trace: Vec<TraceEntry>,
+    /// The metadata of every segment.
+    #[cfg(feature = "manifest")]
+    pub manifest: Manifest,
+    /// The entry plan computed over the segments of all modules.
+    pub entry_plan: EntryPlan,
+    /// The scoped stylesheets of every module, in input order.
+    pub styles: Vec<ScopedStyle>,
+    /// Translates the opaque ids of the manifest back to display names. Only present when
+    /// [TransformOptions::obfuscate_names] is enabled, and meant to be stored privately rather than shipped.
+    #[cfg(feature = "manifest")]
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub name_mapping: Option<NameMapping>,
+}
+
+impl TransformResult {
+    /// Merges the per-file `results` into a single result, registering their segments in a new [SymbolRegistry].
+    /// Fails if two files produce different segments with the same symbol name.
+    pub(crate) fn from_results(
+        results: Vec<(PathBuf, OptimizationResult)>,
+        options: &TransformOptions,
+    ) -> Result<Self> {
+        let registry = SymbolRegistry::new();
+        for (_, result) in &results {
+            registry.register_all(&result.optimized_app.components)?;
+        }
+        Ok(Self::from_registry(results, registry, options))
+    }
+
+    /// Merges the per-file `results` into a single result, whose segments were already registered in `registry`,
+    /// e.g. by the threads which transformed them.
+    pub(crate) fn from_registry(
+        results: Vec<(PathBuf, OptimizationResult)>,
+        registry: SymbolRegistry,
+        options: &TransformOptions,
+    ) -> Self {
+        let mut modules = Vec::with_capacity(results.len());
+        let mut segments = Vec::new();
+        let mut trace = Vec::new();
+        let mut styles = Vec::new();
+        let mut segment_counts = Vec::with_capacity(results.len());
+
+        for (path, result) in results {
+            let OptimizationResult {
+                optimized_app,
+                errors,
+                diagnostics,
+                trace: module_trace,
+                styles: module_styles,
+                kind,
+                stats,
+                ..
+            } = result;
+            segment_counts.push(optimized_app.components.len());
+            segments.extend(optimized_app.components);
+            trace.extend(module_trace);
+            styles.extend(module_styles);
+            modules.push(TransformedModule {
+                path,
+                code: optimized_app.body,
+                errors,
+                diagnostics,
+                kind,
+                stats,
+            });
+        }
+
+        if let Some(max) = options.max_segments_per_project {
+            let paths: Vec<_> = modules
+                .iter()
+                .map(|module| module.path.to_string_lossy())
+                .collect();
+            let diagnostic = check_project_segments(
+                paths
+                    .iter()
+                    .map(AsRef::as_ref)
+                    .zip(segment_counts.iter().copied()),
+                max,
+            );
+            // The diagnostic is reported on the first of the modules with the most segments.
+            let largest = segment_counts
+                .iter()
+                .enumerate()
+                .max_by_key(|(index, count)| (**count, std::cmp::Reverse(*index)))
+                .map(|(index, _)| index);
+            if let (Some(diagnostic), Some(largest)) = (diagnostic, largest) {
+                modules[largest].diagnostics.push(diagnostic);
+            }
+        }
+
+        if options.normalize_output {
+            modules.sort_by(|a, b| a.path.cmp(&b.path));
+            sort_segments(&mut segments);
+        }
+
+        Self::assemble(
+            modules,
+            segments,
+            registry,
+            trace,
+            styles,
+            options.entry_strategy,
+            options.build_id.clone(),
+            options.obfuscate_names,
+        )
+    }
+
+    /// Combines results of transforming disjoint sets of files, e.g. by hosts which shard the file list across
+    /// processes, into a single result with one [Manifest] and [EntryPlan].
+    ///
+    /// Modules and segments present in several results are only kept once, as long as they are identical. Fails if
+    /// two results contain different modules for the same path or different segments with the same symbol name, or
+    /// if they were produced with a different entry strategy, build id or name obfuscation.
+    pub fn merge(results: Vec<TransformResult>) -> Result<TransformResult> {
+        let strategy = results
+            .first()
+            .map(|result| result.entry_plan.strategy)
+            .unwrap_or_default();
+        #[cfg(feature = "manifest")]
+        let (build_id, obfuscate_names) = (
+            results
+                .first()
+                .and_then(|result| result.manifest.build_id.clone()),
+            results
+                .first()
+                .is_some_and(|result| result.name_mapping.is_some()),
+        );
+        #[cfg(not(feature = "manifest"))]
+        let (build_id, obfuscate_names) = (None, false);
+
+        let mut modules: Vec<TransformedModule> = Vec::new();
+        let mut segments: Vec<QrlComponent> = Vec::new();
+        let mut trace = Vec::new();
+        let mut styles = Vec::new();
+        let mut module_by_path: HashMap<PathBuf, usize> = HashMap::new();
+        let mut segment_by_symbol: HashMap<Name, usize> = HashMap::new();
+        let mut seen_trace = HashSet::new();
+        let mut seen_styles = HashSet::new();
+
+        for result in results {
+            if result.entry_plan.strategy != strategy {
+                return Err(Error::MergeConflict(format!(
+                    "entry strategies {:?} and {:?} differ",
+                    strategy, result.entry_plan.strategy
+                )));
+            }
+            #[cfg(feature = "manifest")]
+            {
+                if result.manifest.build_id != build_id {
+                    return Err(Error::MergeConflict(format!(
+                        "build ids {:?} and {:?} differ",
+                        build_id, result.manifest.build_id
+                    )));
+                }
+                if result.name_mapping.is_some() != obfuscate_names {
+                    return Err(Error::MergeConflict(
+                        "only some of the results have obfuscated names".to_string(),
+                    ));
+                }
+            }
+
+            for module in result.modules {
+                match module_by_path.get(&module.path) {
+                    Some(index) if modules[*index].code == module.code => {}
+                    Some(_) => {
+                        return Err(Error::MergeConflict(format!(
+                            "module '{}' differs between results",
+                            module.path.to_string_lossy()
+                        )))
+                    }
+                    None => {
+                        module_by_path.insert(module.path.clone(), modules.len());
+                        modules.push(module);
+                    }
+                }
+            }
+            for segment in result.segments {
+                match segment_by_symbol.get(&segment.id.symbol_name) {
+                    Some(index) if segments[*index] == segment => {}
+                    Some(index) => {
+                        return Err(Error::MergeConflict(format!(
+                            "symbol '{}' is produced by both '{}' and '{}'",
+                            segment.id.symbol_name, segments[*index].origin, segment.origin
+                        )))
+                    }
+                    None => {
+                        segment_by_symbol.insert(segment.id.symbol_name.clone(), segments.len());
+                        segments.push(segment);
+                    }
+                }
+            }
+            trace.extend(
+                result
+                    .trace
+                    .into_iter()
+                    .filter(|entry| seen_trace.insert(entry.clone())),
+            );
+            styles.extend(
+                result
+                    .styles
+                    .into_iter()
+                    .filter(|style| seen_styles.insert(style.clone())),
+            );
+        }
+
+        let registry = SymbolRegistry::new();
+        registry.register_all(&segments)?;
+
+        Ok(Self::assemble(
+            modules,
+            segments,
+            registry,
+            trace,
+            styles,
+            strategy,
+            build_id,
+            obfuscate_names,
+        ))
+    }
+
+    /// The build id only applies to the manifest. With name obfuscation, entries are planned over the obfuscated
+    /// manifest, so that the [EntryPlan] does not reveal the names it hides either.
+    #[cfg_attr(not(feature = "manifest"), allow(unused_variables))]
+    #[allow(clippy::too_many_arguments)]
+    fn assemble(
+        modules: Vec<TransformedModule>,
+        segments: Vec<QrlComponent>,
+        registry: SymbolRegistry,
+        trace: Vec<TraceEntry>,
+        styles: Vec<ScopedStyle>,
+        strategy: EntryStrategy,
+        build_id: Option<String>,
+        obfuscate_names: bool,
+    ) -> Self {
+        // Entries are planned over the segments in the order of the manifest, sorted by symbol name.
+        let metadata = registry.into_segments();
+
+        #[cfg(feature = "manifest")]
+        let (manifest, name_mapping) = {
+            let manifest = Manifest::new(metadata).with_build_id(build_id);
+            if obfuscate_names {
+                let (manifest, mapping) = manifest.obfuscated();
+                (manifest, Some(mapping))
+            } else {
+                (manifest, None)
+            }
+        };
+        #[cfg(feature = "manifest")]
+        let entry_plan = EntryPlan::new(strategy, &manifest.segments);
+        #[cfg(not(feature = "manifest"))]
+        let entry_plan = EntryPlan::new(strategy, &metadata);
+
+        TransformResult {
+            modules,
+            segments,
+            trace,
+            #[cfg(feature = "manifest")]
+            manifest,
+            entry_plan,
+            styles,
+            #[cfg(feature = "manifest")]
+            name_mapping,
+        }
+    }
+
+    /// The code of the module of the entry named `entry` in the [EntryPlan], which re-exports the symbols of its
+    /// segments from the modules they are emitted to, e.g.
+    /// `export { s_ckEPmXZlub0 } from "./src/app.tsx_s_ckEPmXZlub0";`. The module is meant to be emitted next to
+    /// the segments, at the root of the output. `None` if the plan has no such entry.
+    pub fn entry_module(&self, entry: &str) -> Option<String> {
+        let entry = self.entry_plan.entries.iter().find(|e| e.name == entry)?;
+        let allocator = Allocator::default();
+        let ast_builder = AstBuilder::new(&allocator);
+        let exports = entry.symbols.iter().filter_map(|symbol| {
+            let segment = self.segments.iter().find(|s| &s.id.symbol_name == symbol)?;
+            Some(ast_builder.create_export_from_statement(
+                &[(symbol, symbol)],
+                &segment.import_specifier(Path::new(""), false),
+            ))
+        });
+        let program = ast_builder.program(
+            SPAN,
+            SourceType::mjs(),
+            "",
+            ast_builder.vec(),
+            None,
+            ast_builder.vec(),
+            ast_builder.vec_from_iter(exports),
+        );
+        Some(Codegen::new().build(&program).code)
+    }
+
+    /// All processing failures, across every module.
+    pub fn errors(&self) -> impl Iterator<Item = &ProcessingFailure> {
+        self.modules.iter().flat_map(|m| m.errors.iter())
+    }
+
+    /// All diagnostics, across every module.
+    pub fn diagnostics(&self) -> impl Iterator<Item = &Diagnostic> {
+        self.modules.iter().flat_map(|m| m.diagnostics.iter())
+    }
+
+    /// What transforming every module cost, added up. Times add up the work of every thread, so they exceed the wall
+    /// time of a parallel transform.
+    pub fn stats(&self) -> TransformStats {
+        self.modules.iter().map(|m| m.stats).sum()
+    }
+}
+
+/// Transforms many source files in parallel.
+///
+/// Every file is parsed and transformed on the rayon thread pool with its own `Allocator`. The threads register the
+/// segments of their files in a single [SymbolRegistry], from which the [Manifest] is built, and the per-file results
+/// are merged, in input order, into a single [TransformResult]. Fails if any of the files can not be read, or if two
+/// files produce different segments with the same symbol name.
+///
+/// Segments extracted from files inside a qwik-city `routes/` directory are tagged with their owning route in the
+/// [Manifest], see [RouteFile](crate::routes::RouteFile).
+pub fn transform_fs(paths: Vec<PathBuf>, options: TransformOptions) -> Result<TransformResult> {
+    let registry = SymbolRegistry::new();
+    let results = for_each_input(paths, |path| {
+        let source = Source::from_file(&path)?;
+        let result = transform(source, options.clone())?;
+        registry.register_all(&result.optimized_app.components)?;
+        let privacy = options.path_privacy();
+        Ok((
+            private_path(&path, privacy, options.root_dir.as_deref()),
+            result,
+        ))
+    })?;
+
+    Ok(TransformResult::from_registry(results, registry, &options))
+}
+
+/// Transforms the files below `src_dir` selected by glob `patterns`, e.g. `["**/*.tsx", "!**/*.spec.*"]`, like
+/// [transform_fs] with the paths of the files. The directory is walked by the optimizer, so hosts do not need to list
+/// the files themselves. See [FileSet] for the syntax and the defaults of the patterns.
+pub fn transform_dir<P: AsRef<Path>, S: AsRef<str>>(
+    src_dir: P,
+    patterns: &[S],
+    options: TransformOptions,
+) -> Result<TransformResult> {
+    let paths = FileSet::new(patterns)?.find(src_dir.as_ref())?;
+    transform_fs(paths, options)
+}
+
+/// Transforms in-memory modules, e.g. as provided by a bundler plugin.
+pub fn transform_modules(config: TransformModulesOptions) -> Result<TransformResult> {
+    let options = TransformOptions::from(&config);
+
+    let registry = SymbolRegistry::new();
+    let results = for_each_input(config.input, |input| {
+        let path = PathBuf::from(input.path);
+        let source = Source::from_module(&path, input.code)?;
+        let result = transform(source, options.clone())?;
+        registry.register_all(&result.optimized_app.components)?;
+        Ok((path, result))
+    })?;
+
+    Ok(TransformResult::from_registry(results, registry, &options))
+}
+
+/// Transforms a single in-memory module, whose `path` determines its language and the names of its segments.
+///
+/// This is the stable entry point of the optimizer, re-exported as `qwik_optimizer::transform`:
+/// ```
+/// use qwik_optimizer::api::TransformOptions;
+///
+/// let code = "export const App = component$(() => <div />);";
+/// let result = qwik_optimizer::transform("src/app.tsx", code, TransformOptions::default())?;
+/// assert_eq!(result.modules.len(), 1);
+/// assert_eq!(result.segments.len(), 1);
+/// # Ok::<(), qwik_optimizer::api::Error>(())
+/// ```
+pub fn transform_module<P: AsRef<Path>, T: AsRef<str>>(
+    path: P,
+    code: T,
+    options: TransformOptions,
+) -> Result<TransformResult> {
+    let path = path.as_ref().to_path_buf();
+    let source = Source::from_module(&path, code)?;
+    let result = transform(source, options.clone())?;
+    TransformResult::from_results(vec![(path, result)], &options)
+}
+
+/// Maps every input in parallel, preserving input order. WebAssembly builds have no thread pool, so the inputs are
+/// processed sequentially there.
+fn for_each_input<I, O, F>(inputs: Vec<I>, f: F) -> Result<Vec<O>>
+where
+    I: Send,
+    O: Send,
+    F: Fn(I) -> Result<O> + Send + Sync,
+{
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        inputs.into_par_iter().map(f).collect()
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    {
+        inputs.into_iter().map(f).collect()
+    }
+}
+
+/// A module using the common markers, transformed by [warm_up].
+const WARM_UP_SOURCE: &str = r#"
+import { component$, useSignal, $ } from "@qwik.dev/core";
+export const App = component$(() => {
+    const count = useSignal(0);
+    return <button onClick$={$(() => count.value++)}>{count.value}</button>;
+});
+export default App;
+"#;
+
+/// Prepares the process for transforming modules with `options`, so that the first modules of a build are not
+/// slower than the rest. Hosts can call it at startup, e.g. while the dev server is still binding its port.
+///
+/// Validates `options`, starts the thread pool used by [transform_fs] and [transform_modules], and transforms a small
+/// module, which pages in the code of the parser, the transforms and the code generator and fills the caches of
+/// configuration derived from `options`. Measured on a release build, the first transform of a module of the size of
+/// `test_example_1.tsx` took 0.6–3.7ms rather than 0.2–0.3ms, and as long as the following ones after warming up,
+/// which itself took about 0.7ms.
+pub fn warm_up(options: &TransformOptions) -> Result<()> {
+    Transpile::validate(options)?;
+    #[cfg(not(target_arch = "wasm32"))]
+    rayon::current_num_threads();
+
+    let source = Source::from_source(
+        WARM_UP_SOURCE,
+        crate::component::Language::Typescript,
+        Some("warm_up".into()),
+    )?;
+    transform(source, options.clone()).map(|_| ())
+}
+
+pub fn transform(script_source: Source, options: TransformOptions) -> Result<OptimizationResult> {
+    transform_with(script_source, options, None)
+}
+
+/// Transforms `script_source` like [transform], numbering duplicate segment names with `registry` so that they stay
+/// stable while the file is edited. The registry is updated with the names of the file.
+pub fn transform_with_registry(
+    script_source: Source,
+    options: TransformOptions,
+    registry: &NameRegistry,
+) -> Result<OptimizationResult> {
+    transform_with(script_source, options, Some(registry))
+}
+
+fn transform_with(
+    script_source: Source,
+    options: TransformOptions,
+    registry: Option<&NameRegistry>,
+) -> Result<OptimizationResult> {
+    let normalize_output = options.normalize_output;
+    let listener = options.symbol_listener.clone();
+    let bytes_in = script_source.source_code().len();
+    let stopwatch = Stopwatch::start();
+    let mut result = transform_guarded(script_source, options, registry)?;
+    if normalize_output {
+        sort_segments(&mut result.optimized_app.components);
+    }
+    let app = &result.optimized_app;
+    let stats = &mut result.stats;
+    stats.transform_ms = (millis(stopwatch.elapsed()) - stats.parse_ms - stats.codegen_ms).max(0.0);
+    stats.bytes_in = bytes_in;
+    stats.bytes_out = app.body.len() + app.components.iter().map(|c| c.code.len()).sum::<usize>();
+    stats.segment_count = app.components.len();
+    if let Some(listener) = listener {
+        listener.notify(&result.optimized_app.components);
+    }
+    Ok(result)
+}
+
+/// Sorts `segments` by hash, and by symbol name for segments sharing a hash, see
+/// [TransformOptions::normalize_output].
+fn sort_segments(segments: &mut [QrlComponent]) {
+    segments.sort_by(|a, b| (&a.id.hash, &a.id.symbol_name).cmp(&(&b.id.hash, &b.id.symbol_name)));
+}
+
+/// Drops the byte order mark of `source` and converts its line endings to `\n`, see
+/// [TransformOptions::normalize_output].
+fn normalize_source(source: &str) -> Cow<'_, str> {
+    let source = source.strip_prefix('\u{feff}').unwrap_or(source);
+    match source.contains('\r') {
+        true => Cow::Owned(source.replace("\r\n", "\n").replace('\r', "\n")),
+        false => Cow::Borrowed(source),
+    }
+}
+
+fn transform_guarded(
+    script_source: Source,
+    options: TransformOptions,
+    registry: Option<&NameRegistry>,
+) -> Result<OptimizationResult> {
+    if !options.hardened {
+        return transform_source(script_source, options, registry);
+    }
+
+    let limits = options.limits;
+    let entry_strategy = options.entry_strategy;
+    let transformed = catch_panics(limits.stack_size(), || {
+        transform_source(script_source, options, registry)
+    });
+    match transformed {
+        Ok(Ok(Ok(result))) => {
+            match limits.check_segment_count(result.optimized_app.components.len()) {
+                Some(diagnostic) => Ok(OptimizationResult::rejected(diagnostic, entry_strategy)),
+                None => Ok(result),
+            }
+        }
+        Ok(Ok(Err(e))) => Err(e),
+        Ok(Err(panic)) => {
+            let reason = panic
+                .downcast_ref::<&str>()
+                .map(|s| s.to_string())
+                .or_else(|| panic.downcast_ref::<String>().cloned())
+                .unwrap_or_default();
+            let diagnostic = Diagnostic::error(
+                DiagnosticCode::InternalError,
+                format!("The optimizer failed to transform the module: {reason}"),
+            );
+            Ok(OptimizationResult::rejected(diagnostic, entry_strategy))
+        }
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Runs `f` on a thread with a stack of `stack_size` bytes, catching its panics.
+#[cfg(not(target_arch = "wasm32"))]
+fn catch_panics<T: Send>(
+    stack_size: usize,
+    f: impl FnOnce() -> T + Send,
+) -> std::io::Result<std::thread::Result<T>> {
+    std::thread::scope(|scope| {
+        std::thread::Builder::new()
+            .stack_size(stack_size)
+            .spawn_scoped(scope, f)
+            .map(|thread| thread.join())
+    })
+}
+
+/// Runs `f` on the current thread, catching its panics, since threads cannot be spawned on wasm32. The stack of
+/// the module must hold `stack_size` bytes.
+#[cfg(target_arch = "wasm32")]
+fn catch_panics<T>(
+    _stack_size: usize,
+    f: impl FnOnce() -> T,
+) -> std::io::Result<std::thread::Result<T>> {
+    Ok(std::panic::catch_unwind(std::panic::AssertUnwindSafe(f)))
+}
+
+fn transform_source(
+    script_source: Source,
+    options: TransformOptions,
+    registry: Option<&NameRegistry>,
+) -> Result<OptimizationResult> {
+    Transpile::validate(&options)?;
+    let allocator = ALLOCATORS.get();
+    let preprocessed = match &options.preprocessor {
+        Some(preprocessor) => preprocessor.preprocess(
+            &script_source.source_info().rel_path,
+            script_source.source_code(),
+        )?,
+        None => None,
+    };
+    let source_code = preprocessed
+        .as_deref()
+        .unwrap_or(script_source.source_code());
+    let source_text = match options.normalize_output {
+        true => normalize_source(source_code),
+        false => Cow::Borrowed(source_code),
+    };
+    let source_text = source_text.as_ref();
+    let source_info = &script_source
+        .source_info()
+        .with_path_privacy(options.path_privacy(), options.root_dir.as_deref())?;
+    let source_type: SourceType = script_source.source_info().try_into()?;
+    if options.hardened {
+        let limits = &options.limits;
+        let rejected = limits
+            .check_source_size(source_text)
+            .or_else(|| limits.check_nesting(source_text, source_type.is_jsx()));
+        if let Some(diagnostic) = rejected {
+            return Ok(OptimizationResult::rejected(
+                diagnostic,
+                options.entry_strategy,
+            ));
+        }
+    }
+
+    if let Some(kind) = classify_source(source_text) {
+        return Ok(OptimizationResult::unchanged(
+            source_text,
+            kind,
+            options.entry_strategy,
+        ));
+    }
+
+    let stopwatch = Stopwatch::start();
+    let parse_return = Parser::new(&allocator, source_text, source_type).parse();
+    let path = &script_source.source_info().rel_path;
+    if options.is_external(path) {
+        let mut result = OptimizationResult::unchanged(
+            source_text,
+            ModuleKind::External,
+            options.entry_strategy,
+        );
+        if options.forbid_external_markers == Some(ExternalMarkers::Error) {
+            result.diagnostics = external_marker_diagnostics(&parse_return.program, path);
+        }
+        return Ok(result);
+    }
+    let mut diagnostics: Vec<Diagnostic> =
+        parse_return.errors.iter().map(Diagnostic::from).collect();
+
+    let recovered = match options.lenient && !diagnostics.is_empty() {
+        true => Recovered::new(source_text, source_type),
+        false => None,
+    };
+    let (mut program, source_text) = match &recovered {
+        Some(recovered) => (
+            Parser::new(&allocator, &recovered.source, source_type)
+                .parse()
+                .program,
+            recovered.source.as_str(),
+        ),
+        None => (parse_return.program, source_text),
+    };
+    let parse_time = stopwatch.elapsed();
+    let passthrough = match script_source.source_info().is_markdown() {
+        true => Passthrough::new(&mut program, source_text, AstBuilder::new(&allocator)),
+        false => Passthrough::default(),
+    };
+    let restore = |body: String| {
+        let body = passthrough.restore(&body);
+        match &recovered {
+            Some(recovered) => recovered.restore(&body),
+            None => body,
+        }
+    };
+    if options.hardened {
+        if let Some(diagnostic) = options.limits.check_depth(&program) {
+            return Ok(OptimizationResult::rejected(
+                diagnostic,
+                options.entry_strategy,
+            ));
+        }
+    }
+    if diagnostics.is_empty() {
+        let kind = classify_program(&program, &options.markers)
+            .filter(|kind| *kind != ModuleKind::MarkerFree || !options.rewrites_plain_modules());
+        if let Some(kind) = kind {
+            return Ok(OptimizationResult::unchanged(
+                source_text,
+                kind,
+                options.entry_strategy,
+            ));
+        }
+    }
+    if !options.defines.is_empty() {
+        replace_defines(&mut program, &options.defines, &allocator)?;
+        prune_dead_branches(&mut program, AstBuilder::new(&allocator));
+    }
+    if let Some(names) = &options.strip_exports {
+        strip_exports(&mut program, names, AstBuilder::new(&allocator));
+    }
+
+    // Endpoints only run on the server, so their `$` calls are left in place rather than extracted.
+    if is_endpoint_only(&program, &source_info.rel_path, &options.markers) {
+        if options.strict_esm {
+            let semantic = SemanticBuilder::new().build(&program).semantic;
+            diagnostics.extend(validate_esm(&semantic));
+        }
+        if let Some(max) = options.max_diagnostics {
+            cap_diagnostics(&mut diagnostics, max);
+        }
+        ImportCleanUp::prune(&mut program);
+        ImportCleanUp::rename_import_sources(&mut program, &allocator);
+        let preserved_comments = PreservedComments::new(&program, &options.preserve_comments);
+        let stopwatch = Stopwatch::start();
+        let body = render_host(
+            &mut program,
+            source_info,
+            &options,
+            &preserved_comments,
+            &allocator,
+        );
+        let codegen_time = stopwatch.elapsed();
+        let result = OptimizationResult::new(
+            OptimizedApp {
+                body: restore(body),
+                components: Vec::new(),
+            },
+            Vec::new(),
+            diagnostics,
+            Vec::new(),
+            EntryPlan::new(options.entry_strategy, &[]),
+            Vec::new(),
+        );
+        return Ok(result.with_timings(parse_time, codegen_time));
+    }
+
+    wrap_method_markers(&mut program, AstBuilder::new(&allocator));
+    destructure_props(&mut program, &allocator, options.is_compact());
+
+    let SemanticBuilderReturn {
+        semantic,
+        errors: semantic_errors,
+    } = SemanticBuilder::new()
+        .with_check_syntax_error(true) // Enable extra syntax error checking
+        .with_build_jsdoc(true) // Enable JSDoc parsing
+        .with_cfg(true) // Build a Control Flow Graph
+        .build(&program);
+    diagnostics.extend(semantic_errors.iter().map(Diagnostic::from));
+    if options.strict_esm {
+        diagnostics.extend(validate_esm(&semantic));
+    }
+
+    let file = source_info.rel_path.to_string_lossy().to_string();
+    let claims = registry.map(|registry| NameClaims::new(registry.file_names(&file)));
+    let preserved_comments = PreservedComments::new(&program, &options.preserve_comments);
+    let mut transform = &mut TransformGenerator::new(
+        source_info,
+        source_text,
+        preserved_comments,
+        options,
+        claims,
+    );
+
+    let (symbols, scopes) = semantic.into_symbol_table_and_scope_tree();
+
+    traverse_mut(transform, &allocator, &mut program, symbols, scopes);
+    if let Some(failure) = transform.failure.take() {
+        return Err(failure);
+    }
+
+    if let Some(registry) = registry {
+        let builder = std::mem::replace(&mut transform.segment_builder, SegmentBuilder::new());
+        if let Some(claims) = builder.into_claims() {
+            registry.update(&file, claims.into_names());
+        }
+    }
+
+    let segments: Vec<SegmentMetadata> = transform
+        .app
+        .components
+        .iter()
+        .map(SegmentMetadata::from)
+        .collect();
+    let entry_plan = EntryPlan::new(transform.options.entry_strategy, &segments);
+
+    diagnostics.append(&mut transform.diagnostics);
+    if let Some(max) = transform.options.max_segments_per_file {
+        diagnostics.extend(check_file_segments(&transform.app.components, max));
+    }
+    if transform.options.strict_captures {
+        let invalid_captures: Vec<String> = diagnostics
+            .iter()
+            .filter(|d| d.is_invalid_capture())
+            .map(Diagnostic::to_string)
+            .collect();
+        if !invalid_captures.is_empty() {
+            return Err(Error::InvalidCaptures(invalid_captures));
+        }
+    }
+    if let Some(max) = transform.options.max_diagnostics {
+        cap_diagnostics(&mut diagnostics, max);
+    }
+
+    let app = OptimizedApp {
+        body: restore(transform.app.body.clone()),
+        components: transform.app.components.clone(),
+    };
+    let result = OptimizationResult::new(
+        app,
+        transform.errors.clone(),
+        diagnostics,
+        std::mem::take(&mut transform.trace),
+        entry_plan,
+        std::mem::take(&mut transform.styles),
+    );
+    Ok(result.with_timings(parse_time, transform.codegen_time))
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::entry_strategy::WORKER_ENTRY;
+    use crate::manifest::SegmentEnvironment;
+    use insta::assert_yaml_snapshot;
+    use std::path::PathBuf;
+    use std::sync::{Arc, Mutex};
+
+    #[test]
+    fn test_example_1() {
+        assert_valid_transform_debug!();
+    }
+
+    #[test]
+    fn test_example_2() {
+        assert_valid_transform!();
+    }
+
+    #[test]
+    fn test_example_3() {
+        assert_valid_transform!();
+    }
+
+    #[test]
+    fn test_example_4() {
+        assert_valid_transform!();
+    }
+
+    #[test]
+    fn test_example_5() {
+        assert_valid_transform!();
+    }
+
+    #[test]
+    fn test_example_6() {
+        assert_valid_transform!();
+    }
+
+    #[test]
+    fn test_example_7() {
+        assert_valid_transform_debug!();
+    }
+
+    #[test]
+    fn test_example_8() {
+        assert_valid_transform_debug!();
+    }
+
+    // #[test]
+    fn test_example_9() {
+        // Not removing:
+        // const decl8 = 1, decl9;
+        assert_valid_transform_debug!();
+    }
+
+    // #[test]
+    fn test_example_10() {
+        // Not converting:
+        // const a = ident1 + ident3;
+        // const b = ident1 + ident3;
+        // to:
+        // ident1, ident3;
+        // ident1, ident3;
+        assert_valid_transform!();
+    }
+
+    #[test]
+    fn test_example_11() {
+        assert_valid_transform!();
+    }
+
+    #[test]
+    fn test_example_capture_imports() {
+        assert_valid_transform!();
+    }
+
+    #[test]
+    fn test_transpile_ts_strips_segments() {
+        let source = test_source!(
+            r#"
+            import { component$ } from '@qwik.dev/core';
+            interface Props { name: string }
+            export const App = component$((props: Props) => {
+                const label = props.name as string;
+                return label;
+            });
+            "#
+        );
+        let options = TransformOptions {
+            transpile_ts: true,
+            ..TransformOptions::default()
+        };
+        let app = transform(source, options).unwrap().optimized_app;
+
+        assert!(!app.body.contains("interface"));
+        assert_eq!(app.components.len(), 1);
+        let comp = &app.components[0];
+        assert_eq!(comp.language, crate::component::Language::Javascript);
+        assert!(comp.code.contains("(props) =>"));
+        assert!(!comp.code.contains(" as string"));
+    }
+
+    #[test]
+    fn test_decision_trace() {
+        let source = test_source!(
+            r#"
+            import { component$ } from '@qwik.dev/core';
+            import { format } from './format';
+            export const App = component$(() => format('a'));
+            "#,
+            crate::component::Language::Javascript
+        );
+
+        let untraced = transform(source.clone(), TransformOptions::default()).unwrap();
+        assert!(untraced.trace().is_empty());
+
+        let options = TransformOptions {
+            trace: true,
+            ..TransformOptions::default()
+        };
+        let result = transform(source, options).unwrap();
+        let trace = result.trace();
+
+        assert_eq!(trace.len(), 1);
+        let entry = &trace[0];
+        assert_eq!(entry.call_site, "component$");
+        assert_eq!(
+            entry.symbol_name,
+            result.optimized_app().components[0].id.symbol_name
+        );
+        assert_eq!(entry.decisions_of(DecisionKind::Naming).count(), 2);
+        assert_eq!(entry.decisions_of(DecisionKind::EntryStrategy).count(), 1);
+        assert_eq!(entry.decisions_of(DecisionKind::Extraction).count(), 1);
+        let captures: Vec<&str> = entry
+            .decisions_of(DecisionKind::Capture)
+            .map(|d| d.detail.as_str())
+            .collect();
+        assert_eq!(
+            captures,
+            vec![r#"captured import { format } from "./format""#]
+        );
+    }
+
+    #[test]
+    fn test_entry_plan_is_exposed() {
+        let source = Source::from_file("./src/test_input/test_example_2.tsx").unwrap();
+        let options = TransformOptions {
+            entry_strategy: EntryStrategy::Single,
+            ..TransformOptions::default()
+        };
+        let result = transform(source, options).unwrap();
+        let plan = result.entry_plan();
+
+        assert_eq!(plan.strategy, EntryStrategy::Single);
+        assert_eq!(plan.entries.len(), 1);
+        let symbols = &plan.entries[0].symbols;
+        assert_eq!(symbols.len(), result.optimized_app().components.len());
+        assert!(symbols.windows(2).all(|w| w[0] < w[1]));
+        for comp in &result.optimized_app().components {
+            assert_eq!(comp.entry.as_deref(), Some(plan.entries[0].name.as_str()));
+        }
+    }
+
+    #[test]
+    #[cfg(all(feature = "manifest", feature = "serde"))]
+    fn test_transform_fs() {
+        let paths: Vec<PathBuf> = [
+            "test_example_1.tsx",
+            "test_example_2.tsx",
+            "test_example_11.tsx",
+        ]
+        .iter()
+        .map(|name| PathBuf::from("./src/test_input").join(name))
+        .collect();
+
+        let result = transform_fs(paths.clone(), TransformOptions::default()).unwrap();
+
+        let module_paths: Vec<PathBuf> = result.modules.iter().map(|m| m.path.clone()).collect();
+        assert_eq!(module_paths, paths);
+
+        let expected_segments: usize = paths
+            .iter()
+            .map(|path| {
+                let source = Source::from_file(path).unwrap();
+                let result = transform(source, TransformOptions::default()).unwrap();
+                result.optimized_app().components.len()
+            })
+            .sum();
+        assert_eq!(result.segments.len(), expected_segments);
+        assert_eq!(result.entry_plan.standalone.len(), result.segments.len());
+        assert_eq!(result.errors().count(), 0);
+        assert_eq!(result.manifest.segments.len(), result.segments.len());
+
+        let manifest = Manifest::from_json(result.manifest.to_json().unwrap()).unwrap();
+        let simulated = crate::entry_strategy::simulate_strategy(&manifest, EntryStrategy::Segment);
+        assert_eq!(simulated, result.entry_plan);
+    }
+
+    #[test]
+    #[cfg(all(feature = "manifest", feature = "serde"))]
+    fn test_transform_modules() {
+        let config: TransformModulesOptions = serde_json::from_str(
+            r#"{
+                "srcDir": "/app/src",
+                "input": [
+                    { "path": "routes/index.tsx", "code": "import { component$ } from '@qwik.dev/core';\nexport default component$(() => 1);" },
+                    { "path": "utils.ts", "code": "export const a = 1;" }
+                ],
+                "sourceMaps": false,
+                "minify": "none",
+                "transpileTs": true,
+                "transpileJsx": false,
+                "preserveFilenames": false,
+                "entryStrategy": "single",
+                "explicitExtensions": false,
+                "mode": "prod",
+                "stripEventHandlers": false
+            }"#,
+        )
+        .unwrap();
+
+        let result = transform_modules(config).unwrap();
+
+        assert_eq!(result.modules.len(), 2);
+        assert_eq!(result.modules[0].path, PathBuf::from("routes/index.tsx"));
+        assert_eq!(result.segments.len(), 1);
+        assert!(result.segments[0].id.symbol_name.starts_with("s_"));
+        assert_eq!(result.entry_plan.strategy, EntryStrategy::Single);
+        assert_eq!(result.manifest.segments[0].route.as_deref(), Some("/"));
+    }
+
+    #[test]
+    fn test_max_diagnostics() {
+        let code: String = (0..60)
+            .map(|i| format!("export const handler{i} = $();\n"))
+            .collect();
+        let diagnostics = |options: TransformOptions| {
+            let result = transform_module("handlers.ts", &code, options).unwrap();
+            result.diagnostics().cloned().collect::<Vec<_>>()
+        };
+
+        let capped = diagnostics(TransformOptions::default());
+        assert_eq!(capped.len(), DEFAULT_MAX_DIAGNOSTICS + 1);
+        let summary = capped.last().unwrap();
+        assert_eq!(summary.code, DiagnosticCode::TooManyDiagnostics);
+        assert!(summary
+            .message
+            .starts_with("10 more diagnostics were omitted"));
+
+        let options = TransformOptions::builder()
+            .max_diagnostics(None)
+            .build()
+            .unwrap();
+        assert_eq!(diagnostics(options).len(), 60);
+    }
+
+    #[test]
+    fn test_optimized_jsx() {
+        let code = r#"
+            import { component$, useSignal } from "@qwik.dev/core";
+            export const Counter = component$(() => {
+                const count = useSignal(0);
+                return <div class="counter"><span>Count</span>{count.value}</div>;
+            });
+            "#;
+        let options = TransformOptions::builder()
+            .transpile_jsx(true)
+            .optimize_jsx(true)
+            .build()
+            .unwrap();
+        let result = transform_module("counter.tsx", code, options).unwrap();
+        let counter = &result.segments[0].code;
+        assert!(
+            counter.contains(r#"import { _jsxQ } from "@qwik.dev/core";"#),
+            "{counter}"
+        );
+        assert!(counter.contains(r#"_jsxQ("span", null, null, "Count", 3, null)"#));
+        assert!(counter.contains(r#"{ class: "counter" }, [_jsxQ("span""#));
+        assert!(counter.contains("count.value], 1, null)"));
+        assert!(!counter.contains("jsx-runtime"));
+    }
+
+    #[test]
+    fn test_destructured_props() {
+        let code = r#"
+            import { component$ } from "@qwik.dev/core";
+            export const Button = component$(({ label, ...rest }) => <button {...rest}>{label}</button>);
+            "#;
+        let result = transform_module("button.tsx", code, TransformOptions::default()).unwrap();
+        let button = &result.segments[0];
+        assert!(button
+            .code
+            .contains(r#"import { _restProps } from "@qwik.dev/core";"#));
+        assert!(button
+            .code
+            .contains(r#"const rest = _restProps(props, ["label"]);"#));
+        assert!(button.code.contains("{props.label}"));
+    }
+
+    #[test]
+    fn test_destructured_props_in_nested_segments() {
+        let code = r#"
+            import { component$, $ } from "@qwik.dev/core";
+            export const Button = component$(({ name }) => <button onClick$={$(() => console.log(name))}>{name}</button>);
+            "#;
+        let result = transform_module("button.tsx", code, TransformOptions::default()).unwrap();
+        let segment = |ctx_name: &str| {
+            result
+                .segments
+                .iter()
+                .find(|segment| segment.ctx_name == ctx_name)
+                .unwrap()
+        };
+        let button = segment("component$");
+        assert!(!button.qrl.captures.contains(&"props".to_string()));
+        assert!(!button.code.contains("useLexicalScope"), "{}", button.code);
+        assert!(button.code.contains("(props) => "), "{}", button.code);
+        assert!(button.code.contains("[props])"), "{}", button.code);
+
+        let handler = segment("$");
+        assert_eq!(handler.qrl.captures, vec!["props".to_string()]);
+        assert!(handler.code.contains("const [props] = useLexicalScope();"));
+        assert!(handler.code.contains("console.log(props.name)"));
+        assert!(result.modules[0].code.contains("componentQrl(qrl("));
+    }
+
+    #[test]
+    fn test_unchanged_modules() {
+        let kind = |path: &str, code: &str, options: TransformOptions| {
+            let result = transform(Source::from_module(path, code).unwrap(), options).unwrap();
+            if result.kind().is_unchanged() {
+                assert_eq!(result.optimized_app().body, code);
+                assert!(result.optimized_app().components.is_empty());
+                assert!(result.diagnostics().is_empty());
+            }
+            result.kind()
+        };
+        let plain = "export const sum = (a: number, b: number) => a + b;\n";
+
+        assert_eq!(
+            kind("empty.ts", "", TransformOptions::default()),
+            ModuleKind::Empty
+        );
+        assert_eq!(
+            kind(
+                "license.ts",
+                "/* @license MIT */\n",
+                TransformOptions::default()
+            ),
+            ModuleKind::CommentsOnly
+        );
+        assert_eq!(
+            kind("utils.ts", plain, TransformOptions::default()),
+            ModuleKind::MarkerFree
+        );
+        assert_eq!(
+            kind(
+                "app.tsx",
+                "export const App = component$(() => <div />);",
+                TransformOptions::default()
+            ),
+            ModuleKind::Transformed
+        );
+
+        let transpiled = TransformOptions::builder()
+            .transpile_ts(true)
+            .build()
+            .unwrap();
+        assert_eq!(kind("utils.ts", plain, transpiled), ModuleKind::Transformed);
+
+        let result = transform_module("empty.ts", "", TransformOptions::default()).unwrap();
+        assert_eq!(result.modules[0].kind, ModuleKind::Empty);
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn test_json_round_trip() {
+        let options: TransformOptions = serde_json::from_str(
+            r#"{ "target": "Prod", "transpileTs": true, "limits": { "maxSegments": 8 } }"#,
+        )
+        .unwrap();
+        assert_eq!(options.target, Target::Prod);
+        let json = serde_json::to_value(&options).unwrap();
+        assert_eq!(json["target"], "prod");
+        assert_eq!(json["limits"]["maxSegments"], 8);
+        let options: TransformOptions = serde_json::from_value(json.clone()).unwrap();
+        assert_eq!(serde_json::to_value(&options).unwrap(), json);
+
+        let code = std::fs::read_to_string("src/test_input/test_example_1.tsx").unwrap();
+        let result = transform_module("src/app.tsx", code, options).unwrap();
+        let json = serde_json::to_value(&result).unwrap();
+        assert_eq!(
+            json["segments"][0]["id"],
+            serde_json::to_value(&result.segments[0].id).unwrap()
+        );
+        let parsed: TransformResult = serde_json::from_value(json.clone()).unwrap();
+        assert_eq!(parsed.segments, result.segments);
+        assert_eq!(parsed.diagnostics().count(), result.diagnostics().count());
+        assert_eq!(parsed.modules[0].code, result.modules[0].code);
+    }
+
+    #[test]
+    fn test_unused_imports_are_pruned() {
+        let source = test_source!(
+            r#"
+            import './global.css';
+            import { component$, useSignal } from '@qwik.dev/core';
+            import { a, b } from 'x';
+            import * as ns from 'y';
+            export const App = component$(() => {
+                const s = useSignal(0);
+                return a + ns.q;
+            });
+            b();
+            "#
+        );
+        let app = transform(source, TransformOptions::default())
+            .unwrap()
+            .optimized_app;
+
+        assert!(app.body.starts_with("import \"./global.css\";\n"));
+        assert!(app.body.contains(r#"import { b } from "x";"#));
+        assert!(!app.body.contains("useSignal"));
+        assert!(!app.body.contains(r#"from "y""#));
+
+        let imports: Vec<&str> = app.components[0]
+            .code
+            .lines()
+            .filter(|line| line.starts_with("import"))
+            .collect();
+        assert_eq!(
+            imports,
+            vec![
+                r#"import { a } from "x";"#,
+                r#"import { useSignal } from "@qwik.dev/core";"#,
+                r#"import * as ns from "y";"#,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_endpoint_files_are_not_extracted() {
+        let source = Source::from_module(
+            "src/routes/api/index.ts",
+            r#"
+            import { z } from '@builder.io/qwik-city';
+            const schema = z.object({ id: z.number() });
+            export const onGet = async ({ json, query }) => json(200, schema.parse(query));
+            "#,
+        )
+        .unwrap();
+        let app = transform(source, TransformOptions::default())
+            .unwrap()
+            .optimized_app;
+
+        assert!(app.components.is_empty());
+        assert!(app
+            .body
+            .contains(r#"import { z } from "@qwik.dev/router";"#));
+        assert!(app.body.contains("schema.parse(query)"));
+    }
+
+    #[test]
+    fn test_transform_dir() {
+        let result = transform_dir(
+            "./src/test_input",
+            &["test_example_{1,2}.tsx", "**/*.js", "!**/*_fn_*"],
+            TransformOptions::default(),
+        )
+        .unwrap();
+        let paths: Vec<_> = result
+            .modules
+            .iter()
+            .map(|module| module.path.file_name().unwrap().to_string_lossy())
+            .collect();
+        assert_eq!(
+            paths,
+            [
+                "test_example_1.tsx",
+                "test_example_2.tsx",
+                "test_example_capture_imports.js"
+            ]
+        );
+
+        let invalid = transform_dir("./src/test_input", &["*.{tsx"], TransformOptions::default());
+        assert!(matches!(invalid, Err(Error::InvalidGlob(_, _))));
+    }
+
+    #[test]
+    #[cfg(feature = "manifest")]
+    fn test_reproducible_transform_fs() {
+        let relative = PathBuf::from("src/test_input/test_example_1.tsx");
+        let root = std::env::current_dir().unwrap();
+        let absolute = root.join(&relative);
+        let options = TransformOptions {
+            reproducible: true,
+            root_dir: Some(root),
+            ..TransformOptions::default()
+        };
+
+        let from_relative = transform_fs(vec![relative.clone()], options.clone()).unwrap();
+        let from_absolute = transform_fs(vec![absolute], options).unwrap();
+
+        assert_eq!(from_absolute.modules[0].path, relative);
+        assert_eq!(from_absolute.modules[0].code, from_relative.modules[0].code);
+        assert_eq!(from_absolute.manifest, from_relative.manifest);
+    }
+
+    #[test]
+    #[cfg(all(feature = "manifest", feature = "serde"))]
+    fn test_hashed_path_privacy() {
+        let path = std::env::current_dir()
+            .unwrap()
+            .join("src/test_input/test_example_1.tsx");
+        let options = TransformOptions {
+            path_privacy: PathPrivacy::Hashed,
+            ..TransformOptions::default()
+        };
+
+        let result = transform_fs(vec![path], options).unwrap();
+        let manifest = serde_json::to_string(&result.manifest).unwrap();
+
+        assert_eq!(
+            result.modules[0].path.file_name().unwrap(),
+            "test_example_1.tsx"
+        );
+        for leaked in ["test_input", "src/"] {
+            assert!(!result.modules[0].path.to_string_lossy().contains(leaked));
+            assert!(!result.modules[0].code.contains(leaked));
+            assert!(!manifest.contains(leaked));
+        }
+    }
+
+    #[test]
+    fn test_use_styles_scoped() {
+        let source = test_source!(
+            r#"
+            import { component$, useStylesScoped$ } from '@qwik.dev/core';
+            export const Header = component$(() => {
+                useStylesScoped$(`.title { color: red; }`);
+                return <div class="title"><Logo /></div>;
+            });
+            "#
+        );
+        let result = transform(source, TransformOptions::default()).unwrap();
+        let components = &result.optimized_app().components;
+
+        let header = components
+            .iter()
+            .find(|c| c.ctx_name == "component$")
+            .unwrap();
+        let style = components
+            .iter()
+            .find(|c| c.ctx_name == "useStylesScoped$")
+            .unwrap();
+
+        assert_eq!(
+            result.styles(),
+            &[ScopedStyle {
+                symbol_name: style.id.symbol_name.to_string(),
+                component: header.id.symbol_name.to_string(),
+                scope_id: header.id.hash.to_string(),
+                style: Some(".title { color: red; }".to_string()),
+            }]
+        );
+        let class = format!(
+            r#"<div class="{} title">"#,
+            result.styles()[0].scope_class()
+        );
+        assert!(header.code.contains(&class));
+        assert!(header.code.contains("<Logo />"));
+        assert!(header.code.contains("useStylesScopedQrl("));
+    }
+
+    #[test]
+    fn test_lexical_captures() {
+        let source = test_source!(
+            r#"
+            import { component$, $ } from '@qwik.dev/core';
+            export const App = component$((props) => {
+                const count = 1;
+                return <div onClick={$(() => console.log(count, props.x))} />;
+            });
+            "#
+        );
+        let result = transform(source, TransformOptions::default()).unwrap();
+        let components = &result.optimized_app().components;
+
+        let handler = components.iter().find(|c| c.ctx_name == "$").unwrap();
+        assert_eq!(handler.qrl.captures, vec!["count", "props"]);
+        assert!(handler
+            .code
+            .contains(r#"import { useLexicalScope } from "@qwik.dev/core";"#));
+        assert!(handler
+            .code
+            .contains("const [count, props] = useLexicalScope();"));
+
+        let app = components
+            .iter()
+            .find(|c| c.ctx_name == "component$")
+            .unwrap();
+        assert!(app.qrl.captures.is_empty());
+        let capture_array = format!(r#""{}", [count, props])"#, handler.id.symbol_name);
+        assert!(app.code.contains(&capture_array));
+        assert!(result.errors().is_empty());
+    }
+
+    #[test]
+    fn test_diagnostics() {
+        let code = r#"
+            import { component$, $ } from '@qwik.dev/core';
+            export const App = component$(() => {
+                let count = 0;
+                count = 1;
+                const noop = $(...[]);
+                return <div onClick={$(() => console.log(count))} />;
+            });
+            "#;
+        let source = test_source!(code);
+        let result = transform(source, TransformOptions::default()).unwrap();
+        let codes: Vec<DiagnosticCode> = result.diagnostics().iter().map(|d| d.code).collect();
+
+        assert_eq!(
+            codes,
+            vec![
+                DiagnosticCode::InvalidQrlArgument,
+                DiagnosticCode::MutableCapture
+            ]
+        );
+        let label = result.diagnostics()[1].label.as_ref().unwrap();
+        assert_eq!(&code[label.start as usize..label.end as usize], "count");
+    }
+
+    #[test]
+    fn test_syntax_errors_are_diagnostics() {
+        let source = test_source!("export const a = ;");
+        let result = transform(source, TransformOptions::default()).unwrap();
+
+        assert_eq!(result.diagnostics().len(), 1);
+        assert_eq!(result.diagnostics()[0].code, DiagnosticCode::SyntaxError);
+    }
+
+    #[test]
+    #[cfg(feature = "manifest")]
+    fn test_obfuscated_manifest() {
+        let paths = vec![PathBuf::from("src/test_input/test_example_1.tsx")];
+        let options = TransformOptions {
+            target: Target::Prod,
+            obfuscate_names: true,
+            entry_strategy: EntryStrategy::Component,
+            ..TransformOptions::default()
+        };
+
+        let result = transform_fs(paths, options).unwrap();
+        let mapping = result.name_mapping.as_ref().unwrap();
+
+        let plan = format!("{:?}", result.entry_plan);
+        let roots: Vec<&String> = mapping
+            .names
+            .iter()
+            .filter(|(id, _)| id.starts_with("r_"))
+            .map(|(_, root)| root)
+            .collect();
+        assert!(!roots.is_empty());
+        for root in roots {
+            assert!(!plan.contains(&format!("_entry_{root}")), "{plan}");
+        }
+
+        for metadata in &result.manifest.segments {
+            let component = result
+                .segments
+                .iter()
+                .find(|s| s.id.symbol_name == metadata.symbol_name)
+                .unwrap();
+            assert_eq!(
+                mapping.resolve(&metadata.display_name),
+                Some(component.id.display_name.as_str())
+            );
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "manifest")]
+    fn test_build_id() {
+        let paths = vec![PathBuf::from("src/test_input/test_example_1.tsx")];
+        let options = TransformOptions {
+            build_id: Some("v42".to_string()),
+            ..TransformOptions::default()
+        };
+
+        let tagged = transform_fs(paths.clone(), options.clone()).unwrap();
+        assert_eq!(tagged.manifest.build_id.as_deref(), Some("v42"));
+        assert!(tagged
+            .manifest
+            .to_json()
+            .unwrap()
+            .contains(r#""buildId": "v42""#));
+        assert!(!tagged.modules[0].code.contains("_v42"));
+
+        let renamed = transform_fs(
+            paths,
+            TransformOptions {
+                build_id_in_file_names: true,
+                ..options
+            },
+        )
+        .unwrap();
+        for segment in &renamed.segments {
+            let file_name = format!(
+                "{}_v42",
+                segment.id.local_file_name.trim_end_matches("_v42")
+            );
+            assert_eq!(segment.qrl.rel_path, PathBuf::from(&file_name));
+            let specifier = segment.import_specifier(Path::new("src/test_input"), false);
+            assert!(specifier.ends_with("_v42"), "{specifier}");
+            let imported = std::iter::once(&renamed.modules[0].code)
+                .chain(renamed.segments.iter().map(|s| &s.code))
+                .any(|code| code.contains(&format!("import(\"{specifier}\")")));
+            assert!(imported, "{file_name} is never imported");
+        }
+    }
+
+    #[test]
+    fn test_symbol_prefix_and_hash_length() {
+        let paths = vec![PathBuf::from("src/test_input/test_example_1.tsx")];
+        let result = transform_fs(
+            paths,
+            TransformOptions {
+                target: Target::Prod,
+                symbol_prefix: Some("ui-kit".to_string()),
+                hash_length: Some(6),
+                ..TransformOptions::default()
+            },
+        )
+        .unwrap();
+
+        assert!(!result.segments.is_empty());
+        for segment in &result.segments {
+            assert_eq!(segment.id.hash.len(), 6);
+            assert_eq!(
+                segment.id.symbol_name,
+                format!("ui_kit_{}", segment.id.hash)
+            );
+        }
+    }
+
+    #[test]
+    fn test_sanitize_mode() {
+        let code = r#"
+import { component$ } from "@qwik.dev/core";
+export const 计数器 = component$(() => <div />);
+export const 按钮 = component$(() => <button />);
+"#;
+        let names = |sanitize_mode| {
+            let options = TransformOptions {
+                sanitize_mode,
+                ..TransformOptions::default()
+            };
+            let result = transform_module("src/app.tsx", code, options).unwrap();
+            let mut names: Vec<String> = result
+                .segments
+                .iter()
+                .map(|segment| segment.id.display_name.to_string())
+                .collect();
+            names.sort();
+            names
+        };
+
+        assert_eq!(
+            names(SanitizeMode::Ascii),
+            vec!["app.tsx__component", "app.tsx__component_1"]
+        );
+        let hashed = names(SanitizeMode::Hashed);
+        assert!(hashed
+            .iter()
+            .all(|name| name.is_ascii() && !name.ends_with("_1")));
+        assert_eq!(
+            names(SanitizeMode::Unicode),
+            vec!["app.tsx_按钮_component", "app.tsx_计数器_component"]
+        );
+    }
+
+    #[test]
+    fn test_inline_entry_strategy() {
+        let source = test_source!(
+            r#"
+            import { component$, $ } from '@qwik.dev/core';
+            export const App = component$(() => {
+                const name = 'qwik';
+                return <div onClick={$(() => console.log(name))} />;
+            });
+            "#
+        );
+        let options = TransformOptions {
+            entry_strategy: EntryStrategy::Inline,
+            ..TransformOptions::default()
+        };
+
+        let result = transform(source, options).unwrap();
+        let body = &result.optimized_app().body;
+
+        assert!(result.optimized_app().components.is_empty());
+        for import in ["componentQrl", "inlinedQrl", "useLexicalScope"] {
+            assert!(body.contains(&format!(r#"import {{ {import} }} from "@qwik.dev/core";"#)));
+        }
+        assert!(body.contains("componentQrl(inlinedQrl(() => {"));
+        assert!(body.contains("const [name] = useLexicalScope();"));
+        assert!(body.contains(r#""App_component_div_onClick_"#));
+        assert!(body.contains("[name])"));
+        assert!(!body.contains("import(\""));
+    }
+
+    #[test]
+    fn test_server_stripping() {
+        let source = || {
+            test_source!(
+                r#"
+                import { component$, server$, $ } from '@qwik.dev/core';
+                import { routeLoader$ } from '@qwik.dev/router';
+                import { db } from './db.server';
+                export const onGet = async () => db.health();
+                export const useUser = routeLoader$(async () => db.user($(() => 1)));
+                export const App = component$(() => {
+                    const id = 1;
+                    const load = server$(() => db.find(id));
+                    return <div onClick={$(() => load())} />;
+                });
+                export default App;
+                "#
+            )
+        };
+
+        let server = transform(source(), TransformOptions::default()).unwrap();
+        assert_eq!(server.optimized_app().components.len(), 5);
+
+        let client = TransformOptions {
+            is_server: Some(false),
+            strip_exports: Some(vec!["onGet".to_string()]),
+            ..TransformOptions::default()
+        };
+        let result = transform(source(), client).unwrap();
+        let app = result.optimized_app();
+
+        let ctx_names: Vec<&str> = app.components.iter().map(|c| c.ctx_name.as_str()).collect();
+        assert_eq!(ctx_names, vec!["$", "component$"]);
+        assert!(!app.body.contains("db.server"));
+        assert!(app
+            .body
+            .contains("routeLoaderQrl(_noopQrl(\"useUser_routeLoader_"));
+        assert!(app.body.contains(&format!(
+            "export const onGet = () => {{\n\tthrow \"{STRIPPED_EXPORT_ERROR}\";"
+        )));
+        let component = &app.components[1].code;
+        assert!(component.contains("serverQrl(_noopQrl(\"App_component_load_server_"));
+        assert!(component.contains("[id])"));
+        assert!(component.contains(r#"import { _noopQrl } from "@qwik.dev/core";"#));
+        assert!(!component.contains("db"));
+    }
+
+    #[test]
+    fn test_client_stripping() {
+        let source = test_source!(
+            r#"
+            import { component$, useVisibleTask$, useTask$ } from '@qwik.dev/core';
+            export const App = component$(() => {
+                useVisibleTask$(() => window.scrollTo(0, 0));
+                useTask$(() => console.log('task'));
+                return <div />;
+            });
+            "#
+        );
+        let server = TransformOptions {
+            is_server: Some(true),
+            strip_ctx_name: Some(vec!["useVisible".to_string()]),
+            ..TransformOptions::default()
+        };
+
+        let result = transform(source, server).unwrap();
+        let app = result.optimized_app();
+
+        let ctx_names: Vec<&str> = app.components.iter().map(|c| c.ctx_name.as_str()).collect();
+        assert_eq!(ctx_names, vec!["useTask$", "component$"]);
+        assert!(app.components[1]
+            .code
+            .contains("useVisibleTaskQrl(_noopQrl(\"App_component_useVisibleTask_"));
+        assert!(app.components.iter().all(|c| !c.code.contains("window")));
+    }
+
+    #[test]
+    fn test_strict_esm() {
+        let source = || test_source!("export const onGet = () => require('./db').query();");
+
+        let lenient = transform(source(), TransformOptions::default()).unwrap();
+        assert!(lenient.diagnostics().is_empty());
+
+        let strict = TransformOptions {
+            strict_esm: true,
+            ..TransformOptions::default()
+        };
+        let result = transform(source(), strict).unwrap();
+        let codes: Vec<_> = result.diagnostics().iter().map(|d| d.code).collect();
+        assert_eq!(codes, vec![DiagnosticCode::NonEsm]);
+    }
+
+    #[test]
+    fn test_virtual_module() {
+        let source = || {
+            Source::from_virtual(
+                "\0qwik-city-plan",
+                "export const App = component$(() => <div />);",
+                crate::component::Language::Typescript,
+            )
+        };
+        let options = TransformOptions {
+            path_privacy: PathPrivacy::Hashed,
+            ..TransformOptions::default()
+        };
+
+        let first = transform(source(), options.clone()).unwrap();
+        let second = transform(source(), options).unwrap();
+
+        let segment = &first.optimized_app().components[0];
+        assert_eq!(
+            segment.id.local_file_name,
+            "_virtual/qwik-city-plan.tsx_App_component_".to_string() + &segment.id.hash
+        );
+        assert_eq!(segment.origin, "_virtual/qwik-city-plan.tsx");
+        assert_eq!(first.optimized_app(), second.optimized_app());
+    }
+
+    #[test]
+    fn test_class_features_in_segments() {
+        let source = || {
+            test_source!(
+                r#"
+                import { component$ } from "@qwik.dev/core";
+                class Store { items = []; static empty = true; }
+                export const store = new Store();
+                export const App = component$(() => {
+                    class Counter { count = 0; static step = 1; }
+                    return <div>{new Counter().count}</div>;
+                });
+                export default App;
+                "#
+            )
+        };
+        let code = |es_target: Option<&str>| {
+            let result = transform(
+                source(),
+                TransformOptions {
+                    es_target: es_target.map(str::to_string),
+                    ..TransformOptions::default()
+                },
+            )
+            .unwrap();
+            let app = result.optimized_app();
+            (app.body.clone(), app.components[0].code.clone())
+        };
+
+        let (host, segment) = code(None);
+        assert!(host.contains("items = [];"));
+        assert!(host.contains("static empty = true;"));
+        assert!(segment.contains("count = 0;"));
+        assert!(segment.contains("static step = 1;"));
+
+        let (host, segment) = code(Some("es2020"));
+        assert!(host.contains("this.items = [];"));
+        assert!(host.contains("Store.empty = true;"));
+        assert!(segment.contains("this.count = 0;"));
+        assert!(segment.contains("Counter.step = 1;"));
+        assert!(!host.contains("@oxc-project/runtime"));
+        assert!(!segment.contains("@oxc-project/runtime"));
+    }
+
+    #[test]
+    fn test_method_markers() {
+        let source = test_source!(
+            r#"
+            import { component$ } from "@qwik.dev/core";
+            class Counter {
+                count = 0;
+                onIncrement$() { this.count++; }
+                onReset$ = () => { this.count = 0; };
+            }
+            export const App = component$(() => {
+                const handlers = { onClick$(event) { console.log(event); } };
+                return <div>{handlers}</div>;
+            });
+            export default App;
+            "#
+        );
+
+        let result = transform(source, TransformOptions::default()).unwrap();
+        let app = result.optimized_app();
+        let segment = |name: &str| {
+            app.components
+                .iter()
+                .find(|c| c.id.display_name == format!("test.tsx_{name}"))
+                .unwrap()
+        };
+
+        let increment = segment("Counter_onIncrement");
+        assert!(increment.code.contains("= function() {\n\tthis.count++;"));
+        assert!(app.body.contains(&format!(
+            "onIncrement$ = qrl(() => import(\"{}\"), \"{}\");",
+            increment.id.local_file_name, increment.id.symbol_name
+        )));
+        let reset = segment("Counter_onReset");
+        assert!(app.body.contains(&format!(
+            "onReset$ = qrl(() => import(\"{}\")",
+            reset.id.local_file_name
+        )));
+        assert!(app.body.contains("count = 0;"));
+
+        let click = segment("App_component_handlers_onClick");
+        assert!(click.code.contains("= function(event) {"));
+        let component = segment("App_component");
+        assert!(component.code.contains(&format!(
+            "onClick$: qrl(() => import(\"{}\")",
+            click.id.local_file_name
+        )));
+    }
+
+    #[test]
+    fn test_transform_module() {
+        let options = TransformOptions::builder()
+            .mode(Target::Prod)
+            .scope("ui-kit")
+            .transpile_ts(true)
+            .transpile_jsx(true)
+            .build()
+            .unwrap();
+        assert_eq!(options.target, Target::Prod);
+        assert_eq!(options.scope.as_deref(), Some("ui-kit"));
+        assert!(matches!(
+            TransformOptions::builder().es_target("es1999").build(),
+            Err(Error::UnsupportedTarget(..))
+        ));
+
+        let code = std::fs::read_to_string("src/test_input/test_example_1.tsx").unwrap();
+        let result = transform_module("src/app.tsx", code, options).unwrap();
+        assert_eq!(result.modules.len(), 1);
+        assert_eq!(result.modules[0].path, PathBuf::from("src/app.tsx"));
+        assert!(!result.segments.is_empty());
+        assert!(result
+            .segments
+            .iter()
+            .all(|segment| segment.id.symbol_name.starts_with("s_")
+                && segment.origin == "src/app.tsx"));
+        assert!(!result.modules[0].code.contains(": number"));
+    }
+
+    #[test]
+    fn test_conditional_segments() {
+        let code = r#"
+            import { component$, $ } from "@qwik.dev/core";
+            export const App = component$((props) => {
+                const handler = props.flag ? $(() => console.log("a")) : $(() => console.log("b"));
+                const fallback = props.handler ?? $(() => console.log("c"));
+                return <button onClick$={props.flag && $(() => console.log("d"))}>{handler}{fallback}</button>;
+            });
+            "#;
+        let result = transform_module("app.tsx", code, TransformOptions::default()).unwrap();
+        let symbol = |text: &str| {
+            let segment = result
+                .segments
+                .iter()
+                .find(|s| s.code.contains(text))
+                .unwrap();
+            segment.id.symbol_name.clone()
+        };
+        let (a, b, c, d) = (
+            symbol(r#""a""#),
+            symbol(r#""b""#),
+            symbol(r#""c""#),
+            symbol(r#""d""#),
+        );
+        assert!(a.starts_with("App_component_handler_"), "{a}");
+        assert!(b.starts_with("App_component_handler_1_"), "{b}");
+        assert!(c.starts_with("App_component_fallback_"), "{c}");
+        assert!(d.starts_with("App_component_button_onClick_"), "{d}");
+
+        let app = result
+            .segments
+            .iter()
+            .find(|s| s.ctx_name == "component$")
+            .unwrap();
+        let qrl = |symbol: &str| format!(r#""{symbol}")"#);
+        for expected in [
+            "const handler = props.flag ? qrl(".to_string(),
+            format!("{} : qrl(", qrl(&a)),
+            "const fallback = props.handler ?? qrl(".to_string(),
+            "on:click={props.flag && qrl(".to_string(),
+        ] {
+            assert!(app.code.contains(&expected), "{expected}\n{}", app.code);
+        }
+        for symbol in [&a, &b, &c, &d] {
+            assert!(app.code.contains(&qrl(symbol)), "{symbol}\n{}", app.code);
+        }
+        let handler = result.segments.iter().find(|s| s.id.symbol_name == d);
+        assert!(handler.unwrap().listener.is_some());
+
+        let again = transform_module("app.tsx", code, TransformOptions::default()).unwrap();
+        let names = |result: &TransformResult| -> Vec<String> {
+            result
+                .segments
+                .iter()
+                .map(|s| s.id.symbol_name.to_string())
+                .collect()
+        };
+        assert_eq!(names(&result), names(&again));
+    }
+
+    #[test]
+    fn test_normalize_output() {
+        let code = "import { component$, $ } from \"@qwik.dev/core\";\n\
+            export const App = component$(() => {\n\
+                const b = $(() => console.log(\"b\"));\n\
+                return <button onClick$={$(() => console.log(\"a\"))}>Click</button>;\n\
+            });\n";
+        let windows = format!("\u{feff}{}", code.replace('\n', "\r\n"));
+        let options = || {
+            TransformOptions::builder()
+                .normalize_output()
+                .build()
+                .unwrap()
+        };
+
+        let unix = transform_module("src/app.tsx", code, options()).unwrap();
+        let windows = transform_module("src/app.tsx", &windows, options()).unwrap();
+        assert_eq!(unix.modules[0].code, windows.modules[0].code);
+        assert_eq!(unix.segments, windows.segments);
+        let hashes: Vec<_> = unix.segments.iter().map(|s| s.id.hash.clone()).collect();
+        let mut sorted = hashes.clone();
+        sorted.sort();
+        assert_eq!(hashes, sorted);
+
+        let paths = [
+            PathBuf::from("./src/test_input/test_example_1.tsx"),
+            PathBuf::from("./src/test_input/test_example_2.tsx"),
+        ];
+        let forward = transform_fs(paths.to_vec(), options()).unwrap();
+        let backward = transform_fs(paths.iter().rev().cloned().collect(), options()).unwrap();
+        let module_paths = |result: &TransformResult| -> Vec<PathBuf> {
+            result.modules.iter().map(|m| m.path.clone()).collect()
+        };
+        assert_eq!(module_paths(&forward), paths);
+        assert_eq!(module_paths(&forward), module_paths(&backward));
+        assert_eq!(forward.segments, backward.segments);
+    }
+
+    #[test]
+    #[cfg(feature = "manifest")]
+    fn test_segment_metrics() {
+        let code = r#"
+            import { component$, useSignal, $ } from "@qwik.dev/core";
+            import { format } from "./format";
+            export const App = component$(() => {
+                const count = useSignal(0);
+                return <button onClick$={$(() => console.log(format(count.value)))}>Click</button>;
+            });
+            "#;
+        let result = transform_module("src/app.tsx", code, TransformOptions::default()).unwrap();
+        let metrics = |ctx_name: &str| {
+            let segment = result
+                .manifest
+                .segments
+                .iter()
+                .find(|s| s.ctx_name == ctx_name);
+            segment.unwrap().metrics.clone()
+        };
+
+        let app = metrics("component$");
+        assert_eq!(app.captures, 0);
+        assert!(
+            app.imports.contains(&"@qwik.dev/core".to_string()),
+            "{app:?}"
+        );
+        let handler = metrics("$");
+        assert_eq!(handler.captures, 1);
+        assert_eq!(handler.imports, ["./format", "@qwik.dev/core"]);
+        let code = &result.segments.iter().find(|s| s.ctx_name == "$");
+        assert!(handler.size > 0 && handler.size < code.unwrap().code.len());
+    }
+
+    #[test]
+    fn test_hook_segment_kinds() {
+        let code = r#"
+            import { component$, useComputed$, useSignal, useTask$, useVisibleTask$ } from "@qwik.dev/core";
+            export const App = component$(() => {
+                const count = useSignal(0);
+                const double = useComputed$(() => count.value * 2);
+                useTask$(({ track }) => track(() => count.value));
+                useVisibleTask$(() => console.log(window.innerWidth));
+                return <p>{double.value}</p>;
+            });
+            "#;
+        let result = transform_module("src/app.tsx", code, TransformOptions::default()).unwrap();
+        let kind = |ctx_name: &str| {
+            let segment = result.segments.iter().find(|s| s.ctx_name == ctx_name);
+            segment.unwrap().ctx_kind
+        };
+
+        assert_eq!(kind("component$"), SegmentKind::Function);
+        assert_eq!(kind("useComputed$"), SegmentKind::Computed);
+        assert_eq!(kind("useTask$"), SegmentKind::Task);
+        assert_eq!(kind("useVisibleTask$"), SegmentKind::VisibleTask);
+        assert_eq!(
+            kind("useVisibleTask$").environment(),
+            SegmentEnvironment::Browser
+        );
+    }
+
+    #[test]
+    fn test_lenient() {
+        let code = r#"
+import { component$, $ } from "@qwik.dev/core";
+
+export const Broken = () => {
+    return <div>{count +}</div>;
+};
+
+export const App = component$(() => {
+    return <button onClick$={$(() => console.log("clicked"))}>Click</button>;
+});
+"#;
+        let strict = transform_module("src/app.tsx", code, TransformOptions::default()).unwrap();
+        assert!(strict.segments.is_empty());
+
+        let options = TransformOptions::builder().lenient().build().unwrap();
+        let result = transform_module("src/app.tsx", code, options).unwrap();
+        let host = &result.modules[0].code;
+        assert_eq!(result.segments.len(), 2);
+        assert!(host.contains("componentQrl("), "{host}");
+        assert!(host.contains("return <div>{count +}</div>;"), "{host}");
+        assert!(!host.contains("qwik:broken"), "{host}");
+        let errors = result
+            .diagnostics()
+            .filter(|d| d.code == DiagnosticCode::SyntaxError);
+        assert_eq!(errors.count(), 1);
+
+        let short = format!("let a = ;\n{code}");
+        let options = TransformOptions::builder().lenient().build().unwrap();
+        let result = transform_module("src/app.tsx", &short, options).unwrap();
+        let host = &result.modules[0].code;
+        assert_eq!(result.segments.len(), 2);
+        assert!(host.contains("let a = ;"), "{host}");
+        assert!(!host.contains("qwik:broken"), "{host}");
+    }
+
+    #[test]
+    fn test_inline_threshold() {
+        let code = r#"
+import { component$, useSignal, $ } from "@qwik.dev/core";
+import { track } from "./analytics";
+
+export const App = component$(() => {
+    const count = useSignal(0);
+    return (
+        <div>
+            <button onClick$={$(() => track("clicked"))}>Track</button>
+            <button onClick$={$(() => count.value++)}>{count.value}</button>
+        </div>
+    );
+});
+"#;
+        let extracted = transform_module("app.tsx", code, TransformOptions::default()).unwrap();
+        assert_eq!(extracted.segments.len(), 3);
+
+        let options = TransformOptions::builder()
+            .inline_threshold_bytes(200)
+            .build()
+            .unwrap();
+        let result = transform_module("app.tsx", code, options).unwrap();
+        assert_eq!(result.segments.len(), 2);
+        let component = result
+            .segments
+            .iter()
+            .find(|segment| segment.ctx_name == "component$")
+            .unwrap();
+        // The handler capturing nothing stays in the component, the one capturing `count` is extracted.
+        assert!(component
+            .code
+            .contains(r#"inlinedQrl(() => track("clicked"), "#));
+        assert!(component
+            .code
+            .contains(r#"import { track } from "./analytics";"#));
+        assert!(component
+            .code
+            .contains(r#"import { inlinedQrl } from "@qwik.dev/core";"#));
+        assert!(component.code.contains("[count])"));
+    }
+
+    #[test]
+    fn test_markdown_front_matter() {
+        let mdx = "# Hello\n\n<Counter />\n";
+        let compiled = r#"
+import { component$, $ } from "@qwik.dev/core";
+export const frontmatter = { title: "Hello", action: $(() => 1) };
+export const head = { title: frontmatter.title };
+export default component$(() => {
+    return <h1 onClick$={$(() => console.log(frontmatter.title))}>Hello</h1>;
+});
+"#;
+        let options = TransformOptions::builder()
+            .preprocessor(Preprocessor::new(|request| {
+                match request.source.starts_with("# ") {
+                    true => Ok(Some(compiled.to_string())),
+                    false => Err("not markdown".to_string()),
+                }
+            }))
+            .build()
+            .unwrap();
+        let result = transform_module("src/routes/index.mdx", mdx, options.clone()).unwrap();
+        let host = &result.modules[0].code;
+        assert_eq!(result.segments.len(), 2, "{host}");
+        assert!(
+            host.contains(r#"export const frontmatter = { title: "Hello", action: $(() => 1) };"#),
+            "{host}"
+        );
+        assert!(
+            host.contains("export const head = { title: frontmatter.title };"),
+            "{host}"
+        );
+        assert!(!host.contains("qwik:passthrough"), "{host}");
+        assert!(result.segments[0]
+            .id
+            .local_file_name
+            .starts_with("src/routes/index.mdx_"));
+
+        assert!(matches!(
+            transform_module("src/routes/about.md", "About", options),
+            Err(Error::Preprocess(path, _)) if path == "src/routes/about.md"
+        ));
+    }
+
+    #[test]
+    fn test_type_imports() {
+        let code = r#"
+import { component$, $ } from "@qwik.dev/core";
+import type { Props } from "./types";
+import { type Theme, Store } from "./store";
+import { helper } from "./helper";
+
+export const App = component$((props: Props) => {
+    const onClick = $((theme: Theme) => {
+        const store: Store = new Store(helper(theme));
+        return store as Store;
+    });
+    return <button onClick$={onClick}>{props.label}</button>;
+});
+"#;
+        let result = transform_module("src/app.tsx", code, TransformOptions::default()).unwrap();
+        let segment = |name: &str| {
+            result
+                .segments
+                .iter()
+                .find(|segment| segment.id.symbol_name.rsplit_once('_').unwrap().0 == name)
+                .unwrap()
+        };
+        let app = segment("App_component");
+        let on_click = segment("App_component_onClick");
+        assert_eq!(app.extension, "tsx");
+        assert_eq!(app.file_name(), format!("{}.tsx", app.id.chunk_name()));
+        assert!(
+            app.code
+                .contains(r#"import type { Props } from "./types";"#),
+            "{}",
+            app.code
+        );
+        assert!(
+            on_click
+                .code
+                .contains(r#"import type { Theme } from "./store";"#),
+            "{}",
+            on_click.code
+        );
+        assert!(
+            on_click
+                .code
+                .contains(r#"import { Store } from "./store";"#),
+            "{}",
+            on_click.code
+        );
+        assert!(
+            !on_click.code.contains("import type { Store }"),
+            "{}",
+            on_click.code
+        );
+
+        let result = transform_module(
+            "src/app.ts",
+            code.replace(
+                "<button onClick$={onClick}>{props.label}</button>",
+                "onClick",
+            ),
+            TransformOptions::default(),
+        )
+        .unwrap();
+        assert!(result
+            .segments
+            .iter()
+            .all(|segment| segment.extension == "ts"));
+
+        let options = TransformOptions::builder()
+            .transpile_ts(true)
+            .transpile_jsx(true)
+            .build()
+            .unwrap();
+        let result = transform_module("src/app.tsx", code, options).unwrap();
+        assert!(result
+            .segments
+            .iter()
+            .all(|segment| segment.extension == "js"));
+        assert!(
+            !result.segments[1].code.contains("Theme"),
+            "{}",
+            result.segments[1].code
+        );
+    }
+
+    #[test]
+    fn test_type_only_references() {
+        let code = r#"
+import { component$, $ } from "@qwik.dev/core";
+import type { Theme } from "./theme";
+import { type Item, loadItems } from "./items";
+
+export const App = component$(() => {
+    type Row = { item: Item; theme: Theme };
+    class Formatter {}
+    const rows: Row[] = [];
+    const onClick = $((row: Row, formatter?: Formatter): typeof rows => loadItems(row));
+    return <button onClick$={onClick}>{rows.length}</button>;
+});
+"#;
+        let segments = |options: TransformOptions| {
+            let result = transform_module("src/app.tsx", code, options).unwrap();
+            assert_eq!(result.diagnostics().count(), 0);
+            let on_click = result
+                .segments
+                .iter()
+                .find(|segment| segment.id.symbol_name.contains("onClick"))
+                .unwrap()
+                .clone();
+            let app = result
+                .segments
+                .into_iter()
+                .find(|segment| !segment.id.symbol_name.contains("onClick"))
+                .unwrap();
+            (app, on_click)
+        };
+
+        let (app, on_click) = segments(TransformOptions::default());
+        assert!(on_click.qrl.captures.is_empty());
+        assert!(
+            app.code.contains(r#"import type { Item } from "./items";"#),
+            "{}",
+            app.code
+        );
+        assert!(
+            app.code
+                .contains(r#"import type { Theme } from "./theme";"#),
+            "{}",
+            app.code
+        );
+        assert!(!on_click.code.contains("import type"), "{}", on_click.code);
+
+        let options = TransformOptions::builder()
+            .transpile_ts(true)
+            .transpile_jsx(true)
+            .build()
+            .unwrap();
+        let (app, on_click) = segments(options);
+        assert!(on_click.qrl.captures.is_empty());
+        assert!(!app.code.contains("./items"), "{}", app.code);
+        assert!(!app.code.contains("./theme"), "{}", app.code);
+        assert!(
+            on_click
+                .code
+                .starts_with(r#"import { loadItems } from "./items";"#),
+            "{}",
+            on_click.code
+        );
+    }
+
+    #[test]
+    fn test_defines() {
+        let code = r#"
+import { component$ } from "@qwik.dev/core";
+import { inspect } from "./devtools";
+export const App = component$(() => {
+    if (import.meta.env.DEV) {
+        inspect("rendering");
+    }
+    return <p>{process.env.NODE_ENV}</p>;
+});
+"#;
+        let transform = |options: TransformOptions| {
+            let result = transform_module("src/app.tsx", code, options).unwrap();
+            result.segments[0].code.clone()
+        };
+        let dev = transform(TransformOptions::default());
+        assert!(dev.contains("import.meta.env.DEV"), "{dev}");
+        assert!(dev.contains("./devtools"), "{dev}");
+
+        let options = TransformOptions::builder()
+            .define("import.meta.env.DEV", "false")
+            .define("process.env.NODE_ENV", r#""production""#)
+            .build()
+            .unwrap();
+        let prod = transform(options);
+        assert!(!prod.contains("inspect"), "{prod}");
+        assert!(!prod.contains("./devtools"), "{prod}");
+        assert!(prod.contains(r#"<p>{"production"}</p>"#), "{prod}");
+
+        assert!(matches!(
+            TransformOptions::builder().define("DEBUG", "{").build(),
+            Err(Error::InvalidDefine(..))
+        ));
+
+        #[cfg(feature = "serde")]
+        {
+            let json = r#"{"defines":{"import.meta.env.DEV":false,"import.meta.env.MODE":"prod"}}"#;
+            let options: TransformOptions = serde_json::from_str(json).unwrap();
+            assert_eq!(options.defines["import.meta.env.DEV"], "false");
+            assert_eq!(options.defines["import.meta.env.MODE"], r#""prod""#);
+            assert!(serde_json::to_string(&options)
+                .unwrap()
+                .contains(r#""import.meta.env.DEV":false"#));
+        }
+    }
+
+    #[test]
+    fn test_scope_dir() {
+        let code = r#"
+            import { component$ } from "@qwik.dev/core";
+            export const App = component$(() => <p>Hello</p>);
+            "#;
+        let transform = |target: Target| {
+            let options = TransformOptions::builder()
+                .mode(target)
+                .scope("@my/lib/")
+                .build()
+                .unwrap();
+            transform_module("src/app.tsx", code, options).unwrap()
+        };
+
+        let lib = transform(Target::Lib);
+        let file_name = &lib.segments[0].id.local_file_name;
+        assert!(
+            file_name.starts_with("@my/lib/src/app.tsx_s_"),
+            "{file_name}"
+        );
+        let host = &lib.modules[0].code;
+        let specifier = format!("../{file_name}");
+        assert!(host.contains(&format!("import(\"{specifier}\")")), "{host}");
+
+        let prod = transform(Target::Prod);
+        let file_name = &prod.segments[0].id.local_file_name;
+        assert!(file_name.starts_with("src/app.tsx_s_"), "{file_name}");
+        assert_eq!(prod.segments[0].id.hash, lib.segments[0].id.hash);
+    }
+
+    #[test]
+    fn test_entry_module() {
+        let code = r#"
+            import { component$, $ } from "@qwik.dev/core";
+            export const App = component$(() => <button onClick$={$(() => {})} />);
+            "#;
+        let options = TransformOptions::builder()
+            .entry_strategy(EntryStrategy::Single)
+            .build()
+            .unwrap();
+        let result = transform_module("src/app.tsx", code, options).unwrap();
+        let module = result
+            .entry_module(crate::entry_strategy::ENTRY_SEGMENTS)
+            .unwrap();
+
+        for segment in &result.segments {
+            let export = format!(
+                "export {{ {} }} from \"{}\";",
+                segment.id.symbol_name,
+                segment.import_specifier(Path::new(""), false)
+            );
+            assert!(module.contains(&export), "{module}");
+            assert!(module.contains("from \"./src/app.tsx_"), "{module}");
+        }
+        assert_eq!(module.lines().count(), 2);
+        assert_eq!(result.entry_module("entry_unknown"), None);
+    }
+
+    #[test]
+    fn test_provenance_comments() {
+        let code = "import { component$, $ } from \"@qwik.dev/core\";\n\
+            export const App = component$(() => {\n\
+            \x20   return <button onClick$={$(() => console.log(\"clicked\"))}>Click</button>;\n\
+            });\n";
+        let transform = |options: TransformOptionsBuilder| {
+            transform_module("src/app.tsx", code, options.build().unwrap()).unwrap()
+        };
+
+        let dev = transform(TransformOptions::builder().provenance_comments());
+        let app = dev.segments.iter().find(|s| s.ctx_name == "component$");
+        let app = app.unwrap();
+        let handler = dev.segments.iter().find(|s| s.ctx_name == "$").unwrap();
+        let host = &dev.modules[0].code;
+        assert!(
+            host.contains(&format!(
+                "import(/* src/app.tsx:2:31 app.tsx_App_component */ \"./{}\")",
+                app.id.local_file_name.trim_start_matches("src/")
+            )),
+            "{host}"
+        );
+        assert!(
+            app.code
+                .starts_with("/* src/app.tsx:2:31 app.tsx_App_component */\n"),
+            "{}",
+            app.code
+        );
+        assert!(
+            app.code.contains(&format!(
+                "import(/* src/app.tsx:3:32 {} */ \"./{}\")",
+                handler.id.display_name,
+                handler.id.local_file_name.trim_start_matches("src/")
+            )),
+            "{}",
+            app.code
+        );
+
+        let prod = transform(
+            TransformOptions::builder()
+                .mode(Target::Prod)
+                .provenance_comments(),
+        );
+        assert!(!prod.modules[0].code.contains("/*"));
+        assert!(prod.segments.iter().all(|s| !s.code.contains("/*")));
+    }
+
+    #[test]
+    fn test_emit_style() {
+        let code = r#"
+            import { component$ } from "@qwik.dev/core";
+            export const Greeting = component$(({ name }) => {
+                return <p>{name}</p>;
+            });
+            "#;
+        let transform = |target: Target, emit: EmitStyle| {
+            let options = TransformOptions::builder().mode(target).emit(emit);
+            transform_module("src/app.tsx", code, options.build().unwrap()).unwrap()
+        };
+
+        let compact = transform(Target::Prod, EmitStyle::ByTarget);
+        let segment = &compact.segments[0].code;
+        assert!(segment.contains("=_p=>{"), "{segment}");
+        assert!(segment.contains("_p.name"), "{segment}");
+        assert_eq!(segment.trim_end().lines().count(), 1, "{segment}");
+
+        for (target, emit) in [
+            (Target::Dev, EmitStyle::ByTarget),
+            (Target::Prod, EmitStyle::Pretty),
+        ] {
+            let pretty = transform(target, emit);
+            let segment = &pretty.segments[0].code;
+            assert!(segment.contains("(props) => {\n"), "{segment}");
+        }
+        let compact = transform(Target::Dev, EmitStyle::Compact);
+        assert!(!compact.modules[0].code.contains(" => "));
+    }
+
+    #[test]
+    fn test_embed_origin() {
+        let code = r#"
+            import { component$, $ } from "@qwik.dev/core";
+            export const App = component$(() => {
+                return <button onClick$={$(() => console.log("clicked"))}>Click</button>;
+            });
+            "#;
+        let transform = |options: TransformOptionsBuilder| {
+            transform_module("src/app.tsx", code, options.build().unwrap()).unwrap()
+        };
+
+        let lib = transform(TransformOptions::builder().mode(Target::Lib).embed_origin());
+        let host = &lib.modules[0].code;
+        assert!(
+            host.contains(r#"import { qrlDEV } from "@qwik.dev/core";"#),
+            "{host}"
+        );
+        assert!(host.contains("componentQrl(qrlDEV("), "{host}");
+        assert!(host.contains(r#"file: "src/app.tsx","#), "{host}");
+        let app = lib
+            .segments
+            .iter()
+            .find(|s| s.ctx_name == "component$")
+            .unwrap();
+        let dev = app.qrl.dev.as_ref().unwrap();
+        assert_eq!(dev.file, "src/app.tsx");
+        assert_eq!(&code[dev.lo as usize..dev.lo as usize + 5], "() =>");
+        assert_eq!(dev.display_name, app.id.display_name);
+        let handler = lib.segments.iter().find(|s| s.ctx_name == "$").unwrap();
+        assert!(handler.qrl.dev.is_some());
+        assert!(app.code.contains("qrlDEV("), "{}", app.code);
+        assert!(!app.code.contains("qrl("), "{}", app.code);
+
+        for options in [
+            TransformOptions::builder().mode(Target::Lib),
+            TransformOptions::builder()
+                .mode(Target::Prod)
+                .embed_origin(),
+        ] {
+            let result = transform(options);
+            assert!(!result.modules[0].code.contains("qrlDEV"));
+            assert!(result.segments.iter().all(|s| s.qrl.dev.is_none()));
+        }
+    }
+
+    #[test]
+    fn test_source_excerpts() {
+        let code = r#"
+            import { component$, $ } from "@qwik.dev/core";
+            export const App = component$(() => {
+                return <button onClick$={$(() => console.log("clicked"))}>Click</button>;
+            });
+            "#;
+        let segments = |options: TransformOptions| {
+            transform_module("app.tsx", code, options).unwrap().segments
+        };
+
+        let segments_without = segments(TransformOptions::default());
+        assert!(segments_without.iter().all(|s| s.excerpt.is_none()));
+
+        let options = TransformOptions::builder().source_excerpt_length(12);
+        let with = segments(options.clone().build().unwrap());
+        let handler = with.iter().find(|s| s.ctx_name == "$").unwrap();
+        assert_eq!(handler.excerpt.as_deref(), Some("() => consol…"));
+        assert_eq!(SegmentMetadata::from(handler).excerpt, handler.excerpt);
+
+        let prod = segments(options.mode(Target::Prod).build().unwrap());
+        assert!(prod.iter().all(|s| s.excerpt.is_none()));
+    }
+
+    #[test]
+    fn test_sync_listeners() {
+        let code = r#"
+            import { component$, sync$ } from "@qwik.dev/core";
+            export const App = component$(() => {
+                return <input onKeyDown$={sync$((event) => event.preventDefault())} />;
+            });
+            "#;
+        let result = transform_module("app.tsx", code, TransformOptions::default()).unwrap();
+        assert!(result.diagnostics().next().is_none());
+        assert!(result.segments.iter().all(|s| s.ctx_name != SYNC_MARKER));
+        let app = result
+            .segments
+            .iter()
+            .find(|s| s.ctx_name == "component$")
+            .unwrap();
+        assert!(
+            app.code.contains(
+                r#"_qrlSync((event) => event.preventDefault(), "event=>event.preventDefault()")"#
+            ),
+            "{}",
+            app.code
+        );
+        assert!(app
+            .code
+            .contains(r#"import { _qrlSync } from "@qwik.dev/core";"#));
+        assert!(!app.code.contains(SYNC_MARKER));
+
+        let capturing = r#"
+            import { sync$ } from "@qwik.dev/core";
+            const key = "Enter";
+            export const onKeyDown = sync$((event) => event.key === key && event.preventDefault());
+            "#;
+        let result = transform_module("app.tsx", capturing, TransformOptions::default()).unwrap();
+        let codes: Vec<_> = result.diagnostics().map(|d| d.code).collect();
+        assert_eq!(codes, [DiagnosticCode::SyncCapture]);
+
+        let typed = r#"
+            import { sync$ } from "@qwik.dev/core";
+            export const onKeyDown = sync$((event: KeyboardEvent): void => event.preventDefault());
+            "#;
+        for transpile_ts in [false, true] {
+            let options = TransformOptions {
+                transpile_ts,
+                ..TransformOptions::default()
+            };
+            let result = transform_module("app.tsx", typed, options).unwrap();
+            let host = &result.modules[0].code;
+            assert!(
+                host.contains(r#""event=>event.preventDefault()")"#),
+                "{host}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_event_listeners() {
+        let code = r#"
+            import { component$, $ } from "@qwik.dev/core";
+            import { Child } from "./child";
+            export const App = component$(() => {
+                return (
+                    <div onDocumentScroll$={$(() => console.log("scroll"))} window:onResize$={$(() => null)}>
+                        <button onDblClick$={$(() => console.log("dblclick"))} on-qvisible$={$(() => null)} />
+                        <Child onClick$={$(() => console.log("child"))} />
+                    </div>
+                );
+            });
+            "#;
+        let result = transform_module("app.tsx", code, TransformOptions::default()).unwrap();
+        let listener = |event: &str| {
+            let segment = result.segments.iter().find(|s| s.code.contains(event))?;
+            let listener = segment.listener.as_ref()?;
+            Some((listener.to_string(), segment.ctx_kind))
+        };
+        assert_eq!(
+            listener("\"scroll\""),
+            Some(("on-document:scroll".to_string(), SegmentKind::EventHandler))
+        );
+        assert_eq!(
+            listener("\"dblclick\""),
+            Some(("on:dblclick".to_string(), SegmentKind::EventHandler))
+        );
+        assert_eq!(listener("\"child\""), None);
+
+        let app = result
+            .segments
+            .iter()
+            .find(|s| s.ctx_name == "component$")
+            .unwrap();
+        for attribute in [
+            "on-document:scroll=",
+            "on-window:resize=",
+            "on:dblclick=",
+            "on:qvisible=",
+        ] {
+            assert!(app.code.contains(attribute), "{attribute}: {}", app.code);
+        }
+        assert!(app.code.contains("<Child onClick$="), "{}", app.code);
+
+        let metadata: Vec<_> = result.segments.iter().map(SegmentMetadata::from).collect();
+        assert_eq!(metadata.iter().filter(|s| s.listener.is_some()).count(), 4);
+    }
+
+    #[test]
+    fn test_warm_up() {
+        let options = TransformOptions {
+            es_target: Some("es2020".to_string()),
+            transpile_jsx: true,
+            ..TransformOptions::default()
+        };
+        warm_up(&options).unwrap();
+        assert!(warm_up(&TransformOptions::default()).is_ok());
+
+        let invalid = TransformOptions {
+            es_target: Some("es1999".to_string()),
+            ..TransformOptions::default()
+        };
+        assert!(matches!(
+            warm_up(&invalid),
+            Err(Error::UnsupportedTarget(target, _)) if target == "es1999"
+        ));
+    }
+
+    #[test]
+    fn test_dedupe_segments() {
+        let source = || {
+            test_source!(
+                r#"
+                import { component$, $ } from "@qwik.dev/core";
+                export const App = component$(() => {
+                    const first = $(() => console.log("row"));
+                    const second = $(() => console.log("row"));
+                    const other = $(() => console.log("other"));
+                    return <div onClick$={first} onInput$={second} onBlur$={other} />;
+                });
+                export default App;
+                "#
+            )
+        };
+        let segments = |dedupe_segments| {
+            let options = TransformOptions {
+                dedupe_segments,
+                ..TransformOptions::default()
+            };
+            transform(source(), options)
+                .unwrap()
+                .optimized_app()
+                .components
+                .clone()
+        };
+
+        assert_eq!(segments(false).len(), 4);
+
+        let deduped = segments(true);
+        assert_eq!(deduped.len(), 3);
+        let row = deduped.iter().find(|c| c.code.contains("\"row\"")).unwrap();
+        let component = deduped.iter().find(|c| c.ctx_name == "component$").unwrap();
+        let references = format!("\"{}\")", row.id.symbol_name);
+        assert_eq!(component.code.matches(&references).count(), 2);
+    }
+
+    #[test]
+    fn test_symbol_collisions() {
+        let source = test_source!(
+            r#"
+            import { $ } from "@qwik.dev/core";
+            export const handlers = {
+                "on-click$": $(() => console.log("first")),
+                on_click$: $(() => console.log("second")),
+                "on.click$": $(() => console.log("third")),
+            };
+            "#
+        );
+        let result = transform(source, TransformOptions::default()).unwrap();
+
+        let mut names: Vec<&str> = result
+            .optimized_app()
+            .components
+            .iter()
+            .map(|c| c.id.display_name.as_str())
+            .collect();
+        names.sort();
+        assert_eq!(
+            names,
+            [
+                "test.tsx_handlers_on_click",
+                "test.tsx_handlers_on_click_1",
+                "test.tsx_handlers_on_click_2"
+            ]
+        );
+        let collisions = result
+            .diagnostics()
+            .iter()
+            .filter(|d| d.code == DiagnosticCode::SymbolCollision && !d.is_error())
+            .count();
+        assert_eq!(collisions, 2);
+    }
+
+    #[test]
+    fn test_hardened() {
+        let source = |code: String| test_source!(code);
+        let hardened = |limits| TransformOptions {
+            hardened: true,
+            limits,
+            ..TransformOptions::default()
+        };
+        let codes = |result: &OptimizationResult| -> Vec<_> {
+            result.diagnostics().iter().map(|d| d.code).collect()
+        };
+
+        // Deep enough to overflow the stack of the calling thread, were it not for the limits.
+        let deep = format!(
+            "export const x = {}1{};",
+            "(".repeat(100_000),
+            ")".repeat(100_000)
+        );
+        let result = transform(source(deep), hardened(ResourceLimits::default())).unwrap();
+        assert_eq!(codes(&result), vec![DiagnosticCode::LimitExceeded]);
+        assert!(result.optimized_app().body.is_empty());
+
+        // Nested just within the limit, which the stack of the transform holds.
+        let n = ResourceLimits::DEFAULT_MAX_DEPTH - 8;
+        let within = [
+            format!("export const x = {}1{};", "(".repeat(n), ")".repeat(n)),
+            format!("export const x = {}1;", "!".repeat(n)),
+            format!("export const x = {}1;", "a => ".repeat(n)),
+            format!(
+                "import {{ component$ }} from \"@qwik.dev/core\";\nexport const X = component$(() => {}1{});",
+                "<div onClick$={() => ".repeat(n / 8),
+                "}/>".repeat(n / 8)
+            ),
+        ];
+        for code in within {
+            let result = transform(source(code), hardened(ResourceLimits::default())).unwrap();
+            assert!(!codes(&result).contains(&DiagnosticCode::InternalError));
+        }
+
+        let limits = ResourceLimits {
+            max_source_size: 16,
+            ..ResourceLimits::default()
+        };
+        let result =
+            transform(source("export const x = 1;".to_string()), hardened(limits)).unwrap();
+        assert_eq!(codes(&result), vec![DiagnosticCode::LimitExceeded]);
+
+        let app = std::fs::read_to_string("src/test_input/test_example_1.tsx").unwrap();
+        let limits = ResourceLimits {
+            max_segments: 1,
+            ..ResourceLimits::default()
+        };
+        let result = transform(source(app.clone()), hardened(limits)).unwrap();
+        assert_eq!(codes(&result), vec![DiagnosticCode::LimitExceeded]);
+        assert!(result.optimized_app().components.is_empty());
+
+        let result = transform(source(app), hardened(ResourceLimits::default())).unwrap();
+        assert!(!codes(&result).contains(&DiagnosticCode::LimitExceeded));
+        assert!(result.optimized_app().components.len() > 1);
+    }
+
+    #[test]
+    fn test_preserve_comments() {
+        let source = || {
+            test_source!(
+                r#"/* @license MIT */
+                // Regular header comment
+                "use client";
+                import { component$ } from "@qwik.dev/core";
+
+                export const App = component$(() => {
+                    // eslint-disable-next-line no-console
+                    console.log("rendered");
+                    // Regular comment
+                    return <div />;
+                });
+                export default App;
+                "#
+            )
+        };
+        let code = |preserve_comments: Vec<String>| {
+            let options = TransformOptions {
+                preserve_comments,
+                ..TransformOptions::default()
+            };
+            let result = transform(source(), options).unwrap();
+            let app = result.optimized_app();
+            (app.body.clone(), app.components[0].code.clone())
+        };
+
+        let (host, segment) = code(Vec::new());
+        assert!(host.starts_with("\"use client\";"));
+        assert!(segment.starts_with("\"use client\";"));
+        assert!(!segment.contains("//"));
+        assert!(!segment.contains("/*"));
+
+        let (host, segment) = code(vec!["@license".to_string(), "eslint-disable".to_string()]);
+        assert!(host.starts_with("/* @license MIT */\n\"use client\";"));
+        assert!(!host.contains("eslint-disable"));
+        assert!(segment.starts_with("/* @license MIT */\n\"use client\";"));
+        assert_eq!(
+            segment.contains("\t// eslint-disable-next-line no-console\n\tconsole.log"),
+            cfg!(feature = "source-maps")
+        );
+        assert!(!segment.contains("Regular"));
+    }
+
+    #[test]
+    fn test_segment_caps() {
+        let paths = vec![
+            PathBuf::from("src/test_input/test_example_1.tsx"),
+            PathBuf::from("src/test_input/test_example_2.tsx"),
+        ];
+        let transform = |options| transform_fs(paths.clone(), options).unwrap();
+        let too_many = |result: &TransformResult| -> Vec<(usize, String)> {
+            result
+                .modules
+                .iter()
+                .enumerate()
+                .flat_map(|(index, module)| {
+                    module
+                        .diagnostics
+                        .iter()
+                        .filter(|d| d.code == DiagnosticCode::TooManySegments)
+                        .map(move |d| (index, d.message.clone()))
+                })
+                .collect()
+        };
+
+        let uncapped = transform(TransformOptions::default());
+        assert!(too_many(&uncapped).is_empty());
+        let counts: Vec<usize> = paths
+            .iter()
+            .map(|path| {
+                let origin = path.to_string_lossy();
+                uncapped
+                    .segments
+                    .iter()
+                    .filter(|s| s.origin == origin)
+                    .count()
+            })
+            .collect();
+
+        let max = counts[0] - 1;
+        let result = transform(TransformOptions {
+            max_segments_per_file: Some(max),
+            ..TransformOptions::default()
+        });
+        let diagnostics = too_many(&result);
+        let exceeding: Vec<usize> = (0..counts.len()).filter(|i| counts[*i] > max).collect();
+        assert_eq!(
+            diagnostics.iter().map(|(i, _)| *i).collect::<Vec<_>>(),
+            exceeding
+        );
+        assert!(diagnostics[0]
+            .1
+            .ends_with("Most were extracted from `renderHeader` (3)"));
+
+        let total = counts.iter().sum::<usize>();
+        let capped = |max_segments_per_project| {
+            transform(TransformOptions {
+                max_segments_per_project,
+                ..TransformOptions::default()
+            })
+        };
+        assert!(too_many(&capped(Some(total))).is_empty());
+        let diagnostics = too_many(&capped(Some(total - 1)));
+        assert_eq!(diagnostics.len(), 1);
+        assert!(diagnostics[0]
+            .1
+            .contains("src/test_input/test_example_1.tsx"));
+        assert!(diagnostics[0]
+            .1
+            .contains("src/test_input/test_example_2.tsx"));
+    }
+
+    #[test]
+    fn test_symbol_listener() {
+        let paths = vec![
+            PathBuf::from("src/test_input/test_example_1.tsx"),
+            PathBuf::from("src/test_input/test_example_3.tsx"),
+        ];
+        let symbols = Arc::new(Mutex::new(Vec::new()));
+        let registry = symbols.clone();
+        let options = TransformOptions::builder()
+            .symbol_listener(SymbolListener::new(move |symbol| {
+                registry.lock().unwrap().push((
+                    symbol.id.symbol_name.clone(),
+                    symbol.code.to_string(),
+                    symbol.captures.to_vec(),
+                    symbol.origin.to_string(),
+                ));
+            }))
+            .build()
+            .unwrap();
+
+        let result = transform_fs(paths, options).unwrap();
+
+        let mut symbols = symbols.lock().unwrap().clone();
+        symbols.sort();
+        let mut expected: Vec<_> = result
+            .segments
+            .iter()
+            .map(|segment| {
+                (
+                    segment.id.symbol_name.clone(),
+                    segment.code.clone(),
+                    segment.qrl.captures.clone(),
+                    segment.origin.clone(),
+                )
+            })
+            .collect();
+        expected.sort();
+        assert!(!symbols.is_empty());
+        assert_eq!(symbols, expected);
+    }
+
+    #[test]
+    fn test_chunk_resolver() {
+        let paths = vec![PathBuf::from("src/test_input/test_example_1.tsx")];
+        let options = TransformOptions {
+            chunk_resolver: Some(ChunkResolver::new(|request| {
+                request
+                    .symbol_name
+                    .contains("onClick")
+                    .then(|| format!("https://cdn.example.com/{}.js", request.symbol_name))
+            })),
+            ..TransformOptions::default()
+        };
+
+        let result = transform_fs(paths, options).unwrap();
+
+        let code = result
+            .segments
+            .iter()
+            .map(|segment| segment.code.as_str())
+            .chain(std::iter::once(result.modules[0].code.as_str()))
+            .collect::<String>();
+        for segment in &result.segments {
+            let resolved = format!("https://cdn.example.com/{}.js", segment.id.symbol_name);
+            let expected = match &segment.qrl.chunk {
+                Some(chunk) => {
+                    assert_eq!(chunk, &resolved);
+                    resolved
+                }
+                None => segment.import_specifier(Path::new("src/test_input"), false),
+            };
+            assert!(code.contains(&format!("import(\"{expected}\")")));
+        }
+        assert!(result.segments.iter().any(|s| s.qrl.chunk.is_some()));
+        assert!(result.segments.iter().any(|s| s.qrl.chunk.is_none()));
+    }
+
+    #[test]
+    fn test_segment_naming() {
+        let paths = vec![PathBuf::from("src/test_input/test_example_1.tsx")];
+        let file_names = |segment_naming| {
+            let result = transform_fs(
+                paths.clone(),
+                TransformOptions {
+                    segment_naming,
+                    ..TransformOptions::default()
+                },
+            )
+            .unwrap();
+            for segment in &result.segments {
+                let specifier = &segment.qrl.import_specifier;
+                assert!(specifier.starts_with("./") || specifier.starts_with("../"));
+                let import = format!("import(\"{specifier}\")");
+                let imported = std::iter::once(&result.modules[0].code)
+                    .chain(result.segments.iter().map(|s| &s.code))
+                    .any(|code| code.contains(&import));
+                assert!(imported, "{import} is missing");
+            }
+            result
+                .segments
+                .into_iter()
+                .map(|segment| (segment.id.local_file_name, segment.id.hash))
+                .collect::<Vec<_>>()
+        };
+
+        for (file_name, hash) in file_names(SegmentNaming::DirectoryMirrored) {
+            assert!(file_name.starts_with("src/test_input/test_example_1.tsx_"));
+            assert!(file_name.ends_with(hash.as_str()));
+        }
+        for (file_name, hash) in file_names(SegmentNaming::DisplayName) {
+            assert!(file_name.starts_with("test_example_1.tsx_"));
+            assert!(file_name.ends_with(hash.as_str()));
+        }
+        let hashed = file_names(SegmentNaming::ContentHash);
+        for (file_name, hash) in &hashed {
+            assert_eq!(file_name.len(), 11);
+            assert_ne!(file_name, hash);
+        }
+        assert_eq!(hashed, file_names(SegmentNaming::ContentHash));
+    }
+
+    #[test]
+    fn test_segment_import_specifiers() {
+        let code = r#"
+            import { component$, $ } from "@qwik.dev/core";
+            export const App = component$(() => <button onClick$={$(() => console.log("clicked"))} />);
+            "#;
+        // The host module, the component segment and its handler segment, transformed with `segment_naming`.
+        let transform = |segment_naming, import_extensions| {
+            let options = TransformOptions {
+                segment_naming,
+                import_extensions,
+                ..TransformOptions::default()
+            };
+            let result = transform_module("src/routes/index.tsx", code, options).unwrap();
+            let segment = |ctx_name: &str| {
+                let segment = result.segments.iter().find(|s| s.ctx_name == ctx_name);
+                segment.unwrap().clone()
+            };
+            let (app, handler) = (segment("component$"), segment("$"));
+            (result.modules[0].code.clone(), app, handler)
+        };
+        let imports = |module: &str, specifier: String| {
+            let import = format!("import(\"{specifier}\")");
+            assert!(
+                module.contains(&import),
+                "{import} is missing from {module}"
+            );
+        };
+
+        let (host, app, handler) = transform(SegmentNaming::DirectoryMirrored, false);
+        let in_routes = |file_name: &str| file_name.trim_start_matches("src/routes/").to_string();
+        imports(&host, format!("./{}", in_routes(&app.id.local_file_name)));
+        imports(
+            &app.code,
+            format!("./{}", in_routes(&handler.id.local_file_name)),
+        );
+
+        for segment_naming in [SegmentNaming::DisplayName, SegmentNaming::ContentHash] {
+            let (host, app, handler) = transform(segment_naming, false);
+            imports(&host, format!("../../{}", app.id.local_file_name));
+            imports(&app.code, format!("./{}", handler.id.local_file_name));
+        }
+
+        // Only given the extension of the emitted files when asked to.
+        let (host, app, handler) = transform(SegmentNaming::DisplayName, true);
+        imports(&host, format!("../../{}.tsx", app.id.local_file_name));
+        imports(&app.code, format!("./{}.tsx", handler.id.local_file_name));
+        assert_eq!(
+            app.qrl.import_specifier,
+            app.import_specifier(Path::new("src/routes"), true)
+        );
+
+        let (_, app, _) = transform(SegmentNaming::ContentHash, false);
+        assert_eq!(app.id.local_file_name.len(), 11);
+    }
+
+    #[test]
+    fn test_segment_parents() {
+        let code = r#"
+import { component$, useTask$, $ } from "@qwik.dev/core";
+export const App = component$(() => {
+    useTask$(() => {
+        const log = $(() => console.log("tracked"));
+        log();
+    });
+    return <button onClick$={$(() => console.log("clicked"))}>Click</button>;
+});
+export const standalone = $(() => 1);
+"#;
+        let result = transform_module("src/app.tsx", code, TransformOptions::default()).unwrap();
+        let segment = |name: &str| {
+            result
+                .segments
+                .iter()
+                .find(|segment| segment.id.symbol_name.rsplit_once('_').unwrap().0 == name)
+                .unwrap_or_else(|| panic!("no segment {name}"))
+        };
+        let app = segment("App_component");
+        let task = segment("App_component_useTask");
+        let log = segment("App_component_useTask_log");
+        let on_click = segment("App_component_button_onClick_1");
+
+        assert_eq!(result.segments.len(), 5);
+        assert_eq!(app.parent, None);
+        assert_eq!(app.id.parent_hash, None);
+        assert_eq!(segment("standalone").parent, None);
+        assert_eq!(task.parent.as_deref(), Some(app.id.symbol_name.as_str()));
+        assert_eq!(task.id.parent_hash.as_ref(), Some(&app.id.hash));
+        assert_eq!(log.parent.as_deref(), Some(task.id.symbol_name.as_str()));
+        assert_eq!(log.id.parent_hash.as_ref(), Some(&task.id.hash));
+        assert_eq!(
+            on_click.parent.as_deref(),
+            Some(app.id.symbol_name.as_str())
+        );
+        assert_eq!(
+            SegmentMetadata::from(log).parent.as_deref(),
+            Some(task.id.symbol_name.as_str())
+        );
+    }
+
+    #[test]
+    fn test_inline_const_captures() {
+        let code = r#"
+import { component$, useSignal, $ } from "@qwik.dev/core";
+export const App = component$(() => {
+    const label = "Save";
+    const sizes = Object.freeze([1, 2]);
+    const count = useSignal(0);
+    const onClick = $(() => count.value += sizes[0]);
+    return <button onClick$={onClick} title={label}>{label}</button>;
+});
+"#;
+        let handler = |options: TransformOptions| {
+            let result = transform_module("src/app.tsx", code, options).unwrap();
+            result
+                .segments
+                .into_iter()
+                .find(|segment| segment.id.symbol_name.contains("onClick"))
+                .unwrap()
+        };
+        let captured = handler(TransformOptions::default());
+        assert_eq!(captured.qrl.captures, vec!["count", "sizes"]);
+        assert!(captured.qrl.inlined_captures.is_empty());
+
+        let options = TransformOptions::builder()
+            .inline_const_captures()
+            .build()
+            .unwrap();
+        let inlined = handler(options);
+        assert_eq!(inlined.qrl.captures, vec!["count"]);
+        assert_eq!(
+            inlined.qrl.inlined_captures,
+            BTreeMap::from([("sizes".to_string(), "Object.freeze([1, 2])".to_string())])
+        );
+        assert!(
+            inlined
+                .code
+                .contains("const sizes = Object.freeze([1, 2]);"),
+            "{}",
+            inlined.code
+        );
+        assert!(
+            inlined.code.contains("const [count] = useLexicalScope();"),
+            "{}",
+            inlined.code
+        );
+        assert_eq!(
+            SegmentMetadata::from(&inlined).inlined_captures,
+            inlined.qrl.inlined_captures
+        );
+    }
+
+    #[test]
+    fn test_transform_stats() {
+        let code = r#"
+import { component$, $ } from "@qwik.dev/core";
+export const App = component$(() => <button onClick$={$(() => console.log("hi"))}>Hi</button>);
+"#;
+        let result = transform_module("src/app.tsx", code, TransformOptions::default()).unwrap();
+        let stats = result.modules[0].stats;
+        assert_eq!(stats.bytes_in, code.len());
+        assert_eq!(
+            stats.bytes_out,
+            result.modules[0].code.len()
+                + result.segments.iter().map(|s| s.code.len()).sum::<usize>()
+        );
+        assert_eq!(stats.segment_count, 2);
+        assert!(stats.parse_ms > 0.0 && stats.codegen_ms > 0.0 && stats.transform_ms > 0.0);
+        assert_eq!(result.stats(), stats);
+
+        let plain = transform_module(
+            "src/utils.ts",
+            "export const a = 1;",
+            TransformOptions::default(),
+        )
+        .unwrap();
+        assert_eq!(plain.modules[0].stats.segment_count, 0);
+        assert_eq!(plain.modules[0].stats.bytes_in, 19);
+    }
+
+    #[test]
+    fn test_standalone_markers() {
+        let code = r#"
+import { $, event$ } from "@qwik.dev/core";
+const handlers = { onSave: $(() => save()), onLoad: event$((ev) => load(ev)) };
+const list = [$(() => 1), event$(() => 2)];
+window.onTick = event$(() => tick());
+"#;
+        let result = transform_module("src/app.tsx", code, TransformOptions::default()).unwrap();
+        let mut names: Vec<&str> = result
+            .segments
+            .iter()
+            .map(|segment| segment.id.display_name.as_str())
+            .collect();
+        names.sort();
+        assert_eq!(
+            names,
+            vec![
+                "app.tsx_handlers_onLoad_event",
+                "app.tsx_handlers_onSave",
+                "app.tsx_list",
+                "app.tsx_list_event",
+                "app.tsx_onTick_event",
+            ]
+        );
+
+        let host = &result.modules[0].code;
+        assert!(
+            host.contains("const handlers = {\n\tonSave: qrl(() => import("),
+            "{host}"
+        );
+        assert!(
+            host.contains("onLoad: eventQrl(qrl(() => import("),
+            "{host}"
+        );
+        assert!(host.contains("const list = [qrl(() => import("), "{host}");
+        assert!(
+            host.contains("window.onTick = eventQrl(qrl(() => import("),
+            "{host}"
+        );
+    }
+
+    #[test]
+    fn test_forbid_external_markers() {
+        let code = r#"
+import { component$ } from "@qwik.dev/core";
+export const Button = component$(() => <button>Click</button>);
+"#;
+        let options = |mode| {
+            TransformOptions::builder()
+                .forbid_external_markers(mode)
+                .build()
+                .unwrap()
+        };
+
+        let skipped = transform_module(
+            "node_modules/ui/button.tsx",
+            code,
+            options(ExternalMarkers::Skip),
+        )
+        .unwrap();
+        assert_eq!(skipped.modules[0].kind, ModuleKind::External);
+        assert_eq!(skipped.modules[0].code, code);
+        assert!(skipped.segments.is_empty());
+        assert!(skipped.modules[0].diagnostics.is_empty());
+
+        let rejected = transform_module(
+            "node_modules/ui/button.tsx",
+            code,
+            options(ExternalMarkers::Error),
+        )
+        .unwrap();
+        assert_eq!(rejected.modules[0].kind, ModuleKind::External);
+        let codes: Vec<_> = rejected.modules[0]
+            .diagnostics
+            .iter()
+            .map(|diagnostic| diagnostic.code)
+            .collect();
+        assert_eq!(codes, vec![DiagnosticCode::ExternalMarker]);
+
+        let app =
+            transform_module("src/button.tsx", code, options(ExternalMarkers::Error)).unwrap();
+        assert_eq!(app.modules[0].kind, ModuleKind::Transformed);
+        assert_eq!(app.segments.len(), 1);
+
+        let linked = transform_module(
+            "packages/ui/button.tsx",
+            code,
+            TransformOptions::builder()
+                .forbid_external_markers(ExternalMarkers::Skip)
+                .external_paths(ExternalPaths::new(|path| path.starts_with("packages")))
+                .build()
+                .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(linked.modules[0].kind, ModuleKind::External);
+    }
+
+    #[test]
+    fn test_reexport_segments() {
+        let code = r#"
+import { component$, $ } from "@qwik.dev/core";
+export const handler = $(() => console.log("handler"));
+export const App = component$(() => <button onClick$={$(() => console.log("click"))} />);
+const Other = component$(() => <div />);
+export { Other as Renamed };
+const Local = component$(() => <p />);
+export function useHandler() {
+    return $(() => console.log("nested"));
+}
+export default component$(() => <Local />);
+"#;
+        let exports = |options: TransformOptions| {
+            let result = transform_module("src/app.tsx", code, options).unwrap();
+            let mut exports: Vec<(String, Option<String>)> = result
+                .segments
+                .iter()
+                .map(|segment| {
+                    (
+                        segment.id.display_name.to_string(),
+                        segment.exported.clone(),
+                    )
+                })
+                .collect();
+            exports.sort();
+            (result, exports)
+        };
+
+        let (result, exported) = exports(TransformOptions::default());
+        let name = |name: &str| Some(name.to_string());
+        assert_eq!(
+            exported,
+            vec![
+                ("app.tsx_App_component".to_string(), name("App")),
+                ("app.tsx_App_component_button_onClick_1".to_string(), None),
+                ("app.tsx_Local_component".to_string(), None),
+                ("app.tsx_Other_component".to_string(), name("Renamed")),
+                ("app.tsx_component".to_string(), name("default")),
+                ("app.tsx_handler".to_string(), name("handler")),
+                ("app.tsx_useHandler".to_string(), None),
+            ]
+        );
+        let app = result
+            .segments
+            .iter()
+            .find(|segment| segment.exported.as_deref() == Some("App"))
+            .unwrap();
+        #[cfg(feature = "manifest")]
+        assert_eq!(
+            result.manifest.exports().get(app.id.symbol_name.as_str()),
+            Some(&"App")
+        );
+        assert!(!result.modules[0].code.contains(" from \"./app.tsx_"));
+
+        let (result, _) = exports(
+            TransformOptions::builder()
+                .reexport_segments()
+                .build()
+                .unwrap(),
+        );
+        let host = &result.modules[0].code;
+        let reexports: Vec<&str> = host
+            .lines()
+            .filter(|line| line.starts_with("export {") && line.contains(" from "))
+            .collect();
+        assert_eq!(reexports.len(), 4, "{host}");
+        let reexport = format!(
+            "export {{ {0} }} from \"./app.tsx_{0}\";",
+            app.id.symbol_name
+        );
+        assert!(reexports.contains(&reexport.as_str()), "{host}");
+    }
+
+    #[test]
+    fn test_worker_segments() {
+        let code = r#"
+import { component$, worker$, useSignal } from "@qwik.dev/core";
+export const sum = worker$((values: number[]) => values.reduce((a, b) => a + b, 0));
+export const App = component$(() => {
+    const count = useSignal(1);
+    const double = worker$(() => count.value * 2);
+    return <button onClick$={() => sum([count.value])}>{double}</button>;
+});
+"#;
+        let result = transform_module("src/app.tsx", code, TransformOptions::default()).unwrap();
+        let workers: Vec<&QrlComponent> = result
+            .segments
+            .iter()
+            .filter(|segment| segment.ctx_kind == SegmentKind::Worker)
+            .collect();
+        assert_eq!(workers.len(), 2);
+        for worker in &workers {
+            assert_eq!(worker.entry.as_deref(), Some(WORKER_ENTRY));
+            assert!(worker.qrl.captures.is_empty());
+            assert!(!worker.code.contains(USE_LEXICAL_SCOPE), "{}", worker.code);
+        }
+        assert!(result.modules[0].code.contains("workerQrl"));
+
+        let diagnostics: Vec<_> = result.modules[0]
+            .diagnostics
+            .iter()
+            .map(|diagnostic| (diagnostic.code, diagnostic.message.as_str()))
+            .collect();
+        assert_eq!(
+            diagnostics,
+            vec![(
+                DiagnosticCode::WorkerCapture,
+                "`worker$` closures run in a web worker and can not capture `count`"
+            )]
+        );
+    }
+
+    #[test]
+    fn test_route_exports() {
+        let code = r#"
+import { component$ } from "@qwik.dev/core";
+import { routeLoader$, routeAction$, globalAction$ } from "@qwik.dev/router";
+export const useProduct = routeLoader$(() => ({ name: "Qwik" }));
+const useAddToCart = routeAction$((data) => ({ added: data }));
+export { useAddToCart };
+export const login = globalAction$(() => true);
+const useHidden = routeLoader$(() => 1);
+export default component$(() => {
+    const product = useProduct();
+    return <div>{product.value.name}</div>;
+});
+"#;
+        let result =
+            transform_module("src/routes/index.tsx", code, TransformOptions::default()).unwrap();
+        let mut route_exports: Vec<(&str, Option<RouteExportKind>)> = result
+            .segments
+            .iter()
+            .filter(|segment| segment.ctx_name != COMPONENT)
+            .map(|segment| (segment.id.display_name.as_str(), segment.route_export))
+            .collect();
+        route_exports.sort();
+        assert_eq!(
+            route_exports,
+            vec![
+                (
+                    "index.tsx_login_globalAction",
+                    Some(RouteExportKind::GlobalAction)
+                ),
+                (
+                    "index.tsx_useAddToCart_routeAction",
+                    Some(RouteExportKind::Action)
+                ),
+                ("index.tsx_useHidden_routeLoader", None),
+                (
+                    "index.tsx_useProduct_routeLoader",
+                    Some(RouteExportKind::Loader)
+                ),
+            ]
+        );
+        #[cfg(feature = "manifest")]
+        assert_eq!(
+            result
+                .manifest
+                .route_exports()
+                .get(&("src/routes/index.tsx", "useAddToCart")),
+            Some(&RouteExportKind::Action)
+        );
+
+        let diagnostics: Vec<_> = result.modules[0]
+            .diagnostics
+            .iter()
+            .map(|diagnostic| (diagnostic.severity, diagnostic.message.as_str()))
+            .collect();
+        assert_eq!(
+            diagnostics,
+            vec![
+                (
+                    Severity::Warning,
+                    "`login` is created with `globalAction$` but its name does not start with `use`"
+                ),
+                (
+                    Severity::Error,
+                    "`routeLoader$` must be the value of a top-level named export"
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_module_format() {
+        let code = r#"
+import { component$, useSignal } from "@qwik.dev/core";
+export const App = component$(() => {
+    const count = useSignal(0);
+    return <button onClick$={() => count.value++}>{count.value}</button>;
+});
+"#;
+        let transform =
+            |options: TransformOptions| transform_module("src/app.tsx", code, options).unwrap();
+        let result = transform(
+            TransformOptions::builder()
+                .module_format(ModuleFormat::Cjs)
+                .build()
+                .unwrap(),
+        );
+        let host = &result.modules[0].code;
+        assert!(!host.contains("import {"), "{host}");
+        assert!(host.contains("require(\"@qwik.dev/core\")"), "{host}");
+        assert!(
+            host.contains("Object.defineProperty(exports, \"App\", {\n\tenumerable: true,\n\tget: () => App\n});"),
+            "{host}"
+        );
+        assert!(host.contains("() => import(\""), "{host}");
+        for segment in &result.segments {
+            assert!(!segment.code.contains("export const"), "{}", segment.code);
+            assert!(
+                segment
+                    .code
+                    .contains(&format!("get: () => {}\n", segment.id.symbol_name)),
+                "{}",
+                segment.code
+            );
+        }
+
+        let result = transform(
+            TransformOptions::builder()
+                .module_format(ModuleFormat::Cjs)
+                .require_dynamic_imports()
+                .build()
+                .unwrap(),
+        );
+        let host = &result.modules[0].code;
+        assert!(!host.contains("import("), "{host}");
+        assert!(
+            host.contains("() => Promise.resolve().then(() => require(\""),
+            "{host}"
+        );
+    }
+
+    #[test]
+    fn test_top_level_awaits() {
+        let code = r#"
+import { component$, $, sync$ } from "@qwik.dev/core";
+export const config = $(await loadConfig());
+export const button = <button onClick$={$(await getHandler())} onFocus$={$(async () => await focus())} />;
+export const App = component$(() => <input onInput$={sync$(async (event) => event.preventDefault())} />);
+"#;
+        let result = transform_module("src/app.tsx", code, TransformOptions::default()).unwrap();
+        let mut async_only: Vec<(&str, bool)> = result
+            .segments
+            .iter()
+            .map(|segment| (segment.id.display_name.as_str(), segment.async_only))
+            .collect();
+        async_only.sort();
+        assert_eq!(
+            async_only,
+            vec![
+                ("app.tsx_App_component", false),
+                ("app.tsx_button_button_onClick_1", true),
+                ("app.tsx_button_button_onFocus_1", false),
+                ("app.tsx_config", true),
+            ]
+        );
+
+        let diagnostics: Vec<_> = result.modules[0]
+            .diagnostics
+            .iter()
+            .map(|diagnostic| (diagnostic.code, diagnostic.message.as_str()))
+            .collect();
+        assert_eq!(
+            diagnostics,
+            vec![
+                (
+                    DiagnosticCode::AwaitInSyncContext,
+                    "An event handler awaits outside of its function, while its segment is loaded"
+                ),
+                (
+                    DiagnosticCode::AwaitInSyncContext,
+                    "`sync$` functions run synchronously while the event is dispatched, and can not be `async`"
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_custom_markers() {
+        let code = r#"
+import { lazy_, defer } from "@acme/design-system";
+export const Card = lazy_(() => <div>card</div>);
+export const load = defer(() => fetch("/data"));
+export const plain = lazy(() => 1);
+"#;
+        let options = TransformOptions::builder()
+            .marker_suffix("_")
+            .marker_name("defer")
+            .build()
+            .unwrap();
+        let result = transform_module("src/app.tsx", code, options).unwrap();
+        let mut names: Vec<(&str, &str)> = result
+            .segments
+            .iter()
+            .map(|segment| (segment.id.display_name.as_str(), segment.ctx_name.as_str()))
+            .collect();
+        names.sort();
+        assert_eq!(
+            names,
+            vec![
+                ("app.tsx_Card_lazy", "lazy$"),
+                ("app.tsx_load_defer", "defer$")
+            ]
+        );
+        let host = &result.modules[0].code;
+        assert!(host.contains("lazyQrl(qrl("), "{host}");
+        assert!(host.contains("deferQrl(qrl("), "{host}");
+        assert!(host.contains("from \"@acme/design-system\""), "{host}");
+        assert!(host.contains("lazy(() => 1)"), "{host}");
+
+        // Without the options, the module has no markers.
+        let result = transform_module("src/app.tsx", code, TransformOptions::default()).unwrap();
+        assert!(result.segments.is_empty());
+    }
+
+    #[test]
+    fn test_event_modifiers() {
+        let code = r#"
+import { component$ } from "@qwik.dev/core";
+export const App = component$(() => (
+    <div>
+        <a href="/" preventdefault:click onClick$={() => console.log("a")} />
+        <form preventDefault:onSubmit$ onSubmit$={() => console.log("form")} />
+        <button stoppropagation:clik onClick$={() => console.log("button")} />
+        <Link preventDefault:click />
+    </div>
+));
+"#;
+        let result = transform_module("src/app.tsx", code, TransformOptions::default()).unwrap();
+        let component = &result.segments[0].code;
+        assert!(
+            component.contains("<a href=\"/\" preventdefault:click "),
+            "{component}"
+        );
+        assert!(
+            component.contains("<form preventdefault:submit "),
+            "{component}"
+        );
+        assert!(
+            component.contains("<button stoppropagation:clik "),
+            "{component}"
+        );
+        // Props of components are passed on as they are.
+        assert!(
+            component.contains("<Link preventDefault:click "),
+            "{component}"
+        );
+
+        let diagnostics: Vec<_> = result.modules[0]
+            .diagnostics
+            .iter()
+            .map(|diagnostic| {
+                (
+                    diagnostic.code,
+                    diagnostic.message.as_str(),
+                    diagnostic.suggestion.as_deref(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            diagnostics,
+            vec![
+                (
+                    DiagnosticCode::InvalidEventModifier,
+                    "`preventDefault:onSubmit$` is not an event modifier, and is emitted as `preventdefault:submit`",
+                    Some("Write `preventdefault:submit`"),
+                ),
+                (
+                    DiagnosticCode::InvalidEventModifier,
+                    "`stoppropagation:clik` modifies the `clik` event, which is not a DOM event",
+                    Some("Write `stoppropagation:click` if the `click` event was meant"),
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_use_on_listeners() {
+        let code = r#"
+import { component$, useOn, useOnDocument, useOnWindow, $ } from "@qwik.dev/core";
+export const App = component$(() => {
+    useOn("qvisible", $(() => console.log("visible")));
+    useOnDocument("scroll", $(() => console.log("scroll")));
+    useOnWindow("resize", cond ? $(() => console.log("a")) : $(() => console.log("b")));
+    const log = $(() => console.log("log"));
+    return <div />;
+});
+"#;
+        let result = transform_module("src/app.tsx", code, TransformOptions::default()).unwrap();
+        let mut listeners: Vec<(SegmentKind, Option<String>)> = result
+            .segments
+            .iter()
+            .filter(|segment| segment.ctx_name != COMPONENT)
+            .map(|segment| {
+                (
+                    segment.ctx_kind,
+                    segment.listener.as_ref().map(ToString::to_string),
+                )
+            })
+            .collect();
+        listeners.sort();
+        let handler = |listener: &str| (SegmentKind::EventHandler, Some(listener.to_string()));
+        assert_eq!(
+            listeners,
+            vec![
+                (SegmentKind::Function, None),
+                handler("on-document:scroll"),
+                handler("on-window:resize"),
+                handler("on-window:resize"),
+                handler("on:qvisible"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_avoids_shadowing() {
+        let code = r#"
+import { component$, useSignal, $ } from "@qwik.dev/core";
+export const App = component$(() => {
+    const qrl = useSignal("https://qwik.dev");
+    const onClick = $(() => console.log(qrl.value));
+    return <button onClick$={onClick}>Open</button>;
+});
+"#;
+        let result = transform_module("src/app.tsx", code, TransformOptions::default()).unwrap();
+        let component = result
+            .segments
+            .iter()
+            .find(|segment| {
+                segment.id.symbol_name.starts_with("App_component_")
+                    && !segment.id.symbol_name.contains("onClick")
+            })
+            .unwrap();
+        assert!(
+            component
+                .code
+                .contains(r#"import { qrl as qrl$1 } from "@qwik.dev/core";"#),
+            "{}",
+            component.code
+        );
+        assert!(
+            component
+                .code
+                .contains("const onClick = qrl$1(() => import("),
+            "{}",
+            component.code
+        );
+        assert!(component.code.contains(", [qrl]);"), "{}", component.code);
+
+        let listener = result
+            .segments
+            .iter()
+            .find(|segment| segment.id.symbol_name.contains("onClick"))
+            .unwrap();
+        assert!(
+            listener.code.contains("const [qrl] = useLexicalScope();"),
+            "{}",
+            listener.code
+        );
+
+        let code = r#"
+import { component$ } from "@qwik.dev/core";
+const qrl = "https://qwik.dev";
+export const App = component$(() => <a href={qrl}>Qwik</a>);
+"#;
+        let result = transform_module("src/app.tsx", code, TransformOptions::default()).unwrap();
+        let host = &result.modules[0].code;
+        assert!(
+            host.contains(r#"import { qrl as qrl$1 } from "@qwik.dev/core";"#),
+            "{host}"
+        );
+        assert!(host.contains("const qrl = \"https://qwik.dev\";"), "{host}");
+        assert!(host.contains("componentQrl(qrl$1(() => import("), "{host}");
+    }
+
+    #[test]
+    fn test_strict_captures() {
+        let source = || {
+            test_source!(
+                r#"
+                import { component$, $ } from '@qwik.dev/core';
+                export const App = component$(() => {
+                    const format = (v) => `${v}`;
+                    const date = new Date();
+                    return <div onClick={$(() => console.log(format(date)))} />;
+                });
+                "#
+            )
+        };
+
+        let lenient = transform(source(), TransformOptions::default()).unwrap();
+        assert!(lenient.diagnostics().is_empty());
+
+        let strict = TransformOptions {
+            strict_captures: true,
+            ..TransformOptions::default()
+        };
+        let Err(Error::InvalidCaptures(messages)) = transform(source(), strict) else {
+            panic!("expected the transform to fail");
+        };
+        assert_eq!(messages.len(), 2);
+        assert!(messages[0].contains(
+            "'date' can not be used inside a Qrl($) scope because it's a class instance"
+        ));
+        assert!(messages[1].contains("help: Wrap `format` with `$()`"));
+    }
+
+    #[test]
+    fn test_name_registry_keeps_names_stable() {
+        let source = |handlers: &str| {
+            test_source!(format!(
+                r#"
+                    import {{ component$, $ }} from '@qwik.dev/core';
+                    export const App = component$(() => {{
+                        return [{handlers}];
+                    }});
+                    "#
+            ))
+        };
+        // The symbol name of the handler returning `value`.
+        let symbol = |result: &OptimizationResult, value: &str| -> String {
+            result
+                .optimized_app()
+                .components
+                .iter()
+                .find(|c| c.ctx_name == "$" && c.code.contains(value))
+                .map(|c| c.id.symbol_name.to_string())
+                .unwrap()
+        };
+        let original = "$(() => 'a'), $(() => 'b')";
+        let edited = "$(() => 'new'), $(() => 'a'), $(() => 'b')";
+
+        let registry = NameRegistry::new();
+        let first =
+            transform_with_registry(source(original), TransformOptions::default(), &registry)
+                .unwrap();
+        let plain = transform(source(original), TransformOptions::default()).unwrap();
+        assert_eq!(symbol(&first, "\"a\""), symbol(&plain, "\"a\""));
+
+        let second =
+            transform_with_registry(source(edited), TransformOptions::default(), &registry)
+                .unwrap();
+        let shifted = transform(source(edited), TransformOptions::default()).unwrap();
+        for value in ["\"a\"", "\"b\""] {
+            assert_eq!(symbol(&second, value), symbol(&first, value));
+            assert_ne!(symbol(&shifted, value), symbol(&first, value));
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "manifest")]
+    fn test_merge_results() {
+        let options = TransformOptions::default();
+        let input_1 = PathBuf::from("src/test_input/test_example_1.tsx");
+        let input_2 = PathBuf::from("src/test_input/test_example_2.tsx");
+
+        let whole = transform_fs(vec![input_1.clone(), input_2.clone()], options.clone()).unwrap();
+        let shard_1 = transform_fs(vec![input_1.clone()], options.clone()).unwrap();
+        let shard_2 = transform_fs(vec![input_2, input_1.clone()], options.clone()).unwrap();
+
+        let merged = TransformResult::merge(vec![shard_1.clone(), shard_2]).unwrap();
+        assert_eq!(merged.modules.len(), 2);
+        assert_eq!(merged.manifest, whole.manifest);
+        assert_eq!(merged.entry_plan, whole.entry_plan);
+
+        let mut changed = shard_1.clone();
+        changed.modules[0].code.push_str("// changed");
+        assert!(matches!(
+            TransformResult::merge(vec![shard_1.clone(), changed]),
+            Err(Error::MergeConflict(_))
+        ));
+
+        let single = TransformOptions {
+            entry_strategy: EntryStrategy::Single,
+            ..options
+        };
+        let other = transform_fs(vec![input_1], single).unwrap();
+        assert!(TransformResult::merge(vec![shard_1, other]).is_err());
+    }
+
+    #[test]
+    fn test_transform_fs_missing_file() {
+        let paths = vec![PathBuf::from("./src/test_input/does_not_exist.tsx")];
+        assert!(transform_fs(paths, TransformOptions::default()).is_err());
+    }
+
+    #[test]
+    fn test_optimization_result_display() {
+        let source = test_source!(
+            "import { $ } from '@qwik.dev/core';\nexport const a = $(() => console.log('a'));",
+            crate::component::Language::Javascript
+        );
+        let rendered = transform(source, TransformOptions::default())
+            .unwrap()
+            .to_string();
+
+        assert!(rendered.starts_with("OptimizationResult\n├── body:"));
+        assert!(rendered.contains("├── components[1]:"));
+        assert!(rendered.contains("│   QrlComponent [Javascript]"));
+        assert!(rendered.ends_with("└── errors[0]:\n"));
     }
 
     #[test]
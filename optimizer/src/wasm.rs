@@ -0,0 +1,19 @@
+use crate::transform::{self, TransformModulesOptions, TransformOptions};
+use wasm_bindgen::prelude::*;
+
+/// Transforms the modules described by `json_options`, a JSON encoded [TransformModulesOptions], and returns the
+/// [TransformResult](crate::transform::TransformResult) encoded as JSON.
+#[wasm_bindgen(js_name = transformModules)]
+pub fn transform_modules(json_options: &str) -> Result<String, JsError> {
+    let config: TransformModulesOptions = serde_json::from_str(json_options)?;
+    let result = transform::transform_modules(config)?;
+    Ok(serde_json::to_string(&result)?)
+}
+
+/// Prepares the module for transforms with the options described by `json_options`, a JSON encoded
+/// [TransformOptions], see [warm_up](crate::transform::warm_up).
+#[wasm_bindgen(js_name = warmUp)]
+pub fn warm_up(json_options: &str) -> Result<(), JsError> {
+    let options: TransformOptions = serde_json::from_str(json_options)?;
+    Ok(transform::warm_up(&options)?)
+}
@@ -0,0 +1,248 @@
+use crate::cache::TransformCache;
+use crate::prelude::*;
+use crate::source::Source;
+use crate::transform::{OptimizationResult, TransformOptions, TransformResult};
+#[cfg(not(target_arch = "wasm32"))]
+use rayon::prelude::*;
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, MutexGuard};
+
+/// A long-lived optimizer, e.g. of a dev server, which keeps the results of the files it transformed and only
+/// re-transforms the files reported as changed by a file watcher.
+///
+/// Files are transformed through a [TransformCache], so a file reported as changed whose contents are unchanged
+/// is not transformed again. The [result](Optimizer::result) always covers every file transformed so far, with a
+/// single [Manifest](crate::manifest::Manifest) and [EntryPlan](crate::entry_strategy::EntryPlan).
+///
+/// ```no_run
+/// use qwik_optimizer::api::{Optimizer, TransformOptions};
+/// use std::path::PathBuf;
+///
+/// let optimizer = Optimizer::new(TransformOptions::default());
+/// optimizer.transform_changed(vec![PathBuf::from("src/app.tsx"), PathBuf::from("src/counter.tsx")])?;
+/// // Later, when the watcher reports a change:
+/// let changes = optimizer.transform_changed(vec![PathBuf::from("src/counter.tsx")])?;
+/// for entry in &changes.entries {
+///     println!("reload {entry}");
+/// }
+/// # Ok::<(), qwik_optimizer::api::Error>(())
+/// ```
+#[derive(Debug)]
+pub struct Optimizer {
+    options: TransformOptions,
+    cache: TransformCache,
+    /// The latest result of every file, by path.
+    modules: Mutex<BTreeMap<PathBuf, OptimizationResult>>,
+}
+
+/// What changed after [Optimizer::transform_changed].
+#[derive(Debug, Clone)]
+pub struct WatchUpdate {
+    /// The files whose output changed, sorted.
+    pub transformed: Vec<PathBuf>,
+    /// The files whose output is unchanged, sorted.
+    pub unchanged: Vec<PathBuf>,
+    /// The files which no longer exist, and were dropped from the result, sorted.
+    pub removed: Vec<PathBuf>,
+    /// The entries of the [EntryPlan](crate::entry_strategy::EntryPlan) which contain a segment of a changed or
+    /// removed file, or whose segments changed, sorted. Segments which are their own chunk are not listed.
+    pub entries: Vec<String>,
+    /// The result of every file transformed so far.
+    pub result: TransformResult,
+}
+
+impl Optimizer {
+    pub fn new(options: TransformOptions) -> Self {
+        Self::with_cache(options, TransformCache::in_memory())
+    }
+
+    /// Creates an optimizer transforming files through `cache`, e.g. one persisted on disk so that restarting
+    /// the dev server is fast.
+    pub fn with_cache(options: TransformOptions, cache: TransformCache) -> Self {
+        Self {
+            options,
+            cache,
+            modules: Mutex::new(BTreeMap::new()),
+        }
+    }
+
+    pub fn options(&self) -> &TransformOptions {
+        &self.options
+    }
+
+    /// Forgets the cached result of the file at `path`, so that the next [Optimizer::transform_changed] transforms
+    /// it even if its contents are unchanged, e.g. after a file it depends on changed. Returns whether the file was
+    /// transformed before.
+    pub fn invalidate(&self, path: &Path) -> bool {
+        self.cache.invalidate(path);
+        self.modules().contains_key(path)
+    }
+
+    /// Transforms the files at `paths`, which are added to the result if they were not transformed before, and
+    /// drops the ones which no longer exist. Fails if a file exists but can not be read.
+    pub fn transform_changed(&self, paths: Vec<PathBuf>) -> Result<WatchUpdate> {
+        let paths: BTreeSet<PathBuf> = paths.into_iter().collect();
+        let (existing, removed): (Vec<PathBuf>, Vec<PathBuf>) =
+            paths.into_iter().partition(|path| path.exists());
+        let transform = |path: PathBuf| -> Result<(PathBuf, OptimizationResult)> {
+            let result = self
+                .cache
+                .transform(Source::from_file(&path)?, &self.options)?;
+            Ok((path, result))
+        };
+        #[cfg(not(target_arch = "wasm32"))]
+        let results: Vec<_> = existing
+            .into_par_iter()
+            .map(transform)
+            .collect::<Result<_>>()?;
+        #[cfg(target_arch = "wasm32")]
+        let results: Vec<_> = existing.into_iter().map(transform).collect::<Result<_>>()?;
+
+        let previous = self.result()?;
+        let mut modules = self.modules();
+        let mut changed_symbols = BTreeSet::new();
+        let mut transformed = Vec::new();
+        let mut unchanged = Vec::new();
+        for (path, result) in results {
+            let old = modules.insert(path.clone(), result);
+            let new = &modules[&path];
+            if old
+                .as_ref()
+                .is_some_and(|old| old.optimized_app() == new.optimized_app())
+            {
+                unchanged.push(path);
+                continue;
+            }
+            changed_symbols.extend(symbols(new));
+            changed_symbols.extend(old.iter().flat_map(symbols));
+            transformed.push(path);
+        }
+        let removed: Vec<PathBuf> = removed
+            .into_iter()
+            .filter(|path| match modules.remove(path) {
+                Some(old) => {
+                    changed_symbols.extend(symbols(&old));
+                    true
+                }
+                None => false,
+            })
+            .collect();
+        let result = TransformResult::from_results(
+            modules
+                .iter()
+                .map(|(path, result)| (path.clone(), result.clone()))
+                .collect(),
+            &self.options,
+        );
+        drop(modules);
+        let result = result?;
+
+        let old_entries = &previous.entry_plan.entries;
+        let new_entries = &result.entry_plan.entries;
+        let entries: BTreeSet<String> = new_entries
+            .iter()
+            .chain(old_entries.iter())
+            .filter(|entry| {
+                entry
+                    .symbols
+                    .iter()
+                    .any(|symbol| changed_symbols.contains(symbol))
+                    || !new_entries.contains(entry)
+                    || !old_entries.contains(entry)
+            })
+            .map(|entry| entry.name.clone())
+            .collect();
+
+        Ok(WatchUpdate {
+            transformed,
+            unchanged,
+            removed,
+            entries: entries.into_iter().collect(),
+            result,
+        })
+    }
+
+    /// The result of every file transformed so far. Fails if two of the files produce different segments with the
+    /// same symbol name.
+    pub fn result(&self) -> Result<TransformResult> {
+        TransformResult::from_results(
+            self.modules()
+                .iter()
+                .map(|(path, result)| (path.clone(), result.clone()))
+                .collect(),
+            &self.options,
+        )
+    }
+
+    fn modules(&self) -> MutexGuard<'_, BTreeMap<PathBuf, OptimizationResult>> {
+        self.modules
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+fn symbols(result: &OptimizationResult) -> impl Iterator<Item = String> + '_ {
+    result
+        .optimized_app()
+        .components
+        .iter()
+        .map(|comp| comp.id.symbol_name.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::entry_strategy::{EntryStrategy, ENTRY_SEGMENTS};
+    use std::fs;
+
+    #[test]
+    fn transforms_changed_files() {
+        let dir = std::env::temp_dir().join(format!("qwik-optimizer-watch-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let app = dir.join("app.tsx");
+        let counter = dir.join("counter.tsx");
+        let component = |name: &str, text: &str| {
+            format!(
+                "import {{ component$ }} from \"@qwik.dev/core\";\n\
+                 export const {name} = component$(() => <p>{text}</p>);\n"
+            )
+        };
+        fs::write(&app, component("App", "Hello")).unwrap();
+        fs::write(&counter, component("Counter", "0")).unwrap();
+        let options = TransformOptions::builder()
+            .entry_strategy(EntryStrategy::Single)
+            .build()
+            .unwrap();
+        let optimizer = Optimizer::new(options);
+
+        let initial = optimizer
+            .transform_changed(vec![app.clone(), counter.clone()])
+            .unwrap();
+        assert_eq!(initial.transformed, [app.clone(), counter.clone()]);
+        assert_eq!(initial.entries, [ENTRY_SEGMENTS]);
+        assert_eq!(initial.result.segments.len(), 2);
+
+        fs::write(&counter, component("Counter", "1")).unwrap();
+        let update = optimizer
+            .transform_changed(vec![app.clone(), counter.clone()])
+            .unwrap();
+        assert_eq!(update.transformed, std::slice::from_ref(&counter));
+        assert_eq!(update.unchanged, std::slice::from_ref(&app));
+        assert_eq!(update.entries, [ENTRY_SEGMENTS]);
+        assert!(update.result.modules.iter().any(|m| m.path == app));
+
+        assert!(optimizer.invalidate(&app));
+        let update = optimizer.transform_changed(vec![app.clone()]).unwrap();
+        assert_eq!(update.unchanged, std::slice::from_ref(&app));
+        assert!(update.entries.is_empty());
+
+        fs::remove_file(&counter).unwrap();
+        let update = optimizer.transform_changed(vec![counter.clone()]).unwrap();
+        assert_eq!(update.removed, [counter]);
+        assert_eq!(update.result.modules.len(), 1);
+        assert_eq!(optimizer.result().unwrap().segments.len(), 1);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,45 @@
+//! Runs `qwik-optimize --serve` as a child process, as JS build tools do, so that anything else written to its stdout
+//! is caught.
+
+use serde_json::Value;
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+#[test]
+fn serve_writes_only_responses_to_stdout() {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_qwik-optimize"))
+        .arg("--serve")
+        .current_dir(env!("CARGO_MANIFEST_DIR"))
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .unwrap();
+
+    let input = [
+        r#"{"jsonrpc":"2.0","id":1,"method":"transformFs","params":{"paths":["src/test_input/test_example_1.tsx"]}}"#,
+        r#"{"jsonrpc":"2.0","id":2,"method":"shutdown"}"#,
+    ]
+    .join("\n");
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(input.as_bytes())
+        .unwrap();
+    let output = child.wait_with_output().unwrap();
+    assert!(output.status.success());
+
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let responses: Vec<Value> = stdout
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap_or_else(|e| panic!("{e}: {line}")))
+        .collect();
+    assert_eq!(responses.len(), 2);
+    assert_eq!(responses[0]["id"], 1);
+    assert!(!responses[0]["result"]["segments"]
+        .as_array()
+        .unwrap()
+        .is_empty());
+    assert_eq!(responses[1]["id"], 2);
+}